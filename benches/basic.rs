@@ -103,6 +103,11 @@ fn q5_sparse_filter(b: &mut test::Bencher) {
     bench_query(b, "select trip_id from test where (passenger_count = 5) AND (vendor_id = \"CMT\") AND (total_amount < 500) AND (store_and_fwd_flag = \"1\") limit 100;");
 }
 
+#[bench]
+fn count_vendor_id_equals(b: &mut test::Bencher) {
+    bench_query(b, "select count(0) from test where vendor_id = \"CMT\";");
+}
+
 #[bench]
 fn q6_top_n(b: &mut test::Bencher) {
     bench_query(b, "SELECT passenger_count, trip_distance, total_amount FROM test ORDER BY total_amount DESC LIMIT 100;");
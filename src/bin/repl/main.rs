@@ -166,8 +166,13 @@ fn table_stats(locustdb: &LocustDB) {
     for table in stats {
         let size = table.batches_bytes + table.buffer_bytes;
         println!("\n# Table `{}` ({} rows, {}) #", &table.name, table.rows, bite(size));
-        for &(ref columname, heapsize) in &table.size_per_column {
-            println!("{}: {:.2}", columname, bite(heapsize));
+        for &(ref columname, ref report) in &table.memory_per_column {
+            if report.dictionary_bytes > 0 {
+                println!("{} ({}): {:.2} (dictionary {:.2})",
+                         columname, report.encoding, bite(report.total), bite(report.dictionary_bytes));
+            } else {
+                println!("{} ({}): {:.2}", columname, report.encoding, bite(report.total));
+            }
         }
     }
 }
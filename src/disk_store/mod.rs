@@ -1,5 +1,6 @@
 pub mod interface;
 pub mod noop_storage;
+pub mod serialization;
 
 #[cfg(feature = "enable_rocksdb")]
 pub mod rocksdb;
\ No newline at end of file
@@ -11,9 +11,11 @@ use self::rocksdb::*;
 use storage_format_capnp::*;
 
 use disk_store::interface::*;
-use mem_store::column::{Column, DataSection};
+use mem_store::bloom_filter::BloomFilter;
+use mem_store::column::{Column, ColumnStats, DataSection, EncodingEstimate, MappedSection};
 use scheduler::inner_locustdb::InnerLocustDB;
 use mem_store::codec::CodecOp;
+use mem_store::strings;
 use engine::types::EncodingType as Type;
 
 use time;
@@ -160,6 +162,23 @@ fn deserialize_column(data: &[u8]) -> Column {
         }
     };
 
+    let case_insensitive = column.get_case_insensitive();
+    let null_code = match column.get_null_code().which().unwrap() {
+        column::null_code::Which::None(_) => None,
+        column::null_code::Which::Code(code) => Some(code),
+    };
+    let stats = match column.get_stats().which().unwrap() {
+        column::stats::Which::None(_) => None,
+        column::stats::Which::Stats(stats) => Some(deserialize_stats(stats.unwrap())),
+    };
+    let bloom_filter = match column.get_bloom_filter().which().unwrap() {
+        column::bloom_filter::Which::None(_) => None,
+        column::bloom_filter::Which::Filter(filter) => {
+            let filter = filter.unwrap();
+            Some(BloomFilter::from_words(filter.get_words().unwrap().iter().collect(), filter.get_num_hashes()))
+        }
+    };
+
     let codec = column.get_codec().unwrap().iter().map(|op| {
         use storage_format_capnp::codec_op::Which::*;
         match op.which().unwrap() {
@@ -171,15 +190,28 @@ fn deserialize_column(data: &[u8]) -> Column {
             ToI64(toi64) => CodecOp::ToI64(deserialize_type(toi64.unwrap())),
             PushDataSection(section) => CodecOp::PushDataSection(section as usize),
             DictLookup(t) => CodecOp::DictLookup(deserialize_type(t.unwrap())),
+            UnorderedDictLookup(t) => CodecOp::UnorderedDictLookup(deserialize_type(t.unwrap())),
             Lz4(lz4) => {
                 let lz4 = lz4.unwrap();
                 CodecOp::LZ4(deserialize_type(lz4.get_type().unwrap()), lz4.get_len_decoded() as usize)
             }
+            RunLengthDecode(rle) => {
+                let rle = rle.unwrap();
+                CodecOp::RunLengthDecode(deserialize_type(rle.get_type().unwrap()), rle.get_len_decoded() as usize)
+            }
+            UnpackBits(unpack) => {
+                let unpack = unpack.unwrap();
+                CodecOp::UnpackBits(deserialize_type(unpack.get_type().unwrap()), unpack.get_bits(), unpack.get_len_decoded() as usize)
+            }
             UnpackStrings(_) => CodecOp::UnpackStrings,
             UnhexpackStrings(uhps) => {
                 let uhps = uhps.unwrap();
                 CodecOp::UnhexpackStrings(uhps.get_uppercase(), uhps.get_total_bytes() as usize)
             }
+            UnpackFrontCodedStrings(total_bytes) => CodecOp::UnpackFrontCodedStrings(total_bytes as usize),
+            UnpackSymbolCodedStrings(total_bytes) => CodecOp::UnpackSymbolCodedStrings(total_bytes as usize),
+            UnpackComponentCodedStrings(total_bytes) => CodecOp::UnpackComponentCodedStrings(total_bytes as usize),
+            UnpackHybridStrings(_) => CodecOp::UnpackHybridStrings,
         }
     }).collect::<Vec<_>>();
 
@@ -220,7 +252,58 @@ fn deserialize_column(data: &[u8]) -> Column {
         }
     }).collect::<Vec<_>>();
 
-    Column::new(name, len, range, codec, data_sections)
+    let mut column = Column::new(name, len, range, codec, data_sections);
+    column.set_case_insensitive(case_insensitive);
+    column.set_null_code(null_code);
+    if let Some(mut stats) = stats {
+        stats.row_count = column.len();
+        stats.heap_bytes = column.data_heap_size();
+        column.set_stats(stats);
+    }
+    if let Some(bloom_filter) = bloom_filter {
+        column.set_bloom_filter(bloom_filter);
+    }
+    strings::validate_string_column(&column).unwrap();
+    column
+}
+
+/// Row count and heap size are filled in from the column.
+fn deserialize_stats(stats: column_stats::Reader) -> ColumnStats {
+    let min = match stats.get_min().which().unwrap() {
+        column_stats::min::Which::None(_) => None,
+        column_stats::min::Which::Value(value) => Some(value.unwrap().to_string()),
+    };
+    let max = match stats.get_max().which().unwrap() {
+        column_stats::max::Which::None(_) => None,
+        column_stats::max::Which::Value(value) => Some(value.unwrap().to_string()),
+    };
+    let int_range = match stats.get_int_range().which().unwrap() {
+        column_stats::int_range::Which::None(_) => None,
+        column_stats::int_range::Which::Range(range) => {
+            let range = range.unwrap();
+            Some((range.get_start(), range.get_end()))
+        }
+    };
+    let encoding_estimate = match stats.get_encoding_estimate().which().unwrap() {
+        column_stats::encoding_estimate::Which::None(_) => None,
+        column_stats::encoding_estimate::Which::Estimate(estimate) => {
+            let estimate = estimate.unwrap();
+            Some(EncodingEstimate {
+                dictionary_bytes: estimate.get_dictionary_bytes() as usize,
+                packed_bytes: estimate.get_packed_bytes() as usize,
+            })
+        }
+    };
+    ColumnStats {
+        row_count: 0,
+        null_count: stats.get_null_count() as usize,
+        distinct_count: stats.get_distinct_count() as usize,
+        min,
+        max,
+        int_range,
+        heap_bytes: 0,
+        encoding_estimate,
+    }
 }
 
 fn deserialize_type(t: EncodingType) -> Type {
@@ -279,6 +362,24 @@ fn serialize_column(col: &Column) -> Vec<u8> {
         let mut column = builder.init_root::<column::Builder>();
         column.set_name(col.name());
         column.set_len(col.len() as u64);
+        column.set_case_insensitive(col.is_case_insensitive());
+        match col.null_code() {
+            None => column.reborrow().init_null_code().set_none(()),
+            Some(code) => column.reborrow().init_null_code().set_code(code),
+        }
+        match col.stats() {
+            None => column.reborrow().init_stats().set_none(()),
+            Some(stats) => serialize_stats(column.reborrow().init_stats().init_stats(), stats),
+        }
+        match col.bloom_filter() {
+            None => column.reborrow().init_bloom_filter().set_none(()),
+            Some(bloom_filter) => {
+                let mut filter = column.reborrow().init_bloom_filter().init_filter();
+                filter.set_num_hashes(bloom_filter.num_hashes());
+                let mut words = filter.init_words(bloom_filter.words().len() as u32);
+                populate_primitive_list(&mut words, bloom_filter.words());
+            }
+        }
         {
             let mut range = column.reborrow().init_range();
             match col.range() {
@@ -304,17 +405,33 @@ fn serialize_column(col: &Column) -> Vec<u8> {
                     CodecOp::ToI64(t) => capnp_op.set_to_i64(encoding_type_to_capnp(t)),
                     CodecOp::PushDataSection(section) => capnp_op.set_push_data_section(section as u64),
                     CodecOp::DictLookup(t) => capnp_op.set_dict_lookup(encoding_type_to_capnp(t)),
+                    CodecOp::UnorderedDictLookup(t) => capnp_op.set_unordered_dict_lookup(encoding_type_to_capnp(t)),
                     CodecOp::LZ4(t, decoded_length) => {
                         let mut lz4 = capnp_op.init_lz4();
                         lz4.set_type(encoding_type_to_capnp(t));
                         lz4.set_len_decoded(decoded_length as u64);
                     }
+                    CodecOp::RunLengthDecode(t, decoded_length) => {
+                        let mut rle = capnp_op.init_run_length_decode();
+                        rle.set_type(encoding_type_to_capnp(t));
+                        rle.set_len_decoded(decoded_length as u64);
+                    }
+                    CodecOp::UnpackBits(t, bits, decoded_length) => {
+                        let mut unpack = capnp_op.init_unpack_bits();
+                        unpack.set_type(encoding_type_to_capnp(t));
+                        unpack.set_bits(bits);
+                        unpack.set_len_decoded(decoded_length as u64);
+                    }
                     CodecOp::UnpackStrings => capnp_op.set_unpack_strings(()),
                     CodecOp::UnhexpackStrings(uppercase, total_bytes) => {
                         let mut uhps = capnp_op.init_unhexpack_strings();
                         uhps.set_uppercase(uppercase);
                         uhps.set_total_bytes(total_bytes as u64);
                     }
+                    CodecOp::UnpackFrontCodedStrings(total_bytes) => capnp_op.set_unpack_front_coded_strings(total_bytes as u64),
+                    CodecOp::UnpackSymbolCodedStrings(total_bytes) => capnp_op.set_unpack_symbol_coded_strings(total_bytes as u64),
+                    CodecOp::UnpackComponentCodedStrings(total_bytes) => capnp_op.set_unpack_component_coded_strings(total_bytes as u64),
+                    CodecOp::UnpackHybridStrings => capnp_op.set_unpack_hybrid_strings(()),
                     CodecOp::Unknown => panic!("Trying to serialize CodecOp::Unkown"),
                 }
             }
@@ -345,6 +462,26 @@ fn serialize_column(col: &Column) -> Vec<u8> {
                         populate_primitive_list(&mut builder, x);
                     }
                     DataSection::Null(count) => ds.set_null(*count as u64),
+                    DataSection::Mapped(MappedSection::U8(ref x)) => {
+                        let mut builder = ds.init_u8(x.len() as u32);
+                        populate_primitive_list(&mut builder, x);
+                    }
+                    DataSection::Mapped(MappedSection::U16(ref x)) => {
+                        let mut builder = ds.init_u16(x.len() as u32);
+                        populate_primitive_list(&mut builder, x);
+                    }
+                    DataSection::Mapped(MappedSection::U32(ref x)) => {
+                        let mut builder = ds.init_u32(x.len() as u32);
+                        populate_primitive_list(&mut builder, x);
+                    }
+                    DataSection::Mapped(MappedSection::U64(ref x)) => {
+                        let mut builder = ds.init_u64(x.len() as u32);
+                        populate_primitive_list(&mut builder, x);
+                    }
+                    DataSection::Mapped(MappedSection::I64(ref x)) => {
+                        let mut builder = ds.init_i64(x.len() as u32);
+                        populate_primitive_list(&mut builder, x);
+                    }
                 }
             }
         }
@@ -354,6 +491,35 @@ fn serialize_column(col: &Column) -> Vec<u8> {
     buffer
 }
 
+fn serialize_stats(mut builder: column_stats::Builder, stats: &ColumnStats) {
+    builder.set_null_count(stats.null_count as u64);
+    builder.set_distinct_count(stats.distinct_count as u64);
+    match stats.min {
+        None => builder.reborrow().init_min().set_none(()),
+        Some(ref min) => builder.reborrow().init_min().set_value(min),
+    }
+    match stats.max {
+        None => builder.reborrow().init_max().set_none(()),
+        Some(ref max) => builder.reborrow().init_max().set_value(max),
+    }
+    match stats.int_range {
+        None => builder.reborrow().init_int_range().set_none(()),
+        Some((min, max)) => {
+            let mut range = builder.reborrow().init_int_range().init_range();
+            range.set_start(min);
+            range.set_end(max);
+        }
+    }
+    match stats.encoding_estimate {
+        None => builder.reborrow().init_encoding_estimate().set_none(()),
+        Some(estimate) => {
+            let mut capnp_estimate = builder.reborrow().init_encoding_estimate().init_estimate();
+            capnp_estimate.set_dictionary_bytes(estimate.dictionary_bytes as u64);
+            capnp_estimate.set_packed_bytes(estimate.packed_bytes as u64);
+        }
+    }
+}
+
 fn encoding_type_to_capnp(t: Type) -> EncodingType {
     match t {
         Type::U8 => EncodingType::U8,
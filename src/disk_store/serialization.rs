@@ -0,0 +1,1297 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::fs::File;
+use std::mem;
+use std::path::Path;
+use std::str;
+use std::sync::{Arc, Mutex};
+use std::u32;
+
+use byteorder::{ByteOrder, LittleEndian};
+use memmap::Mmap;
+use num::PrimInt;
+use seahash;
+#[cfg(feature = "enable_zstd")]
+use zstd;
+#[cfg(feature = "enable_aes_gcm")]
+use rand::{OsRng, Rng};
+#[cfg(feature = "enable_aes_gcm")]
+use crypto::aead::{AeadDecryptor, AeadEncryptor};
+#[cfg(feature = "enable_aes_gcm")]
+use crypto::aes::KeySize;
+#[cfg(feature = "enable_aes_gcm")]
+use crypto::aes_gcm::AesGcm;
+
+use engine::types::EncodingType;
+use errors::QueryError;
+use mem_store::*;
+use mem_store::bloom_filter::BloomFilter;
+
+
+const MAGIC: &[u8; 4] = b"LCOL";
+const VERSION: u8 = 6;
+/// Magic, version and checksum. The checksum covers all bytes after the header.
+const HEADER_LEN: usize = 13;
+/// Bit in the flags byte of the column metadata that is set for case insensitive string columns.
+const FLAG_CASE_INSENSITIVE: u8 = 1;
+/// Bit in the flags byte of the column metadata that is set if the flags are followed by the code that represents null
+/// in the dictionary of the column.
+const FLAG_NULL_CODE: u8 = 2;
+/// Bit in the flags byte of the column metadata that is set if the column statistics follow the null code.
+const FLAG_STATS: u8 = 4;
+/// Bit in the flags byte of the column metadata that is set if the bloom filter of the column follows the stats.
+const FLAG_BLOOM_FILTER: u8 = 8;
+/// Data section payloads start at multiples of this offset so they can be used in place when memory mapped.
+const ALIGNMENT: usize = 8;
+const BLOCKED_MAGIC: &[u8; 4] = b"LBLK";
+const BLOCKED_VERSION: u8 = 4;
+#[cfg(feature = "enable_zstd")]
+const ZSTD_LEVEL: i32 = 3;
+const ENCRYPTED_MAGIC: &[u8; 4] = b"LENC";
+const ENCRYPTED_VERSION: u8 = 1;
+#[cfg(feature = "enable_aes_gcm")]
+const AES_GCM_NONCE_LEN: usize = 12;
+#[cfg(feature = "enable_aes_gcm")]
+const AES_GCM_TAG_LEN: usize = 16;
+
+/// Serializes a column into a self contained byte buffer that can be read back with `deserialize_column`.
+///
+/// Layout (all integers little endian):
+/// magic "LCOL", version byte, checksum, name, len, range, codec ops, flags, null code (if flagged),
+/// stats (if flagged), bloom filter (if flagged), data sections.
+/// Variable length fields are prefixed with their length as u64.
+/// The payload of each data section is zero padded to start at a multiple of `ALIGNMENT`.
+pub fn serialize_column(column: &Column) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(MAGIC);
+    buffer.push(VERSION);
+    write_u64(&mut buffer, 0);
+    write_column_metadata(&mut buffer, column);
+
+    write_u64(&mut buffer, column.data().len() as u64);
+    for section in column.data() {
+        serialize_data_section(&mut buffer, section);
+    }
+    write_checksum(&mut buffer);
+    buffer
+}
+
+/// Name, length, range, codec, flags, null code, stats and bloom filter of a column.
+fn write_column_metadata(buffer: &mut Vec<u8>, column: &Column) {
+    write_bytes(buffer, column.name().as_bytes());
+    write_u64(buffer, column.len() as u64);
+    match column.range() {
+        Some((start, end)) => {
+            buffer.push(1);
+            write_u64(buffer, start as u64);
+            write_u64(buffer, end as u64);
+        }
+        None => buffer.push(0),
+    }
+
+    let codec = column.codec();
+    write_u64(buffer, codec.ops().len() as u64);
+    for op in codec.ops() {
+        serialize_codec_op(buffer, op);
+    }
+    let mut flags = 0;
+    if column.is_case_insensitive() { flags |= FLAG_CASE_INSENSITIVE }
+    if column.null_code().is_some() { flags |= FLAG_NULL_CODE }
+    if column.stats().is_some() { flags |= FLAG_STATS }
+    if column.bloom_filter().is_some() { flags |= FLAG_BLOOM_FILTER }
+    buffer.push(flags);
+    if let Some(null_code) = column.null_code() {
+        write_u64(buffer, null_code as u64);
+    }
+    if let Some(stats) = column.stats() {
+        write_stats(buffer, stats);
+    }
+    if let Some(bloom_filter) = column.bloom_filter() {
+        write_u64(buffer, bloom_filter.num_hashes() as u64);
+        write_u64(buffer, bloom_filter.words().len() as u64);
+        for &word in bloom_filter.words() {
+            write_u64(buffer, word);
+        }
+    }
+}
+
+/// Row count and heap size are not stored since they follow from the column.
+fn write_stats(buffer: &mut Vec<u8>, stats: &ColumnStats) {
+    write_u64(buffer, stats.null_count as u64);
+    write_u64(buffer, stats.distinct_count as u64);
+    for value in &[&stats.min, &stats.max] {
+        match **value {
+            Some(ref value) => {
+                buffer.push(1);
+                write_bytes(buffer, value.as_bytes());
+            }
+            None => buffer.push(0),
+        }
+    }
+    match stats.int_range {
+        Some((min, max)) => {
+            buffer.push(1);
+            write_u64(buffer, min as u64);
+            write_u64(buffer, max as u64);
+        }
+        None => buffer.push(0),
+    }
+    match stats.encoding_estimate {
+        Some(estimate) => {
+            buffer.push(1);
+            write_u64(buffer, estimate.dictionary_bytes as u64);
+            write_u64(buffer, estimate.packed_bytes as u64);
+        }
+        None => buffer.push(0),
+    }
+}
+
+fn write_checksum(buffer: &mut [u8]) {
+    let checksum = seahash::hash(&buffer[HEADER_LEN..]);
+    LittleEndian::write_u64(&mut buffer[(HEADER_LEN - 8)..HEADER_LEN], checksum);
+}
+
+/// Checksum of the serialized representation of `column`, see `Column::checksum`.
+pub fn checksum(column: &Column) -> u64 {
+    LittleEndian::read_u64(&serialize_column(column)[(HEADER_LEN - 8)..HEADER_LEN])
+}
+
+/// Deserializes a column and verifies its checksum.
+pub fn deserialize_column(data: &[u8]) -> Result<Column, QueryError> {
+    let (metadata, sections) = read_column(data, None, true)?;
+    metadata.into_column(sections)
+}
+
+/// Like `deserialize_column` but skips verifying the checksum.
+pub fn deserialize_column_unverified(data: &[u8]) -> Result<Column, QueryError> {
+    let (metadata, sections) = read_column(data, None, false)?;
+    metadata.into_column(sections)
+}
+
+/// Loads a column written by `serialize_column` without copying its data sections onto the heap.
+/// Data sections borrow directly from a memory map of the file which is kept alive by the column.
+/// Verifying the checksum reads the entire file.
+pub fn load_mapped(path: &Path, verify_checksum: bool) -> Result<Column, QueryError> {
+    ensure!(cfg!(target_endian = "little"), "Memory mapped columns require a little endian platform");
+    let file = File::open(path)
+        .map_err(|err| QueryError::FatalError(format!("Failed to open {:?}: {}", path, err)))?;
+    let mmap = unsafe { Mmap::map(&file) }
+        .map_err(|err| QueryError::FatalError(format!("Failed to map {:?}: {}", path, err)))?;
+    let mmap = Arc::new(mmap);
+    let (metadata, sections) = read_column(&mmap, Some(&mmap), verify_checksum)?;
+    metadata.into_column(sections)
+}
+
+/// Compression applied to the blocks of a column serialized with `serialize_column_blocked`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CompressionCodec {
+    None,
+    /// Requires the `enable_lz4` feature.
+    Lz4,
+    /// Requires the `enable_zstd` feature.
+    Zstd,
+}
+
+/// Serializes a column with its data sections split into blocks of `block_len` elements that are compressed
+/// independently, so that reading a range of rows with `BlockedColumn` only decompresses the blocks it touches.
+///
+/// Layout (all integers little endian):
+/// magic "LBLK", version byte, checksum, compression, block length, name, len, range, codec ops, flags, null code (if
+/// flagged), stats (if flagged), block index, blocks.
+/// The block index holds the type, length and the compressed size of each block for every data section.
+/// Returns an error if `compression` is not supported by this build.
+pub fn serialize_column_blocked(column: &Column,
+                                compression: CompressionCodec,
+                                block_len: usize) -> Result<Vec<u8>, QueryError> {
+    ensure!(block_len > 0, "Block length must be positive");
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(BLOCKED_MAGIC);
+    buffer.push(BLOCKED_VERSION);
+    write_u64(&mut buffer, 0);
+    buffer.push(compression_tag(compression));
+    write_u64(&mut buffer, block_len as u64);
+    write_column_metadata(&mut buffer, column);
+
+    let mut blocks = Vec::new();
+    write_u64(&mut buffer, column.data().len() as u64);
+    for section in column.data() {
+        let t = section.encoding_type();
+        buffer.push(encoding_type_tag(t));
+        write_u64(&mut buffer, section.len() as u64);
+        let mut payload = Vec::new();
+        write_section_payload(&mut payload, section);
+        let block_bytes = block_len * element_size(t)?;
+        let chunks = if block_bytes == 0 { vec![] } else { payload.chunks(block_bytes).collect::<Vec<_>>() };
+        write_u64(&mut buffer, chunks.len() as u64);
+        for chunk in chunks {
+            let compressed = compress_block(compression, chunk)?;
+            write_u64(&mut buffer, compressed.len() as u64);
+            blocks.push(compressed);
+        }
+    }
+    for block in blocks {
+        buffer.extend_from_slice(&block);
+    }
+    write_checksum(&mut buffer);
+    Ok(buffer)
+}
+
+/// Reads a column written by `serialize_column_blocked` and verifies its checksum. Blocks are only decompressed when
+/// they are first accessed and are cached afterwards.
+pub fn deserialize_column_blocked(data: Vec<u8>) -> Result<BlockedColumn, QueryError> {
+    let (metadata, compression, block_len, sections) = {
+        let mut reader = Reader { data: &data, pos: 0 };
+        read_header(&mut reader, BLOCKED_MAGIC, BLOCKED_VERSION, true)?;
+        let compression = match reader.u8()? {
+            0 => CompressionCodec::None,
+            1 => CompressionCodec::Lz4,
+            2 => CompressionCodec::Zstd,
+            tag => bail!(QueryError::FatalError, "Invalid compression tag {}", tag),
+        };
+        let block_len = reader.u64()? as usize;
+        ensure!(block_len > 0, "Invalid block length 0");
+        let metadata = read_column_metadata(&mut reader)?;
+
+        let section_count = reader.u64()?;
+        ensure!(section_count > 0, "Column without data sections");
+        let mut sections = Vec::new();
+        for _ in 0..section_count {
+            let t = reader.encoding_type()?;
+            let len = reader.u64()? as usize;
+            let block_count = reader.u64()? as usize;
+            ensure!(block_count == if element_size(t)? == 0 { 0 } else { (len + block_len - 1) / block_len },
+                    "Invalid block count {} for data section of length {}", block_count, len);
+            let mut blocks = Vec::new();
+            for _ in 0..block_count {
+                blocks.push(reader.u64()? as usize);
+            }
+            sections.push(BlockedSection { t, len, blocks });
+        }
+        // Turn compressed sizes into offsets
+        for section in &mut sections {
+            for size in &mut section.blocks {
+                let offset = reader.pos;
+                reader.bytes(*size)?;
+                *size = offset;
+            }
+        }
+        ensure!(reader.pos == data.len(), "{} trailing bytes after column data", data.len() - reader.pos);
+        (metadata, compression, block_len, sections)
+    };
+    Ok(BlockedColumn { metadata, compression, block_len, sections, data, cache: Mutex::default() })
+}
+
+/// Column in the format written by `serialize_column_blocked` that decompresses blocks on demand.
+pub struct BlockedColumn {
+    metadata: ColumnMetadata,
+    compression: CompressionCodec,
+    block_len: usize,
+    sections: Vec<BlockedSection>,
+    data: Vec<u8>,
+    cache: Mutex<HashMap<(usize, usize), Arc<Vec<u8>>>>,
+}
+
+struct BlockedSection {
+    t: EncodingType,
+    len: usize,
+    /// Offset of each block in the serialized column, blocks end where the next block starts.
+    blocks: Vec<usize>,
+}
+
+impl BlockedColumn {
+    pub fn name(&self) -> &str { &self.metadata.name }
+    pub fn len(&self) -> usize { self.metadata.len }
+    pub fn compression(&self) -> CompressionCodec { self.compression }
+
+    /// Number of blocks that have been decompressed so far.
+    pub fn decompressed_blocks(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    /// Decompresses all blocks into a regular column.
+    pub fn to_column(&self) -> Result<Column, QueryError> {
+        let mut sections = Vec::with_capacity(self.sections.len());
+        for (index, section) in self.sections.iter().enumerate() {
+            sections.push(self.section_range(index, 0, section.len)?);
+        }
+        self.metadata.clone().into_column(sections)
+    }
+
+    /// Elements `start..(start + count)` of the data section with index `section`.
+    pub fn section_range(&self, section: usize, start: usize, count: usize) -> Result<DataSection, QueryError> {
+        ensure!(section < self.sections.len(), "Column {} has no data section {}", self.metadata.name, section);
+        let s = &self.sections[section];
+        let end = start.saturating_add(count);
+        ensure!(end <= s.len, "Range {}..{} out of bounds for data section of length {}", start, end, s.len);
+        let size = element_size(s.t)?;
+        let mut bytes = Vec::with_capacity(count * size);
+        if size > 0 && count > 0 {
+            for block in (start / self.block_len)..((end - 1) / self.block_len + 1) {
+                let block_start = block * self.block_len;
+                let data = self.block(section, block)?;
+                let from = cmp::max(start, block_start) - block_start;
+                let to = cmp::min(end, block_start + self.block_len) - block_start;
+                bytes.extend_from_slice(&data[(from * size)..(to * size)]);
+            }
+        }
+        Ok(section_from_payload(s.t, count, &bytes))
+    }
+
+    /// Decodes the rows `start..(start + len)` of a string column. Dictionary encoded columns only decompress the
+    /// blocks of the codes in this range and the dictionary, other columns are decompressed in full.
+    pub fn decode_strings_range(&self, start: usize, len: usize) -> Result<Option<Vec<Option<String>>>, QueryError> {
+        let start = cmp::min(start, self.metadata.len);
+        let end = cmp::min(start.saturating_add(len), self.metadata.len);
+        let codec = &self.metadata.codec;
+        let plain_dictionary = codec.len() == 3 && strings::is_dictionary_lookup(&codec[2]);
+        if !plain_dictionary {
+            return Ok(self.to_column()?.decode_strings_range(start, len));
+        }
+        let sections = vec![
+            self.section_range(0, start, end - start)?,
+            self.section_range(1, 0, self.sections[1].len)?,
+            self.section_range(2, 0, self.sections[2].len)?,
+        ];
+        let metadata = ColumnMetadata { len: end - start, stats: None, ..self.metadata.clone() };
+        Ok(metadata.into_column(sections)?.decode_strings())
+    }
+
+    fn block(&self, section: usize, block: usize) -> Result<Arc<Vec<u8>>, QueryError> {
+        if let Some(data) = self.cache.lock().unwrap().get(&(section, block)) {
+            return Ok(data.clone());
+        }
+        let s = &self.sections[section];
+        let start = s.blocks[block];
+        let end = match s.blocks.get(block + 1) {
+            Some(&next) => next,
+            None => self.sections[(section + 1)..].iter()
+                .filter_map(|s| s.blocks.first().cloned())
+                .next()
+                .unwrap_or_else(|| self.data.len()),
+        };
+        let decoded_len = cmp::min(self.block_len, s.len - block * self.block_len) * element_size(s.t)?;
+        let data = Arc::new(decompress_block(self.compression, &self.data[start..end], decoded_len)?);
+        self.cache.lock().unwrap().insert((section, block), data.clone());
+        Ok(data)
+    }
+}
+
+fn compression_tag(compression: CompressionCodec) -> u8 {
+    match compression {
+        CompressionCodec::None => 0,
+        CompressionCodec::Lz4 => 1,
+        CompressionCodec::Zstd => 2,
+    }
+}
+
+fn compress_block(compression: CompressionCodec, data: &[u8]) -> Result<Vec<u8>, QueryError> {
+    match compression {
+        CompressionCodec::None => Ok(data.to_vec()),
+        #[cfg(feature = "enable_lz4")]
+        CompressionCodec::Lz4 => Ok(unsafe { lz4::encode(data) }),
+        #[cfg(feature = "enable_zstd")]
+        CompressionCodec::Zstd => zstd::encode_all(data, ZSTD_LEVEL)
+            .map_err(|err| QueryError::FatalError(format!("Failed to compress block: {}", err))),
+        #[allow(unreachable_patterns)]
+        _ => bail!(QueryError::NotImplemented, "{:?} compression requires recompiling with the corresponding feature", compression),
+    }
+}
+
+fn decompress_block(compression: CompressionCodec, data: &[u8], decoded_len: usize) -> Result<Vec<u8>, QueryError> {
+    let decoded = match compression {
+        CompressionCodec::None => data.to_vec(),
+        #[cfg(feature = "enable_lz4")]
+        CompressionCodec::Lz4 => {
+            let mut decoded = vec![0u8; decoded_len];
+            let len = unsafe { lz4::decode(&mut lz4::decoder(data), &mut decoded) };
+            decoded.truncate(len);
+            decoded
+        }
+        #[cfg(feature = "enable_zstd")]
+        CompressionCodec::Zstd => zstd::decode_all(data)
+            .map_err(|err| QueryError::FatalError(format!("Failed to decompress block: {}", err)))?,
+        #[allow(unreachable_patterns)]
+        _ => bail!(QueryError::NotImplemented, "{:?} compression requires recompiling with the corresponding feature", compression),
+    };
+    ensure!(decoded.len() == decoded_len, "Block decompressed to {} bytes, expected {}", decoded.len(), decoded_len);
+    Ok(decoded)
+}
+
+/// Encrypts and decrypts serialized columns, see `serialize_column_encrypted`.
+pub trait EncryptionProvider {
+    /// Identifies the provider and its key. Stored in plaintext so that a column is never decrypted by the wrong
+    /// provider.
+    fn id(&self) -> &str;
+    fn encrypt(&self, plaintext: &[u8]) -> Vec<u8>;
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, QueryError>;
+}
+
+/// Provider that leaves the data unchanged, for testing.
+pub struct IdentityEncryption;
+
+impl EncryptionProvider for IdentityEncryption {
+    fn id(&self) -> &str { "identity" }
+    fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> { plaintext.to_vec() }
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, QueryError> { Ok(ciphertext.to_vec()) }
+}
+
+/// AES-256-GCM with a random nonce per column. The provider id is authenticated along with the ciphertext.
+#[cfg(feature = "enable_aes_gcm")]
+pub struct AesGcmEncryption {
+    id: String,
+    key: [u8; 32],
+    rng: Mutex<OsRng>,
+}
+
+#[cfg(feature = "enable_aes_gcm")]
+impl AesGcmEncryption {
+    /// `key_id` should be different for every key.
+    /// Returns an error if the random number generator of the operating system is not available.
+    pub fn new(key_id: &str, key: [u8; 32]) -> Result<AesGcmEncryption, QueryError> {
+        let rng = OsRng::new()
+            .map_err(|err| QueryError::FatalError(format!("Failed to open OS random number generator: {}", err)))?;
+        Ok(AesGcmEncryption {
+            id: format!("aes-256-gcm:{}", key_id),
+            key,
+            rng: Mutex::new(rng),
+        })
+    }
+
+    /// Nonces are drawn from the random number generator of the operating system.
+    fn nonce(&self) -> [u8; AES_GCM_NONCE_LEN] {
+        let mut nonce = [0; AES_GCM_NONCE_LEN];
+        self.rng.lock().unwrap().fill_bytes(&mut nonce);
+        nonce
+    }
+}
+
+#[cfg(feature = "enable_aes_gcm")]
+impl EncryptionProvider for AesGcmEncryption {
+    fn id(&self) -> &str { &self.id }
+
+    /// Returns nonce, ciphertext and authentication tag.
+    fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = self.nonce();
+        let mut output = vec![0; AES_GCM_NONCE_LEN + plaintext.len() + AES_GCM_TAG_LEN];
+        output[..AES_GCM_NONCE_LEN].copy_from_slice(&nonce);
+        {
+            let (ciphertext, tag) = output[AES_GCM_NONCE_LEN..].split_at_mut(plaintext.len());
+            AesGcm::new(KeySize::KeySize256, &self.key, &nonce, self.id.as_bytes()).encrypt(plaintext, ciphertext, tag);
+        }
+        output
+    }
+
+    fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, QueryError> {
+        ensure!(ciphertext.len() >= AES_GCM_NONCE_LEN + AES_GCM_TAG_LEN, "Encrypted column data is too short");
+        let (nonce, rest) = ciphertext.split_at(AES_GCM_NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - AES_GCM_TAG_LEN);
+        let mut plaintext = vec![0; ciphertext.len()];
+        let authentic = AesGcm::new(KeySize::KeySize256, &self.key, nonce, self.id.as_bytes())
+            .decrypt(ciphertext, &mut plaintext, tag);
+        ensure!(authentic, "Failed to decrypt column with provider {}", self.id);
+        Ok(plaintext)
+    }
+}
+
+/// Serializes a column with `serialize_column` and encrypts the result with `provider`.
+///
+/// Layout: magic "LENC", version byte, checksum, provider id, encrypted column.
+/// The header stays in plaintext and the checksum covers the ciphertext, so corruption is detected without the key.
+pub fn serialize_column_encrypted(column: &Column, provider: &EncryptionProvider) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    buffer.extend_from_slice(ENCRYPTED_MAGIC);
+    buffer.push(ENCRYPTED_VERSION);
+    write_u64(&mut buffer, 0);
+    write_bytes(&mut buffer, provider.id().as_bytes());
+    buffer.extend(provider.encrypt(&serialize_column(column)));
+    write_checksum(&mut buffer);
+    buffer
+}
+
+/// Verifies the checksum of a column written by `serialize_column_encrypted`, decrypts and deserializes it.
+/// Fails if the column was encrypted by a provider with a different id.
+pub fn deserialize_column_encrypted(data: &[u8], provider: &EncryptionProvider) -> Result<Column, QueryError> {
+    let mut reader = Reader { data, pos: 0 };
+    read_header(&mut reader, ENCRYPTED_MAGIC, ENCRYPTED_VERSION, true)?;
+    let id = reader.length_prefixed()?;
+    ensure!(id == provider.id().as_bytes(),
+            "Column is encrypted by provider {:?}, not {:?}", String::from_utf8_lossy(id), provider.id());
+    let plaintext = provider.decrypt(&data[reader.pos..])?;
+    deserialize_column(&plaintext)
+}
+
+/// Everything `write_column_metadata` stores about a column except for its data sections.
+#[derive(Clone)]
+struct ColumnMetadata {
+    name: String,
+    len: usize,
+    range: Option<(i64, i64)>,
+    codec: Vec<CodecOp>,
+    case_insensitive: bool,
+    null_code: Option<u32>,
+    stats: Option<ColumnStats>,
+    bloom_filter: Option<BloomFilter>,
+}
+
+impl ColumnMetadata {
+    /// Fails if the data sections of a string column are malformed, which the checksum only catches when verified.
+    fn into_column(self, sections: Vec<DataSection>) -> Result<Column, QueryError> {
+        let mut column = Column::new(&self.name, self.len, self.range, self.codec, sections);
+        column.set_case_insensitive(self.case_insensitive);
+        column.set_null_code(self.null_code);
+        if let Some(mut stats) = self.stats {
+            stats.row_count = column.len();
+            stats.heap_bytes = column.data_heap_size();
+            column.set_stats(stats);
+        }
+        if let Some(bloom_filter) = self.bloom_filter {
+            column.set_bloom_filter(bloom_filter);
+        }
+        strings::validate_string_column(&column)?;
+        Ok(column)
+    }
+}
+
+/// Data sections refer to `mmap` instead of being copied if it is set, in which case `data` has to be `mmap`.
+fn read_column(data: &[u8],
+               mmap: Option<&Arc<Mmap>>,
+               verify_checksum: bool) -> Result<(ColumnMetadata, Vec<DataSection>), QueryError> {
+    ensure!(!data.starts_with(ENCRYPTED_MAGIC), "Column is encrypted, use `deserialize_column_encrypted`");
+    let mut reader = Reader { data, pos: 0 };
+    read_header(&mut reader, MAGIC, VERSION, verify_checksum)?;
+    let metadata = read_column_metadata(&mut reader)?;
+
+    let section_count = reader.u64()?;
+    ensure!(section_count > 0, "Column without data sections");
+    let mut sections = Vec::new();
+    for _ in 0..section_count {
+        sections.push(deserialize_data_section(&mut reader, mmap)?);
+    }
+    ensure!(reader.pos == data.len(), "{} trailing bytes after column data", data.len() - reader.pos);
+
+    Ok((metadata, sections))
+}
+
+/// Checks magic and version and verifies the checksum of the remaining bytes.
+fn read_header(reader: &mut Reader, magic: &[u8], version: u8, verify_checksum: bool) -> Result<(), QueryError> {
+    ensure!(reader.bytes(magic.len())? == magic, "Invalid column header");
+    let actual_version = reader.u8()?;
+    ensure!(actual_version == version, "Unsupported column format version {}", actual_version);
+    let checksum = reader.u64()?;
+    if verify_checksum {
+        let data = reader.data;
+        let actual = seahash::hash(&data[HEADER_LEN..]);
+        if actual != checksum {
+            // The name might be corrupted as well
+            let name = Reader { data, pos: HEADER_LEN }.length_prefixed()
+                .map(|name| String::from_utf8_lossy(name).to_string())
+                .unwrap_or_else(|_| "<unknown>".to_string());
+            bail!(QueryError::FatalError, "Checksum mismatch for column {}: expected {:x}, found {:x}", name, checksum, actual);
+        }
+    }
+    Ok(())
+}
+
+fn read_column_metadata(reader: &mut Reader) -> Result<ColumnMetadata, QueryError> {
+    let name = str::from_utf8(reader.length_prefixed()?)
+        .map_err(|_| QueryError::FatalError("Column name is not valid UTF-8".to_string()))?
+        .to_string();
+    let len = reader.u64()? as usize;
+    let range = match reader.u8()? {
+        0 => None,
+        1 => Some((reader.u64()? as i64, reader.u64()? as i64)),
+        tag => bail!(QueryError::FatalError, "Invalid range tag {}", tag),
+    };
+
+    let op_count = reader.u64()?;
+    let mut codec = Vec::new();
+    for _ in 0..op_count {
+        codec.push(deserialize_codec_op(reader)?);
+    }
+    let flags = reader.u8()?;
+    ensure!(flags & !(FLAG_CASE_INSENSITIVE | FLAG_NULL_CODE | FLAG_STATS | FLAG_BLOOM_FILTER) == 0,
+            "Invalid column flags {:x}", flags);
+    let null_code = if flags & FLAG_NULL_CODE != 0 { Some(reader.u64()? as u32) } else { None };
+    let stats = if flags & FLAG_STATS != 0 { Some(read_stats(reader, len)?) } else { None };
+    let bloom_filter = if flags & FLAG_BLOOM_FILTER != 0 { Some(read_bloom_filter(reader)?) } else { None };
+    let case_insensitive = flags & FLAG_CASE_INSENSITIVE != 0;
+    Ok(ColumnMetadata { name, len, range, codec, case_insensitive, null_code, stats, bloom_filter })
+}
+
+fn read_bloom_filter(reader: &mut Reader) -> Result<BloomFilter, QueryError> {
+    let num_hashes = reader.u64()?;
+    ensure!(num_hashes > 0 && num_hashes <= u64::from(u32::MAX), "Invalid bloom filter hash count {}", num_hashes);
+    let word_count = reader.u64()? as usize;
+    ensure!(word_count > 0 && word_count <= (reader.data.len() - reader.pos) / 8,
+            "Invalid bloom filter size of {} words", word_count);
+    let mut words = Vec::with_capacity(word_count);
+    for _ in 0..word_count {
+        words.push(reader.u64()?);
+    }
+    Ok(BloomFilter::from_words(words, num_hashes as u32))
+}
+
+fn read_stats(reader: &mut Reader, row_count: usize) -> Result<ColumnStats, QueryError> {
+    let null_count = reader.u64()? as usize;
+    let distinct_count = reader.u64()? as usize;
+    let mut min_max = Vec::with_capacity(2);
+    for _ in 0..2 {
+        min_max.push(match reader.u8()? {
+            0 => None,
+            1 => Some(str::from_utf8(reader.length_prefixed()?)
+                .map_err(|_| QueryError::FatalError("Column stats are not valid UTF-8".to_string()))?
+                .to_string()),
+            tag => bail!(QueryError::FatalError, "Invalid min/max tag {}", tag),
+        });
+    }
+    let int_range = match reader.u8()? {
+        0 => None,
+        1 => Some((reader.u64()? as i64, reader.u64()? as i64)),
+        tag => bail!(QueryError::FatalError, "Invalid integer range tag {}", tag),
+    };
+    let encoding_estimate = match reader.u8()? {
+        0 => None,
+        1 => Some(EncodingEstimate { dictionary_bytes: reader.u64()? as usize, packed_bytes: reader.u64()? as usize }),
+        tag => bail!(QueryError::FatalError, "Invalid encoding estimate tag {}", tag),
+    };
+    ensure!(null_count <= row_count, "Null count {} exceeds row count {}", null_count, row_count);
+    let max = min_max.pop().unwrap();
+    let min = min_max.pop().unwrap();
+    Ok(ColumnStats { row_count, null_count, distinct_count, min, max, int_range, heap_bytes: 0, encoding_estimate })
+}
+
+fn serialize_codec_op(buffer: &mut Vec<u8>, op: &CodecOp) {
+    match *op {
+        CodecOp::Add(t, amount) => {
+            buffer.push(0);
+            buffer.push(encoding_type_tag(t));
+            write_u64(buffer, amount as u64);
+        }
+        CodecOp::Delta(t) => {
+            buffer.push(1);
+            buffer.push(encoding_type_tag(t));
+        }
+        CodecOp::ToI64(t) => {
+            buffer.push(2);
+            buffer.push(encoding_type_tag(t));
+        }
+        CodecOp::PushDataSection(section) => {
+            buffer.push(3);
+            write_u64(buffer, section as u64);
+        }
+        CodecOp::DictLookup(t) => {
+            buffer.push(4);
+            buffer.push(encoding_type_tag(t));
+        }
+        CodecOp::UnorderedDictLookup(t) => {
+            buffer.push(5);
+            buffer.push(encoding_type_tag(t));
+        }
+        CodecOp::LZ4(t, len_decoded) => {
+            buffer.push(6);
+            buffer.push(encoding_type_tag(t));
+            write_u64(buffer, len_decoded as u64);
+        }
+        CodecOp::UnpackStrings => buffer.push(7),
+        CodecOp::UnhexpackStrings(uppercase, total_bytes) => {
+            buffer.push(8);
+            buffer.push(uppercase as u8);
+            write_u64(buffer, total_bytes as u64);
+        }
+        CodecOp::UnpackFrontCodedStrings(total_bytes) => {
+            buffer.push(9);
+            write_u64(buffer, total_bytes as u64);
+        }
+        CodecOp::UnpackSymbolCodedStrings(total_bytes) => {
+            buffer.push(10);
+            write_u64(buffer, total_bytes as u64);
+        }
+        CodecOp::RunLengthDecode(t, len_decoded) => {
+            buffer.push(11);
+            buffer.push(encoding_type_tag(t));
+            write_u64(buffer, len_decoded as u64);
+        }
+        CodecOp::UnpackBits(t, bits, len_decoded) => {
+            buffer.push(12);
+            buffer.push(encoding_type_tag(t));
+            buffer.push(bits);
+            write_u64(buffer, len_decoded as u64);
+        }
+        CodecOp::UnpackComponentCodedStrings(total_bytes) => {
+            buffer.push(13);
+            write_u64(buffer, total_bytes as u64);
+        }
+        CodecOp::UnpackHybridStrings => buffer.push(14),
+        CodecOp::Unknown => panic!("Trying to serialize CodecOp::Unknown"),
+    }
+}
+
+fn deserialize_codec_op(reader: &mut Reader) -> Result<CodecOp, QueryError> {
+    Ok(match reader.u8()? {
+        0 => {
+            let t = reader.encoding_type()?;
+            CodecOp::Add(t, reader.u64()? as i64)
+        }
+        1 => CodecOp::Delta(reader.encoding_type()?),
+        2 => CodecOp::ToI64(reader.encoding_type()?),
+        3 => CodecOp::PushDataSection(reader.u64()? as usize),
+        4 => CodecOp::DictLookup(reader.encoding_type()?),
+        5 => CodecOp::UnorderedDictLookup(reader.encoding_type()?),
+        6 => {
+            let t = reader.encoding_type()?;
+            CodecOp::LZ4(t, reader.u64()? as usize)
+        }
+        7 => CodecOp::UnpackStrings,
+        8 => {
+            let uppercase = reader.u8()? != 0;
+            CodecOp::UnhexpackStrings(uppercase, reader.u64()? as usize)
+        }
+        9 => CodecOp::UnpackFrontCodedStrings(reader.u64()? as usize),
+        10 => CodecOp::UnpackSymbolCodedStrings(reader.u64()? as usize),
+        11 => {
+            let t = reader.encoding_type()?;
+            CodecOp::RunLengthDecode(t, reader.u64()? as usize)
+        }
+        12 => {
+            let t = reader.encoding_type()?;
+            let bits = reader.u8()?;
+            CodecOp::UnpackBits(t, bits, reader.u64()? as usize)
+        }
+        13 => CodecOp::UnpackComponentCodedStrings(reader.u64()? as usize),
+        14 => CodecOp::UnpackHybridStrings,
+        tag => bail!(QueryError::FatalError, "Invalid codec op tag {}", tag),
+    })
+}
+
+fn serialize_data_section(buffer: &mut Vec<u8>, section: &DataSection) {
+    buffer.push(encoding_type_tag(section.encoding_type()));
+    write_u64(buffer, section.len() as u64);
+    while buffer.len() % ALIGNMENT != 0 {
+        buffer.push(0);
+    }
+    write_section_payload(buffer, section);
+}
+
+/// Appends the elements of `section` in little endian byte order.
+fn write_section_payload(buffer: &mut Vec<u8>, section: &DataSection) {
+    match *section {
+        DataSection::U8(ref data) => buffer.extend_from_slice(data),
+        DataSection::U16(ref data) => write_u16s(buffer, data),
+        DataSection::U32(ref data) => write_u32s(buffer, data),
+        DataSection::U64(ref data) => for &x in data { write_u64(buffer, x) },
+        DataSection::I64(ref data) => for &x in data { write_u64(buffer, x as u64) },
+        DataSection::Null(_) => {}
+        DataSection::Mapped(MappedSection::U8(ref data)) => buffer.extend_from_slice(data),
+        DataSection::Mapped(MappedSection::U16(ref data)) => write_u16s(buffer, data),
+        DataSection::Mapped(MappedSection::U32(ref data)) => write_u32s(buffer, data),
+        DataSection::Mapped(MappedSection::U64(ref data)) => for &x in data.iter() { write_u64(buffer, x) },
+        DataSection::Mapped(MappedSection::I64(ref data)) => for &x in data.iter() { write_u64(buffer, x as u64) },
+    }
+}
+
+fn deserialize_data_section(reader: &mut Reader, mmap: Option<&Arc<Mmap>>) -> Result<DataSection, QueryError> {
+    let t = reader.encoding_type()?;
+    let len = reader.u64()? as usize;
+    reader.align()?;
+    if let Some(mmap) = mmap {
+        return Ok(match t {
+            EncodingType::U8 => DataSection::Mapped(MappedSection::U8(reader.mapped_slice(mmap, len)?)),
+            EncodingType::U16 => DataSection::Mapped(MappedSection::U16(reader.mapped_slice(mmap, len)?)),
+            EncodingType::U32 => DataSection::Mapped(MappedSection::U32(reader.mapped_slice(mmap, len)?)),
+            EncodingType::U64 => DataSection::Mapped(MappedSection::U64(reader.mapped_slice(mmap, len)?)),
+            EncodingType::I64 => DataSection::Mapped(MappedSection::I64(reader.mapped_slice(mmap, len)?)),
+            EncodingType::Null => DataSection::Null(len),
+            t => bail!(QueryError::FatalError, "Invalid data section type {:?}", t),
+        });
+    }
+    let bytes = reader.bytes(len.saturating_mul(element_size(t)?))?;
+    Ok(section_from_payload(t, len, bytes))
+}
+
+/// Size in bytes of each element of a data section of type `t`.
+fn element_size(t: EncodingType) -> Result<usize, QueryError> {
+    Ok(match t {
+        EncodingType::U8 => 1,
+        EncodingType::U16 => 2,
+        EncodingType::U32 => 4,
+        EncodingType::U64 | EncodingType::I64 => 8,
+        EncodingType::Null => 0,
+        t => bail!(QueryError::FatalError, "Invalid data section type {:?}", t),
+    })
+}
+
+/// Inverse of `write_section_payload`, `bytes` has to hold exactly `len` elements of type `t`.
+fn section_from_payload(t: EncodingType, len: usize, bytes: &[u8]) -> DataSection {
+    match t {
+        EncodingType::U8 => DataSection::U8(bytes.to_vec()),
+        EncodingType::U16 => DataSection::U16(bytes.chunks(2).map(LittleEndian::read_u16).collect()),
+        EncodingType::U32 => DataSection::U32(bytes.chunks(4).map(LittleEndian::read_u32).collect()),
+        EncodingType::U64 => DataSection::U64(bytes.chunks(8).map(LittleEndian::read_u64).collect()),
+        EncodingType::I64 => DataSection::I64(bytes.chunks(8).map(LittleEndian::read_i64).collect()),
+        EncodingType::Null => DataSection::Null(len),
+        t => panic!("Invalid data section type {:?}", t),
+    }
+}
+
+fn encoding_type_tag(t: EncodingType) -> u8 {
+    match t {
+        EncodingType::U8 => 0,
+        EncodingType::U16 => 1,
+        EncodingType::U32 => 2,
+        EncodingType::U64 => 3,
+        EncodingType::I64 => 4,
+        EncodingType::Null => 5,
+        t => panic!("Unexpected encoding type {:?} in column", t),
+    }
+}
+
+fn write_u64(buffer: &mut Vec<u8>, x: u64) {
+    let mut bytes = [0; 8];
+    LittleEndian::write_u64(&mut bytes, x);
+    buffer.extend_from_slice(&bytes);
+}
+
+fn write_u16s(buffer: &mut Vec<u8>, data: &[u16]) {
+    for &x in data {
+        let mut bytes = [0; 2];
+        LittleEndian::write_u16(&mut bytes, x);
+        buffer.extend_from_slice(&bytes);
+    }
+}
+
+fn write_u32s(buffer: &mut Vec<u8>, data: &[u32]) {
+    for &x in data {
+        let mut bytes = [0; 4];
+        LittleEndian::write_u32(&mut bytes, x);
+        buffer.extend_from_slice(&bytes);
+    }
+}
+
+fn write_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    write_u64(buffer, bytes.len() as u64);
+    buffer.extend_from_slice(bytes);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], QueryError> {
+        ensure!(len <= self.data.len() - self.pos,
+                "Unexpected end of column data at byte {} (need {} more bytes)", self.pos, len);
+        let bytes = &self.data[self.pos..(self.pos + len)];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn align(&mut self) -> Result<(), QueryError> {
+        let padding = (ALIGNMENT - self.pos % ALIGNMENT) % ALIGNMENT;
+        self.bytes(padding)?;
+        Ok(())
+    }
+
+    /// Refers to the next `len` elements of `mmap`, which has to be the buffer that is being read.
+    fn mapped_slice<T: PrimInt>(&mut self, mmap: &Arc<Mmap>, len: usize) -> Result<MappedSlice<T>, QueryError> {
+        let offset = self.pos;
+        self.bytes(len.saturating_mul(mem::size_of::<T>()))?;
+        MappedSlice::new(mmap.clone(), offset, len)
+    }
+
+    fn length_prefixed(&mut self) -> Result<&'a [u8], QueryError> {
+        let len = self.u64()? as usize;
+        self.bytes(len)
+    }
+
+    fn u8(&mut self) -> Result<u8, QueryError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, QueryError> {
+        Ok(LittleEndian::read_u64(self.bytes(8)?))
+    }
+
+    fn encoding_type(&mut self) -> Result<EncodingType, QueryError> {
+        Ok(match self.u8()? {
+            0 => EncodingType::U8,
+            1 => EncodingType::U16,
+            2 => EncodingType::U32,
+            3 => EncodingType::U64,
+            4 => EncodingType::I64,
+            5 => EncodingType::Null,
+            tag => bail!(QueryError::FatalError, "Invalid encoding type tag {}", tag),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mem_store::column_builder::*;
+    use mem_store::strings::*;
+    use heapsize::HeapSizeOf;
+    use stringpack::PackedStrings;
+    use std::io::Write;
+    use tempdir::TempDir;
+
+    fn round_trip(column: &Column) -> Column {
+        let serialized = serialize_column(column);
+        let deserialized = deserialize_column(&serialized).unwrap();
+        assert_eq!(deserialized.name(), column.name());
+        assert_eq!(deserialized.len(), column.len());
+        assert_eq!(deserialized.range(), column.range());
+        assert_eq!(deserialized.codec().ops(), column.codec().ops());
+        assert_eq!(deserialized.data(), column.data());
+        assert_eq!(deserialized.is_case_insensitive(), column.is_case_insensitive());
+        assert_eq!(deserialized.null_code(), column.null_code());
+        deserialized
+    }
+
+    /// Dictionary encoded column with 1000 rows and 300 distinct values.
+    fn test_column() -> Arc<Column> {
+        let mut builder = StringColBuilder::new();
+        for i in 0..1000 {
+            builder.push(&format!("value{}", i % 300));
+        }
+        builder.finalize("dict")
+    }
+
+    #[test]
+    fn test_dictionary_round_trip() {
+        let column = test_column();
+        assert_eq!(column.encoding_type(), EncodingType::U16);
+        let deserialized = round_trip(&column);
+        assert_eq!(deserialized.group_count(), column.group_count());
+    }
+
+    #[test]
+    fn test_case_insensitive_round_trip() {
+        let mut builder = StringColBuilder::with_options(StringColumnOptions::default().with_case_insensitive(true));
+        for value in &["Gmail.com", "gmail.com", "yahoo.com"] {
+            builder.push(value);
+        }
+        let column = builder.finalize("domain");
+        assert!(column.is_case_insensitive());
+        let deserialized = round_trip(&column);
+        assert_eq!(deserialized.filter_eq_str(Some("GMAIL.COM"), false), Some(vec![1, 1, 0]));
+
+        let blocked = deserialize_column_blocked(serialize_column_blocked(&column, CompressionCodec::None, 2).unwrap());
+        assert!(blocked.unwrap().to_column().unwrap().is_case_insensitive());
+    }
+
+    #[test]
+    fn test_null_code_round_trip() {
+        let mut builder = StringColBuilder::new();
+        for value in &[Some("a"), None, Some(""), None] {
+            builder.push_opt(*value);
+        }
+        let column = builder.finalize("nullable");
+        assert!(column.null_code().is_some());
+        let deserialized = round_trip(&column);
+        assert_eq!(deserialized.decode_strings(), Some(vec![Some("a".to_string()), None, Some("".to_string()), None]));
+        assert_eq!(deserialized.null_count(), 2);
+    }
+
+    #[test]
+    fn test_stats_round_trip() {
+        let dir = TempDir::new("test_stats_round_trip").unwrap();
+        let values = [Some("b"), None, Some(""), None, Some("a")];
+        let mut integers = IntColBuilder::new();
+        for i in 0..100 {
+            integers.push(&(i * 3 - 50));
+        }
+        let columns = vec![
+            build_string_column_from_strs("dict", &values, &StringColumnOptions::default()),
+            build_string_column_from_strs(
+                "packed", &values, &StringColumnOptions::default().with_max_unique_strings(0)),
+            integers.finalize("int"),
+            Arc::new(Column::null("null", 42)),
+        ];
+        for column in &columns {
+            let expected = ColumnStats { heap_bytes: 0, ..column.stats().unwrap().clone() };
+            let path = dir.path().join(column.name());
+            File::create(&path).unwrap().write_all(&serialize_column(column)).unwrap();
+            let loaded = vec![round_trip(column), load_mapped(&path, true).unwrap()];
+            for loaded in &loaded {
+                assert_eq!(loaded.null_count(), column.null_count());
+                assert_eq!(loaded.stats().map(|stats| ColumnStats { heap_bytes: 0, ..stats.clone() }),
+                           Some(expected.clone()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bloom_filter_round_trip() {
+        let dir = TempDir::new("test_bloom_filter_round_trip").unwrap();
+        let values = (0..1000).map(|i| format!("value{}", i)).collect::<Vec<_>>();
+        let strs = values.iter().map(|s| Some(s.as_str())).collect::<Vec<_>>();
+        let options = StringColumnOptions::default().with_bloom_filter(10, 7);
+        for column in &[build_string_column_from_strs("dict", &strs, &options),
+                        build_string_column_from_strs("packed", &strs, &options.with_max_unique_strings(0))] {
+            assert!(column.bloom_filter().is_some());
+            let path = dir.path().join(column.name());
+            File::create(&path).unwrap().write_all(&serialize_column(column)).unwrap();
+            for loaded in &[round_trip(column), load_mapped(&path, true).unwrap()] {
+                assert_eq!(loaded.bloom_filter(), column.bloom_filter());
+                assert!(strs.iter().all(|s| loaded.might_contain(s.unwrap())));
+                let false_positives = (1000..11_000).filter(|i| loaded.might_contain(&format!("value{}", i))).count();
+                assert!(false_positives < 300, "{}", false_positives);
+            }
+        }
+        let without_filter = build_string_column_from_strs("plain", &strs, &StringColumnOptions::default());
+        assert!(round_trip(&without_filter).bloom_filter().is_none());
+    }
+
+    #[test]
+    fn test_packed_round_trip() {
+        let values = (0..1000).map(|i| format!("unique{}", i * 7919 % 1000)).collect::<Vec<_>>();
+        let column = fast_build_string_column(
+            "packed", values.iter().map(|s| s.as_str()), values.len(), false, false, 0,
+            &StringColumnOptions::default().with_max_unique_strings(10));
+        assert!(column.codec().ops().contains(&CodecOp::UnpackStrings), "{:?}", column);
+        let deserialized = round_trip(&column);
+        assert_eq!(deserialized.distinct_values(), column.distinct_values());
+    }
+
+    #[test]
+    fn test_integer_and_null_round_trip() {
+        round_trip(&Column::new("int", 3, Some((-5, 1000)), vec![], vec![DataSection::I64(vec![-5, 0, 1000])]));
+        round_trip(&Column::null("null", 42));
+    }
+
+    #[test]
+    fn test_load_mapped() {
+        let column = test_column();
+
+        let dir = TempDir::new("test_load_mapped").unwrap();
+        let path = dir.path().join("dict.col");
+        File::create(&path).unwrap().write_all(&serialize_column(&column)).unwrap();
+        let mapped = load_mapped(&path, true).unwrap();
+
+        assert!(mapped.data().iter().all(|d| match d { DataSection::Mapped(_) => true, _ => false }));
+        assert_eq!(mapped.codec().ops(), column.codec().ops());
+        assert_eq!(mapped.group_count(), column.group_count());
+        assert_eq!(mapped.data_heap_size(), 0);
+        assert!(mapped.heap_size_of_children() < column.heap_size_of_children() / 10);
+        assert_eq!(serialize_column(&mapped), serialize_column(&column));
+    }
+
+    #[test]
+    fn test_mapped_sections_outlive_column() {
+        let column = test_column();
+        let dir = TempDir::new("test_mapped_sections_outlive_column").unwrap();
+        let path = dir.path().join("dict.col");
+        File::create(&path).unwrap().write_all(&serialize_column(&column)).unwrap();
+
+        let sections = load_mapped(&path, true).unwrap().data().to_vec();
+        let mut rebuilt = Column::new(
+            column.name(), column.len(), column.range(), column.codec().ops().to_vec(), sections);
+        rebuilt.set_stats(column.stats().unwrap().clone());
+        assert_eq!(serialize_column(&rebuilt), serialize_column(&column));
+    }
+
+    #[test]
+    fn test_mapped_slice_bounds() {
+        let dir = TempDir::new("test_mapped_slice_bounds").unwrap();
+        let path = dir.path().join("bytes");
+        File::create(&path).unwrap().write_all(&[0; 16]).unwrap();
+        let mmap = Arc::new(unsafe { Mmap::map(&File::open(&path).unwrap()) }.unwrap());
+
+        assert_eq!(MappedSlice::<u64>::new(mmap.clone(), 8, 1).unwrap().len(), 1);
+        assert!(MappedSlice::<u64>::new(mmap.clone(), 8, 2).is_err());
+        assert!(MappedSlice::<u64>::new(mmap.clone(), 16, usize::max_value()).is_err());
+        assert!(MappedSlice::<u64>::new(mmap, 4, 1).is_err());
+    }
+
+    fn supported_compression() -> Vec<CompressionCodec> {
+        let mut codecs = vec![CompressionCodec::None];
+        if cfg!(feature = "enable_lz4") {
+            codecs.push(CompressionCodec::Lz4);
+        }
+        if cfg!(feature = "enable_zstd") {
+            codecs.push(CompressionCodec::Zstd);
+        }
+        codecs
+    }
+
+    #[test]
+    fn test_blocked_round_trip() {
+        let values = (0..1000).map(|i| format!("value{}", i % 300)).collect::<Vec<_>>();
+        let columns = vec![
+            test_column(),
+            fast_build_string_column(
+                "packed", values.iter().map(|s| s.as_str()), values.len(), false, false, 0,
+                &StringColumnOptions::default().with_max_unique_strings(10)),
+            Arc::new(Column::new("int", 3, Some((-5, 1000)), vec![], vec![DataSection::I64(vec![-5, 0, 1000])])),
+            Arc::new(Column::null("null", 42)),
+        ];
+        for &compression in &supported_compression() {
+            for column in &columns {
+                let serialized = serialize_column_blocked(column, compression, 64).unwrap();
+                let blocked = deserialize_column_blocked(serialized).unwrap();
+                assert_eq!(blocked.compression(), compression);
+                assert_eq!(blocked.decompressed_blocks(), 0);
+                let deserialized = blocked.to_column().unwrap();
+                assert_eq!(deserialized.name(), column.name());
+                assert_eq!(deserialized.len(), column.len());
+                assert_eq!(deserialized.range(), column.range());
+                assert_eq!(deserialized.codec().ops(), column.codec().ops());
+                assert_eq!(deserialized.data(), column.data());
+            }
+        }
+    }
+
+    #[test]
+    fn test_blocked_partial_read() {
+        let values = (0..10_000).map(|i| format!("value{}", i % 300)).collect::<Vec<_>>();
+        let mut builder = StringColBuilder::new();
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("dict");
+        let codes = match column.data()[0] {
+            DataSection::U16(ref codes) => codes.clone(),
+            ref section => panic!("Unexpected section {:?}", section),
+        };
+        for &compression in &supported_compression() {
+            let blocked = deserialize_column_blocked(serialize_column_blocked(&column, compression, 1000).unwrap()).unwrap();
+            assert_eq!(blocked.section_range(0, 2500, 100).unwrap(), DataSection::U16(codes[2500..2600].to_vec()));
+            assert_eq!(blocked.decompressed_blocks(), 1);
+            // Spans the cached block and the next one
+            assert_eq!(blocked.section_range(0, 2950, 100).unwrap(), DataSection::U16(codes[2950..3050].to_vec()));
+            assert_eq!(blocked.decompressed_blocks(), 2);
+            assert!(blocked.section_range(0, 9950, 100).is_err());
+
+            assert_eq!(blocked.decode_strings_range(5000, 10).unwrap(), column.decode_strings_range(5000, 10));
+            assert_eq!(blocked.decode_strings_range(9995, 10).unwrap(), column.decode_strings_range(9995, 10));
+        }
+    }
+
+    #[test]
+    fn test_blocked_invalid_data() {
+        let column = Column::new("int", 3, None, vec![], vec![DataSection::I64(vec![1, 2, 3])]);
+        let serialized = serialize_column_blocked(&column, CompressionCodec::None, 2).unwrap();
+        assert!(deserialize_column_blocked(serialized[..serialized.len() - 1].to_vec()).is_err());
+        let mut corrupted = serialized.clone();
+        let index = corrupted.len() - 1;
+        corrupted[index] ^= 1;
+        assert!(deserialize_column_blocked(corrupted).is_err());
+        assert!(deserialize_column(&serialized).is_err());
+        assert!(serialize_column_blocked(&column, CompressionCodec::None, 0).is_err());
+        if !cfg!(feature = "enable_zstd") {
+            assert!(serialize_column_blocked(&column, CompressionCodec::Zstd, 2).is_err());
+        }
+    }
+
+    #[test]
+    fn test_encrypted_round_trip() {
+        let column = test_column();
+        let serialized = serialize_column_encrypted(&column, &IdentityEncryption);
+        let deserialized = deserialize_column_encrypted(&serialized, &IdentityEncryption).unwrap();
+        assert_eq!(deserialized.codec().ops(), column.codec().ops());
+        assert_eq!(deserialized.data(), column.data());
+        assert!(deserialize_column(&serialized).is_err());
+
+        let mut corrupted = serialized.clone();
+        let index = corrupted.len() - 1;
+        corrupted[index] ^= 1;
+        assert!(deserialize_column_encrypted(&corrupted, &IdentityEncryption).is_err());
+
+        struct Reversed;
+        impl EncryptionProvider for Reversed {
+            fn id(&self) -> &str { "reversed" }
+            fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> { plaintext.iter().rev().cloned().collect() }
+            fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>, QueryError> { Ok(self.encrypt(ciphertext)) }
+        }
+        assert!(deserialize_column_encrypted(&serialized, &Reversed).is_err());
+        let reversed = serialize_column_encrypted(&column, &Reversed);
+        assert_eq!(deserialize_column_encrypted(&reversed, &Reversed).unwrap().data(), column.data());
+    }
+
+    #[cfg(feature = "enable_aes_gcm")]
+    #[test]
+    fn test_aes_gcm() {
+        let column = build_string_column_from_strs(
+            "secret", &[Some("hunter2"), None, Some("hunter2")], &StringColumnOptions::default());
+        let provider = AesGcmEncryption::new("test", [7; 32]).unwrap();
+        let serialized = serialize_column_encrypted(&column, &provider);
+        assert!(!serialized.windows(7).any(|w| w == b"hunter2"));
+        assert!(serialize_column_encrypted(&column, &provider) != serialized);
+        assert_eq!(deserialize_column_encrypted(&serialized, &provider).unwrap().data(), column.data());
+
+        let wrong_key = AesGcmEncryption::new("test", [8; 32]).unwrap();
+        assert!(deserialize_column_encrypted(&serialized, &wrong_key).is_err());
+        let wrong_id = AesGcmEncryption::new("other", [7; 32]).unwrap();
+        assert!(deserialize_column_encrypted(&serialized, &wrong_id).is_err());
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        let column = Column::new("int", 3, None, vec![], vec![DataSection::I64(vec![1, 2, 3])]);
+        let serialized = serialize_column(&column);
+        assert!(deserialize_column(&serialized[..serialized.len() - 1]).is_err());
+        assert!(deserialize_column(b"LCOL").is_err());
+        let mut wrong_version = serialized.clone();
+        wrong_version[4] = VERSION + 1;
+        assert!(deserialize_column(&wrong_version).is_err());
+        let mut wrong_magic = serialized;
+        wrong_magic[0] = b'X';
+        assert!(deserialize_column(&wrong_magic).is_err());
+    }
+
+    #[test]
+    fn test_checksum() {
+        let column = test_column();
+        let serialized = serialize_column(&column);
+        assert_eq!(checksum(&deserialize_column(&serialized).unwrap()), checksum(&column));
+
+        // Flip a bit in the dictionary
+        let mut corrupted = serialized.clone();
+        let index = corrupted.len() - 10;
+        corrupted[index] ^= 1;
+        let error = deserialize_column(&corrupted).unwrap_err();
+        assert!(format!("{}", error).contains("Checksum mismatch for column dict"), "{}", error);
+        let unverified = deserialize_column_unverified(&corrupted).unwrap();
+        assert_ne!(unverified.checksum(), column.checksum());
+
+        let mut corrupted_checksum = serialized;
+        corrupted_checksum[HEADER_LEN - 1] ^= 0x80;
+        assert!(deserialize_column(&corrupted_checksum).is_err());
+
+        let dir = TempDir::new("test_checksum").unwrap();
+        let path = dir.path().join("dict.col");
+        File::create(&path).unwrap().write_all(&corrupted).unwrap();
+        assert!(load_mapped(&path, true).is_err());
+        assert!(load_mapped(&path, false).is_ok());
+    }
+
+    #[test]
+    fn test_unverified_corrupt_strings() {
+        let serialized = serialize_column(&test_column());
+        let value = serialized.windows(8).position(|w| w == b"value299").unwrap();
+
+        let mut invalid_utf8 = serialized.clone();
+        invalid_utf8[value] = 0xff;
+        let error = deserialize_column_unverified(&invalid_utf8).unwrap_err();
+        assert!(format!("{}", error).contains("not valid UTF-8"), "{}", error);
+
+        // Hashes don't compress, so the packed values are stored as is even with LZ4 enabled
+        let values = (0..100u8).map(|i| format!("{:016x}", seahash::hash(&[i]))).collect::<Vec<_>>();
+        let mut packed = PackedStrings::default();
+        for value in &values {
+            packed.push(value);
+        }
+        let mut serialized = serialize_column(&packed_string_column("packed", 100, packed, &StringColumnOptions::default()));
+        assert!(deserialize_column_unverified(&serialized).is_ok());
+        let last = serialized.windows(16).position(|w| w == values[99].as_bytes()).unwrap();
+        serialized[last - 1] = 0x7f;
+        let error = deserialize_column_unverified(&serialized).unwrap_err();
+        assert!(format!("{}", error).contains("Corrupt data in string column packed"), "{}", error);
+
+        let dir = TempDir::new("test_unverified_corrupt_strings").unwrap();
+        let path = dir.path().join("dict.col");
+        File::create(&path).unwrap().write_all(&invalid_utf8).unwrap();
+        assert!(load_mapped(&path, false).is_err());
+    }
+}
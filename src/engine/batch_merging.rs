@@ -121,6 +121,27 @@ pub fn combine<'a>(batch1: BatchResult<'a>, batch2: BatchResult<'a>, limit: usiz
                 },
             })
         }
+        // Aggregation without group by, each batch holds a single row of counts
+        (None, None) if !batch1.aggregators.is_empty() => {
+            let select = batch1.select.iter().zip(&batch2.select)
+                .map(|(count1, count2)| AnyVec::owned(vec![count1.cast_ref_i64()[0] + count2.cast_ref_i64()[0]]))
+                .collect();
+            Ok(BatchResult {
+                group_by: None,
+                sort_by: None,
+                desc: batch1.desc,
+                select,
+                aggregators: batch1.aggregators,
+                level: batch1.level + 1,
+                batch_count: batch1.batch_count + batch2.batch_count,
+                show: batch1.show && batch2.show,
+                unsafe_referenced_buffers: {
+                    let mut urb = batch1.unsafe_referenced_buffers;
+                    urb.extend(batch2.unsafe_referenced_buffers.into_iter());
+                    urb
+                },
+            })
+        }
         // No aggregation
         (None, None) => {
             match batch1.sort_by {
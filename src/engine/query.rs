@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::i64;
 use std::iter::Iterator;
 use std::sync::Arc;
 
@@ -66,6 +67,19 @@ impl Query {
                         self.order_desc),
                     &mut executor)
             };
+            // Sort indices refer to the filtered rows, map them back to row indices
+            let sort_indices = match filter {
+                Filter::BitVec(filter) => query_plan::prepare(
+                    QueryPlan::Select(
+                        Box::new(QueryPlan::NonzeroIndices(
+                            Box::new(QueryPlan::ReadBuffer(filter)),
+                            EncodingType::U8,
+                            EncodingType::USize)),
+                        Box::new(QueryPlan::ReadBuffer(sort_indices)),
+                        EncodingType::USize),
+                    &mut executor),
+                _ => sort_indices,
+            };
             filter = Filter::Indices(sort_indices);
         }
         for expr in &self.select {
@@ -310,6 +324,85 @@ impl Query {
         }
     }
 
+    /// Aggregation without group by columns over a partition of length `len`, i.e. `SELECT COUNT(*) WHERE ..`. Only
+    /// the filter is evaluated, the selected rows are counted from the filter without reading or decoding any other
+    /// column data.
+    #[inline(never)] // produces more useful profiles
+    pub fn run_count<'a>(&self, columns: &'a HashMap<String, Arc<Column>>, len: usize, explain: bool, show: bool)
+                         -> Result<(BatchResult<'a>, Option<String>), QueryError> {
+        if let Some(&(aggregator, _)) = self.aggregate.iter().find(|&&(aggregator, _)| aggregator != Aggregator::Count) {
+            bail!(QueryError::NotImplemented, "{:?} without group by", aggregator)
+        }
+        let mut executor = QueryExecutor::default();
+        let (filter_plan, filter_type) = QueryPlan::create_query_plan(&self.filter, Filter::None, columns)?;
+        let count = match filter_type.encoding_type() {
+            EncodingType::BitVec => {
+                // A filter of type BitVec always references at least one column
+                let column = columns.iter().next().unwrap().1;
+                let filter = query_plan::prepare_no_alias(filter_plan, &mut executor);
+                let mut results = executor.prepare(Query::column_data(columns));
+                debug!("{:#}", &executor);
+                executor.run(len, &mut results, show);
+                column.count_filtered(results.collect(filter).cast_ref_u8())
+            }
+            _ => len,
+        };
+        let batch = BatchResult {
+            group_by: None,
+            sort_by: None,
+            select: self.aggregate.iter().map(|_| AnyVec::owned(vec![count as i64])).collect(),
+            desc: self.order_desc,
+            aggregators: self.aggregate.iter().map(|x| x.0).collect(),
+            level: 0,
+            batch_count: 1,
+            show,
+            unsafe_referenced_buffers: Vec::new(),
+        };
+        Ok((batch, if explain { Some(format!("{}", executor)) } else { None }))
+    }
+
+    /// Whether column statistics and bloom filters prove that the filter excludes every row of the partition.
+    pub fn can_skip(&self, columns: &HashMap<String, Arc<Column>>) -> bool {
+        Query::excludes_all_rows(&self.filter, columns)
+    }
+
+    fn excludes_all_rows(filter: &Expr, columns: &HashMap<String, Arc<Column>>) -> bool {
+        match *filter {
+            Expr::Func2(Func2Type::Equals, ref lhs, ref rhs) => match (&**lhs, &**rhs) {
+                (&Expr::ColName(ref name), &Expr::Const(RawVal::Str(ref value))) |
+                (&Expr::Const(RawVal::Str(ref value)), &Expr::ColName(ref name)) =>
+                    columns.get(name).map_or(false, |c| !c.might_contain(value)),
+                (&Expr::ColName(ref name), &Expr::Const(RawVal::Int(value))) |
+                (&Expr::Const(RawVal::Int(value)), &Expr::ColName(ref name)) =>
+                    Query::excludes_int_range(name, value, value, columns),
+                _ => false,
+            },
+            Expr::Func2(Func2Type::LT, ref lhs, ref rhs) => match (&**lhs, &**rhs) {
+                (&Expr::ColName(ref name), &Expr::Const(RawVal::Int(value))) =>
+                    value.checked_sub(1).map_or(true, |hi| Query::excludes_int_range(name, i64::MIN, hi, columns)),
+                (&Expr::Const(RawVal::Int(value)), &Expr::ColName(ref name)) =>
+                    value.checked_add(1).map_or(true, |lo| Query::excludes_int_range(name, lo, i64::MAX, columns)),
+                _ => false,
+            },
+            Expr::Func2(Func2Type::GT, ref lhs, ref rhs) => match (&**lhs, &**rhs) {
+                (&Expr::ColName(ref name), &Expr::Const(RawVal::Int(value))) =>
+                    value.checked_add(1).map_or(true, |lo| Query::excludes_int_range(name, lo, i64::MAX, columns)),
+                (&Expr::Const(RawVal::Int(value)), &Expr::ColName(ref name)) =>
+                    value.checked_sub(1).map_or(true, |hi| Query::excludes_int_range(name, i64::MIN, hi, columns)),
+                _ => false,
+            },
+            Expr::Func2(Func2Type::And, ref lhs, ref rhs) =>
+                Query::excludes_all_rows(lhs, columns) || Query::excludes_all_rows(rhs, columns),
+            Expr::Func2(Func2Type::Or, ref lhs, ref rhs) =>
+                Query::excludes_all_rows(lhs, columns) && Query::excludes_all_rows(rhs, columns),
+            _ => false,
+        }
+    }
+
+    fn excludes_int_range(name: &str, lo: i64, hi: i64, columns: &HashMap<String, Arc<Column>>) -> bool {
+        columns.get(name).map_or(false, |c| !c.contains_int_range(lo, hi))
+    }
+
     pub fn is_select_star(&self) -> bool {
         if self.select.len() == 1 {
             match self.select[0] {
@@ -5,6 +5,7 @@ use chrono::{Datelike, NaiveDateTime};
 use crypto::digest::Digest;
 use crypto::md5::Md5;
 use itertools::Itertools;
+use regex::Regex;
 
 use ::QueryError;
 use engine::aggregator::Aggregator;
@@ -25,10 +26,19 @@ pub enum QueryPlan {
 
     DictLookup(Box<QueryPlan>, EncodingType, Box<QueryPlan>, Box<QueryPlan>),
     InverseDictLookup(Box<QueryPlan>, Box<QueryPlan>, Box<QueryPlan>),
+    DictBound(Box<QueryPlan>, Box<QueryPlan>, Box<QueryPlan>, bool),
+    DictMatch(Box<QueryPlan>, Box<QueryPlan>, Box<QueryPlan>, StringMatchKind),
+    LookupBitmap(Box<QueryPlan>, EncodingType, Box<QueryPlan>),
     Cast(Box<QueryPlan>, EncodingType, EncodingType),
     LZ4Decode(Box<QueryPlan>, usize, EncodingType),
+    RunLengthDecode(Box<QueryPlan>, Box<QueryPlan>, usize, EncodingType),
+    UnpackBits(Box<QueryPlan>, u8, usize, EncodingType),
     UnpackStrings(Box<QueryPlan>),
     UnhexpackStrings(Box<QueryPlan>, bool, usize),
+    UnpackFrontCodedStrings(Box<QueryPlan>, usize),
+    UnpackSymbolCodedStrings(Box<QueryPlan>, usize),
+    UnpackComponentCodedStrings(Box<QueryPlan>, usize),
+    UnpackHybridStrings(Box<QueryPlan>),
     DeltaDecode(Box<QueryPlan>, EncodingType),
 
     Exists(Box<QueryPlan>, EncodingType, Box<QueryPlan>),
@@ -42,6 +52,8 @@ pub enum QueryPlan {
     BitUnpack(Box<QueryPlan>, u8, u8),
 
     LessThanVS(EncodingType, Box<QueryPlan>, Box<QueryPlan>),
+    GreaterThanVS(EncodingType, Box<QueryPlan>, Box<QueryPlan>),
+    StringMatchVS(Box<QueryPlan>, StringMatchKind, Box<QueryPlan>),
     EqualsVS(EncodingType, Box<QueryPlan>, Box<QueryPlan>),
     NotEqualsVS(EncodingType, Box<QueryPlan>, Box<QueryPlan>),
     DivideVS(Box<QueryPlan>, Box<QueryPlan>),
@@ -106,18 +118,52 @@ fn _prepare<'a>(plan: QueryPlan, no_alias: bool, result: &mut QueryExecutor<'a>)
                 prepare(*dict_data, result),
                 prepare(*constant, result),
                 result.named_buffer("encoded")),
+        QueryPlan::DictBound(dict_indices, dict_data, constant, upper) =>
+            VecOperator::dict_bound(
+                prepare(*dict_indices, result),
+                prepare(*dict_data, result),
+                prepare(*constant, result),
+                result.named_buffer("bound"),
+                upper),
+        QueryPlan::DictMatch(dict_indices, dict_data, constant, kind) =>
+            VecOperator::dict_match(
+                prepare(*dict_indices, result),
+                prepare(*dict_data, result),
+                prepare(*constant, result),
+                result.named_buffer("dict_matches"),
+                kind),
+        QueryPlan::LookupBitmap(indices, t, bitmap) =>
+            VecOperator::lookup_bitmap(prepare(*indices, result), prepare(*bitmap, result), result.named_buffer("matches"), t),
         QueryPlan::Cast(plan, initial_type, target_type) =>
             VecOperator::type_conversion(prepare(*plan, result), result.named_buffer("casted"), initial_type, target_type),
         QueryPlan::DeltaDecode(plan, t) =>
             VecOperator::delta_decode(prepare(*plan, result), result.named_buffer("decoded"), t),
         QueryPlan::LZ4Decode(plan, decoded_len, t) =>
             VecOperator::lz4_decode(prepare(*plan, result), result.named_buffer("decoded"), decoded_len, t),
+        QueryPlan::RunLengthDecode(values, lengths, decoded_len, t) =>
+            VecOperator::run_length_decode(prepare(*values, result), prepare(*lengths, result), result.named_buffer("decoded"), decoded_len, t),
+        QueryPlan::UnpackBits(plan, bits, decoded_len, t) =>
+            VecOperator::unpack_bits(prepare(*plan, result), result.named_buffer("unpacked"), bits, decoded_len, t),
         QueryPlan::UnpackStrings(plan) =>
             VecOperator::unpack_strings(prepare(*plan, result), result.named_buffer("unpacked")),
         QueryPlan::UnhexpackStrings(plan, uppercase, total_bytes) => {
             let stringstore = result.named_buffer("stringstore");
             VecOperator::unhexpack_strings(prepare(*plan, result), result.named_buffer("unpacked"), stringstore, uppercase, total_bytes)
         }
+        QueryPlan::UnpackFrontCodedStrings(plan, total_bytes) => {
+            let stringstore = result.named_buffer("stringstore");
+            VecOperator::unpack_front_coded_strings(prepare(*plan, result), result.named_buffer("unpacked"), stringstore, total_bytes)
+        }
+        QueryPlan::UnpackSymbolCodedStrings(plan, total_bytes) => {
+            let stringstore = result.named_buffer("stringstore");
+            VecOperator::unpack_symbol_coded_strings(prepare(*plan, result), result.named_buffer("unpacked"), stringstore, total_bytes)
+        }
+        QueryPlan::UnpackComponentCodedStrings(plan, total_bytes) => {
+            let stringstore = result.named_buffer("stringstore");
+            VecOperator::unpack_component_coded_strings(prepare(*plan, result), result.named_buffer("unpacked"), stringstore, total_bytes)
+        }
+        QueryPlan::UnpackHybridStrings(plan) =>
+            VecOperator::unpack_hybrid_strings(prepare(*plan, result), result.named_buffer("unpacked")),
         QueryPlan::Exists(indices, t, max_index) =>
             VecOperator::exists(prepare(*indices, result), result.named_buffer("exists"), t, prepare(*max_index, result)),
         QueryPlan::Compact(data, data_t, select, select_t) => {
@@ -141,6 +187,10 @@ fn _prepare<'a>(plan: QueryPlan, no_alias: bool, result: &mut QueryExecutor<'a>)
             VecOperator::bit_unpack(prepare(*inner, result), result.named_buffer("unpacked"), shift, width),
         QueryPlan::LessThanVS(left_type, lhs, rhs) =>
             VecOperator::less_than_vs(left_type, prepare(*lhs, result), prepare(*rhs, result), result.named_buffer("less_than")),
+        QueryPlan::StringMatchVS(plan, kind, constant) =>
+            VecOperator::string_match_vs(prepare(*plan, result), prepare(*constant, result), result.named_buffer("matches"), kind),
+        QueryPlan::GreaterThanVS(left_type, lhs, rhs) =>
+            VecOperator::greater_than_vs(left_type, prepare(*lhs, result), prepare(*rhs, result), result.named_buffer("greater_than")),
         QueryPlan::EqualsVS(left_type, lhs, rhs) =>
             VecOperator::equals_vs(left_type, prepare(*lhs, result), prepare(*rhs, result), result.named_buffer("equals")),
         QueryPlan::NotEqualsVS(left_type, lhs, rhs) =>
@@ -280,9 +330,27 @@ impl QueryPlan {
                 None => bail!(QueryError::NotImplemented, "Referencing missing column {}", name)
             }
             Func2(LT, ref lhs, ref rhs) => {
-                let (plan_lhs, type_lhs) = QueryPlan::create_query_plan(lhs, filter, columns)?;
+                let (mut plan_lhs, mut type_lhs) = QueryPlan::create_query_plan(lhs, filter, columns)?;
                 let (plan_rhs, type_rhs) = QueryPlan::create_query_plan(rhs, filter, columns)?;
                 match (type_lhs.decoded, type_rhs.decoded) {
+                    (BasicType::String, BasicType::String) => {
+                        if !type_lhs.is_order_preserving() {
+                            let (plan, t) = order_preserving((plan_lhs, type_lhs));
+                            plan_lhs = plan;
+                            type_lhs = t;
+                        }
+                        let plan = if type_rhs.is_scalar {
+                            if type_lhs.is_encoded() {
+                                let encoded = type_lhs.codec.clone().unwrap().encode_str_bound(Box::new(plan_rhs), false);
+                                QueryPlan::LessThanVS(type_lhs.encoding_type(), Box::new(plan_lhs), encoded)
+                            } else {
+                                QueryPlan::LessThanVS(type_lhs.encoding_type(), Box::new(plan_lhs), Box::new(plan_rhs))
+                            }
+                        } else {
+                            bail!(QueryError::NotImplemented, "< operator only implemented for column < constant")
+                        };
+                        (plan, Type::new(BasicType::Boolean, None).mutable())
+                    }
                     (BasicType::Integer, BasicType::Integer) => {
                         let plan = if type_rhs.is_scalar {
                             if type_lhs.is_encoded() {
@@ -299,7 +367,50 @@ impl QueryPlan {
                     _ => bail!(QueryError::TypeError, "{:?} < {:?}", type_lhs, type_rhs)
                 }
             }
+            Func2(GT, ref lhs, ref rhs) => {
+                let (mut plan_lhs, mut type_lhs) = QueryPlan::create_query_plan(lhs, filter, columns)?;
+                let (plan_rhs, type_rhs) = QueryPlan::create_query_plan(rhs, filter, columns)?;
+                match (type_lhs.decoded, type_rhs.decoded) {
+                    (BasicType::String, BasicType::String) => {
+                        if !type_lhs.is_order_preserving() {
+                            let (plan, t) = order_preserving((plan_lhs, type_lhs));
+                            plan_lhs = plan;
+                            type_lhs = t;
+                        }
+                        let plan = if type_rhs.is_scalar {
+                            if type_lhs.is_encoded() {
+                                let encoded = type_lhs.codec.clone().unwrap().encode_str_bound(Box::new(plan_rhs), true);
+                                QueryPlan::GreaterThanVS(type_lhs.encoding_type(), Box::new(plan_lhs), encoded)
+                            } else {
+                                QueryPlan::GreaterThanVS(type_lhs.encoding_type(), Box::new(plan_lhs), Box::new(plan_rhs))
+                            }
+                        } else {
+                            bail!(QueryError::NotImplemented, "> operator only implemented for column > constant")
+                        };
+                        (plan, Type::new(BasicType::Boolean, None).mutable())
+                    }
+                    (BasicType::Integer, BasicType::Integer) => {
+                        let plan = if type_rhs.is_scalar {
+                            if type_lhs.is_encoded() {
+                                let encoded = QueryPlan::EncodeIntConstant(Box::new(plan_rhs), type_lhs.codec.clone().unwrap());
+                                QueryPlan::GreaterThanVS(type_lhs.encoding_type(), Box::new(plan_lhs), Box::new(encoded))
+                            } else {
+                                QueryPlan::GreaterThanVS(type_lhs.encoding_type(), Box::new(plan_lhs), Box::new(plan_rhs))
+                            }
+                        } else {
+                            bail!(QueryError::NotImplemented, "> operator only implemented for column > constant")
+                        };
+                        (plan, Type::new(BasicType::Boolean, None).mutable())
+                    }
+                    _ => bail!(QueryError::TypeError, "{:?} > {:?}", type_lhs, type_rhs)
+                }
+            }
             Func2(Equals, ref lhs, ref rhs) => {
+                if let Const(RawVal::Str(ref value)) = **rhs {
+                    if is_case_insensitive(lhs, columns) {
+                        return QueryPlan::string_match(lhs, StringMatchKind::EqualsIgnoreCase, value.to_string(), filter, columns);
+                    }
+                }
                 let (plan_lhs, type_lhs) = QueryPlan::create_query_plan(lhs, filter, columns)?;
                 let (plan_rhs, type_rhs) = QueryPlan::create_query_plan(rhs, filter, columns)?;
                 match (type_lhs.decoded, type_rhs.decoded) {
@@ -333,6 +444,11 @@ impl QueryPlan {
                 }
             }
             Func2(NotEquals, ref lhs, ref rhs) => {
+                if let Const(RawVal::Str(ref value)) = **rhs {
+                    if is_case_insensitive(lhs, columns) {
+                        return QueryPlan::string_match(lhs, StringMatchKind::NotEqualsIgnoreCase, value.to_string(), filter, columns);
+                    }
+                }
                 let (plan_lhs, type_lhs) = QueryPlan::create_query_plan(lhs, filter, columns)?;
                 let (plan_rhs, type_rhs) = QueryPlan::create_query_plan(rhs, filter, columns)?;
                 match (type_lhs.decoded, type_rhs.decoded) {
@@ -365,6 +481,49 @@ impl QueryPlan {
                     _ => bail!(QueryError::TypeError, "{:?} <> {:?}", type_lhs, type_rhs)
                 }
             }
+            Func2(Like, ref lhs, ref rhs) => {
+                let pattern = match **rhs {
+                    Const(RawVal::Str(ref pattern)) => pattern,
+                    _ => bail!(QueryError::NotImplemented, "LIKE only implemented for constant patterns"),
+                };
+                let wildcards = pattern.matches('%').count();
+                if is_case_insensitive(lhs, columns) {
+                    let regex = format!("(?i){}", like_to_regex(pattern));
+                    QueryPlan::string_match(lhs, StringMatchKind::Regex, regex, filter, columns)?
+                } else if pattern.contains('_') {
+                    QueryPlan::string_match(lhs, StringMatchKind::Regex, like_to_regex(pattern), filter, columns)?
+                } else if wildcards == 1 && pattern.ends_with('%') {
+                    let prefix = pattern[..pattern.len() - 1].to_string();
+                    QueryPlan::string_match(lhs, StringMatchKind::Prefix, prefix, filter, columns)?
+                } else if wildcards == 2 && pattern.len() >= 2 && pattern.starts_with('%') && pattern.ends_with('%') {
+                    let needle = pattern[1..pattern.len() - 1].to_string();
+                    QueryPlan::string_match(lhs, StringMatchKind::Contains, needle, filter, columns)?
+                } else {
+                    QueryPlan::string_match(lhs, StringMatchKind::Regex, like_to_regex(pattern), filter, columns)?
+                }
+            }
+            Func2(Contains, ref lhs, ref rhs) => {
+                let needle = match **rhs {
+                    Const(RawVal::Str(ref needle)) => needle.to_string(),
+                    _ => bail!(QueryError::NotImplemented, "CONTAINS only implemented for constant strings"),
+                };
+                if is_case_insensitive(lhs, columns) {
+                    let regex = format!("(?i){}", regex::escape(&needle));
+                    QueryPlan::string_match(lhs, StringMatchKind::Regex, regex, filter, columns)?
+                } else {
+                    QueryPlan::string_match(lhs, StringMatchKind::Contains, needle, filter, columns)?
+                }
+            }
+            Func2(RegexMatch, ref lhs, ref rhs) => {
+                let pattern = match **rhs {
+                    Const(RawVal::Str(ref pattern)) => pattern,
+                    _ => bail!(QueryError::NotImplemented, "regex only implemented for constant patterns"),
+                };
+                if let Err(err) = Regex::new(pattern) {
+                    bail!(QueryError::ParseError, "Invalid regex {:?}: {}", pattern, err)
+                }
+                QueryPlan::string_match(lhs, StringMatchKind::Regex, pattern.to_string(), filter, columns)?
+            }
             Func2(Or, ref lhs, ref rhs) => {
                 let (plan_lhs, type_lhs) = QueryPlan::create_query_plan(lhs, filter, columns)?;
                 let (plan_rhs, type_rhs) = QueryPlan::create_query_plan(rhs, filter, columns)?;
@@ -415,6 +574,26 @@ impl QueryPlan {
         })
     }
 
+    fn string_match(
+        expr: &Expr,
+        kind: StringMatchKind,
+        pattern: String,
+        filter: Filter,
+        columns: &HashMap<String, Arc<Column>>) -> Result<(QueryPlan, Type), QueryError> {
+        let (plan, t) = QueryPlan::create_query_plan(expr, filter, columns)?;
+        if t.decoded != BasicType::String {
+            bail!(QueryError::TypeError, "Expected string, found {:?}", t)
+        }
+        let pattern = Box::new(QueryPlan::Constant(RawVal::Str(pattern), false));
+        let plan = if t.is_encoded() {
+            let bitmap = t.codec.clone().unwrap().dict_match(pattern, kind);
+            QueryPlan::LookupBitmap(Box::new(plan), t.encoding_type(), bitmap)
+        } else {
+            QueryPlan::StringMatchVS(Box::new(plan), kind, pattern)
+        };
+        Ok((plan, Type::new(BasicType::Boolean, None).mutable()))
+    }
+
     pub fn compile_grouping_key<'b>(
         exprs: &[Expr],
         filter: Filter,
@@ -527,6 +706,8 @@ impl QueryPlan {
                 left.encoding_range().map(|(min, max)| (min + c, max + c)),
             Cast(ref left, _, _) => left.encoding_range(),
             LZ4Decode(ref plan, _, _) => plan.encoding_range(),
+            RunLengthDecode(ref plan, _, _, _) => plan.encoding_range(),
+            UnpackBits(ref plan, _, _, _) => plan.encoding_range(),
             DeltaDecode(ref plan, _) => plan.encoding_range(),
             _ => None, // TODO(clemens): many more cases where we can determine range
         }
@@ -570,6 +751,34 @@ fn replace_common_subexpression(plan: QueryPlan, executor: &mut QueryExecutor) -
                 hasher.input(&s3);
                 InverseDictLookup(dict_indices, dict_data, constant)
             }
+            DictBound(dict_indices, dict_data, constant, upper) => {
+                let (dict_indices, s1) = replace_common_subexpression(*dict_indices, executor);
+                let (dict_data, s2) = replace_common_subexpression(*dict_data, executor);
+                let (constant, s3) = replace_common_subexpression(*constant, executor);
+                hasher.input(&s1);
+                hasher.input(&s2);
+                hasher.input(&s3);
+                hasher.input(&[upper as u8]);
+                DictBound(dict_indices, dict_data, constant, upper)
+            }
+            DictMatch(dict_indices, dict_data, constant, kind) => {
+                let (dict_indices, s1) = replace_common_subexpression(*dict_indices, executor);
+                let (dict_data, s2) = replace_common_subexpression(*dict_data, executor);
+                let (constant, s3) = replace_common_subexpression(*constant, executor);
+                hasher.input(&s1);
+                hasher.input(&s2);
+                hasher.input(&s3);
+                hasher.input(&discriminant_value(&kind).to_bytes());
+                DictMatch(dict_indices, dict_data, constant, kind)
+            }
+            LookupBitmap(indices, t, bitmap) => {
+                let (indices, s1) = replace_common_subexpression(*indices, executor);
+                let (bitmap, s2) = replace_common_subexpression(*bitmap, executor);
+                hasher.input(&s1);
+                hasher.input(&s2);
+                hasher.input(&discriminant_value(&t).to_bytes());
+                LookupBitmap(indices, t, bitmap)
+            }
             Cast(plan, initial_type, target_type) => {
                 let (plan, s1) = replace_common_subexpression(*plan, executor);
                 hasher.input(&s1);
@@ -583,6 +792,21 @@ fn replace_common_subexpression(plan: QueryPlan, executor: &mut QueryExecutor) -
                 hasher.input(&discriminant_value(&t).to_bytes());
                 LZ4Decode(plan, decoded_len, t)
             }
+            RunLengthDecode(values, lengths, decoded_len, t) => {
+                let (values, s1) = replace_common_subexpression(*values, executor);
+                let (lengths, s2) = replace_common_subexpression(*lengths, executor);
+                hasher.input(&s1);
+                hasher.input(&s2);
+                hasher.input(&discriminant_value(&t).to_bytes());
+                RunLengthDecode(values, lengths, decoded_len, t)
+            }
+            UnpackBits(plan, bits, decoded_len, t) => {
+                let (plan, s1) = replace_common_subexpression(*plan, executor);
+                hasher.input(&s1);
+                hasher.input(&[bits]);
+                hasher.input(&discriminant_value(&t).to_bytes());
+                UnpackBits(plan, bits, decoded_len, t)
+            }
             UnpackStrings(plan) => {
                 let (plan, s1) = replace_common_subexpression(*plan, executor);
                 hasher.input(&s1);
@@ -595,6 +819,29 @@ fn replace_common_subexpression(plan: QueryPlan, executor: &mut QueryExecutor) -
                 hasher.input(&[uppercase as u8]);
                 UnhexpackStrings(plan, uppercase, total_bytes)
             }
+            UnpackFrontCodedStrings(plan, total_bytes) => {
+                let (plan, s1) = replace_common_subexpression(*plan, executor);
+                hasher.input(&s1);
+                hasher.input(&total_bytes.to_bytes());
+                UnpackFrontCodedStrings(plan, total_bytes)
+            }
+            UnpackSymbolCodedStrings(plan, total_bytes) => {
+                let (plan, s1) = replace_common_subexpression(*plan, executor);
+                hasher.input(&s1);
+                hasher.input(&total_bytes.to_bytes());
+                UnpackSymbolCodedStrings(plan, total_bytes)
+            }
+            UnpackComponentCodedStrings(plan, total_bytes) => {
+                let (plan, s1) = replace_common_subexpression(*plan, executor);
+                hasher.input(&s1);
+                hasher.input(&total_bytes.to_bytes());
+                UnpackComponentCodedStrings(plan, total_bytes)
+            }
+            UnpackHybridStrings(plan) => {
+                let (plan, s1) = replace_common_subexpression(*plan, executor);
+                hasher.input(&s1);
+                UnpackHybridStrings(plan)
+            }
             DeltaDecode(plan, t) => {
                 let (plan, s1) = replace_common_subexpression(*plan, executor);
                 hasher.input(&s1);
@@ -660,6 +907,22 @@ fn replace_common_subexpression(plan: QueryPlan, executor: &mut QueryExecutor) -
                 hasher.input(&discriminant_value(&left_type).to_bytes());
                 LessThanVS(left_type, lhs, rhs)
             }
+            GreaterThanVS(left_type, lhs, rhs) => {
+                let (lhs, s1) = replace_common_subexpression(*lhs, executor);
+                let (rhs, s2) = replace_common_subexpression(*rhs, executor);
+                hasher.input(&s1);
+                hasher.input(&s2);
+                hasher.input(&discriminant_value(&left_type).to_bytes());
+                GreaterThanVS(left_type, lhs, rhs)
+            }
+            StringMatchVS(plan, kind, constant) => {
+                let (plan, s1) = replace_common_subexpression(*plan, executor);
+                let (constant, s2) = replace_common_subexpression(*constant, executor);
+                hasher.input(&s1);
+                hasher.input(&s2);
+                hasher.input(&discriminant_value(&kind).to_bytes());
+                StringMatchVS(plan, kind, constant)
+            }
             EqualsVS(left_type, lhs, rhs) => {
                 let (lhs, s1) = replace_common_subexpression(*lhs, executor);
                 let (rhs, s2) = replace_common_subexpression(*rhs, executor);
@@ -759,6 +1022,32 @@ fn replace_common_subexpression(plan: QueryPlan, executor: &mut QueryExecutor) -
     }
 }
 
+/// Whether `expr` reads a string column that matches values regardless of case.
+fn is_case_insensitive(expr: &Expr, columns: &HashMap<String, Arc<Column>>) -> bool {
+    match *expr {
+        Expr::ColName(ref name) => columns.get(name).map_or(false, |c| c.is_case_insensitive()),
+        _ => false,
+    }
+}
+
+/// Translates a LIKE pattern into an anchored regex where `%` matches any sequence and `_` any single character.
+fn like_to_regex(pattern: &str) -> String {
+    let mut result = String::from("(?s)^");
+    let mut literal = String::new();
+    for c in pattern.chars() {
+        if c == '%' || c == '_' {
+            result.push_str(&regex::escape(&literal));
+            literal.clear();
+            result.push_str(if c == '%' { ".*" } else { "." });
+        } else {
+            literal.push(c);
+        }
+    }
+    result.push_str(&regex::escape(&literal));
+    result.push('$');
+    result
+}
+
 fn to_hex_string(bytes: &[u8]) -> String {
     bytes.iter()
         .map(|b| format!("{:02X}", b))
@@ -129,13 +129,20 @@ impl QueryTask {
         let mut colstack = Vec::new();
         let mut batch_results = Vec::<BatchResult>::new();
         let mut explains = Vec::new();
+        let mut skipped_batches = 0;
         while let Some((partition, id)) = self.next_partition() {
             trace_start!("Batch {}", id);
             let show = self.show.iter().any(|&x| x == id);
             let cols = partition.get_cols(&self.referenced_cols, &self.db);
+            if self.query.can_skip(&cols) {
+                skipped_batches += 1;
+                continue;
+            }
             rows_scanned += cols.iter().next().map_or(0, |c| c.1.len());
             let (mut batch_result, explain) = match if self.aggregate.is_empty() {
                 self.query.run(unsafe { mem::transmute(&cols) }, self.explain, show, id)
+            } else if self.query.select.is_empty() {
+                self.query.run_count(unsafe { mem::transmute(&cols) }, partition.len(), self.explain, show)
             } else {
                 self.query.run_aggregate(unsafe { mem::transmute(&cols) }, self.explain, show, id)
             } {
@@ -177,9 +184,9 @@ impl QueryTask {
         }
 
         match QueryTask::combine_results(batch_results, self.combined_limit()) {
-            Ok(Some(result)) => self.push_result(result, rows_scanned, rows_collected, explains),
+            Ok(None) if skipped_batches == 0 => {}
+            Ok(result) => self.push_result(result, skipped_batches, rows_scanned, rows_collected, explains),
             Err(error) => self.fail_with(error),
-            _ => {}
         }
         // need to keep colstack alive, otherwise results may reference freed data
         self.push_colstack(colstack);
@@ -197,29 +204,38 @@ impl QueryTask {
         Ok(full_result)
     }
 
-    fn push_result(&self, result: BatchResult, rows_scanned: usize, rows_collected: usize, explains: Vec<String>) {
+    fn push_result(&self,
+                   result: Option<BatchResult>,
+                   skipped_batches: usize,
+                   rows_scanned: usize,
+                   rows_collected: usize,
+                   explains: Vec<String>) {
         let mut state = self.unsafe_state.lock().unwrap();
         if self.completed.load(Ordering::SeqCst) { return; }
-        state.completed_batches += result.batch_count;
+        state.completed_batches += skipped_batches;
         state.explains.extend(explains);
         state.rows_scanned += rows_scanned;
         state.rows_collected += rows_collected;
-        unsafe {
-            let result = mem::transmute::<_, BatchResult<'static>>(result);
-            state.partial_results.push(result);
+        if let Some(result) = result {
+            state.completed_batches += result.batch_count;
+            unsafe {
+                let result = mem::transmute::<_, BatchResult<'static>>(result);
+                state.partial_results.push(result);
+            }
         }
         if state.completed_batches == self.partitions.len() || self.sufficient_rows(state.rows_collected) {
             let mut owned_results = Vec::with_capacity(0);
             mem::swap(&mut owned_results, &mut state.partial_results);
             // TODO(clemens): Handle empty table
             let full_result = match QueryTask::combine_results(owned_results, self.combined_limit()) {
-                Ok(result) => result.unwrap(),
+                Ok(result) => result,
                 Err(error) => {
                     self.fail_with_no_lock(error);
                     return;
                 }
             };
-            let final_result = self.convert_to_output_format(&full_result, state.rows_scanned, &state.explains);
+            // Result is None if every partition was skipped
+            let final_result = self.convert_to_output_format(full_result.as_ref(), state.rows_scanned, &state.explains);
             self.sender.send(Ok(final_result));
             self.completed.store(true, Ordering::SeqCst);
         }
@@ -253,24 +269,26 @@ impl QueryTask {
     }
 
     fn convert_to_output_format(&self,
-                                full_result: &BatchResult,
+                                full_result: Option<&BatchResult>,
                                 rows_scanned: usize,
                                 explains: &[String]) -> QueryOutput {
         let limit = self.query.limit.limit as usize;
         let offset = self.query.limit.offset as usize;
         let mut result_rows = Vec::new();
-        let count = cmp::min(limit, full_result.len() - offset);
-        for i in offset..(count + offset) {
-            let mut record = Vec::with_capacity(self.output_colnames.len());
-            if let Some(ref gs) = full_result.group_by {
-                for g in gs {
-                    record.push(g.get_raw(i));
+        if let Some(full_result) = full_result {
+            let count = cmp::min(limit, full_result.len() - offset);
+            for i in offset..(count + offset) {
+                let mut record = Vec::with_capacity(self.output_colnames.len());
+                if let Some(ref gs) = full_result.group_by {
+                    for g in gs {
+                        record.push(g.get_raw(i));
+                    }
                 }
+                for col in &full_result.select {
+                    record.push(col.get_raw(i));
+                }
+                result_rows.push(record);
             }
-            for col in &full_result.select {
-                record.push(col.get_raw(i));
-            }
-            result_rows.push(record);
         }
 
         let mut query_plans = HashMap::new();
@@ -1,6 +1,7 @@
 use engine::types::*;
 use heapsize::HeapSizeOf;
 use ingest::raw_val::RawVal;
+use mem_store::MappedSlice;
 use itertools::Itertools;
 use num::PrimInt;
 use std::cmp::min;
@@ -192,6 +193,51 @@ impl<'a> AnyVec<'a> for &'a [Premerge] {
 }
 
 
+impl<'a, T: GenericVec<T> + 'a> AnyVec<'a> for MappedSlice<T> {
+    fn len(&self) -> usize { <[T]>::len(self) }
+    fn get_raw(&self, i: usize) -> RawVal { T::wrap_one(self[i]) }
+    fn get_type(&self) -> EncodingType { T::t() }
+    fn sort_indices_desc(&self, indices: &mut Vec<usize>) {
+        indices.sort_unstable_by(|i, j| self[*i].cmp(&self[*j]).reverse());
+    }
+    fn sort_indices_asc(&self, indices: &mut Vec<usize>) {
+        indices.sort_unstable_by_key(|i| self[*i]);
+    }
+    fn slice_box<'b>(&'b self, from: usize, to: usize) -> BoxedVec<'b> where 'a: 'b {
+        let to = min(to, self.len());
+        Box::new(&self[from..to])
+    }
+
+    fn type_error(&self, func_name: &str) -> String { format!("Mapped[{:?}].{}", T::t(), func_name) }
+
+    fn extend(&mut self, _other: BoxedVec<'a>, _count: usize) -> Option<BoxedVec<'a>> {
+        panic!(self.type_error("extend"))
+    }
+
+    fn display(&self) -> String { format!("Mapped{:?}{}", T::t(), display_slice(self, 120)) }
+}
+
+impl<'a> AnyVec<'a> for MappedSlice<i64> {
+    fn cast_ref_i64(&self) -> &[i64] { self }
+}
+
+impl<'a> AnyVec<'a> for MappedSlice<u64> {
+    fn cast_ref_u64(&self) -> &[u64] { self }
+}
+
+impl<'a> AnyVec<'a> for MappedSlice<u32> {
+    fn cast_ref_u32(&self) -> &[u32] { self }
+}
+
+impl<'a> AnyVec<'a> for MappedSlice<u16> {
+    fn cast_ref_u16(&self) -> &[u16] { self }
+}
+
+impl<'a> AnyVec<'a> for MappedSlice<u8> {
+    fn cast_ref_u8(&self) -> &[u8] { self }
+}
+
+
 impl<'a> AnyVec<'a> for usize {
     fn len(&self) -> usize { *self }
     fn get_raw(&self, i: usize) -> RawVal {
@@ -341,6 +387,10 @@ impl CastUsize for u64 {
     fn cast_usize(&self) -> usize { *self as usize }
 }
 
+impl CastUsize for usize {
+    fn cast_usize(&self) -> usize { *self }
+}
+
 #[derive(Debug, PartialEq, PartialOrd, Ord, Eq, Copy, Clone, HeapSizeOf)]
 pub enum MergeOp {
     TakeLeft,
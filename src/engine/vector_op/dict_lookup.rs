@@ -92,3 +92,55 @@ impl<'a> VecOperator<'a> for InverseDictLookup {
         format!("inverse_dict_lookup({}, {}, {})", self.dict_indices, self.dict_data, self.constant)
     }
 }
+
+/// Maps a string constant to a bound on the codes of a sorted dictionary.
+/// If `upper` is false, the output is the number of entries less than the constant.
+/// If `upper` is true, the output is the index of the last entry less than or equal to the constant.
+#[derive(Debug)]
+pub struct DictBound {
+    pub dict_indices: BufferRef,
+    pub dict_data: BufferRef,
+    pub constant: BufferRef,
+    pub output: BufferRef,
+    pub upper: bool,
+}
+
+impl<'a> VecOperator<'a> for DictBound {
+    fn execute(&mut self, _: bool, scratchpad: &mut Scratchpad<'a>) {
+        let result = {
+            let constant = scratchpad.get_const::<String>(self.constant);
+            let dict_indices = scratchpad.get::<u64>(self.dict_indices);
+            let dict_data = scratchpad.get::<u8>(self.dict_data);
+            let entry = |i: usize| {
+                let offset_len = dict_indices[i];
+                let offset = (offset_len >> 24) as usize;
+                let len = (offset_len & 0xffffff) as usize;
+                unsafe { str::from_utf8_unchecked(&dict_data[offset..(offset + len)]) }
+            };
+            let mut lo = 0;
+            let mut hi = dict_indices.len();
+            while lo < hi {
+                let mid = lo + (hi - lo) / 2;
+                let before = if self.upper {
+                    entry(mid) <= constant.as_str()
+                } else {
+                    entry(mid) < constant.as_str()
+                };
+                if before { lo = mid + 1 } else { hi = mid }
+            }
+            if self.upper { lo as i64 - 1 } else { lo as i64 }
+        };
+        scratchpad.set(self.output, AnyVec::constant(RawVal::Int(result)));
+    }
+
+    fn inputs(&self) -> Vec<BufferRef> { vec![self.constant, self.dict_indices, self.dict_data] }
+    fn outputs(&self) -> Vec<BufferRef> { vec![self.output] }
+    fn can_stream_input(&self, _: BufferRef) -> bool { false }
+    fn can_stream_output(&self, _: BufferRef) -> bool { false }
+    fn allocates(&self) -> bool { false }
+
+    fn display_op(&self, _: bool) -> String {
+        let bound = if self.upper { "upper" } else { "lower" };
+        format!("dict_{}_bound({}, {}, {})", bound, self.dict_indices, self.dict_data, self.constant)
+    }
+}
@@ -0,0 +1,134 @@
+use std::marker::PhantomData;
+
+use num::NumCast;
+
+use engine::*;
+use engine::vector_op::vector_operator::*;
+
+
+/// Number of values compared per iteration of the unrolled loop in `filter_eq_code`.
+const LANES: usize = 32;
+
+/// Compares dictionary codes against a constant code.
+/// Constants that can't be represented as `T` match no values.
+#[derive(Debug)]
+pub struct EqualsCode<T> {
+    lhs: BufferRef,
+    rhs: BufferRef,
+    output: BufferRef,
+    t: PhantomData<T>,
+}
+
+impl<T> EqualsCode<T> {
+    pub fn new(lhs: BufferRef, rhs: BufferRef, output: BufferRef) -> EqualsCode<T> {
+        EqualsCode { lhs, rhs, output, t: PhantomData }
+    }
+}
+
+impl<'a, T: GenericIntVec<T>> VecOperator<'a> for EqualsCode<T> {
+    fn execute(&mut self, stream: bool, scratchpad: &mut Scratchpad<'a>) {
+        let data = scratchpad.get::<T>(self.lhs);
+        let c = scratchpad.get_const::<i64>(self.rhs);
+        let mut output = scratchpad.get_mut::<u8>(self.output);
+        if stream { output.clear(); }
+        match <T as NumCast>::from(c) {
+            Some(code) => filter_eq_code(&data, code, &mut output),
+            // Constant is not representable as T and can't match any value
+            None => {
+                let len = output.len() + data.len();
+                output.resize(len, 0);
+            }
+        }
+    }
+
+    fn init(&mut self, _: usize, batch_size: usize, scratchpad: &mut Scratchpad<'a>) {
+        scratchpad.set(self.output, AnyVec::owned(Vec::<u8>::with_capacity(batch_size)));
+    }
+
+    fn inputs(&self) -> Vec<BufferRef> { vec![self.lhs, self.rhs] }
+    fn outputs(&self) -> Vec<BufferRef> { vec![self.output] }
+    fn can_stream_input(&self, _: BufferRef) -> bool { true }
+    fn can_stream_output(&self, _: BufferRef) -> bool { true }
+    fn allocates(&self) -> bool { true }
+
+    fn display_op(&self, _: bool) -> String {
+        format!("{} == {}", self.lhs, self.rhs)
+    }
+}
+
+/// Appends `1` to `output` for every value in `data` that is equal to `code` and `0` otherwise.
+/// Values are compared in fixed size blocks without branches which allows the compiler to vectorize the
+/// inner loop on all targets. The remaining values that don't fill a whole block are compared one by one.
+pub fn filter_eq_code<T: Copy + PartialEq>(data: &[T], code: T, output: &mut Vec<u8>) {
+    output.reserve(data.len());
+    let tail = data.len() - data.len() % LANES;
+    let mut block = [0u8; LANES];
+    for chunk in data[..tail].chunks(LANES) {
+        for (b, &value) in block.iter_mut().zip(chunk) {
+            *b = (value == code) as u8;
+        }
+        output.extend_from_slice(&block);
+    }
+    for &value in &data[tail..] {
+        output.push((value == code) as u8);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::{black_box, Bencher};
+
+    fn scalar<T: Copy + PartialEq>(data: &[T], code: T) -> Vec<u8> {
+        data.iter().map(|&value| (value == code) as u8).collect()
+    }
+
+    #[test]
+    fn test_filter_eq_code() {
+        for &len in &[0, 1, LANES - 1, LANES, LANES + 1, 3 * LANES + 7, 1000] {
+            let data = (0..len).map(|i| (i * 7 % 13) as u16).collect::<Vec<_>>();
+            for code in 0..14 {
+                let mut output = Vec::new();
+                filter_eq_code(&data, code, &mut output);
+                assert_eq!(output, scalar(&data, code), "len {} code {}", len, code);
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_eq_code_appends() {
+        let mut output = vec![1, 1];
+        filter_eq_code(&[3u8; 40], 3, &mut output);
+        assert_eq!(output.len(), 42);
+        assert!(output.iter().all(|&b| b == 1));
+    }
+
+    fn bench_data() -> Vec<u16> {
+        (0..10_000_000u32).map(|i| (i.wrapping_mul(2_654_435_761) % 1000) as u16).collect()
+    }
+
+    #[bench]
+    fn bench_filter_eq_code_10m(b: &mut Bencher) {
+        let data = bench_data();
+        let mut output = Vec::with_capacity(data.len());
+        b.iter(|| {
+            output.clear();
+            filter_eq_code(&data, black_box(17), &mut output);
+            black_box(&output);
+        });
+    }
+
+    #[bench]
+    fn bench_filter_eq_scalar_10m(b: &mut Bencher) {
+        let data = bench_data();
+        let mut output = Vec::with_capacity(data.len());
+        b.iter(|| {
+            output.clear();
+            let code = black_box(17i64);
+            for &d in &data {
+                output.push((Into::<i64>::into(d) == code) as u8);
+            }
+            black_box(&output);
+        });
+    }
+}
@@ -14,6 +14,7 @@ mod delta_decode;
 mod dict_lookup;
 mod division_vs;
 mod encode_const;
+mod equals_code;
 mod exists;
 mod filter;
 mod hashmap_grouping;
@@ -25,13 +26,20 @@ mod merge_keep;
 mod nonzero_compact;
 mod nonzero_indices;
 mod parameterized_vec_vec_int_op;
+mod run_length_decode;
 mod select;
 mod sort_indices;
+mod string_match;
 mod sum;
 mod to_year;
 mod top_n;
 mod unhexpack_strings;
+mod unpack_bits;
+mod unpack_front_coded_strings;
 mod unpack_strings;
+mod unpack_symbol_coded_strings;
+mod unpack_component_coded_strings;
+mod unpack_hybrid_strings;
 mod type_conversion;
 mod vec_const_bool_op;
 #[cfg(feature = "enable_lz4")]
@@ -42,5 +50,7 @@ pub mod subpartition;
 
 pub use self::vector_operator::*;
 pub use self::executor::QueryExecutor;
+pub use self::string_match::StringMatchKind;
+pub use self::equals_code::filter_eq_code;
 
 
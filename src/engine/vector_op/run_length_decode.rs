@@ -0,0 +1,59 @@
+use std::cmp;
+use std::marker::PhantomData;
+
+use engine::*;
+use engine::vector_op::vector_operator::*;
+
+
+#[derive(Debug)]
+pub struct RunLengthDecode<T> {
+    pub values: BufferRef,
+    pub lengths: BufferRef,
+    pub decoded: BufferRef,
+    pub decoded_len: usize,
+    /// Index of the current run and number of values of the current run that have already been emitted
+    pub run: usize,
+    pub emitted: usize,
+    pub has_more: bool,
+    pub t: PhantomData<T>,
+}
+
+impl<'a, T: GenericIntVec<T>> VecOperator<'a> for RunLengthDecode<T> {
+    fn execute(&mut self, streaming: bool, scratchpad: &mut Scratchpad<'a>) {
+        let values = scratchpad.get::<T>(self.values);
+        let lengths = scratchpad.get::<u32>(self.lengths);
+        let mut decoded = scratchpad.get_mut::<T>(self.decoded);
+        if streaming { decoded.clear(); }
+        while self.run < values.len() {
+            let space = decoded.capacity() - decoded.len();
+            let count = cmp::min(lengths[self.run] as usize - self.emitted, space);
+            for _ in 0..count {
+                decoded.push(values[self.run]);
+            }
+            self.emitted += count;
+            if self.emitted == lengths[self.run] as usize {
+                self.run += 1;
+                self.emitted = 0;
+            }
+            if decoded.len() == decoded.capacity() { break; }
+        }
+        self.has_more = self.run < values.len();
+    }
+
+    fn init(&mut self, _: usize, batch_size: usize, scratchpad: &mut Scratchpad<'a>) {
+        scratchpad.set(self.decoded, Box::new(Vec::<T>::with_capacity(batch_size)));
+    }
+
+    fn inputs(&self) -> Vec<BufferRef> { vec![self.values, self.lengths] }
+    fn outputs(&self) -> Vec<BufferRef> { vec![self.decoded] }
+    fn can_stream_input(&self, _: BufferRef) -> bool { false }
+    fn can_stream_output(&self, _: BufferRef) -> bool { true }
+    fn allocates(&self) -> bool { true }
+    fn is_streaming_producer(&self) -> bool { true }
+    fn has_more(&self) -> bool { self.has_more }
+    fn custom_output_len(&self) -> Option<usize> { Some(self.decoded_len) }
+
+    fn display_op(&self, _: bool) -> String {
+        format!("run_length_decode({}, {})", self.values, self.lengths)
+    }
+}
@@ -0,0 +1,191 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::str;
+
+use regex::Regex;
+
+use engine::*;
+use engine::vector_op::vector_operator::*;
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StringMatchKind {
+    Prefix,
+    Contains,
+    Regex,
+    /// Equality ignoring case, used for case insensitive dictionary columns.
+    EqualsIgnoreCase,
+    /// Inequality ignoring case, used for case insensitive dictionary columns.
+    NotEqualsIgnoreCase,
+}
+
+impl StringMatchKind {
+    fn symbol(&self) -> &'static str {
+        match *self {
+            StringMatchKind::Prefix => "starts_with",
+            StringMatchKind::Contains => "contains",
+            StringMatchKind::Regex => "regex",
+            StringMatchKind::EqualsIgnoreCase => "eq_ignore_case",
+            StringMatchKind::NotEqualsIgnoreCase => "ne_ignore_case",
+        }
+    }
+}
+
+enum Matcher {
+    Prefix(String),
+    Contains(String),
+    Regex(Regex),
+    /// Holds the lowercased pattern.
+    EqualsIgnoreCase(String),
+    /// Holds the lowercased pattern.
+    NotEqualsIgnoreCase(String),
+}
+
+impl Matcher {
+    fn new(kind: StringMatchKind, pattern: String) -> Matcher {
+        match kind {
+            StringMatchKind::Prefix => Matcher::Prefix(pattern),
+            StringMatchKind::Contains => Matcher::Contains(pattern),
+            // Pattern is validated during query planning
+            StringMatchKind::Regex => Matcher::Regex(Regex::new(&pattern).unwrap()),
+            StringMatchKind::EqualsIgnoreCase => Matcher::EqualsIgnoreCase(pattern.to_lowercase()),
+            StringMatchKind::NotEqualsIgnoreCase => Matcher::NotEqualsIgnoreCase(pattern.to_lowercase()),
+        }
+    }
+
+    #[inline]
+    fn is_match(&self, string: &str) -> bool {
+        match *self {
+            Matcher::Prefix(ref prefix) => string.starts_with(prefix.as_str()),
+            Matcher::Contains(ref needle) => string.contains(needle.as_str()),
+            Matcher::Regex(ref regex) => regex.is_match(string),
+            Matcher::EqualsIgnoreCase(ref pattern) => string.to_lowercase() == *pattern,
+            Matcher::NotEqualsIgnoreCase(ref pattern) => string.to_lowercase() != *pattern,
+        }
+    }
+}
+
+/// Evaluates a string predicate on every element of a (decoded) string vector.
+pub struct StringMatchVS {
+    pub input: BufferRef,
+    pub constant: BufferRef,
+    pub output: BufferRef,
+    pub kind: StringMatchKind,
+    matcher: Option<Matcher>,
+}
+
+impl StringMatchVS {
+    pub fn new(input: BufferRef, constant: BufferRef, output: BufferRef, kind: StringMatchKind) -> StringMatchVS {
+        StringMatchVS { input, constant, output, kind, matcher: None }
+    }
+}
+
+impl<'a> VecOperator<'a> for StringMatchVS {
+    fn execute(&mut self, stream: bool, scratchpad: &mut Scratchpad<'a>) {
+        if self.matcher.is_none() {
+            let pattern = scratchpad.get_const::<String>(self.constant);
+            self.matcher = Some(Matcher::new(self.kind, pattern));
+        }
+        let matcher = self.matcher.as_ref().unwrap();
+        let data = scratchpad.get::<&'a str>(self.input);
+        let mut output = scratchpad.get_mut::<u8>(self.output);
+        if stream { output.clear(); }
+        for s in data.iter() {
+            output.push(matcher.is_match(s) as u8);
+        }
+    }
+
+    fn init(&mut self, _: usize, batch_size: usize, scratchpad: &mut Scratchpad<'a>) {
+        scratchpad.set(self.output, AnyVec::owned(Vec::<u8>::with_capacity(batch_size)));
+    }
+
+    fn inputs(&self) -> Vec<BufferRef> { vec![self.input, self.constant] }
+    fn outputs(&self) -> Vec<BufferRef> { vec![self.output] }
+    fn can_stream_input(&self, _: BufferRef) -> bool { true }
+    fn can_stream_output(&self, _: BufferRef) -> bool { true }
+    fn allocates(&self) -> bool { true }
+
+    fn display_op(&self, _: bool) -> String {
+        format!("{}({}, {})", self.kind.symbol(), self.input, self.constant)
+    }
+}
+
+impl fmt::Debug for StringMatchVS {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StringMatchVS {{ input: {}, constant: {}, output: {}, kind: {:?} }}",
+               self.input, self.constant, self.output, self.kind)
+    }
+}
+
+/// Evaluates a string predicate once for every dictionary entry.
+/// The output contains a 1 at every code whose string matches and 0 otherwise.
+#[derive(Debug)]
+pub struct DictMatch {
+    pub dict_indices: BufferRef,
+    pub dict_data: BufferRef,
+    pub constant: BufferRef,
+    pub output: BufferRef,
+    pub kind: StringMatchKind,
+}
+
+impl<'a> VecOperator<'a> for DictMatch {
+    fn execute(&mut self, _: bool, scratchpad: &mut Scratchpad<'a>) {
+        let matches = {
+            let matcher = Matcher::new(self.kind, scratchpad.get_const::<String>(self.constant));
+            let dict_indices = scratchpad.get::<u64>(self.dict_indices);
+            let dict_data = scratchpad.get::<u8>(self.dict_data);
+            dict_indices.iter().map(|offset_len| {
+                let offset = (offset_len >> 24) as usize;
+                let len = (offset_len & 0xffffff) as usize;
+                let entry = unsafe { str::from_utf8_unchecked(&dict_data[offset..(offset + len)]) };
+                matcher.is_match(entry) as u8
+            }).collect::<Vec<u8>>()
+        };
+        scratchpad.set(self.output, AnyVec::owned(matches));
+    }
+
+    fn inputs(&self) -> Vec<BufferRef> { vec![self.constant, self.dict_indices, self.dict_data] }
+    fn outputs(&self) -> Vec<BufferRef> { vec![self.output] }
+    fn can_stream_input(&self, _: BufferRef) -> bool { false }
+    fn can_stream_output(&self, _: BufferRef) -> bool { false }
+    fn allocates(&self) -> bool { false }
+
+    fn display_op(&self, _: bool) -> String {
+        format!("dict_{}({}, {}, {})", self.kind.symbol(), self.dict_indices, self.dict_data, self.constant)
+    }
+}
+
+/// Maps each code to the corresponding entry of a per-code bitmap.
+#[derive(Debug)]
+pub struct LookupBitmap<T> {
+    pub indices: BufferRef,
+    pub bitmap: BufferRef,
+    pub output: BufferRef,
+    pub t: PhantomData<T>,
+}
+
+impl<'a, T: GenericIntVec<T>> VecOperator<'a> for LookupBitmap<T> {
+    fn execute(&mut self, stream: bool, scratchpad: &mut Scratchpad<'a>) {
+        let indices = scratchpad.get::<T>(self.indices);
+        let bitmap = scratchpad.get::<u8>(self.bitmap);
+        let mut output = scratchpad.get_mut::<u8>(self.output);
+        if stream { output.clear(); }
+        for i in indices.iter() {
+            output.push(bitmap[i.cast_usize()]);
+        }
+    }
+
+    fn init(&mut self, _: usize, batch_size: usize, scratchpad: &mut Scratchpad<'a>) {
+        scratchpad.set(self.output, AnyVec::owned(Vec::<u8>::with_capacity(batch_size)));
+    }
+
+    fn inputs(&self) -> Vec<BufferRef> { vec![self.indices, self.bitmap] }
+    fn outputs(&self) -> Vec<BufferRef> { vec![self.output] }
+    fn can_stream_input(&self, buffer: BufferRef) -> bool { buffer == self.indices }
+    fn can_stream_output(&self, _: BufferRef) -> bool { true }
+    fn allocates(&self) -> bool { true }
+
+    fn display_op(&self, _: bool) -> String {
+        format!("{}[{}]", self.bitmap, self.indices)
+    }
+}
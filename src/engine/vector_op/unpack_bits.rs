@@ -0,0 +1,47 @@
+use std::cmp;
+use std::marker::PhantomData;
+
+use engine::*;
+use engine::vector_op::vector_operator::*;
+use mem_store::bit_packing;
+
+
+#[derive(Debug)]
+pub struct UnpackBits<T> {
+    pub packed: BufferRef,
+    pub unpacked: BufferRef,
+    pub bits: u8,
+    pub decoded_len: usize,
+    pub position: usize,
+    pub has_more: bool,
+    pub t: PhantomData<T>,
+}
+
+impl<'a, T: GenericIntVec<T>> VecOperator<'a> for UnpackBits<T> {
+    fn execute(&mut self, streaming: bool, scratchpad: &mut Scratchpad<'a>) {
+        let packed = scratchpad.get::<u64>(self.packed);
+        let mut unpacked = scratchpad.get_mut::<T>(self.unpacked);
+        if streaming { unpacked.clear(); }
+        let count = cmp::min(unpacked.capacity() - unpacked.len(), self.decoded_len - self.position);
+        bit_packing::unpack_range(&packed, self.bits, self.position, count, &mut unpacked);
+        self.position += count;
+        self.has_more = self.position < self.decoded_len;
+    }
+
+    fn init(&mut self, _: usize, batch_size: usize, scratchpad: &mut Scratchpad<'a>) {
+        scratchpad.set(self.unpacked, Box::new(Vec::<T>::with_capacity(batch_size)));
+    }
+
+    fn inputs(&self) -> Vec<BufferRef> { vec![self.packed] }
+    fn outputs(&self) -> Vec<BufferRef> { vec![self.unpacked] }
+    fn can_stream_input(&self, _: BufferRef) -> bool { false }
+    fn can_stream_output(&self, _: BufferRef) -> bool { true }
+    fn allocates(&self) -> bool { true }
+    fn is_streaming_producer(&self) -> bool { true }
+    fn has_more(&self) -> bool { self.has_more }
+    fn custom_output_len(&self) -> Option<usize> { Some(self.decoded_len) }
+
+    fn display_op(&self, _: bool) -> String {
+        format!("unpack_bits({}, {})", self.packed, self.bits)
+    }
+}
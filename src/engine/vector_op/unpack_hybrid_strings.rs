@@ -0,0 +1,56 @@
+use std::mem;
+use std::fmt;
+
+use engine::vector_op::vector_operator::*;
+use stringpack::HybridIterator;
+
+
+pub struct UnpackHybridStrings<'a> {
+    pub packed: BufferRef,
+    pub unpacked: BufferRef,
+    pub iterator: Option<HybridIterator<'a>>,
+    pub has_more: bool,
+}
+
+impl<'a> VecOperator<'a> for UnpackHybridStrings<'a> {
+    fn execute(&mut self, streaming: bool, scratchpad: &mut Scratchpad<'a>) {
+        let mut decoded = scratchpad.get_mut::<&'a str>(self.unpacked);
+        if streaming { decoded.clear() }
+        let iter = self.iterator.as_mut().unwrap();
+        while let Some(elem) = iter.next() {
+            decoded.push(null_as_empty(elem));
+            if decoded.capacity() == decoded.len() { return; }
+        }
+        self.has_more = false;
+    }
+
+    fn init(&mut self, _: usize, batch_size: usize, scratchpad: &mut Scratchpad<'a>) {
+        // Dictionary entries and escaped values are both returned as slices of the packed data
+        scratchpad.pin(self.packed);
+        scratchpad.set(self.unpacked, Box::new(Vec::<&'a str>::with_capacity(batch_size)));
+        let encoded = scratchpad.get::<u8>(self.packed);
+        self.iterator = Some(unsafe {
+            let iterator: HybridIterator = HybridIterator::from_slice(encoded.as_ref());
+            mem::transmute::<_, HybridIterator<'a>>(iterator)
+        });
+    }
+
+    fn inputs(&self) -> Vec<BufferRef> { vec![self.packed] }
+    fn outputs(&self) -> Vec<BufferRef> { vec![self.unpacked] }
+    fn can_stream_input(&self, _: BufferRef) -> bool { false }
+    fn can_stream_output(&self, _: BufferRef) -> bool { true }
+    fn allocates(&self) -> bool { true }
+    fn is_streaming_producer(&self) -> bool { true }
+    fn has_more(&self) -> bool { self.has_more }
+
+    fn display_op(&self, _: bool) -> String {
+        format!("unpack_hybrid_strings({})", self.packed)
+    }
+}
+
+impl<'a> fmt::Debug for UnpackHybridStrings<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "UnpackHybridStrings {{ packed: {}, unpacked: {} }}", self.packed, self.unpacked)
+    }
+}
+
@@ -18,7 +18,7 @@ impl<'a> VecOperator<'a> for UnpackStrings<'a> {
         if streaming { decoded.clear() }
         let iter = self.iterator.as_mut().unwrap();
         while let Some(elem) = iter.next() {
-            decoded.push(elem);
+            decoded.push(null_as_empty(elem));
             if decoded.capacity() == decoded.len() { return; }
         }
         self.has_more = false;
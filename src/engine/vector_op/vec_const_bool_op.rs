@@ -70,20 +70,38 @@ impl<T: Into<i64> + Copy> BoolOperation<T, i64> for LessThanInt<T> {
 }
 
 #[derive(Debug)]
-pub struct Equals<T> { t: PhantomData<T> }
+pub struct LessThanString;
 
-impl<T: PartialEq> BoolOperation<T, T> for Equals<T> {
+impl<'a> BoolOperation<&'a str, String> for LessThanString {
     #[inline]
-    fn perform(l: &T, r: &T) -> u8 { (l == r) as u8 }
-    fn symbol() -> &'static str { "==" }
+    fn perform(l: &&'a str, r: &String) -> u8 { (*l < r.as_str()) as u8 }
+    fn symbol() -> &'static str { "<" }
+}
+
+#[derive(Debug)]
+pub struct GreaterThanInt<T> { t: PhantomData<T> }
+
+impl<T: Into<i64> + Copy> BoolOperation<T, i64> for GreaterThanInt<T> {
+    #[inline]
+    fn perform(l: &T, r: &i64) -> u8 { (Into::<i64>::into(*l) > *r) as u8 }
+    fn symbol() -> &'static str { ">" }
 }
 
 #[derive(Debug)]
-pub struct EqualsInt<T> { t: PhantomData<T> }
+pub struct GreaterThanString;
 
-impl<T: Into<i64> + Copy> BoolOperation<T, i64> for EqualsInt<T> {
+impl<'a> BoolOperation<&'a str, String> for GreaterThanString {
     #[inline]
-    fn perform(l: &T, r: &i64) -> u8 { (Into::<i64>::into(*l) == *r) as u8 }
+    fn perform(l: &&'a str, r: &String) -> u8 { (*l > r.as_str()) as u8 }
+    fn symbol() -> &'static str { ">" }
+}
+
+#[derive(Debug)]
+pub struct Equals<T> { t: PhantomData<T> }
+
+impl<T: PartialEq> BoolOperation<T, T> for Equals<T> {
+    #[inline]
+    fn perform(l: &T, r: &T) -> u8 { (l == r) as u8 }
     fn symbol() -> &'static str { "==" }
 }
 
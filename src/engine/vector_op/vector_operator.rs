@@ -30,6 +30,7 @@ use engine::vector_op::delta_decode::*;
 use engine::vector_op::dict_lookup::*;
 use engine::vector_op::division_vs::DivideVS;
 use engine::vector_op::encode_const::*;
+use engine::vector_op::equals_code::EqualsCode;
 use engine::vector_op::exists::Exists;
 use engine::vector_op::filter::Filter;
 use engine::vector_op::hashmap_grouping::HashMapGrouping;
@@ -43,15 +44,22 @@ use engine::vector_op::nonzero_compact::NonzeroCompact;
 use engine::vector_op::nonzero_indices::NonzeroIndices;
 use engine::vector_op::parameterized_vec_vec_int_op::*;
 use engine::vector_op::partition::Partition;
+use engine::vector_op::run_length_decode::RunLengthDecode;
 use engine::vector_op::select::Select;
 use engine::vector_op::sort_indices::SortIndices;
+use engine::vector_op::string_match::*;
 use engine::vector_op::subpartition::SubPartition;
 use engine::vector_op::sum::VecSum;
 use engine::vector_op::to_year::ToYear;
 use engine::vector_op::top_n::TopN;
 use engine::vector_op::type_conversion::TypeConversionOperator;
+use engine::vector_op::unpack_bits::UnpackBits;
 use engine::vector_op::unpack_strings::UnpackStrings;
 use engine::vector_op::unhexpack_strings::UnhexpackStrings;
+use engine::vector_op::unpack_front_coded_strings::UnpackFrontCodedStrings;
+use engine::vector_op::unpack_symbol_coded_strings::UnpackSymbolCodedStrings;
+use engine::vector_op::unpack_component_coded_strings::UnpackComponentCodedStrings;
+use engine::vector_op::unpack_hybrid_strings::UnpackHybridStrings;
 use engine::vector_op::vec_const_bool_op::*;
 
 
@@ -93,6 +101,13 @@ pub trait VecOperator<'a>: fmt::Debug {
     fn display_op(&self, alternate: bool) -> String;
 }
 
+/// Decoded string vectors are not nullable, so operators that unpack nullable strings emit null rows as the empty
+/// string, which is also what the null entry of a dictionary decodes to.
+#[inline]
+pub fn null_as_empty(value: Option<&str>) -> &str {
+    value.unwrap_or("")
+}
+
 fn short_type_name<T: ?Sized>() -> String {
     let full_name = unsafe { type_name::<T>() };
     let re = Regex::new(r"\w+::").unwrap();
@@ -209,6 +224,24 @@ impl<'a> VecOperator<'a> {
         panic!("LZ4 is not enabled in this build of LocustDB. Recompile with `features enable_lz4`")
     }
 
+    pub fn run_length_decode(values: BufferRef, lengths: BufferRef, decoded: BufferRef, decoded_len: usize, t: EncodingType) -> BoxedOperator<'a> {
+        match t {
+            EncodingType::U8 => Box::new(RunLengthDecode::<u8> { values, lengths, decoded, decoded_len, run: 0, emitted: 0, has_more: true, t: PhantomData }),
+            EncodingType::U16 => Box::new(RunLengthDecode::<u16> { values, lengths, decoded, decoded_len, run: 0, emitted: 0, has_more: true, t: PhantomData }),
+            EncodingType::U32 => Box::new(RunLengthDecode::<u32> { values, lengths, decoded, decoded_len, run: 0, emitted: 0, has_more: true, t: PhantomData }),
+            _ => panic!("run_length_decode not supported for type {:?}", t),
+        }
+    }
+
+    pub fn unpack_bits(packed: BufferRef, unpacked: BufferRef, bits: u8, decoded_len: usize, t: EncodingType) -> BoxedOperator<'a> {
+        match t {
+            EncodingType::U8 => Box::new(UnpackBits::<u8> { packed, unpacked, bits, decoded_len, position: 0, has_more: true, t: PhantomData }),
+            EncodingType::U16 => Box::new(UnpackBits::<u16> { packed, unpacked, bits, decoded_len, position: 0, has_more: true, t: PhantomData }),
+            EncodingType::U32 => Box::new(UnpackBits::<u32> { packed, unpacked, bits, decoded_len, position: 0, has_more: true, t: PhantomData }),
+            _ => panic!("unpack_bits not supported for type {:?}", t),
+        }
+    }
+
     pub fn unpack_strings(packed: BufferRef, unpacked: BufferRef) -> BoxedOperator<'a> {
         Box::new(UnpackStrings::<'a> { packed, unpacked, iterator: None, has_more: true })
     }
@@ -217,6 +250,22 @@ impl<'a> VecOperator<'a> {
         Box::new(UnhexpackStrings::<'a> { packed, unpacked, stringstore, uppercase, total_bytes, iterator: None, has_more: true })
     }
 
+    pub fn unpack_front_coded_strings(packed: BufferRef, unpacked: BufferRef, stringstore: BufferRef, total_bytes: usize) -> BoxedOperator<'a> {
+        Box::new(UnpackFrontCodedStrings::<'a> { packed, unpacked, stringstore, total_bytes, iterator: None, has_more: true })
+    }
+
+    pub fn unpack_symbol_coded_strings(packed: BufferRef, unpacked: BufferRef, stringstore: BufferRef, total_bytes: usize) -> BoxedOperator<'a> {
+        Box::new(UnpackSymbolCodedStrings::<'a> { packed, unpacked, stringstore, total_bytes, iterator: None, has_more: true })
+    }
+
+    pub fn unpack_component_coded_strings(packed: BufferRef, unpacked: BufferRef, stringstore: BufferRef, total_bytes: usize) -> BoxedOperator<'a> {
+        Box::new(UnpackComponentCodedStrings::<'a> { packed, unpacked, stringstore, total_bytes, iterator: None, has_more: true })
+    }
+
+    pub fn unpack_hybrid_strings(packed: BufferRef, unpacked: BufferRef) -> BoxedOperator<'a> {
+        Box::new(UnpackHybridStrings::<'a> { packed, unpacked, iterator: None, has_more: true })
+    }
+
     pub fn delta_decode(encoded: BufferRef, decoded: BufferRef, t: EncodingType) -> BoxedOperator<'a> {
         match t {
             EncodingType::U8 => Box::new(DeltaDecode::<u8> { encoded, decoded, previous: 0, t: PhantomData }),
@@ -231,6 +280,28 @@ impl<'a> VecOperator<'a> {
         Box::new(InverseDictLookup { dict_indices, dict_data, constant, output })
     }
 
+    pub fn dict_match(dict_indices: BufferRef, dict_data: BufferRef, constant: BufferRef, output: BufferRef, kind: StringMatchKind) -> BoxedOperator<'a> {
+        Box::new(DictMatch { dict_indices, dict_data, constant, output, kind })
+    }
+
+    pub fn lookup_bitmap(indices: BufferRef, bitmap: BufferRef, output: BufferRef, t: EncodingType) -> BoxedOperator<'a> {
+        match t {
+            EncodingType::U8 => Box::new(LookupBitmap::<u8> { indices, bitmap, output, t: PhantomData }),
+            EncodingType::U16 => Box::new(LookupBitmap::<u16> { indices, bitmap, output, t: PhantomData }),
+            EncodingType::U32 => Box::new(LookupBitmap::<u32> { indices, bitmap, output, t: PhantomData }),
+            EncodingType::I64 => Box::new(LookupBitmap::<i64> { indices, bitmap, output, t: PhantomData }),
+            _ => panic!("lookup_bitmap not supported for type {:?}", t),
+        }
+    }
+
+    pub fn string_match_vs(input: BufferRef, constant: BufferRef, output: BufferRef, kind: StringMatchKind) -> BoxedOperator<'a> {
+        Box::new(StringMatchVS::new(input, constant, output, kind))
+    }
+
+    pub fn dict_bound(dict_indices: BufferRef, dict_data: BufferRef, constant: BufferRef, output: BufferRef, upper: bool) -> BoxedOperator<'a> {
+        Box::new(DictBound { dict_indices, dict_data, constant, output, upper })
+    }
+
     pub fn encode_int_const(constant: BufferRef, output: BufferRef, codec: Codec) -> BoxedOperator<'a> {
         Box::new(EncodeIntConstant { constant, output, codec })
     }
@@ -252,6 +323,7 @@ impl<'a> VecOperator<'a> {
             EncodingType::U32 => Box::new(Select::<u32> { input, indices, output, t: PhantomData }),
             EncodingType::U16 => Box::new(Select::<u16> { input, indices, output, t: PhantomData }),
             EncodingType::U8 => Box::new(Select::<u8> { input, indices, output, t: PhantomData }),
+            EncodingType::USize => Box::new(Select::<usize> { input, indices, output, t: PhantomData }),
             EncodingType::Str => Box::new(Select::<&str> { input, indices, output, t: PhantomData }),
             _ => panic!("filter not supported for type {:?}", t),
         }
@@ -271,16 +343,28 @@ impl<'a> VecOperator<'a> {
             EncodingType::U16 => Box::new(VecConstBoolOperator::<u16, i64, LessThanInt<u16>>::new(lhs, rhs, output)),
             EncodingType::U32 => Box::new(VecConstBoolOperator::<u32, i64, LessThanInt<u32>>::new(lhs, rhs, output)),
             EncodingType::I64 => Box::new(VecConstBoolOperator::<i64, i64, LessThanInt<i64>>::new(lhs, rhs, output)),
+            EncodingType::Str => Box::new(VecConstBoolOperator::<_, _, LessThanString>::new(lhs, rhs, output)),
             _ => panic!("less_than_vs not supported for type {:?}", t),
         }
     }
 
+    pub fn greater_than_vs(t: EncodingType, lhs: BufferRef, rhs: BufferRef, output: BufferRef) -> BoxedOperator<'a> {
+        match t {
+            EncodingType::U8 => Box::new(VecConstBoolOperator::<u8, i64, GreaterThanInt<u8>>::new(lhs, rhs, output)),
+            EncodingType::U16 => Box::new(VecConstBoolOperator::<u16, i64, GreaterThanInt<u16>>::new(lhs, rhs, output)),
+            EncodingType::U32 => Box::new(VecConstBoolOperator::<u32, i64, GreaterThanInt<u32>>::new(lhs, rhs, output)),
+            EncodingType::I64 => Box::new(VecConstBoolOperator::<i64, i64, GreaterThanInt<i64>>::new(lhs, rhs, output)),
+            EncodingType::Str => Box::new(VecConstBoolOperator::<_, _, GreaterThanString>::new(lhs, rhs, output)),
+            _ => panic!("greater_than_vs not supported for type {:?}", t),
+        }
+    }
+
     pub fn equals_vs(t: EncodingType, lhs: BufferRef, rhs: BufferRef, output: BufferRef) -> BoxedOperator<'a> {
         match t {
             EncodingType::Str => Box::new(VecConstBoolOperator::<_, _, EqualsString>::new(lhs, rhs, output)),
-            EncodingType::U8 => Box::new(VecConstBoolOperator::<_, _, EqualsInt<u8>>::new(lhs, rhs, output)),
-            EncodingType::U16 => Box::new(VecConstBoolOperator::<_, _, EqualsInt<u16>>::new(lhs, rhs, output)),
-            EncodingType::U32 => Box::new(VecConstBoolOperator::<_, _, EqualsInt<u32>>::new(lhs, rhs, output)),
+            EncodingType::U8 => Box::new(EqualsCode::<u8>::new(lhs, rhs, output)),
+            EncodingType::U16 => Box::new(EqualsCode::<u16>::new(lhs, rhs, output)),
+            EncodingType::U32 => Box::new(EqualsCode::<u32>::new(lhs, rhs, output)),
             EncodingType::I64 => Box::new(VecConstBoolOperator::<_, _, Equals<i64>>::new(lhs, rhs, output)),
             _ => panic!("equals_vs not supported for type {:?}", t),
         }
@@ -423,6 +507,7 @@ impl<'a> VecOperator<'a> {
             (U8, U16) => NonzeroIndices::<u8, u16>::boxed(input, output),
             (U8, U32) => NonzeroIndices::<u8, u32>::boxed(input, output),
             (U8, I64) => NonzeroIndices::<u8, i64>::boxed(input, output),
+            (U8, USize) => NonzeroIndices::<u8, usize>::boxed(input, output),
             (U16, U8) => NonzeroIndices::<u16, u8>::boxed(input, output),
             (U16, U16) => NonzeroIndices::<u16, u16>::boxed(input, output),
             (U16, U32) => NonzeroIndices::<u16, u32>::boxed(input, output),
@@ -1,16 +1,21 @@
 extern crate csv;
 extern crate flate2;
 
+use engine::types::BasicType;
+use fnv::FnvBuildHasher;
 use mem_store::column::*;
 use mem_store::column_builder::*;
-use mem_store::strings::fast_build_string_column;
+use mem_store::interner::StringInterner;
+use mem_store::strings::{fast_build_encoding, fast_build_string_column, DictionaryOrder, StringColumnOptions};
 use scheduler::*;
+use scoped_threadpool::Pool;
 use self::flate2::read::GzDecoder;
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::mem;
 use std::ops::BitOr;
 use std::str;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use super::extractor;
 use stringpack::*;
 
@@ -25,6 +30,11 @@ pub struct Options {
     ignore_cols: HashSet<String>,
     always_string: HashSet<String>,
     unzip: bool,
+    string_options: StringColumnOptions,
+    bit_packed_integers: bool,
+    null_sentinel: Option<String>,
+    ingest_threads: usize,
+    keep_leading_zeros: bool,
 }
 
 impl Options {
@@ -38,6 +48,11 @@ impl Options {
             ignore_cols: HashSet::new(),
             always_string: HashSet::new(),
             unzip: filename.ends_with(".gz"),
+            string_options: StringColumnOptions::default(),
+            bit_packed_integers: false,
+            null_sentinel: None,
+            ingest_threads: 1,
+            keep_leading_zeros: false,
         }
     }
 
@@ -65,13 +80,182 @@ impl Options {
         self.always_string = always_string.into_iter().map(|&x| x.to_owned()).collect();
         self
     }
+
+    pub fn with_max_unique_strings(mut self, max_unique_strings: usize) -> Options {
+        self.string_options = self.string_options.with_max_unique_strings(max_unique_strings);
+        self
+    }
+
+    pub fn with_dictionary_order(mut self, dictionary_order: DictionaryOrder) -> Options {
+        self.string_options = self.string_options.with_dictionary_order(dictionary_order);
+        self
+    }
+
+    pub fn with_bloom_filter(mut self, bits_per_value: usize, hashes: u32) -> Options {
+        self.string_options = self.string_options.with_bloom_filter(bits_per_value, hashes);
+        self
+    }
+
+    pub fn with_symbol_table_compression(mut self, symbol_table_compression: bool) -> Options {
+        self.string_options = self.string_options.with_symbol_table_compression(symbol_table_compression);
+        self
+    }
+
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Options {
+        self.string_options = self.string_options.with_case_insensitive(case_insensitive);
+        self
+    }
+
+    /// Whitespace-only fields are loaded as null by `load_string_columns`, see `StringColumnOptions::trim_to_null`.
+    pub fn with_trim_to_null(mut self, trim_to_null: bool) -> Options {
+        self.string_options = self.string_options.with_trim_to_null(trim_to_null);
+        self
+    }
+
+    pub fn with_store_trimmed(mut self, store_trimmed: bool) -> Options {
+        self.string_options = self.string_options.with_store_trimmed(store_trimmed);
+        self
+    }
+
+    pub fn with_bit_packed_integers(mut self, bit_packed_integers: bool) -> Options {
+        self.bit_packed_integers = bit_packed_integers;
+        self
+    }
+
+    /// Fields equal to `null_sentinel` are loaded as null by `load_string_columns`.
+    pub fn with_null_sentinel(mut self, null_sentinel: &str) -> Options {
+        self.null_sentinel = Some(null_sentinel.to_owned());
+        self
+    }
+
+    /// Load columns that contain numbers with leading zeros, e.g. zip codes, as strings instead of integers which
+    /// would drop the zeros.
+    pub fn with_keep_leading_zeros(mut self, keep_leading_zeros: bool) -> Options {
+        self.keep_leading_zeros = keep_leading_zeros;
+        self
+    }
+
+    /// Number of threads used by `ingest_file` to build the columns of each partition.
+    pub fn with_ingest_threads(mut self, ingest_threads: usize) -> Options {
+        self.ingest_threads = ingest_threads;
+        self
+    }
+}
+
+/// Type and encoding that `ingest_file` would give a column, see `infer_schema`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InferredColumn {
+    pub name: String,
+    pub inferred_type: BasicType,
+    /// Encoding as named by `Column::memory_report`.
+    pub encoding: &'static str,
+    /// Number of distinct values among the sampled rows.
+    pub distinct_est: usize,
+    /// Fraction of sampled rows that are null.
+    pub null_est: f64,
+    /// Whether some sampled values are numbers with leading zeros, which an integer column does not preserve, see
+    /// `Options::with_keep_leading_zeros`.
+    pub leading_zeros: bool,
+}
+
+/// Infers the type and encoding of every column in the CSV file at `path` from its first `sample_rows` rows without
+/// building any columns, using the default `Options`, see `infer_schema_with_options`.
+pub fn infer_schema(path: &str, sample_rows: usize) -> Result<Vec<InferredColumn>, String> {
+    infer_schema_with_options(&Options::new(path, ""), sample_rows)
+}
+
+/// Infers the type and encoding that `ingest_file` would give every column of the file from its first `sample_rows`
+/// rows. Uses the same rules as `ingest_file`, so e.g. zero padded numbers are reported as integers unless
+/// `opts` keeps leading zeros.
+pub fn infer_schema_with_options(opts: &Options, sample_rows: usize) -> Result<Vec<InferredColumn>, String> {
+    read_records(opts, |records, colnames| {
+        let mut raw_cols = (0..colnames.len()).map(|_| RawCol::new()).collect::<Vec<_>>();
+        for row in records.take(sample_rows) {
+            for (i, val) in row.iter().enumerate() {
+                raw_cols[i].push(val);
+            }
+        }
+        Ok(raw_cols.iter().zip(colnames).map(|(col, name)| col.infer(name, opts)).collect())
+    })
 }
 
 pub fn ingest_file(ldb: &InnerLocustDB, opts: &Options) -> Result<(), String> {
+    read_records(opts, |records, headers| auto_ingest(ldb, records, headers, opts))
+}
+
+/// Reads the file into partitions of `opts.partition_size` rows in which every column is a string column.
+/// Rows are processed as they are read, so only the compressed columns are kept in memory.
+pub fn load_string_columns(opts: &Options) -> Result<Vec<Vec<Arc<Column>>>, String> {
+    read_records(opts, |records, colnames| {
+        let load = colnames.iter().map(|x| !opts.ignore_cols.contains(x)).collect::<Vec<_>>();
+        // Values that recur in every partition are only kept in memory once
+        let interners = colnames.iter()
+            .map(|_| Arc::new(Mutex::new(StringInterner::new(opts.string_options.unique_strings_limit()))))
+            .collect::<Vec<_>>();
+        // Files are supplied by the operator, so strings are deduplicated with FNV which is faster than SipHash but
+        // not resistant to collision attacks
+        let new_builders = || interners.iter()
+            .map(|interner| StringColBuilder::<FnvBuildHasher>::with_hasher(opts.string_options)
+                .with_interner(interner.clone()))
+            .collect::<Vec<_>>();
+        let mut builders = new_builders();
+        let mut pool = if opts.ingest_threads > 1 { Some(Pool::new(opts.ingest_threads as u32)) } else { None };
+        let mut partitions = Vec::new();
+        let mut rows = 0;
+        for row in records {
+            for (i, val) in row.iter().enumerate() {
+                if load[i] {
+                    if opts.null_sentinel.as_ref().map_or(false, |null| null == val) {
+                        builders[i].push_null();
+                    } else {
+                        builders[i].push(val);
+                    }
+                }
+            }
+            rows += 1;
+            if rows == opts.partition_size {
+                let full = mem::replace(&mut builders, new_builders());
+                partitions.push(finalize_string_columns(full, colnames, &load, pool.as_mut()));
+                rows = 0;
+            }
+        }
+        if rows > 0 {
+            partitions.push(finalize_string_columns(builders, colnames, &load, pool.as_mut()));
+        }
+        Ok(partitions)
+    })
+}
+
+/// Finalizes the builders of the columns that are loaded, concurrently if a `pool` is given.
+fn finalize_string_columns(builders: Vec<StringColBuilder<FnvBuildHasher>>,
+                           colnames: &[String],
+                           load: &[bool],
+                           pool: Option<&mut Pool>) -> Vec<Arc<Column>> {
+    let mut columns = vec![None; builders.len()];
+    let builders = builders.into_iter()
+        .zip(colnames.iter().zip(load.iter()))
+        .zip(columns.iter_mut())
+        .filter(|&((_, (_, &load)), _)| load);
+    match pool {
+        Some(pool) => pool.scoped(|scope| {
+            for ((builder, (name, _)), column) in builders {
+                scope.execute(move || *column = Some(builder.finalize(name)));
+            }
+        }),
+        None => for ((builder, (name, _)), column) in builders {
+            *column = Some(builder.finalize(name));
+        },
+    }
+    columns.into_iter().filter_map(|column| column).collect()
+}
+
+/// Opens the file and calls `f` with an iterator over its records and the column names.
+fn read_records<F, R>(opts: &Options, f: F) -> Result<R, String>
+    where F: FnOnce(&mut Iterator<Item=csv::StringRecord>, &[String]) -> Result<R, String> {
     // Can't combine these two branches because csv::Reader takes a type param which differs for creating from Reader/File
     if opts.unzip {
-        let f = File::open(&opts.filename).map_err(|x| x.to_string())?;
-        let decoded = GzDecoder::new(f);
+        let file = File::open(&opts.filename).map_err(|x| x.to_string())?;
+        let decoded = GzDecoder::new(file);
         let mut reader = csv::ReaderBuilder::new()
             .has_headers(opts.colnames.is_none())
             .from_reader(decoded);
@@ -79,7 +263,7 @@ pub fn ingest_file(ldb: &InnerLocustDB, opts: &Options) -> Result<(), String> {
             Some(ref colnames) => colnames.clone(),
             None => reader.headers().unwrap().iter().map(str::to_owned).collect()
         };
-        auto_ingest(ldb, reader.records().map(|r| r.unwrap()), &headers, opts)
+        f(&mut reader.records().map(|r| r.unwrap()), &headers)
     } else {
         let mut reader = csv::ReaderBuilder::new()
             .has_headers(opts.colnames.is_none())
@@ -89,7 +273,7 @@ pub fn ingest_file(ldb: &InnerLocustDB, opts: &Options) -> Result<(), String> {
             Some(ref colnames) => colnames.clone(),
             None => reader.headers().unwrap().iter().map(str::to_owned).collect()
         };
-        auto_ingest(ldb, reader.records().map(|r| r.unwrap()), &headers, opts)
+        f(&mut reader.records().map(|r| r.unwrap()), &headers)
     }
 }
 
@@ -98,6 +282,7 @@ fn auto_ingest<T>(ldb: &InnerLocustDB, records: T, colnames: &[String], opts: &O
     let ignore = colnames.iter().map(|x| opts.ignore_cols.contains(x)).collect::<Vec<_>>();
     let string = colnames.iter().map(|x| opts.always_string.contains(x)).collect::<Vec<_>>();
     let mut raw_cols = (0..colnames.len()).map(|_| RawCol::new()).collect::<Vec<_>>();
+    let mut pool = if opts.ingest_threads > 1 { Some(Pool::new(opts.ingest_threads as u32)) } else { None };
     let mut row_num = 0usize;
     for row in records {
         for (i, val) in row.iter().enumerate() {
@@ -107,31 +292,43 @@ fn auto_ingest<T>(ldb: &InnerLocustDB, records: T, colnames: &[String], opts: &O
         }
 
         if row_num % opts.partition_size == opts.partition_size - 1 {
-            let partition = create_batch(&mut raw_cols, colnames, &opts.extractors, &ignore, &string);
+            let partition = create_batch(&mut raw_cols, colnames, opts, &ignore, &string, pool.as_mut());
             ldb.store_partition(&opts.tablename, partition);
         }
         row_num += 1;
     }
 
     if row_num % opts.partition_size != 0 {
-        let partition = create_batch(&mut raw_cols, colnames, &opts.extractors, &ignore, &string);
+        let partition = create_batch(&mut raw_cols, colnames, opts, &ignore, &string, pool.as_mut());
         ldb.store_partition(&opts.tablename, partition);
     }
     Ok(())
 }
 
-fn create_batch(cols: &mut [RawCol], colnames: &[String], extractors: &IngestionTransform, ignore: &[bool], string: &[bool]) -> Vec<Arc<Column>> {
-    let mut mem_store = Vec::new();
-    for (i, col) in cols.iter_mut().enumerate() {
-        if !ignore[i] {
-            let new_column = match extractors.get(&colnames[i]) {
-                Some(extractor) => col.extract(&colnames[i], extractor),
-                None => col.finalize(&colnames[i], string[i]),
-            };
-            mem_store.push(new_column);
-        }
+/// Builds the columns of a partition from `cols`. Columns are independent of each other, so if a `pool` is given
+/// they are built concurrently.
+fn create_batch(cols: &mut [RawCol],
+                colnames: &[String],
+                opts: &Options,
+                ignore: &[bool],
+                string: &[bool],
+                pool: Option<&mut Pool>) -> Vec<Arc<Column>> {
+    let mut mem_store = vec![None; cols.len()];
+    match pool {
+        Some(pool) => pool.scoped(|scope| {
+            for (i, (col, column)) in cols.iter_mut().zip(mem_store.iter_mut()).enumerate() {
+                if !ignore[i] {
+                    scope.execute(move || *column = Some(col.build(&colnames[i], string[i], opts)));
+                }
+            }
+        }),
+        None => for (i, (col, column)) in cols.iter_mut().zip(mem_store.iter_mut()).enumerate() {
+            if !ignore[i] {
+                *column = Some(col.build(&colnames[i], string[i], opts));
+            }
+        },
     }
-    mem_store
+    mem_store.into_iter().filter_map(|column| column).collect()
 }
 
 pub struct CSVIngestionTask {
@@ -161,6 +358,7 @@ impl Task for CSVIngestionTask {
 }
 
 
+#[derive(Clone)]
 struct RawCol {
     types: ColType,
     values: IndexedPackedStrings,
@@ -188,12 +386,19 @@ impl RawCol {
         self.values.push(elem);
     }
 
-    fn finalize(&mut self, name: &str, string: bool) -> Arc<Column> {
-        let result = if self.types.contains_string || string {
+    fn build(&mut self, name: &str, string: bool, opts: &Options) -> Arc<Column> {
+        match opts.extractors.get(name) {
+            Some(extractor) => self.extract(name, extractor, opts),
+            None => self.finalize(name, string, opts),
+        }
+    }
+
+    fn finalize(&mut self, name: &str, string: bool, opts: &Options) -> Arc<Column> {
+        let result = if self.types.is_string(opts.keep_leading_zeros) || string {
             fast_build_string_column(name, self.values.iter(), self.values.len(),
-                                     self.lhex, self.uhex, self.string_bytes)
+                                     self.lhex, self.uhex, self.string_bytes, &opts.string_options)
         } else if self.types.contains_int {
-            let mut builder = IntColBuilder::new();
+            let mut builder = IntColBuilder::new().with_bit_packing(opts.bit_packed_integers);
             for s in self.values.iter() {
                 let int = if s.is_empty() {
                     0
@@ -214,8 +419,33 @@ impl RawCol {
         result
     }
 
-    fn extract(&mut self, name: &str, extractor: &extractor::Extractor) -> Arc<Column> {
-        let mut builder = IntColBuilder::new();
+    /// Type and encoding that `finalize` would produce, see `infer_schema`.
+    fn infer(&self, name: &str, opts: &Options) -> InferredColumn {
+        let len = self.values.len();
+        let nulls = self.values.iter().filter(|s| s.is_empty()).count();
+        let distinct_est = self.values.iter().collect::<HashSet<_>>().len();
+        let (inferred_type, encoding) = if self.types.is_string(opts.keep_leading_zeros) {
+            let sorted = self.values.iter().zip(self.values.iter().skip(1)).all(|(a, b)| a <= b);
+            let encoding = fast_build_encoding(name, distinct_est, len, sorted, self.lhex, self.uhex,
+                                               self.string_bytes, &opts.string_options);
+            (BasicType::String, encoding)
+        } else if self.types.contains_int {
+            (BasicType::Integer, "integer")
+        } else {
+            (BasicType::Null, "null")
+        };
+        InferredColumn {
+            name: name.to_string(),
+            inferred_type,
+            encoding,
+            distinct_est,
+            null_est: if len == 0 { 0.0 } else { nulls as f64 / len as f64 },
+            leading_zeros: self.types.contains_leading_zeros,
+        }
+    }
+
+    fn extract(&mut self, name: &str, extractor: &extractor::Extractor, opts: &Options) -> Arc<Column> {
+        let mut builder = IntColBuilder::new().with_bit_packing(opts.bit_packed_integers);
         for s in self.values.iter() {
             builder.push(&extractor(s));
         }
@@ -229,6 +459,12 @@ impl RawCol {
     }
 }
 
+fn has_leading_zeros(string: &str) -> bool {
+    let digits = if string.starts_with('-') { &string[1..] } else { string };
+    let bytes = digits.as_bytes();
+    bytes.len() > 1 && bytes[0] == b'0' && (bytes[1] as char).is_digit(10)
+}
+
 fn is_lowercase_hex(string: &str) -> bool {
     string.len() & 1 == 0 && string.chars().all(|c| {
         c == '0' || c == '1' || c == '2' || c == '3' ||
@@ -253,11 +489,13 @@ struct ColType {
     contains_string: bool,
     contains_int: bool,
     contains_null: bool,
+    /// Whether some numbers have leading zeros that would be lost in an integer column
+    contains_leading_zeros: bool,
 }
 
 impl ColType {
     fn new(string: bool, int: bool, null: bool) -> ColType {
-        ColType { contains_string: string, contains_int: int, contains_null: null }
+        ColType { contains_string: string, contains_int: int, contains_null: null, contains_leading_zeros: false }
     }
 
     fn string() -> ColType {
@@ -280,11 +518,15 @@ impl ColType {
         if s.is_empty() {
             ColType::null()
         } else if s.parse::<i64>().is_ok() || s.parse::<f64>().is_ok() {
-            ColType::int()
+            ColType { contains_leading_zeros: has_leading_zeros(s), ..ColType::int() }
         } else {
             ColType::string()
         }
     }
+
+    fn is_string(&self, keep_leading_zeros: bool) -> bool {
+        self.contains_string || (keep_leading_zeros && self.contains_leading_zeros)
+    }
 }
 
 impl BitOr for ColType {
@@ -294,7 +536,138 @@ impl BitOr for ColType {
             contains_string: self.contains_string | rhs.contains_string,
             contains_int: self.contains_int | rhs.contains_int,
             contains_null: self.contains_null | rhs.contains_null,
+            contains_leading_zeros: self.contains_leading_zeros | rhs.contains_leading_zeros,
         }
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test::{black_box, Bencher};
+
+    fn counts(column: &Column) -> Vec<(Option<String>, u64)> {
+        column.group_count().unwrap()
+    }
+
+    fn value(s: &str) -> Option<String> { Some(s.to_string()) }
+
+    #[test]
+    fn test_load_string_columns() {
+        let opts = Options::new("test_data/nullable.csv", "default")
+            .with_null_sentinel("")
+            .with_ignore_cols(&["id".to_string()])
+            .with_max_unique_strings(0);
+        let partitions = load_string_columns(&opts).unwrap();
+        assert_eq!(partitions.len(), 1);
+        let columns = &partitions[0];
+        assert_eq!(columns.iter().map(|c| c.name()).collect::<Vec<_>>(), vec!["name", "city", "comment"]);
+        assert!(columns.iter().all(|c| c.len() == 5));
+        assert_eq!(counts(&columns[0]).len(), 5);
+        assert_eq!(counts(&columns[1]), vec![(None, 1), (value("berlin"), 3), (value("paris"), 1)]);
+        assert_eq!(counts(&columns[2]), vec![(None, 2), (value("NULL"), 1), (value("hello"), 1), (value("hi"), 1)]);
+    }
+
+    #[test]
+    fn test_load_string_columns_batched() {
+        let opts = Options::new("test_data/nullable.csv", "default")
+            .with_null_sentinel("NULL")
+            .with_partition_size(2)
+            .with_ingest_threads(3);
+        let partitions = load_string_columns(&opts).unwrap();
+        assert_eq!(partitions.iter().map(|p| p[0].len()).collect::<Vec<_>>(), vec![2, 2, 1]);
+        assert_eq!(counts(&partitions[0][3]), vec![(value(""), 1), (value("hello"), 1)]);
+        assert_eq!(counts(&partitions[1][3]), vec![(None, 1), (value("hi"), 1)]);
+        assert_eq!(counts(&partitions[2][1]), vec![(value("eve"), 1)]);
+    }
+
+    #[test]
+    fn test_load_string_columns_null_and_empty_string() {
+        let opts = Options::new("test_data/nullable.csv", "default").with_null_sentinel("NULL");
+        let partitions = load_string_columns(&opts).unwrap();
+        let comment = &partitions[0][3];
+        assert!(comment.null_code().is_some());
+        assert_eq!(comment.null_count(), 1);
+        assert_eq!(counts(comment), vec![(None, 1), (value(""), 2), (value("hello"), 1), (value("hi"), 1)]);
+        assert_eq!(comment.filter_eq_str(Some(""), false), Some(vec![1, 0, 0, 0, 1]));
+    }
+
+    fn wide_table(columns: usize, rows: usize) -> (Vec<RawCol>, Vec<String>) {
+        let mut cols = (0..columns).map(|_| RawCol::new()).collect::<Vec<_>>();
+        for row in 0..rows {
+            for (i, col) in cols.iter_mut().enumerate() {
+                if i % 2 == 0 {
+                    col.push(&format!("{}", (row * 31 + i) % 1000));
+                } else {
+                    col.push(&format!("value{}-{}", i, (row * 7919) % (100 * i)));
+                }
+            }
+        }
+        (cols, (0..columns).map(|i| format!("col{}", i)).collect())
+    }
+
+    #[test]
+    fn test_parallel_create_batch() {
+        let (cols, colnames) = wide_table(10, 1000);
+        let ignore = (0..10).map(|i| i == 3).collect::<Vec<_>>();
+        let string = (0..10).map(|i| i == 4).collect::<Vec<_>>();
+        let opts = Options::new("", "default");
+        let sequential = create_batch(&mut cols.clone(), &colnames, &opts, &ignore, &string, None);
+        let parallel = create_batch(&mut cols.clone(), &colnames, &opts, &ignore, &string, Some(&mut Pool::new(4)));
+        assert_eq!(sequential.len(), 9);
+        assert_eq!(sequential.iter().map(|c| c.name()).collect::<Vec<_>>(),
+                   parallel.iter().map(|c| c.name()).collect::<Vec<_>>());
+        for (a, b) in sequential.iter().zip(parallel.iter()) {
+            assert_eq!(a.codec().ops(), b.codec().ops());
+            assert_eq!(a.decode_strings(), b.decode_strings());
+        }
+    }
+
+    fn bench_create_batch(b: &mut Bencher, threads: usize) {
+        let (cols, colnames) = wide_table(50, 1 << 14);
+        let no = vec![false; 50];
+        let opts = Options::new("", "default");
+        let mut pool = if threads > 1 { Some(Pool::new(threads as u32)) } else { None };
+        b.iter(|| {
+            let mut cols = cols.clone();
+            black_box(create_batch(&mut cols, &colnames, &opts, &no, &no, pool.as_mut()))
+        });
+    }
+
+    #[bench]
+    fn bench_create_batch_50_columns_sequential(b: &mut Bencher) {
+        bench_create_batch(b, 1);
+    }
+
+    #[bench]
+    fn bench_create_batch_50_columns_parallel(b: &mut Bencher) {
+        bench_create_batch(b, 8);
+    }
+
+    #[test]
+    fn test_infer_schema() {
+        let schema = infer_schema("test_data/zero_padded.csv", 100).unwrap();
+        let summary = schema.iter()
+            .map(|c| (c.name.as_str(), c.inferred_type, c.encoding, c.distinct_est))
+            .collect::<Vec<_>>();
+        assert_eq!(summary, vec![
+            ("id", BasicType::Integer, "integer", 4),
+            ("zip", BasicType::Integer, "integer", 4),
+            ("score", BasicType::Integer, "integer", 4),
+            ("city", BasicType::String, "dictionary", 2),
+            ("comment", BasicType::String, "dictionary", 2),
+        ]);
+        assert_eq!(schema.iter().map(|c| c.leading_zeros).collect::<Vec<_>>(), vec![false, true, false, false, false]);
+        assert_eq!(schema[4].null_est, 0.75);
+        assert_eq!(schema[1].null_est, 0.0);
+        assert_eq!(infer_schema("test_data/zero_padded.csv", 1).unwrap()[4].inferred_type, BasicType::Null);
+
+        let opts = Options::new("test_data/zero_padded.csv", "").with_keep_leading_zeros(true);
+        let schema = infer_schema_with_options(&opts, 100).unwrap();
+        assert_eq!((schema[0].inferred_type, schema[1].inferred_type), (BasicType::Integer, BasicType::String));
+        assert_eq!(schema[1].encoding, "dictionary");
+
+        assert!(has_leading_zeros("007") && has_leading_zeros("-01"));
+        assert!(!has_leading_zeros("0") && !has_leading_zeros("0.5") && !has_leading_zeros("10"));
+    }
+}
@@ -1,5 +1,7 @@
 use chrono::prelude::*;
 
+use ingest::timestamps::{parse_timestamp, TimeUnit};
+
 pub type Extractor = fn(&str) -> i64;
 
 pub fn multiply_by_100(field: &str) -> i64 {
@@ -40,4 +42,19 @@ pub fn date_time(field: &str) -> i64 {
     Utc.datetime_from_str(field, "%Y-%m-%d %H:%M:%S")
         .expect(&format!("Failed to parse {} as date time", &field))
         .timestamp()
-}
\ No newline at end of file
+}
+pub fn timestamp_seconds(field: &str) -> i64 {
+    timestamp(field, TimeUnit::Seconds)
+}
+
+pub fn timestamp_millis(field: &str) -> i64 {
+    timestamp(field, TimeUnit::Millis)
+}
+
+pub fn timestamp_micros(field: &str) -> i64 {
+    timestamp(field, TimeUnit::Micros)
+}
+
+fn timestamp(field: &str, unit: TimeUnit) -> i64 {
+    parse_timestamp(field, unit).expect(&format!("Failed to parse {} as timestamp", &field))
+}
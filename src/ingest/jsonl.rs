@@ -0,0 +1,65 @@
+extern crate serde_json;
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::sync::Arc;
+
+use self::serde_json::Value;
+
+use mem_store::column::*;
+use mem_store::column_builder::*;
+
+
+/// Loads the fields `columns` from a file of newline delimited JSON objects into string columns.
+/// Missing fields and JSON nulls become null values, numbers and booleans are converted to their textual
+/// representation and nested objects and arrays are stored as (compact) JSON text.
+pub fn load(path: &str, columns: &[&str]) -> Result<Vec<Arc<Column>>, String> {
+    let file = File::open(path).map_err(|x| x.to_string())?;
+    let mut builders = columns.iter().map(|_| StringColBuilder::new()).collect::<Vec<_>>();
+    for (line_num, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|x| x.to_string())?;
+        if line.trim().is_empty() { continue; }
+        let object = match serde_json::from_str(&line) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => return Err(format!("Line {} is not a JSON object", line_num + 1)),
+            Err(err) => return Err(format!("Failed to parse line {}: {}", line_num + 1, err)),
+        };
+        for (builder, &column) in builders.iter_mut().zip(columns) {
+            match object.get(column) {
+                None | Some(&Value::Null) => builder.push_null(),
+                Some(&Value::String(ref string)) => builder.push(string),
+                Some(value) => builder.push(&value.to_string()),
+            }
+        }
+    }
+    Ok(builders.into_iter()
+        .zip(columns)
+        .map(|(builder, name)| builder.finalize(name))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(s: &str) -> Option<String> { Some(s.to_string()) }
+
+    #[test]
+    fn test_load() {
+        let columns = load("test_data/logs.jsonl", &["level", "status", "tags", "user"]).unwrap();
+        assert!(columns.iter().all(|c| c.len() == 5));
+        assert_eq!(columns[0].group_count().unwrap(),
+                   vec![(value("error"), 2), (value("info"), 3)]);
+        assert_eq!(columns[1].group_count().unwrap(),
+                   vec![(None, 1), (value("200"), 2), (value("404"), 1), (value("true"), 1)]);
+        assert_eq!(columns[2].group_count().unwrap(),
+                   vec![(None, 2), (value("[\"a\",\"b\"]"), 1), (value("[]"), 1), (value("{\"x\":1}"), 1)]);
+        assert_eq!(columns[3].group_count().unwrap(),
+                   vec![(None, 5)]);
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        assert!(load("test_data/tiny.csv", &["ts"]).is_err());
+    }
+}
@@ -1,6 +1,10 @@
 pub mod csv_loader;
+pub mod jsonl;
+#[cfg(feature = "enable_parquet")]
+pub mod parquet;
 pub mod raw_val;
 pub mod input_column;
 pub mod buffer;
 pub mod extractor;
+pub mod timestamps;
 pub mod nyc_taxi_data;
\ No newline at end of file
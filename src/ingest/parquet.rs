@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::str;
+use std::sync::Arc;
+
+use byteorder::{ByteOrder, LittleEndian};
+
+use parquet::basic::Encoding;
+use parquet::column::page::Page;
+use parquet::column::reader::ColumnReader;
+use parquet::data_type::ByteArray;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+
+use errors::QueryError;
+use mem_store::column::*;
+use mem_store::column_builder::*;
+use mem_store::strings::{unordered_dictionary_column, StringColumnOptions};
+
+
+const BATCH_SIZE: usize = 1024;
+
+/// Reads the string column `column` of a Parquet file.
+/// If all pages of the column are dictionary encoded, the Parquet dictionary and indices are used for the
+/// dictionary of the resulting column directly without decoding and deduplicating the strings.
+/// Otherwise, the values are decoded and passed to `build_string_column`.
+pub fn read_string_column(path: &str,
+                          column: &str,
+                          options: &StringColumnOptions) -> Result<Arc<Column>, QueryError> {
+    let reader = open(path)?;
+    let index = reader.metadata().file_metadata().schema_descr().columns().iter()
+        .position(|c| c.name() == column)
+        .ok_or_else(|| QueryError::FatalError(format!("Column {} not found in {}", column, path)))?;
+    match read_dictionary_encoded(&reader, index)? {
+        Some((codes, dictionary)) => {
+            let dictionary = dictionary.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+            Ok(unordered_dictionary_column(column, &codes, &dictionary, options))
+        }
+        None => read_plain(&reader, index, column, options),
+    }
+}
+
+fn open(path: &str) -> Result<SerializedFileReader<File>, QueryError> {
+    let file = File::open(path)
+        .map_err(|err| QueryError::FatalError(format!("Failed to open {}: {}", path, err)))?;
+    SerializedFileReader::new(file).map_err(parquet_error)
+}
+
+/// Returns the codes and dictionary of the column, or `None` if some page is not dictionary encoded.
+/// Each row group has its own dictionary, whose entries are merged into a single dictionary without duplicates and the
+/// indices of the row group remapped accordingly.
+/// Rows whose definition level is below the maximum are null and refer to a `None` entry.
+fn read_dictionary_encoded(reader: &SerializedFileReader<File>, index: usize)
+                           -> Result<Option<(Vec<u32>, Vec<Option<String>>)>, QueryError> {
+    let mut codes = Vec::new();
+    let mut dictionary = Vec::<Option<String>>::new();
+    let mut dictionary_codes = HashMap::<String, u32>::new();
+    let mut null_code = None;
+    for i in 0..reader.num_row_groups() {
+        let row_group = reader.get_row_group(i).map_err(parquet_error)?;
+        let max_def_level = row_group.metadata().column(index).column_descr().max_def_level();
+        let mut pages = row_group.get_column_page_reader(index).map_err(parquet_error)?;
+        // Maps indices into the dictionary of this row group onto codes of the merged dictionary
+        let mut remap = Vec::<u32>::new();
+        while let Some(page) = pages.get_next_page().map_err(parquet_error)? {
+            match page {
+                Page::DictionaryPage { buf, num_values, .. } => {
+                    let mut data = buf.data();
+                    for _ in 0..num_values {
+                        ensure_len(data, 4)?;
+                        let len = LittleEndian::read_u32(data) as usize;
+                        ensure_len(data, 4 + len)?;
+                        let string = str::from_utf8(&data[4..(4 + len)]).map_err(parquet_error)?;
+                        let code = match dictionary_codes.get(string) {
+                            Some(&code) => code,
+                            None => {
+                                let code = dictionary.len() as u32;
+                                dictionary.push(Some(string.to_string()));
+                                dictionary_codes.insert(string.to_string(), code);
+                                code
+                            }
+                        };
+                        remap.push(code);
+                        data = &data[(4 + len)..];
+                    }
+                }
+                Page::DataPage { buf, num_values, encoding, def_level_encoding, .. } => {
+                    if encoding != Encoding::PLAIN_DICTIONARY && encoding != Encoding::RLE_DICTIONARY {
+                        return Ok(None);
+                    }
+                    let mut data = buf.data();
+                    let num_values = num_values as usize;
+                    let def_levels = if max_def_level > 0 {
+                        if def_level_encoding != Encoding::RLE { return Ok(None); }
+                        ensure_len(data, 4)?;
+                        let len = LittleEndian::read_u32(data) as usize;
+                        ensure_len(data, 4 + len)?;
+                        let levels = decode_rle_hybrid(&data[4..(4 + len)], bit_width(max_def_level as u32), num_values)?;
+                        data = &data[(4 + len)..];
+                        Some(levels)
+                    } else {
+                        None
+                    };
+                    let present = def_levels.as_ref()
+                        .map_or(num_values, |levels| levels.iter().filter(|&&l| l == max_def_level as u32).count());
+                    ensure_len(data, 1)?;
+                    let indices = decode_rle_hybrid(&data[1..], data[0], present)?;
+                    let mut indices = indices.into_iter();
+                    for j in 0..num_values {
+                        let is_null = def_levels.as_ref().map_or(false, |levels| levels[j] != max_def_level as u32);
+                        if is_null {
+                            let code = *null_code.get_or_insert_with(|| {
+                                dictionary.push(None);
+                                dictionary.len() as u32 - 1
+                            });
+                            codes.push(code);
+                        } else {
+                            let index = indices.next()
+                                .ok_or_else(|| QueryError::FatalError("Missing dictionary index".to_string()))?;
+                            let code = remap.get(index as usize)
+                                .ok_or_else(|| QueryError::FatalError("Dictionary index out of bounds".to_string()))?;
+                            codes.push(*code);
+                        }
+                    }
+                }
+                _ => return Ok(None),
+            }
+        }
+    }
+    Ok(Some((codes, dictionary)))
+}
+
+fn read_plain(reader: &SerializedFileReader<File>,
+              index: usize,
+              name: &str,
+              options: &StringColumnOptions) -> Result<Arc<Column>, QueryError> {
+    let mut builder = StringColBuilder::with_options(*options);
+    for i in 0..reader.num_row_groups() {
+        let row_group = reader.get_row_group(i).map_err(parquet_error)?;
+        let max_def_level = row_group.metadata().column(index).column_descr().max_def_level();
+        let mut column_reader = match row_group.get_column_reader(index).map_err(parquet_error)? {
+            ColumnReader::ByteArrayColumnReader(column_reader) => column_reader,
+            _ => bail!(QueryError::TypeError, "Column {} is not a string column", name),
+        };
+        let mut values = vec![ByteArray::new(); BATCH_SIZE];
+        let mut def_levels = vec![0; BATCH_SIZE];
+        loop {
+            let (values_read, levels_read) = column_reader
+                .read_batch(BATCH_SIZE, Some(&mut def_levels[..]), None, &mut values[..])
+                .map_err(parquet_error)?;
+            let rows = if max_def_level > 0 { levels_read } else { values_read };
+            if rows == 0 { break; }
+            let mut present = values[..values_read].iter();
+            for j in 0..rows {
+                if max_def_level > 0 && def_levels[j] != max_def_level {
+                    builder.push_null();
+                } else {
+                    let value = present.next()
+                        .ok_or_else(|| QueryError::FatalError("Missing value".to_string()))?;
+                    builder.push(value.as_utf8().map_err(parquet_error)?);
+                }
+            }
+        }
+    }
+    Ok(builder.finalize(name))
+}
+
+/// Decodes `count` values encoded with Parquet's RLE/bit-packing hybrid encoding.
+fn decode_rle_hybrid(mut data: &[u8], bit_width: u8, count: usize) -> Result<Vec<u32>, QueryError> {
+    let mut values = Vec::with_capacity(count);
+    let value_bytes = (bit_width as usize + 7) / 8;
+    while values.len() < count {
+        let mut header = 0usize;
+        let mut shift = 0;
+        loop {
+            ensure_len(data, 1)?;
+            let byte = data[0];
+            data = &data[1..];
+            header |= ((byte & 0x7f) as usize) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 { break; }
+        }
+        if header & 1 == 1 {
+            let len = (header >> 1) * 8;
+            let bytes = (len * bit_width as usize + 7) / 8;
+            ensure_len(data, bytes)?;
+            for i in 0..len {
+                if values.len() == count { break; }
+                let mut value = 0u32;
+                for b in 0..bit_width as usize {
+                    let bit = i * bit_width as usize + b;
+                    value |= (((data[bit / 8] >> (bit % 8)) & 1) as u32) << b;
+                }
+                values.push(value);
+            }
+            data = &data[bytes..];
+        } else {
+            let len = header >> 1;
+            ensure_len(data, value_bytes)?;
+            let mut value = 0u32;
+            for (i, &byte) in data[..value_bytes].iter().enumerate() {
+                value |= (byte as u32) << (8 * i);
+            }
+            data = &data[value_bytes..];
+            for _ in 0..len {
+                if values.len() == count { break; }
+                values.push(value);
+            }
+        }
+    }
+    Ok(values)
+}
+
+fn parquet_error<E: ::std::fmt::Display>(err: E) -> QueryError {
+    QueryError::FatalError(format!("Failed to read Parquet file: {}", err))
+}
+
+fn bit_width(max: u32) -> u8 {
+    (32 - max.leading_zeros()) as u8
+}
+
+fn ensure_len(data: &[u8], len: usize) -> Result<(), QueryError> {
+    ensure!(data.len() >= len, "Unexpected end of page: expected {} bytes, got {}", len, data.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+    use tempdir::TempDir;
+    use parquet::column::writer::ColumnWriter;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::{FileWriter, RowGroupWriter, SerializedFileWriter};
+    use parquet::schema::parser::parse_message_type;
+    use engine::types::EncodingType;
+    use mem_store::codec::CodecOp;
+    use mem_store::strings::nullable_dictionary_entries;
+
+    fn values() -> Vec<Option<String>> {
+        (0..1000).map(|i| if i % 7 == 0 { None } else { Some(format!("value{}", i % 13)) }).collect()
+    }
+
+    fn write_file(path: &str, dictionary: bool) {
+        let schema = Rc::new(parse_message_type("message schema { OPTIONAL BYTE_ARRAY name (UTF8); }").unwrap());
+        let props = Rc::new(WriterProperties::builder().set_dictionary_enabled(dictionary).build());
+        let mut writer = SerializedFileWriter::new(File::create(path).unwrap(), schema, props).unwrap();
+        // Two row groups to exercise dictionary concatenation
+        for chunk in values().chunks(600) {
+            let mut row_group = writer.next_row_group().unwrap();
+            let mut column = row_group.next_column().unwrap().unwrap();
+            if let ColumnWriter::ByteArrayColumnWriter(ref mut typed) = column {
+                let present = chunk.iter()
+                    .filter_map(|v| v.as_ref().map(|s| ByteArray::from(s.as_str())))
+                    .collect::<Vec<_>>();
+                let def_levels = chunk.iter().map(|v| v.is_some() as i16).collect::<Vec<_>>();
+                typed.write_batch(&present, Some(&def_levels), None).unwrap();
+            }
+            row_group.close_column(column).unwrap();
+            writer.close_row_group(row_group).unwrap();
+        }
+        writer.close().unwrap();
+    }
+
+    #[test]
+    fn test_dictionary_fast_path() {
+        let dir = TempDir::new("test_parquet").unwrap();
+        let path = dir.path().join("dict.parquet");
+        let path = path.to_str().unwrap();
+        write_file(path, true);
+
+        let reader = open(path).unwrap();
+        assert!(read_dictionary_encoded(&reader, 0).unwrap().is_some());
+        let column = read_string_column(path, "name", &StringColumnOptions::default()).unwrap();
+        assert_eq!(column.codec().ops().last(), Some(&CodecOp::UnorderedDictLookup(EncodingType::U8)));
+        assert_eq!(column.decode_strings(), Some(values()));
+        assert_eq!(column.null_count(), values().iter().filter(|v| v.is_none()).count());
+    }
+
+    #[test]
+    fn test_dictionary_shared_between_row_groups() {
+        let dir = TempDir::new("test_parquet").unwrap();
+        let path = dir.path().join("dict.parquet");
+        let path = path.to_str().unwrap();
+        write_file(path, true);
+
+        let column = read_string_column(path, "name", &StringColumnOptions::default()).unwrap();
+        // 13 distinct values and null, even though both row groups contain every value
+        let entries = nullable_dictionary_entries(&column);
+        assert_eq!(entries.len(), 14);
+        assert_eq!(entries.iter().filter(|entry| **entry == Some("value1")).count(), 1);
+        assert!(column.dictionary_code("value1").is_some());
+
+        let value1 = values().iter()
+            .map(|v| (v.as_ref().map(|s| s.as_str()) == Some("value1")) as u8)
+            .collect::<Vec<_>>();
+        assert!(value1[..600].contains(&1) && value1[600..].contains(&1));
+        assert_eq!(column.filter_eq_str(Some("value1"), false), Some(value1.clone()));
+        let groups = column.group_count().unwrap();
+        assert_eq!(groups.len(), 14);
+        let count = value1.iter().filter(|&&x| x == 1).count() as u64;
+        assert!(groups.contains(&(Some("value1".to_string()), count)));
+        assert!(groups.contains(&(None, values().iter().filter(|v| v.is_none()).count() as u64)));
+    }
+
+    #[test]
+    fn test_plain_fallback() {
+        let dir = TempDir::new("test_parquet").unwrap();
+        let path = dir.path().join("plain.parquet");
+        let path = path.to_str().unwrap();
+        write_file(path, false);
+
+        let reader = open(path).unwrap();
+        assert!(read_dictionary_encoded(&reader, 0).unwrap().is_none());
+        let column = read_string_column(path, "name", &StringColumnOptions::default()).unwrap();
+        assert_eq!(column.decode_strings(), Some(values()));
+    }
+
+    #[test]
+    fn test_rle_hybrid() {
+        // Run of 5 threes followed by a bit-packed group of 8 values with bit width 2
+        let data = [10, 3, 3, 0b11_10_01_00, 0b00_01_10_11];
+        assert_eq!(decode_rle_hybrid(&data, 2, 13).unwrap(),
+                   vec![3, 3, 3, 3, 3, 0, 1, 2, 3, 3, 2, 1, 0]);
+        assert!(decode_rle_hybrid(&data, 2, 14).is_err());
+    }
+}
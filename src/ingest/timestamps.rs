@@ -0,0 +1,61 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+
+/// Formats without time zone that are accepted by `parse_timestamp` in addition to RFC 3339. Times are UTC.
+const DATE_TIME_FORMATS: [&str; 3] = ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f", "%Y/%m/%d %H:%M:%S%.f"];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimeUnit {
+    Seconds,
+    Millis,
+    Micros,
+}
+
+impl TimeUnit {
+    pub fn per_second(self) -> i64 {
+        match self {
+            TimeUnit::Seconds => 1,
+            TimeUnit::Millis => 1_000,
+            TimeUnit::Micros => 1_000_000,
+        }
+    }
+}
+
+/// Parses an RFC 3339 timestamp, a date time in one of `DATE_TIME_FORMATS` or a date (`%Y-%m-%d`) into the number
+/// of `unit`s since the Unix epoch. Sub-unit precision is truncated.
+pub fn parse_timestamp(s: &str, unit: TimeUnit) -> Option<i64> {
+    let s = s.trim();
+    let date_time = match DateTime::parse_from_rfc3339(s) {
+        Ok(date_time) => date_time.naive_utc(),
+        Err(_) => match DATE_TIME_FORMATS.iter().filter_map(|f| NaiveDateTime::parse_from_str(s, f).ok()).next() {
+            Some(date_time) => date_time,
+            None => NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?.and_hms(0, 0, 0),
+        },
+    };
+    let subsec = date_time.timestamp_subsec_nanos() as i64 / (1_000_000_000 / unit.per_second());
+    Some(date_time.timestamp() * unit.per_second() + subsec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        assert_eq!(parse_timestamp("2018-03-04T05:06:07Z", TimeUnit::Seconds), Some(1_520_139_967));
+        assert_eq!(parse_timestamp("2018-03-04T06:06:07.25+01:00", TimeUnit::Millis), Some(1_520_139_967_250));
+        assert_eq!(parse_timestamp("2018-03-04 05:06:07.123456", TimeUnit::Micros), Some(1_520_139_967_123_456));
+        assert_eq!(parse_timestamp("2018-03-04 05:06:07", TimeUnit::Millis), Some(1_520_139_967_000));
+        assert_eq!(parse_timestamp("2018/03/04 05:06:07", TimeUnit::Seconds), Some(1_520_139_967));
+        assert_eq!(parse_timestamp("2018-03-04", TimeUnit::Seconds), Some(1_520_121_600));
+        assert_eq!(parse_timestamp("1969-12-31 23:59:59", TimeUnit::Seconds), Some(-1));
+        assert_eq!(parse_timestamp("yesterday", TimeUnit::Seconds), None);
+    }
+
+    #[test]
+    fn test_timestamp_extractor() {
+        use ingest::extractor;
+        assert_eq!(extractor::timestamp_millis("2018-03-04T05:06:07.5Z"), 1_520_139_967_500);
+        assert_eq!(extractor::timestamp_seconds("2018-03-04"), 1_520_121_600);
+    }
+}
@@ -1,4 +1,5 @@
 #![feature(fn_traits, integer_atomics, refcell_replace_swap, specialization, trait_alias, core_intrinsics, box_patterns, int_to_from_bytes, iterator_step_by)]
+#![cfg_attr(test, feature(test))]
 #[macro_use]
 extern crate nom;
 #[macro_use]
@@ -23,12 +24,25 @@ extern crate time;
 extern crate tempdir;
 extern crate fnv;
 extern crate byteorder;
+extern crate bit_vec;
 extern crate lru;
+extern crate scoped_threadpool;
+extern crate memmap;
 extern crate crypto;
 extern crate hex;
 #[cfg(feature = "enable_rocksdb")]
 extern crate capnp;
+#[cfg(feature = "enable_arrow")]
+extern crate arrow;
+#[cfg(feature = "enable_parquet")]
+extern crate parquet;
+#[cfg(feature = "enable_zstd")]
+extern crate zstd;
+#[cfg(feature = "enable_aes_gcm")]
+extern crate rand;
 extern crate std_semaphore;
+#[cfg(test)]
+extern crate test;
 
 #[macro_use]
 mod trace;
@@ -53,6 +67,8 @@ pub use ingest::raw_val::RawVal as Value;
 pub use locustdb::LocustDB as LocustDB;
 pub use locustdb::Options as Options;
 pub use mem_store::table::TableStats;
+pub use mem_store::strings::DictionaryOrder;
+pub use stringpack::StringPackerIterator;
 pub use disk_store::noop_storage::NoopStorage;
 
 pub type QueryResult = Result<QueryOutput, QueryError>;
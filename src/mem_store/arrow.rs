@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayData, ArrayRef, DictionaryArray, StringArray, StringBuilder};
+use arrow::buffer::Buffer;
+use arrow::datatypes::{ArrowDictionaryKeyType, DataType, ToByteSlice, UInt16Type, UInt32Type, UInt8Type};
+
+use mem_store::*;
+use mem_store::column::MappedSection;
+use mem_store::strings;
+
+
+/// Converts a string column into an Arrow array.
+/// Dictionary encoded columns map directly onto a `DictionaryArray` with the codes as keys and the
+/// dictionary as values, all other string columns are decoded into a `StringArray`.
+/// Null values of dictionary encoded columns are marked in the validity bitmap of the keys.
+/// Returns `None` if the column is not a string column.
+pub fn to_arrow(column: &Column) -> Option<ArrayRef> {
+    match column.codec().ops().last() {
+        Some(op) if strings::is_dictionary_lookup(op) => {
+            let values = string_array(&strings::nullable_dictionary_entries(column));
+            let null = column.null_code();
+            Some(match *strings::first_section(column) {
+                DataSection::U8(ref codes) => dictionary_array::<UInt8Type>(codes, &values, null),
+                DataSection::U16(ref codes) => dictionary_array::<UInt16Type>(codes, &values, null),
+                DataSection::U32(ref codes) => dictionary_array::<UInt32Type>(codes, &values, null),
+                DataSection::Mapped(MappedSection::U8(ref codes)) =>
+                    dictionary_array::<UInt8Type>(codes, &values, null),
+                DataSection::Mapped(MappedSection::U16(ref codes)) =>
+                    dictionary_array::<UInt16Type>(codes, &values, null),
+                DataSection::Mapped(MappedSection::U32(ref codes)) =>
+                    dictionary_array::<UInt32Type>(codes, &values, null),
+                ref section => panic!("Unexpected index section {:?}", section),
+            })
+        }
+        _ => {
+            let values = strings::decode_strings(column)?;
+            let values = values.iter().map(|v| v.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+            Some(Arc::new(string_array(&values)))
+        }
+    }
+}
+
+fn string_array(values: &[Option<&str>]) -> StringArray {
+    let mut builder = StringBuilder::new(values.len());
+    for value in values {
+        match *value {
+            Some(value) => builder.append_value(value),
+            None => builder.append_null(),
+        }.expect("Failed to append to string array");
+    }
+    builder.finish()
+}
+
+fn dictionary_array<K>(codes: &[K::Native], values: &StringArray, null_code: Option<u32>) -> ArrayRef
+    where K: ArrowDictionaryKeyType, K::Native: Into<u32> {
+    let mut data = ArrayData::builder(DataType::Dictionary(Box::new(K::get_data_type()), Box::new(DataType::Utf8)))
+        .len(codes.len())
+        .add_buffer(Buffer::from(codes.to_byte_slice()))
+        .add_child_data(values.data());
+    if let Some(null_code) = null_code {
+        let mut validity = vec![0u8; (codes.len() + 7) / 8];
+        let mut null_count = 0;
+        for (i, &code) in codes.iter().enumerate() {
+            if code.into() == null_code {
+                null_count += 1;
+            } else {
+                validity[i / 8] |= 1 << (i % 8);
+            }
+        }
+        data = data.null_count(null_count).null_bit_buffer(Buffer::from(&validity[..]));
+    }
+    Arc::new(DictionaryArray::<K>::from(data.build()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mem_store::column_builder::*;
+    use mem_store::strings::*;
+
+    #[test]
+    fn test_dictionary_to_arrow() {
+        let values = (0..1000).map(|i| format!("value{}", i % 300)).collect::<Vec<_>>();
+        let mut builder = StringColBuilder::new();
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("test");
+        let decoded = column.decode_strings().unwrap();
+
+        let array = to_arrow(&column).unwrap();
+        let dictionary = array.as_any().downcast_ref::<DictionaryArray<UInt16Type>>().unwrap();
+        let dictionary_values = dictionary.values();
+        let dictionary_values = dictionary_values.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(dictionary.len(), decoded.len());
+        assert_eq!(dictionary_values.len(), 300);
+        for (i, key) in dictionary.keys().enumerate() {
+            let value = dictionary_values.value(key.unwrap() as usize);
+            assert_eq!(Some(value.to_string()), decoded[i]);
+        }
+    }
+
+    #[test]
+    fn test_dictionary_with_nulls_to_arrow() {
+        let values = [Some("a"), None, Some(""), Some("a"), None];
+        let mut builder = StringColBuilder::new();
+        for value in &values {
+            builder.push_opt(*value);
+        }
+        let column = builder.finalize("test");
+        assert!(column.null_code().is_some());
+
+        let array = to_arrow(&column).unwrap();
+        let dictionary = array.as_any().downcast_ref::<DictionaryArray<UInt8Type>>().unwrap();
+        let dictionary_values = dictionary.values();
+        let dictionary_values = dictionary_values.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(dictionary.null_count(), 2);
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(dictionary.is_null(i), value.is_none());
+            if let Some(value) = *value {
+                let key = dictionary.keys().nth(i).unwrap().unwrap();
+                assert_eq!(dictionary_values.value(key as usize), value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_packed_to_arrow() {
+        let mut builder = StringColBuilder::with_options(StringColumnOptions::default().with_max_unique_strings(0));
+        for value in &[Some("abc"), None, Some("de"), Some(""), Some("ab")] {
+            match *value {
+                Some(value) => builder.push(value),
+                None => builder.push_null(),
+            }
+        }
+        let column = builder.finalize("test");
+        assert!(column.codec().ops().contains(&CodecOp::UnpackStrings), "{:?}", column);
+        let decoded = column.decode_strings().unwrap();
+
+        let array = to_arrow(&column).unwrap();
+        let strings = array.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(strings.len(), decoded.len());
+        for (i, value) in decoded.iter().enumerate() {
+            match *value {
+                Some(ref value) => assert_eq!(strings.value(i), value.as_str()),
+                None => assert!(strings.is_null(i)),
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use num::{NumCast, PrimInt};
+
+
+/// Number of bits required to store codes `0..n`, at least 1.
+pub fn bits_required(n: usize) -> u8 {
+    if n <= 2 { 1 } else { (64 - (n as u64 - 1).leading_zeros()) as u8 }
+}
+
+/// Packs each code into `bits` bits of consecutive 64 bit words, starting at the least significant bit.
+/// Codes may span two words.
+pub fn pack<T: PrimInt>(codes: &[T], bits: u8) -> Vec<u64> {
+    let bits = bits as usize;
+    let mut words = vec![0u64; (codes.len() * bits + 63) / 64];
+    for (i, code) in codes.iter().enumerate() {
+        let code = code.to_u64().unwrap();
+        debug_assert!(bits == 64 || code >> bits == 0, "code {} does not fit into {} bits", code, bits);
+        let bit = i * bits;
+        let (word, shift) = (bit / 64, bit % 64);
+        words[word] |= code << shift;
+        if shift + bits > 64 {
+            words[word + 1] |= code >> (64 - shift);
+        }
+    }
+    words
+}
+
+/// Returns the code at position `index`.
+#[inline]
+pub fn unpack(words: &[u64], bits: u8, index: usize) -> u64 {
+    let bits = bits as usize;
+    let mask = if bits == 64 { !0 } else { (1u64 << bits) - 1 };
+    let bit = index * bits;
+    let (word, shift) = (bit / 64, bit % 64);
+    let mut code = words[word] >> shift;
+    if shift + bits > 64 {
+        code |= words[word + 1] << (64 - shift);
+    }
+    code & mask
+}
+
+/// Appends the `count` codes starting at position `start` to `output`.
+pub fn unpack_range<T: PrimInt>(words: &[u64], bits: u8, start: usize, count: usize, output: &mut Vec<T>) {
+    output.reserve(count);
+    for i in start..(start + count) {
+        output.push(<T as NumCast>::from(unpack(words, bits, i)).unwrap());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits_required() {
+        assert_eq!(bits_required(0), 1);
+        assert_eq!(bits_required(2), 1);
+        assert_eq!(bits_required(3), 2);
+        assert_eq!(bits_required(256), 8);
+        assert_eq!(bits_required(257), 9);
+        assert_eq!(bits_required(3000), 12);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &bits in &[1u8, 3, 5, 8, 11, 13, 16, 31, 32] {
+            let max = (1u64 << bits) - 1;
+            // Long enough that codes cross several word boundaries for all widths
+            let codes = (0..1000u64).map(|i| (i * 2_654_435_761) & max).collect::<Vec<_>>();
+            let words = pack(&codes, bits);
+            assert_eq!(words.len(), (1000 * bits as usize + 63) / 64);
+            for (i, &code) in codes.iter().enumerate() {
+                assert_eq!(unpack(&words, bits, i), code, "bits {} index {}", bits, i);
+            }
+            let mut unpacked = Vec::<u64>::new();
+            unpack_range(&words, bits, 0, codes.len(), &mut unpacked);
+            assert_eq!(unpacked, codes);
+        }
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        // With 11 bits, code 5 occupies bits 55..66 and spans the first two words
+        let codes = (0..12u16).map(|i| 2047 - i).collect::<Vec<_>>();
+        let words = pack(&codes, 11);
+        assert_eq!(words.len(), 3);
+        assert_eq!(unpack(&words, 11, 5), 2042);
+        let mut unpacked = Vec::<u16>::new();
+        unpack_range(&words, 11, 4, 3, &mut unpacked);
+        assert_eq!(unpacked, vec![2043, 2042, 2041]);
+    }
+}
@@ -0,0 +1,75 @@
+use seahash;
+
+
+/// Bloom filter over byte strings.
+/// Uses double hashing to derive `num_hashes` bit positions from a single 64 bit hash.
+#[derive(Clone, Debug, PartialEq, HeapSizeOf)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    pub fn new(num_bits: usize, num_hashes: u32) -> BloomFilter {
+        assert!(num_hashes > 0);
+        let words = (num_bits + 63) / 64;
+        let words = if words == 0 { 1 } else { words };
+        BloomFilter {
+            bits: vec![0; words],
+            num_bits: (words * 64) as u64,
+            num_hashes,
+        }
+    }
+
+    /// Recreates a filter from the `words` and `num_hashes` of a persisted filter.
+    pub fn from_words(words: Vec<u64>, num_hashes: u32) -> BloomFilter {
+        assert!(num_hashes > 0 && !words.is_empty());
+        BloomFilter { num_bits: (words.len() * 64) as u64, bits: words, num_hashes }
+    }
+
+    pub fn words(&self) -> &[u64] { &self.bits }
+    pub fn num_hashes(&self) -> u32 { self.num_hashes }
+
+    pub fn insert(&mut self, bytes: &[u8]) {
+        let (h1, h2) = BloomFilter::hashes(bytes);
+        for i in 0..u64::from(self.num_hashes) {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+            self.bits[(bit / 64) as usize] |= 1u64 << (bit % 64);
+        }
+    }
+
+    pub fn might_contain(&self, bytes: &[u8]) -> bool {
+        let (h1, h2) = BloomFilter::hashes(bytes);
+        (0..u64::from(self.num_hashes)).all(|i| {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+            self.bits[(bit / 64) as usize] & (1u64 << (bit % 64)) != 0
+        })
+    }
+
+    fn hashes(bytes: &[u8]) -> (u64, u64) {
+        let hash = seahash::hash(bytes);
+        (hash & 0xffff_ffff, (hash >> 32) | 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_false_negatives() {
+        let mut bloom = BloomFilter::new(10_000, 7);
+        for i in 0..1000 {
+            bloom.insert(format!("value{}", i).as_bytes());
+        }
+        for i in 0..1000 {
+            assert!(bloom.might_contain(format!("value{}", i).as_bytes()));
+        }
+        let false_positives = (1000..11_000)
+            .filter(|i| bloom.might_contain(format!("value{}", i).as_bytes()))
+            .count();
+        // Expected false positive rate with 10 bits per value and 7 hashes is below 1%
+        assert!(false_positives < 300, "{}", false_positives);
+    }
+}
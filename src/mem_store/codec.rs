@@ -1,5 +1,6 @@
 use engine::query_plan::QueryPlan;
 use engine::types::*;
+use engine::vector_op::StringMatchKind;
 use ingest::raw_val::RawVal;
 
 
@@ -137,7 +138,7 @@ impl Codec {
                         section_index,
                         None))
                 }
-                CodecOp::DictLookup(t) => {
+                CodecOp::DictLookup(t) | CodecOp::UnorderedDictLookup(t) => {
                     let dict_data = stack.pop().unwrap();
                     let dict_indices = stack.pop().unwrap();
                     let indices = stack.pop().unwrap();
@@ -149,10 +150,25 @@ impl Codec {
                 }
                 CodecOp::LZ4(t, decoded_length) =>
                     Box::new(QueryPlan::LZ4Decode(stack.pop().unwrap(), decoded_length, t)),
+                CodecOp::UnpackBits(t, bits, decoded_length) =>
+                    Box::new(QueryPlan::UnpackBits(stack.pop().unwrap(), bits, decoded_length, t)),
+                CodecOp::RunLengthDecode(t, decoded_length) => {
+                    let lengths = stack.pop().unwrap();
+                    let values = stack.pop().unwrap();
+                    Box::new(QueryPlan::RunLengthDecode(values, lengths, decoded_length, t))
+                }
                 CodecOp::UnpackStrings =>
                     Box::new(QueryPlan::UnpackStrings(stack.pop().unwrap())),
                 CodecOp::UnhexpackStrings(upper, total_bytes) =>
                     Box::new(QueryPlan::UnhexpackStrings(stack.pop().unwrap(), upper, total_bytes)),
+                CodecOp::UnpackFrontCodedStrings(total_bytes) =>
+                    Box::new(QueryPlan::UnpackFrontCodedStrings(stack.pop().unwrap(), total_bytes)),
+                CodecOp::UnpackSymbolCodedStrings(total_bytes) =>
+                    Box::new(QueryPlan::UnpackSymbolCodedStrings(stack.pop().unwrap(), total_bytes)),
+                CodecOp::UnpackComponentCodedStrings(total_bytes) =>
+                    Box::new(QueryPlan::UnpackComponentCodedStrings(stack.pop().unwrap(), total_bytes)),
+                CodecOp::UnpackHybridStrings =>
+                    Box::new(QueryPlan::UnpackHybridStrings(stack.pop().unwrap())),
                 CodecOp::Unknown => panic!("unkown decode plan!"),
             };
             stack.push(plan);
@@ -181,9 +197,32 @@ impl Codec {
     pub fn is_elementwise_decodable(&self) -> bool { self.is_fixed_width }
     pub fn is_identity(&self) -> bool { self.ops.is_empty() }
 
+    /// Width in bytes of each code or integer once decompressed, run length decoded or bit unpacked, e.g. to allocate
+    /// buffers for them. Returns `None` for variable width encodings such as packed strings.
+    pub fn code_width_bytes(&self) -> Option<usize> {
+        let first_op = self.ops.iter().find(|op| match **op {
+            CodecOp::PushDataSection(_) | CodecOp::LZ4(..) | CodecOp::RunLengthDecode(..) | CodecOp::UnpackBits(..) => false,
+            _ => true,
+        });
+        let t = match first_op {
+            None => self.encoding_type,
+            Some(&CodecOp::Add(t, _)) | Some(&CodecOp::Delta(t)) | Some(&CodecOp::ToI64(t)) |
+            Some(&CodecOp::DictLookup(t)) | Some(&CodecOp::UnorderedDictLookup(t)) => t,
+            Some(_) => return None,
+        };
+        match t {
+            EncodingType::U8 => Some(1),
+            EncodingType::U16 => Some(2),
+            EncodingType::U32 => Some(4),
+            EncodingType::U64 | EncodingType::I64 => Some(8),
+            _ => None,
+        }
+    }
+
     pub fn encode_str(&self, string_const: Box<QueryPlan>) -> Box<QueryPlan> {
         match self.ops[..] {
-            [CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), CodecOp::DictLookup(_)] =>
+            [CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), CodecOp::DictLookup(_)] |
+            [CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), CodecOp::UnorderedDictLookup(_)] =>
                 Box::new(QueryPlan::InverseDictLookup(
                     Box::new(QueryPlan::ReadColumnSection(
                         self.column_name.to_string(), 1, None)),
@@ -194,6 +233,37 @@ impl Codec {
         }
     }
 
+    /// Evaluates a string predicate on each dictionary entry, producing a bitmap that can be indexed by code.
+    pub fn dict_match(&self, string_const: Box<QueryPlan>, kind: StringMatchKind) -> Box<QueryPlan> {
+        match self.ops[..] {
+            [CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), CodecOp::DictLookup(_)] |
+            [CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), CodecOp::UnorderedDictLookup(_)] =>
+                Box::new(QueryPlan::DictMatch(
+                    Box::new(QueryPlan::ReadColumnSection(
+                        self.column_name.to_string(), 1, None)),
+                    Box::new(QueryPlan::ReadColumnSection(
+                        self.column_name.to_string(), 2, None)),
+                    string_const,
+                    kind)),
+            _ => panic!("dict_match not supported for {:?}", &self.ops),
+        }
+    }
+
+    /// Encodes a string constant as the bound on dictionary codes used to evaluate `<` (lower) or `>` (upper).
+    pub fn encode_str_bound(&self, string_const: Box<QueryPlan>, upper: bool) -> Box<QueryPlan> {
+        match self.ops[..] {
+            [CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), CodecOp::DictLookup(_)] =>
+                Box::new(QueryPlan::DictBound(
+                    Box::new(QueryPlan::ReadColumnSection(
+                        self.column_name.to_string(), 1, None)),
+                    Box::new(QueryPlan::ReadColumnSection(
+                        self.column_name.to_string(), 2, None)),
+                    string_const,
+                    upper)),
+            _ => panic!("encode_str_bound not supported for {:?}", &self.ops),
+        }
+    }
+
     pub fn encode_int(&self, x: i64) -> RawVal {
         if let CodecOp::Add(_, y) = self.ops[0] {
             assert_eq!(self.ops.len(), 1);
@@ -276,9 +346,24 @@ pub enum CodecOp {
     ToI64(EncodingType),
     PushDataSection(usize),
     DictLookup(EncodingType),
+    /// Dictionary lookup where the order of codes does not correspond to the order of the strings.
+    UnorderedDictLookup(EncodingType),
     LZ4(EncodingType, usize),
+    /// Expands runs of values with the run lengths stored in the data section pushed before. Holds the decoded length.
+    RunLengthDecode(EncodingType, usize),
+    /// Unpacks integers of type `EncodingType` stored with the given number of bits each, see `bit_packing`. Holds the decoded length.
+    UnpackBits(EncodingType, u8, usize),
     UnpackStrings,
     UnhexpackStrings(bool, usize),
+    /// Front coded strings, see `FrontCodedStrings`. Holds the combined length of all decoded strings.
+    UnpackFrontCodedStrings(usize),
+    /// Strings compressed with a symbol table, see `SymbolCodedStrings`. Holds the combined length of all decoded strings.
+    UnpackSymbolCodedStrings(usize),
+    /// Strings split into components stored in a dictionary, see `ComponentCodedStrings`. Holds the combined length
+    /// of all decoded strings.
+    UnpackComponentCodedStrings(usize),
+    /// Dictionary of frequent strings with all other strings stored inline, see `HybridStrings`.
+    UnpackHybridStrings,
     Unknown,
 }
 
@@ -289,9 +374,16 @@ impl CodecOp {
             CodecOp::Delta(t) => t,
             CodecOp::ToI64(t) => t,
             CodecOp::DictLookup(t) => t,
+            CodecOp::UnorderedDictLookup(t) => t,
             CodecOp::LZ4(_, _) => EncodingType::U8,
+            CodecOp::RunLengthDecode(t, _) => t,
+            CodecOp::UnpackBits(_, _, _) => EncodingType::U64,
             CodecOp::UnpackStrings => EncodingType::U8,
             CodecOp::UnhexpackStrings(_, _) => EncodingType::U8,
+            CodecOp::UnpackFrontCodedStrings(_) => EncodingType::U8,
+            CodecOp::UnpackSymbolCodedStrings(_) => EncodingType::U8,
+            CodecOp::UnpackComponentCodedStrings(_) => EncodingType::U8,
+            CodecOp::UnpackHybridStrings => EncodingType::U8,
             CodecOp::PushDataSection(_) => panic!("PushDataSection.input_type()"),
             CodecOp::Unknown => panic!("Unknown.input_type()"),
         }
@@ -303,9 +395,16 @@ impl CodecOp {
             CodecOp::Delta(_) => BasicType::Integer,
             CodecOp::ToI64(_) => BasicType::Integer,
             CodecOp::DictLookup(_) => BasicType::String,
+            CodecOp::UnorderedDictLookup(_) => BasicType::String,
             CodecOp::LZ4(_, _) => BasicType::Integer,
+            CodecOp::RunLengthDecode(_, _) => BasicType::Integer,
+            CodecOp::UnpackBits(_, _, _) => BasicType::Integer,
             CodecOp::UnpackStrings => BasicType::String,
             CodecOp::UnhexpackStrings(_, _) => BasicType::String,
+            CodecOp::UnpackFrontCodedStrings(_) => BasicType::String,
+            CodecOp::UnpackSymbolCodedStrings(_) => BasicType::String,
+            CodecOp::UnpackComponentCodedStrings(_) => BasicType::String,
+            CodecOp::UnpackHybridStrings => BasicType::String,
             CodecOp::PushDataSection(_) => panic!("PushDataSection.input_type()"),
             CodecOp::Unknown => panic!("Unknown.output_type()"),
         }
@@ -318,9 +417,16 @@ impl CodecOp {
             CodecOp::ToI64(_) => true,
             CodecOp::PushDataSection(_) => true,
             CodecOp::DictLookup(_) => false,
+            CodecOp::UnorderedDictLookup(_) => false,
             CodecOp::LZ4(_, _) => false,
+            CodecOp::RunLengthDecode(_, _) => false,
+            CodecOp::UnpackBits(_, _, _) => false,
             CodecOp::UnpackStrings => false,
             CodecOp::UnhexpackStrings(_, _) => false,
+            CodecOp::UnpackFrontCodedStrings(_) => false,
+            CodecOp::UnpackSymbolCodedStrings(_) => false,
+            CodecOp::UnpackComponentCodedStrings(_) => false,
+            CodecOp::UnpackHybridStrings => false,
             CodecOp::Unknown => panic!("Unknown.is_summation_preserving()"),
         }
     }
@@ -332,9 +438,16 @@ impl CodecOp {
             CodecOp::ToI64(_) => true,
             CodecOp::PushDataSection(_) => true,
             CodecOp::DictLookup(_) => true,
+            CodecOp::UnorderedDictLookup(_) => false,
             CodecOp::LZ4(_, _) => false,
+            CodecOp::RunLengthDecode(_, _) => true,
+            CodecOp::UnpackBits(_, _, _) => true,
             CodecOp::UnpackStrings => false,
             CodecOp::UnhexpackStrings(_, _) => false,
+            CodecOp::UnpackFrontCodedStrings(_) => false,
+            CodecOp::UnpackSymbolCodedStrings(_) => false,
+            CodecOp::UnpackComponentCodedStrings(_) => false,
+            CodecOp::UnpackHybridStrings => false,
             CodecOp::Unknown => panic!("Unknown.is_order_preserving()"),
         }
     }
@@ -346,9 +459,16 @@ impl CodecOp {
             CodecOp::ToI64(_) => true, // TODO(clemens): no it's not (hack to make grouping key work)
             CodecOp::PushDataSection(_) => true,
             CodecOp::DictLookup(_) => true,
+            CodecOp::UnorderedDictLookup(_) => true,
             CodecOp::LZ4(_, _) => false,
+            CodecOp::RunLengthDecode(_, _) => true,
+            CodecOp::UnpackBits(_, _, _) => true,
             CodecOp::UnpackStrings => false,
             CodecOp::UnhexpackStrings(_, _) => false,
+            CodecOp::UnpackFrontCodedStrings(_) => false,
+            CodecOp::UnpackSymbolCodedStrings(_) => false,
+            CodecOp::UnpackComponentCodedStrings(_) => false,
+            CodecOp::UnpackHybridStrings => false,
             CodecOp::Unknown => panic!("Unknown.is_positive_integer()"),
         }
     }
@@ -360,9 +480,16 @@ impl CodecOp {
             CodecOp::ToI64(_) => true,
             CodecOp::PushDataSection(_) => true,
             CodecOp::DictLookup(_) => true,
+            CodecOp::UnorderedDictLookup(_) => true,
             CodecOp::LZ4(_, _) => false,
+            CodecOp::RunLengthDecode(_, _) => false,
+            CodecOp::UnpackBits(_, _, _) => false,
             CodecOp::UnpackStrings => false,
             CodecOp::UnhexpackStrings(_, _) => false,
+            CodecOp::UnpackFrontCodedStrings(_) => false,
+            CodecOp::UnpackSymbolCodedStrings(_) => false,
+            CodecOp::UnpackComponentCodedStrings(_) => false,
+            CodecOp::UnpackHybridStrings => false,
             CodecOp::Unknown => panic!("Unknown.is_fixed_width()"),
         }
     }
@@ -374,9 +501,16 @@ impl CodecOp {
             CodecOp::ToI64(_) => 1,
             CodecOp::PushDataSection(_) => 0,
             CodecOp::DictLookup(_) => 3,
+            CodecOp::UnorderedDictLookup(_) => 3,
             CodecOp::LZ4(_, _) => 1,
+            CodecOp::RunLengthDecode(_, _) => 2,
+            CodecOp::UnpackBits(_, _, _) => 1,
             CodecOp::UnpackStrings => 1,
             CodecOp::UnhexpackStrings(_, _) => 1,
+            CodecOp::UnpackFrontCodedStrings(_) => 1,
+            CodecOp::UnpackSymbolCodedStrings(_) => 1,
+            CodecOp::UnpackComponentCodedStrings(_) => 1,
+            CodecOp::UnpackHybridStrings => 1,
             CodecOp::Unknown => panic!("Unknown.is_fixed_width()"),
         }
     }
@@ -392,13 +526,24 @@ impl CodecOp {
             CodecOp::ToI64(t) => format!("ToI64({:?})", t),
             CodecOp::PushDataSection(i) => format!("Data({})", i),
             CodecOp::DictLookup(t) => format!("Dict({:?})", t),
+            CodecOp::UnorderedDictLookup(t) => format!("UnorderedDict({:?})", t),
             CodecOp::LZ4(t, decoded_len) => if alternate {
                 format!("LZ4({:?}, {})", t, decoded_len)
             } else {
                 format!("LZ4({:?})", t)
             }
+            CodecOp::RunLengthDecode(t, decoded_len) => if alternate {
+                format!("RLE({:?}, {})", t, decoded_len)
+            } else {
+                format!("RLE({:?})", t)
+            }
+            CodecOp::UnpackBits(t, bits, _) => format!("UnpackBits({:?}, {})", t, bits),
             CodecOp::UnpackStrings => "StrUnpack".to_string(),
             CodecOp::UnhexpackStrings(_, _) => "StrHexUnpack".to_string(),
+            CodecOp::UnpackFrontCodedStrings(_) => "StrFrontUnpack".to_string(),
+            CodecOp::UnpackSymbolCodedStrings(_) => "StrSymbolUnpack".to_string(),
+            CodecOp::UnpackComponentCodedStrings(_) => "StrComponentUnpack".to_string(),
+            CodecOp::UnpackHybridStrings => "StrHybridUnpack".to_string(),
             CodecOp::Unknown => "Unknown".to_string(),
         }
     }
@@ -431,4 +576,44 @@ mod tests {
             CodecOp::DictLookup(EncodingType::U16),
         ]);
     }
+
+    #[test]
+    fn test_ensure_property_run_length() {
+        let codec = vec![
+            CodecOp::PushDataSection(3),
+            CodecOp::RunLengthDecode(EncodingType::U8, 1000),
+            CodecOp::PushDataSection(1),
+            CodecOp::PushDataSection(2),
+            CodecOp::DictLookup(EncodingType::U8),
+        ];
+        let codec = Codec::new(codec);
+        assert_eq!(codec.encoding_type(), EncodingType::U8);
+        assert!(codec.is_order_preserving());
+        assert!(!codec.is_elementwise_decodable());
+        let (fixed_width, rest) = codec.ensure_property(CodecOp::is_elementwise_decodable);
+        assert_eq!(fixed_width, vec![
+            CodecOp::PushDataSection(3),
+            CodecOp::RunLengthDecode(EncodingType::U8, 1000),
+        ]);
+        assert_eq!(rest, vec![
+            CodecOp::PushDataSection(1),
+            CodecOp::PushDataSection(2),
+            CodecOp::DictLookup(EncodingType::U8),
+        ]);
+    }
+
+    #[test]
+    fn test_code_width_bytes() {
+        let dict = |t| vec![CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), CodecOp::DictLookup(t)];
+        assert_eq!(Codec::new(dict(EncodingType::U8)).code_width_bytes(), Some(1));
+        assert_eq!(Codec::new(dict(EncodingType::U16)).code_width_bytes(), Some(2));
+        assert_eq!(Codec::new(dict(EncodingType::U32)).with_lz4(100).code_width_bytes(), Some(4));
+        let mut bit_packed = vec![CodecOp::UnpackBits(EncodingType::U16, 9, 100)];
+        bit_packed.extend(dict(EncodingType::U16));
+        assert_eq!(Codec::new(bit_packed).code_width_bytes(), Some(2));
+        assert_eq!(Codec::integer_offset(EncodingType::U32, 10).code_width_bytes(), Some(4));
+        assert_eq!(Codec::identity(BasicType::Integer).code_width_bytes(), Some(8));
+        assert_eq!(Codec::new(vec![CodecOp::UnpackStrings]).code_width_bytes(), None);
+        assert_eq!(Codec::identity(BasicType::String).code_width_bytes(), None);
+    }
 }
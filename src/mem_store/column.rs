@@ -1,11 +1,27 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::HashMap;
 use std::fmt;
+use std::io;
+use std::mem;
+use std::ops::{Deref, Range};
+use std::slice;
+use std::sync::{Arc, Mutex};
 
 use mem_store::*;
 use engine::typed_vec::AnyVec;
 use engine::types::*;
 use mem_store::lz4;
+use mem_store::column_builder::{ColumnBuilder, IntColBuilder};
+use mem_store::integers::IntegerColumn;
+use mem_store::bloom_filter::BloomFilter;
+use disk_store::serialization;
+use errors::QueryError;
+use ingest::raw_val::RawVal;
 
 use heapsize::HeapSizeOf;
+use memmap::Mmap;
+use num::PrimInt;
 
 
 #[derive(HeapSizeOf)]
@@ -15,6 +31,102 @@ pub struct Column {
     range: Option<(i64, i64)>,
     codec: Codec,
     data: Vec<DataSection>,
+    stats: Option<ColumnStats>,
+    bloom_filter: Option<BloomFilter>,
+    /// Whether strings that differ only in case were stored as the same value, see `StringColumnOptions`.
+    case_insensitive: bool,
+    /// Dictionary code reserved for null in a dictionary encoded string column, see `null_code`.
+    null_code: Option<u32>,
+    code_lookup: CodeLookup,
+    /// Whether the values are in ascending order, computed on first use.
+    #[ignore_heap_size_of = "Cached flag"]
+    sorted: Mutex<Option<bool>>,
+}
+
+/// Maps the values of a dictionary encoded string column to their codes, built on first use.
+#[derive(Default)]
+struct CodeLookup(Mutex<Option<HashMap<String, u32>>>);
+
+impl HeapSizeOf for CodeLookup {
+    fn heap_size_of_children(&self) -> usize {
+        self.0.lock().unwrap().heap_size_of_children()
+    }
+}
+
+/// Summary statistics computed when a column is built.
+#[derive(Clone, Debug, PartialEq, HeapSizeOf)]
+pub struct ColumnStats {
+    pub row_count: usize,
+    pub null_count: usize,
+    /// Exact for dictionary encoded columns, HyperLogLog estimate otherwise.
+    pub distinct_count: usize,
+    /// Lowercased for case insensitive string columns.
+    pub min: Option<String>,
+    pub max: Option<String>,
+    /// Smallest and largest value of integer columns.
+    pub int_range: Option<(i64, i64)>,
+    pub heap_bytes: usize,
+    /// Estimated sizes that decided between dictionary and packed encoding, if the column was built with
+    /// `StringColumnOptions::adaptive_encoding`.
+    pub encoding_estimate: Option<EncodingEstimate>,
+}
+
+/// Estimated heap size of a string column under dictionary and packed encoding.
+#[derive(Clone, Copy, Debug, PartialEq, HeapSizeOf)]
+pub struct EncodingEstimate {
+    pub dictionary_bytes: usize,
+    pub packed_bytes: usize,
+}
+
+impl EncodingEstimate {
+    pub fn prefers_dictionary(&self) -> bool {
+        self.dictionary_bytes <= self.packed_bytes
+    }
+}
+
+/// Comparison between the rows of two columns, see `Column::compare_with`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CmpOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+impl CmpOp {
+    pub fn eval<T: Ord + ?Sized>(self, lhs: &T, rhs: &T) -> bool {
+        let ordering = lhs.cmp(rhs);
+        match self {
+            CmpOp::Eq => ordering == cmp::Ordering::Equal,
+            CmpOp::NotEq => ordering != cmp::Ordering::Equal,
+            CmpOp::Lt => ordering == cmp::Ordering::Less,
+            CmpOp::LtEq => ordering != cmp::Ordering::Greater,
+            CmpOp::Gt => ordering == cmp::Ordering::Greater,
+            CmpOp::GtEq => ordering != cmp::Ordering::Less,
+        }
+    }
+}
+
+/// Heap memory used by a column or the columns of a table with the same encoding, see `Column::memory_report`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MemoryReport {
+    /// One of "null", "integer", "dictionary", "packed", "front_coded", "symbol_coded", "component_coded", "hybrid"
+    /// or "raw".
+    pub encoding: &'static str,
+    /// Size of the dictionary of dictionary encoded string columns.
+    pub dictionary_bytes: usize,
+    pub data_bytes: usize,
+    pub total: usize,
+}
+
+impl MemoryReport {
+    pub fn add(&mut self, other: &MemoryReport) {
+        self.dictionary_bytes += other.dictionary_bytes;
+        self.data_bytes += other.data_bytes;
+        self.total += other.total;
+    }
 }
 
 impl Column {
@@ -35,6 +147,12 @@ impl Column {
             range,
             codec,
             data,
+            stats: None,
+            bloom_filter: None,
+            case_insensitive: false,
+            null_code: None,
+            code_lookup: CodeLookup::default(),
+            sorted: Mutex::new(None),
         }
     }
 
@@ -45,6 +163,21 @@ impl Column {
             range: None,
             codec: Codec::identity(BasicType::Null),
             data: vec![DataSection::Null(len)],
+            stats: Some(ColumnStats {
+                row_count: len,
+                null_count: len,
+                distinct_count: 0,
+                min: None,
+                max: None,
+                int_range: None,
+                heap_bytes: 0,
+                encoding_estimate: None,
+            }),
+            bloom_filter: None,
+            case_insensitive: false,
+            null_code: None,
+            code_lookup: CodeLookup::default(),
+            sorted: Mutex::new(None),
         }
     }
 
@@ -54,15 +187,19 @@ impl Column {
             if worth_it {
                 self.codec = self.codec.with_lz4(self.data[0].len());
                 self.data[0] = encoded;
+                let heap_bytes = self.data_heap_size();
+                if let Some(ref mut stats) = self.stats {
+                    stats.heap_bytes = heap_bytes;
+                }
             }
         }
     }
 
     #[cfg(feature = "enable_lz4")]
     pub fn lz4_decode(&mut self) {
-        if let Some(CodecOp::LZ4(decoded_type, _)) = self.codec.ops().get(0).map(|c| *c) {
+        if let Some(CodecOp::LZ4(decoded_type, decoded_len)) = self.codec.ops().get(0).map(|c| *c) {
             self.codec = self.codec.without_lz4();
-            self.data[0] = self.data[0].lz4_decode(decoded_type, self.len);
+            self.data[0] = self.data[0].lz4_decode(decoded_type, decoded_len);
         }
     }
 
@@ -79,6 +216,482 @@ impl Column {
     pub fn data_sections(&self) -> Vec<&AnyVec> {
         self.data.iter().map(|d| d.to_any_vec()).collect()
     }
+    pub fn stats(&self) -> Option<&ColumnStats> { self.stats.as_ref() }
+
+    /// Serializes the column into a byte buffer, see `disk_store::serialization`.
+    pub fn serialize(&self) -> Vec<u8> {
+        serialization::serialize_column(self)
+    }
+
+    pub fn deserialize(data: &[u8]) -> Result<Column, QueryError> {
+        serialization::deserialize_column(data)
+    }
+
+    /// Hash of the serialized representation of the column which is used to detect corrupted data on load.
+    pub fn checksum(&self) -> u64 {
+        serialization::checksum(self)
+    }
+
+    /// Makes all `columns` refer to a single copy of their data sections `indices`, which have to hold the same values
+    /// in every column, e.g. the dictionary of the partitions of a split column.
+    pub fn share_data_sections(columns: &mut [Column], indices: &[usize]) {
+        if columns.is_empty() { return; }
+        for &index in indices {
+            let shared = mem::replace(&mut columns[0].data[index], DataSection::Null(0)).into_shared();
+            for column in columns.iter_mut() {
+                debug_assert!(column.data[index].len() == shared.len() || column.data[index].len() == 0);
+                column.data[index] = shared.clone();
+            }
+        }
+        for column in columns {
+            let heap_bytes = column.data_heap_size();
+            if let Some(ref mut stats) = column.stats {
+                stats.heap_bytes = heap_bytes;
+            }
+        }
+    }
+
+    pub fn set_stats(&mut self, stats: ColumnStats) {
+        self.stats = Some(stats);
+    }
+
+    /// Whether the column may contain values in the range `[lo, hi]` according to its min/max statistics.
+    /// Always returns true for columns without statistics.
+    pub fn contains_range(&self, lo: &str, hi: &str) -> bool {
+        match self.stats {
+            Some(ref stats) => match (&stats.min, &stats.max) {
+                (&Some(ref min), &Some(ref max)) => min.as_str() <= hi && lo <= max.as_str(),
+                // Either all values are null, or the column does not contain strings
+                _ => stats.null_count < stats.row_count,
+            },
+            None => true,
+        }
+    }
+
+    /// Whether the column may contain integers in the range `[lo, hi]` according to its min/max statistics.
+    /// Always returns true for columns without statistics.
+    pub fn contains_int_range(&self, lo: i64, hi: i64) -> bool {
+        match self.stats {
+            Some(ref stats) => match stats.int_range {
+                Some((min, max)) => min <= hi && lo <= max,
+                None => stats.null_count < stats.row_count,
+            },
+            None => true,
+        }
+    }
+
+    /// Appends the values of `other` to the values of this column.
+    /// Returns an error if the columns have different types.
+    pub fn concat(&self, other: &Column, options: &strings::StringColumnOptions) -> Result<Arc<Column>, QueryError> {
+        match (self.basic_type(), other.basic_type()) {
+            (BasicType::String, BasicType::String) => Ok(strings::concat_strings(self, other, options)),
+            (BasicType::Integer, BasicType::Integer) => {
+                let mut builder = IntColBuilder::new();
+                for column in &[self, other] {
+                    match IntegerColumn::decode(column) {
+                        Some(values) => for value in values { builder.push(&value) },
+                        None => bail!(QueryError::NotImplemented, "Concatenating column {} with codec {:?}", column.name, column.codec),
+                    }
+                }
+                Ok(builder.finalize(&self.name))
+            }
+            (BasicType::Null, BasicType::Null) => Ok(Arc::new(Column::null(&self.name, self.len + other.len))),
+            (lhs, rhs) => bail!(QueryError::TypeError, "Cannot concatenate column {} of type {:?} with column {} of type {:?}",
+                                self.name, lhs, other.name, rhs),
+        }
+    }
+
+    /// Splits the column into partitions of `partition_size` consecutive rows that can be queried independently.
+    /// The last partition may be smaller.
+    pub fn split(&self, partition_size: usize) -> Result<Vec<Arc<Column>>, QueryError> {
+        ensure!(partition_size > 0, "Partition size must be positive");
+        match self.basic_type() {
+            BasicType::String => Ok(strings::split_strings(self, partition_size)),
+            BasicType::Integer => match IntegerColumn::decode(self) {
+                Some(values) => Ok(values.chunks(partition_size)
+                    .map(|values| {
+                        let mut builder = IntColBuilder::new();
+                        for value in values {
+                            builder.push(value);
+                        }
+                        builder.finalize(&self.name)
+                    })
+                    .collect()),
+                None => bail!(QueryError::NotImplemented, "Splitting column {} with codec {:?}", self.name, self.codec),
+            },
+            BasicType::Null => Ok((0..self.len).step_by(partition_size)
+                .map(|start| Arc::new(Column::null(&self.name, cmp::min(partition_size, self.len - start))))
+                .collect()),
+            t => bail!(QueryError::NotImplemented, "Splitting column {} of type {:?}", self.name, t),
+        }
+    }
+
+    /// Number of null rows, i.e. `COUNT(*) - COUNT(col)`.
+    pub fn null_count(&self) -> usize {
+        match self.stats {
+            Some(ref stats) => stats.null_count,
+            None => match self.basic_type() {
+                BasicType::String => strings::string_column_stats(self).null_count,
+                BasicType::Null => self.len,
+                _ => 0,
+            },
+        }
+    }
+
+    /// Number of rows that are not null, i.e. `COUNT(col)`.
+    pub fn count_non_null(&self) -> usize {
+        self.len - self.null_count()
+    }
+
+    /// Number of rows selected by `filter`, i.e. `COUNT(*) WHERE ..`. Does not read any column data.
+    pub fn count_filtered(&self, filter: &[u8]) -> usize {
+        assert_eq!(filter.len(), self.len, "Filter length does not match length of column {}", self.name);
+        filter.iter().filter(|&&selected| selected != 0).count()
+    }
+
+    pub fn set_bloom_filter(&mut self, bloom_filter: BloomFilter) {
+        self.bloom_filter = Some(bloom_filter);
+    }
+
+    pub fn bloom_filter(&self) -> Option<&BloomFilter> { self.bloom_filter.as_ref() }
+
+    pub fn set_case_insensitive(&mut self, case_insensitive: bool) {
+        self.case_insensitive = case_insensitive;
+    }
+
+    pub fn is_case_insensitive(&self) -> bool { self.case_insensitive }
+
+    /// Whether the column may contain `value`. False positives are possible, false negatives are not.
+    pub fn might_contain(&self, value: &str) -> bool {
+        // Statistics and bloom filter of case insensitive columns are built from lowercased values
+        let folded;
+        let value = if self.case_insensitive {
+            folded = value.to_lowercase();
+            &folded
+        } else {
+            value
+        };
+        self.contains_range(value, value) &&
+            self.bloom_filter.as_ref().map_or(true, |bloom| bloom.might_contain(value.as_bytes()))
+    }
+
+    pub fn data_heap_size(&self) -> usize {
+        self.data.iter().map(|d| d.heap_size_of_children()).sum()
+    }
+
+    /// Breaks down the heap size of the column into dictionary and data. The size of each data section is
+    /// determined from its capacity, so this is cheap enough to be polled frequently.
+    pub fn memory_report(&self) -> MemoryReport {
+        let section_bytes = self.data.iter().map(|d| d.heap_size_of_children()).collect::<Vec<_>>();
+        let total = section_bytes.iter().sum();
+        let ops = self.codec.ops();
+        let is_dictionary = ops.iter().any(strings::is_dictionary_lookup);
+        let encoding = if is_dictionary {
+            "dictionary"
+        } else {
+            match ops.iter().find(|op| strings::is_packed_strings(op)) {
+                Some(&CodecOp::UnpackFrontCodedStrings(_)) => "front_coded",
+                Some(&CodecOp::UnpackSymbolCodedStrings(_)) => "symbol_coded",
+                Some(&CodecOp::UnpackComponentCodedStrings(_)) => "component_coded",
+                Some(&CodecOp::UnpackHybridStrings) => "hybrid",
+                Some(_) => "packed",
+                None if ops.iter().any(|op| match *op { CodecOp::UnhexpackStrings(_, _) => true, _ => false }) =>
+                    "packed",
+                None => match self.basic_type() {
+                    BasicType::Null => "null",
+                    BasicType::Integer => "integer",
+                    _ => "raw",
+                },
+            }
+        };
+        // Dictionary offsets and bytes are stored in sections 1 and 2, see `strings::dictionary_column`
+        let dictionary_bytes = if is_dictionary { section_bytes[1] + section_bytes[2] } else { 0 };
+        MemoryReport { encoding, dictionary_bytes, data_bytes: total - dictionary_bytes, total }
+    }
+
+    /// Estimates the number of distinct values in a string column, see `strings::approx_count_distinct`.
+    pub fn approx_count_distinct(&self, precision: u8) -> Option<u64> {
+        strings::approx_count_distinct(self, precision)
+    }
+
+    /// Sorted distinct values of a string column, see `strings::distinct_values`.
+    pub fn distinct_values(&self) -> Option<strings::DistinctValues> {
+        strings::distinct_values(self)
+    }
+
+    /// Number of occurrences of every distinct value of a string column, see `strings::group_count`.
+    pub fn group_count(&self) -> Option<Vec<(Option<String>, u64)>> {
+        strings::group_count(self)
+    }
+
+    /// The `k` most frequent values of a string column, see `strings::top_k`.
+    pub fn top_k(&self, k: usize) -> Option<Vec<(String, u64)>> {
+        strings::top_k(self, k)
+    }
+
+    /// Histogram of the lengths of the values in a string column, see `strings::length_histogram`.
+    pub fn length_histogram(&self, buckets: &[usize]) -> Option<strings::LengthHistogram> {
+        strings::length_histogram(self, buckets)
+    }
+
+    /// All values of a string column in row order, see `strings::decode_strings`.
+    pub fn decode_strings(&self) -> Option<Vec<Option<String>>> {
+        strings::decode_strings(self)
+    }
+
+    /// The value of every row if all rows are equal, see `strings::constant_value`.
+    pub fn constant_value(&self) -> Option<Option<&str>> {
+        strings::constant_value(self)
+    }
+
+    /// New string column with `f` applied to every value, see `strings::map_strings`.
+    pub fn map_strings<F: Fn(&str) -> String>(&self, f: F) -> Option<Arc<Column>> {
+        strings::map_strings(self, f)
+    }
+
+    /// Copy of a dictionary encoded string column without unreferenced dictionary entries, see `strings::compact_dictionary`.
+    pub fn compact_dictionary(&self) -> Option<Arc<Column>> {
+        strings::compact_dictionary(self)
+    }
+
+    /// Lazily yields all values of a string column in row order, see `strings::iter_strings`.
+    pub fn iter_strings<'a>(&'a self) -> Option<Box<Iterator<Item=Option<Cow<'a, str>>> + 'a>> {
+        strings::iter_strings(self)
+    }
+
+    /// Runs of equal adjacent values of a string column and their lengths, see `strings::runs`.
+    pub fn runs<'a>(&'a self) -> Option<Box<Iterator<Item=(Option<Cow<'a, str>>, usize)> + 'a>> {
+        strings::runs(self)
+    }
+
+    /// Values of a string column for all rows selected by `filter`, see `strings::filter_decode`.
+    pub fn filter_decode_strings<'a>(&'a self, filter: &[u8]) -> Option<Vec<Option<Cow<'a, str>>>> {
+        strings::filter_decode(self, filter)
+    }
+
+    /// Like `filter_decode_strings`, but pairs each value with its row index, see `strings::filter_decode_indexed`.
+    pub fn filter_decode_strings_indexed<'a>(&'a self, filter: &[u8]) -> Option<Vec<(usize, Option<Cow<'a, str>>)>> {
+        strings::filter_decode_indexed(self, filter)
+    }
+
+    /// Whether the values of the column are in ascending order with nulls first, e.g. to filter ranges with binary
+    /// search. Computed on first use, see `strings::is_sorted`.
+    pub fn is_sorted(&self) -> bool {
+        let mut sorted = self.sorted.lock().unwrap();
+        if sorted.is_none() {
+            *sorted = Some(match self.basic_type() {
+                BasicType::Integer => IntegerColumn::decode(self)
+                    .map_or(false, |values| values.windows(2).all(|w| w[0] <= w[1])),
+                BasicType::Null => true,
+                _ => strings::is_sorted(self).unwrap_or(false),
+            });
+        }
+        sorted.unwrap()
+    }
+
+    /// Rows equal to `value` in a sorted string column, see `strings::search_sorted`.
+    /// Returns `None` if the column is not sorted or does not contain `value`.
+    pub fn search_sorted(&self, value: &str) -> Option<Range<usize>> {
+        if self.basic_type() != BasicType::String || !self.is_sorted() {
+            return None;
+        }
+        strings::search_sorted(self, value)
+    }
+
+    /// Writes the (selected) values of the column to `out` without materializing them, see `strings::write_decoded`.
+    pub fn write_decoded(&self, filter: Option<&[u8]>, out: &mut io::Write, separator: u8, null: &[u8]) -> io::Result<()> {
+        match self.basic_type() {
+            BasicType::String => strings::write_decoded(self, filter, out, separator, null),
+            BasicType::Integer => match IntegerColumn::decode(self) {
+                Some(values) => {
+                    let selected = values.iter().enumerate()
+                        .filter(|&(row, _)| filter.map_or(true, |f| f.get(row).map_or(false, |&s| s != 0)));
+                    for (i, (_, value)) in selected.enumerate() {
+                        if i > 0 {
+                            out.write_all(&[separator])?;
+                        }
+                        write!(out, "{}", value)?;
+                    }
+                    Ok(())
+                }
+                None => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Cannot decode column {}", self.name))),
+            },
+            BasicType::Null => {
+                let count = filter.map_or(self.len, |f| f.iter().take(self.len).filter(|&&s| s != 0).count());
+                for i in 0..count {
+                    if i > 0 {
+                        out.write_all(&[separator])?;
+                    }
+                    out.write_all(null)?;
+                }
+                Ok(())
+            }
+            t => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Cannot write column {} of type {:?}", self.name, t))),
+        }
+    }
+
+    /// Like `filter_decode_strings`, but reuses the allocation of `out`, see `strings::filter_decode_into`.
+    pub fn filter_decode_strings_into<'a>(&'a self, filter: &[u8], out: &mut Vec<Option<Cow<'a, str>>>) -> bool {
+        strings::filter_decode_into(self, filter, out)
+    }
+
+    /// All values of a string column in row order with nulls represented as configured by `options`.
+    pub fn decode_strings_with(&self, options: &strings::DecodeOptions) -> Option<Vec<RawVal>> {
+        strings::decode_strings_with(self, options)
+    }
+
+    /// Values of the rows `start..start + len` of a string column, see `strings::decode_strings_range`.
+    pub fn decode_strings_range(&self, start: usize, len: usize) -> Option<Vec<Option<String>>> {
+        strings::decode_strings_range(self, start, len)
+    }
+
+    /// Values of the rows in `indices` of a string column, see `strings::take_strings`.
+    pub fn take_strings(&self, indices: &[usize]) -> Option<Vec<Option<String>>> {
+        strings::take_strings(self, indices)
+    }
+
+    /// `n` random rows of a string column that only depend on `seed`, see `strings::sample_strings`.
+    pub fn sample_strings(&self, n: usize, seed: u64) -> Option<Vec<Option<String>>> {
+        strings::sample_strings(self, n, seed)
+    }
+
+    /// Row indices of a string column in order of their values with nulls last, see `strings::sort_indices`.
+    pub fn sort_indices(&self, descending: bool) -> Option<Vec<usize>> {
+        strings::sort_indices(self, descending, false)
+    }
+
+    /// All values of a string column in row order, decoded on up to `threads` threads.
+    /// See `strings::decode_strings_parallel`.
+    pub fn decode_strings_parallel(&self, threads: usize) -> Option<Vec<Option<String>>> {
+        strings::decode_strings_parallel(self, threads)
+    }
+
+    /// Concatenated bytes and end offsets of all rows of a dictionary encoded string column, see
+    /// `strings::decode_to_bytes`.
+    pub fn decode_to_bytes(&self) -> Option<(Vec<u8>, Vec<u32>)> {
+        strings::decode_to_bytes(self)
+    }
+
+    /// Code of `value` in the dictionary of a dictionary encoded string column, ignoring case if the column is case
+    /// insensitive. The map from values to codes is built on the first call and reused afterwards.
+    pub fn dictionary_code(&self, value: &str) -> Option<u32> {
+        let mut code_lookup = self.code_lookup.0.lock().unwrap();
+        if code_lookup.is_none() {
+            let case_insensitive = self.case_insensitive;
+            let null_code = self.null_code;
+            *code_lookup = Some(strings::dictionary_entries(self).enumerate()
+                .filter(|&(code, _)| Some(code as u32) != null_code)
+                .map(|(code, entry)| (if case_insensitive { entry.to_lowercase() } else { entry.to_string() }, code as u32))
+                .collect());
+        }
+        let lookup = code_lookup.as_ref().unwrap();
+        if self.case_insensitive {
+            lookup.get(&value.to_lowercase()).cloned()
+        } else {
+            lookup.get(value).cloned()
+        }
+    }
+
+    /// Rows of a string column that are (not) equal to `value`, see `strings::filter_eq_str`.
+    pub fn filter_eq_str(&self, value: Option<&str>, negate: bool) -> Option<Vec<u8>> {
+        strings::filter_eq_str(self, value, negate)
+    }
+
+    /// Rows of a string column whose bytes start with `prefix`, see `strings::filter_byte_prefix`.
+    pub fn filter_byte_prefix(&self, prefix: &[u8]) -> Option<Vec<u8>> {
+        strings::filter_byte_prefix(self, prefix)
+    }
+
+    /// Estimated fraction of rows equal to `value` without decoding the column, see
+    /// `strings::estimate_eq_selectivity`.
+    pub fn estimate_eq_selectivity(&self, value: &str) -> f64 {
+        strings::estimate_eq_selectivity(self, value)
+    }
+
+    /// Rows of a string column that are (not) equal to any of `values`, see `strings::filter_in`.
+    pub fn filter_in(&self, values: &[Option<&str>], negate: bool) -> Option<Vec<u8>> {
+        strings::filter_in(self, values, negate)
+    }
+
+    /// Rows of a dictionary encoded string column whose code equals `code`, see `strings::filter_eq_code`.
+    pub fn filter_eq_code(&self, code: u32) -> Option<Vec<u8>> {
+        strings::filter_eq_code(self, code)
+    }
+
+    /// Validity of each row, 1 for values and 0 for nulls, in the same format as filters. Returns `None` if the column
+    /// has no nulls so that callers can skip the mask entirely.
+    pub fn null_bitmap(&self) -> Option<Vec<u8>> {
+        if self.null_count() == 0 {
+            return None;
+        }
+        match self.basic_type() {
+            BasicType::String => strings::null_bitmap(self),
+            BasicType::Null => Some(vec![0; self.len]),
+            _ => None,
+        }
+    }
+
+    /// Dictionary code that represents null, if any. Its dictionary entry is an empty placeholder which is distinct
+    /// from the code of the empty string, so null rows can be tested with a single integer comparison.
+    pub fn null_code(&self) -> Option<u32> { self.null_code }
+
+    pub fn set_null_code(&mut self, null_code: Option<u32>) {
+        self.null_code = null_code;
+        *self.code_lookup.0.lock().unwrap() = None;
+    }
+
+    /// Codes of the dictionary entries between `lo` and `hi`, see `strings::dictionary_code_range`.
+    pub fn dictionary_code_range(&self, lo: &str, hi: &str) -> Option<Range<u32>> {
+        strings::dictionary_code_range(self, lo, hi)
+    }
+
+    /// Rows of a dictionary encoded string column whose code lies between `lo` and `hi`, see
+    /// `strings::filter_code_range`.
+    pub fn filter_code_range(&self, lo: u32, hi: u32) -> Option<Vec<u8>> {
+        strings::filter_code_range(self, lo, hi)
+    }
+
+    /// Ranges of rows of a run length encoded string column whose code lies between `lo` and `hi`, see
+    /// `strings::filter_code_range_runs`.
+    pub fn filter_code_range_runs(&self, lo: u32, hi: u32) -> Option<Vec<Range<usize>>> {
+        strings::filter_code_range_runs(self, lo, hi)
+    }
+
+    /// Rows of a string column between `lo` and `hi`, see `strings::filter_between_str`.
+    pub fn filter_between_str(&self, lo: &str, hi: &str) -> Option<Vec<u8>> {
+        strings::filter_between_str(self, lo, hi)
+    }
+
+    /// Returns one byte per row that is `1` if the row compares to the same row of `other` as `op` requires, e.g. for
+    /// `WHERE first_name = last_name`. Null rows are never selected.
+    /// Returns an error if the columns have different lengths or types that can't be compared.
+    pub fn compare_with(&self, other: &Column, op: CmpOp) -> Result<Vec<u8>, QueryError> {
+        ensure!(self.len == other.len, "Cannot compare column {} of length {} with column {} of length {}",
+                self.name, self.len, other.name, other.len);
+        match (self.basic_type(), other.basic_type()) {
+            (BasicType::String, BasicType::String) => match strings::compare_strings(self, other, op) {
+                Some(selected) => Ok(selected),
+                None => bail!(QueryError::NotImplemented, "Comparing column {} with codec {:?}", self.name, self.codec),
+            },
+            (BasicType::Integer, BasicType::Integer) => match (IntegerColumn::decode(self), IntegerColumn::decode(other)) {
+                (Some(lhs), Some(rhs)) => Ok(lhs.iter().zip(&rhs).map(|(lhs, rhs)| op.eval(lhs, rhs) as u8).collect()),
+                _ => bail!(QueryError::NotImplemented, "Comparing column {} with column {}", self.name, other.name),
+            },
+            (BasicType::Null, _) | (_, BasicType::Null) => Ok(vec![0; self.len]),
+            (lhs, rhs) => bail!(QueryError::TypeError, "Cannot compare column {} of type {:?} with column {} of type {:?}",
+                                self.name, lhs, other.name, rhs),
+        }
+    }
+
+    /// Smallest non-null value of a string column, see `strings::min_max_string`.
+    pub fn min_string(&self) -> Option<String> {
+        strings::min_max_string(self).map(|(min, _)| min)
+    }
+
+    /// Largest non-null value of a string column, see `strings::min_max_string`.
+    pub fn max_string(&self) -> Option<String> {
+        strings::min_max_string(self).map(|(_, max)| max)
+    }
 
     pub fn mem_tree(&self, tree: &mut MemTreeColumn, depth: usize) {
         if depth == 0 { return; }
@@ -115,7 +728,11 @@ impl Column {
 }
 
 impl fmt::Debug for Column {
+    /// The alternate format (`{:#?}`, e.g. `dbg!`) also shows statistics and a sample of the values of string columns.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() && self.basic_type() == BasicType::String {
+            return strings::fmt_debug(self, f);
+        }
         write!(f, "{}[{}][{:?}] {:#}, [{:?}]",
                &self.name,
                self.len(),
@@ -125,7 +742,7 @@ impl fmt::Debug for Column {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum DataSection {
     U8(Vec<u8>),
     U16(Vec<u16>),
@@ -133,6 +750,80 @@ pub enum DataSection {
     U64(Vec<u64>),
     I64(Vec<i64>),
     Null(usize),
+    /// Data that lives in a memory mapped file or is shared with other columns rather than owned by the column.
+    Mapped(MappedSection),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MappedSection {
+    U8(MappedSlice<u8>),
+    U16(MappedSlice<u16>),
+    U32(MappedSlice<u32>),
+    U64(MappedSlice<u64>),
+    I64(MappedSlice<i64>),
+}
+
+/// Elements of type `T` stored in a memory map or shared heap allocation which is kept alive for as long as the
+/// slice exists.
+#[derive(Clone)]
+pub struct MappedSlice<T> {
+    memory: Memory<T>,
+    offset: usize,
+    len: usize,
+}
+
+#[derive(Clone)]
+enum Memory<T> {
+    Mmap(Arc<Mmap>),
+    /// Values shared by several columns, e.g. the dictionary of the partitions of a split column.
+    Shared(Arc<Vec<T>>),
+}
+
+impl<T: PrimInt> MappedSlice<T> {
+    /// Refers to the `len` elements starting at byte `offset` of `mmap` which have to be in bounds and aligned.
+    pub fn new(mmap: Arc<Mmap>, offset: usize, len: usize) -> Result<MappedSlice<T>, QueryError> {
+        let end = len.checked_mul(mem::size_of::<T>()).and_then(|bytes| bytes.checked_add(offset));
+        ensure!(end.map_or(false, |end| end <= mmap.len()),
+                "Data section of {} elements at byte {} exceeds memory map of {} bytes", len, offset, mmap.len());
+        ensure!((mmap.as_ptr() as usize + offset) % mem::align_of::<T>() == 0,
+                "Misaligned data section at byte {}", offset);
+        Ok(MappedSlice { memory: Memory::Mmap(mmap), offset, len })
+    }
+
+    /// Moves `values` into an allocation that can be referred to by the sections of several columns.
+    pub fn shared(values: Vec<T>) -> MappedSlice<T> {
+        MappedSlice { offset: 0, len: values.len(), memory: Memory::Shared(Arc::new(values)) }
+    }
+
+    /// Share of the heap allocation attributed to this slice, which is split evenly between all slices that refer to
+    /// it. Memory maps are not on the heap.
+    fn heap_size(&self) -> usize {
+        match self.memory {
+            Memory::Mmap(_) => 0,
+            Memory::Shared(ref values) => values.heap_size_of_children() / Arc::strong_count(values),
+        }
+    }
+}
+
+impl<T> Deref for MappedSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self.memory {
+            // Safe because `new` checked bounds and alignment, and any bit pattern is a valid integer
+            Memory::Mmap(ref mmap) =>
+                unsafe { slice::from_raw_parts(mmap.as_ptr().offset(self.offset as isize) as *const T, self.len) },
+            Memory::Shared(ref values) => values,
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for MappedSlice<T> {
+    fn eq(&self, other: &MappedSlice<T>) -> bool { **self == **other }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MappedSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { (**self).fmt(f) }
 }
 
 impl DataSection {
@@ -144,6 +835,11 @@ impl DataSection {
             DataSection::U64(ref x) => x,
             DataSection::I64(ref x) => x,
             DataSection::Null(ref x) => x,
+            DataSection::Mapped(MappedSection::U8(ref x)) => x,
+            DataSection::Mapped(MappedSection::U16(ref x)) => x,
+            DataSection::Mapped(MappedSection::U32(ref x)) => x,
+            DataSection::Mapped(MappedSection::U64(ref x)) => x,
+            DataSection::Mapped(MappedSection::I64(ref x)) => x,
         }
     }
 
@@ -155,6 +851,11 @@ impl DataSection {
             DataSection::U64(ref x) => x.len(),
             DataSection::I64(ref x) => x.len(),
             DataSection::Null(ref x) => *x,
+            DataSection::Mapped(MappedSection::U8(ref x)) => x.len(),
+            DataSection::Mapped(MappedSection::U16(ref x)) => x.len(),
+            DataSection::Mapped(MappedSection::U32(ref x)) => x.len(),
+            DataSection::Mapped(MappedSection::U64(ref x)) => x.len(),
+            DataSection::Mapped(MappedSection::I64(ref x)) => x.len(),
         }
     }
 
@@ -166,6 +867,7 @@ impl DataSection {
             DataSection::U64(ref x) => x.capacity(),
             DataSection::I64(ref x) => x.capacity(),
             DataSection::Null(ref x) => *x,
+            DataSection::Mapped(_) => self.len(),
         }
     }
 
@@ -177,6 +879,11 @@ impl DataSection {
             DataSection::U64(_) => EncodingType::U64,
             DataSection::I64(_) => EncodingType::I64,
             DataSection::Null(_) => EncodingType::Null,
+            DataSection::Mapped(MappedSection::U8(_)) => EncodingType::U8,
+            DataSection::Mapped(MappedSection::U16(_)) => EncodingType::U16,
+            DataSection::Mapped(MappedSection::U32(_)) => EncodingType::U32,
+            DataSection::Mapped(MappedSection::U64(_)) => EncodingType::U64,
+            DataSection::Mapped(MappedSection::I64(_)) => EncodingType::I64,
         }
     }
 
@@ -213,42 +920,58 @@ impl DataSection {
                 let len = encoded.len();
                 (DataSection::U8(encoded), len * 100 < x.len() * 8 * min_reduction)
             }
-            DataSection::Null(ref x) => (DataSection::Null(*x), false)
+            DataSection::Null(ref x) => (DataSection::Null(*x), false),
+            // Sections are compressed before they are written to disk if that is worth it
+            DataSection::Mapped(_) => (self.clone(), false),
         }
     }
 
     #[cfg(feature = "enable_lz4")]
     pub fn lz4_decode(&self, decoded_type: EncodingType, len: usize) -> DataSection {
-        match self {
-            DataSection::U8(encoded) => match decoded_type {
-                EncodingType::U8 => {
-                    let mut decoded = vec![0; len];
-                    unsafe { lz4::decode::<u8>(&mut lz4::decoder(encoded), &mut decoded); }
-                    DataSection::U8(decoded)
-                }
-                EncodingType::U16 => {
-                    let mut decoded = vec![0; len];
-                    unsafe { lz4::decode::<u16>(&mut lz4::decoder(encoded), &mut decoded); }
-                    DataSection::U16(decoded)
-                }
-                EncodingType::U32 => {
-                    let mut decoded = vec![0; len];
-                    unsafe { lz4::decode::<u32>(&mut lz4::decoder(encoded), &mut decoded); }
-                    DataSection::U32(decoded)
-                }
-                EncodingType::U64 => {
-                    let mut decoded = vec![0; len];
-                    unsafe { lz4::decode::<u64>(&mut lz4::decoder(encoded), &mut decoded); }
-                    DataSection::U64(decoded)
-                }
-                EncodingType::I64 => {
-                    let mut decoded = vec![0; len];
-                    unsafe { lz4::decode::<i64>(&mut lz4::decoder(encoded), &mut decoded); }
-                    DataSection::I64(decoded)
-                }
-                t => panic!("Unexpected type {:?} for lz4 decode", t),
+        let encoded: &[u8] = match self {
+            DataSection::U8(encoded) => encoded,
+            DataSection::Mapped(MappedSection::U8(ref encoded)) => encoded,
+            _ => panic!("Trying to lz4 decode non u8 data section"),
+        };
+        match decoded_type {
+            EncodingType::U8 => {
+                let mut decoded = vec![0; len];
+                unsafe { lz4::decode::<u8>(&mut lz4::decoder(encoded), &mut decoded); }
+                DataSection::U8(decoded)
+            }
+            EncodingType::U16 => {
+                let mut decoded = vec![0; len];
+                unsafe { lz4::decode::<u16>(&mut lz4::decoder(encoded), &mut decoded); }
+                DataSection::U16(decoded)
+            }
+            EncodingType::U32 => {
+                let mut decoded = vec![0; len];
+                unsafe { lz4::decode::<u32>(&mut lz4::decoder(encoded), &mut decoded); }
+                DataSection::U32(decoded)
             }
-            _ => panic!("Trying to lz4 encode non u8 data section")
+            EncodingType::U64 => {
+                let mut decoded = vec![0; len];
+                unsafe { lz4::decode::<u64>(&mut lz4::decoder(encoded), &mut decoded); }
+                DataSection::U64(decoded)
+            }
+            EncodingType::I64 => {
+                let mut decoded = vec![0; len];
+                unsafe { lz4::decode::<i64>(&mut lz4::decoder(encoded), &mut decoded); }
+                DataSection::I64(decoded)
+            }
+            t => panic!("Unexpected type {:?} for lz4 decode", t),
+        }
+    }
+
+    /// Moves the data into an allocation that can be shared with other columns by cloning the returned section.
+    pub fn into_shared(self) -> DataSection {
+        match self {
+            DataSection::U8(x) => DataSection::Mapped(MappedSection::U8(MappedSlice::shared(x))),
+            DataSection::U16(x) => DataSection::Mapped(MappedSection::U16(MappedSlice::shared(x))),
+            DataSection::U32(x) => DataSection::Mapped(MappedSection::U32(MappedSlice::shared(x))),
+            DataSection::U64(x) => DataSection::Mapped(MappedSection::U64(MappedSlice::shared(x))),
+            DataSection::I64(x) => DataSection::Mapped(MappedSection::I64(MappedSlice::shared(x))),
+            section @ DataSection::Null(_) | section @ DataSection::Mapped(_) => section,
         }
     }
 
@@ -260,7 +983,7 @@ impl DataSection {
                 DataSection::U32(ref mut x) => x.shrink_to_fit(),
                 DataSection::U64(ref mut x) => x.shrink_to_fit(),
                 DataSection::I64(ref mut x) => x.shrink_to_fit(),
-                DataSection::Null(_) => {}
+                DataSection::Null(_) | DataSection::Mapped(_) => {}
             }
         }
     }
@@ -275,6 +998,11 @@ impl HeapSizeOf for DataSection {
             DataSection::U64(ref x) => x.heap_size_of_children(),
             DataSection::I64(ref x) => x.heap_size_of_children(),
             DataSection::Null(_) => 0,
+            DataSection::Mapped(MappedSection::U8(ref x)) => x.heap_size(),
+            DataSection::Mapped(MappedSection::U16(ref x)) => x.heap_size(),
+            DataSection::Mapped(MappedSection::U32(ref x)) => x.heap_size(),
+            DataSection::Mapped(MappedSection::U64(ref x)) => x.heap_size(),
+            DataSection::Mapped(MappedSection::I64(ref x)) => x.heap_size(),
         }
     }
 }
@@ -1,13 +1,17 @@
 use std::cmp;
 use std::i64;
-use std::hash::Hash;
+use std::hash::{BuildHasher, Hash};
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
 use std::collections::hash_set::HashSet;
-use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use errors::QueryError;
 use mem_store::integers::*;
+use mem_store::interner::StringInterner;
 use mem_store::column::*;
 use mem_store::strings::*;
+use stringpack::PackedStrings;
 
 
 pub trait ColumnBuilder<T: ?Sized> {
@@ -16,29 +20,270 @@ pub trait ColumnBuilder<T: ?Sized> {
 }
 
 
-pub struct StringColBuilder {
-    data: Vec<Option<Rc<String>>>,
-    uniques: UniqueValues<Option<Rc<String>>>,
+pub struct StringColBuilder<S = RandomState> {
+    data: Vec<Option<Arc<String>>>,
+    uniques: UniqueValues<Option<Arc<String>>, S>,
+    sorted: bool,
+    options: StringColumnOptions,
+    overflow_policy: OverflowPolicy,
+    interner: Option<Arc<Mutex<StringInterner>>>,
+    /// First occurrence of each lowercased string if `options.case_insensitive` is set.
+    first_seen: HashMap<String, Arc<String>>,
 }
 
 impl StringColBuilder {
     pub fn new() -> StringColBuilder {
+        StringColBuilder::with_options(StringColumnOptions::default())
+    }
+
+    pub fn with_options(options: StringColumnOptions) -> StringColBuilder {
+        StringColBuilder::with_hasher(options)
+    }
+}
+
+impl<S: BuildHasher + Default> StringColBuilder<S> {
+    /// Like `with_options`, but deduplicates strings with the hasher `S`. E.g. `FnvBuildHasher` is considerably faster
+    /// than the default SipHash, but should only be used for trusted input since it is vulnerable to collision attacks.
+    pub fn with_hasher(options: StringColumnOptions) -> StringColBuilder<S> {
         StringColBuilder {
             data: Vec::new(),
-            uniques: UniqueValues::new(1 << 19),// TODO(clemens): use partition size
+            uniques: UniqueValues::new(options.unique_strings_limit()),
+            sorted: true,
+            options,
+            overflow_policy: OverflowPolicy::default(),
+            interner: None,
+            first_seen: HashMap::new(),
+        }
+    }
+
+    /// Shares strings with other builders that use the same interner, see `StringInterner`.
+    pub fn with_interner(mut self, interner: Arc<Mutex<StringInterner>>) -> StringColBuilder<S> {
+        self.interner = Some(interner);
+        self
+    }
+
+    pub fn with_overflow_policy(mut self, overflow_policy: OverflowPolicy) -> StringColBuilder<S> {
+        self.overflow_policy = overflow_policy;
+        self
+    }
+
+    pub fn push_null(&mut self) {
+        self.data.push(None);
+        self.uniques.insert(None);
+    }
+
+    pub fn push_opt(&mut self, elem: Option<&str>) {
+        match elem {
+            Some(elem) => self.push(elem),
+            None => self.push_null(),
+        }
+    }
+
+    fn new_string(&self, s: &str) -> Arc<String> {
+        match self.interner {
+            Some(ref interner) => interner.lock().unwrap().intern(s),
+            None => Arc::new(s.to_string()),
+        }
+    }
+
+    /// Builds an integer column if the column is nonempty and all values are integers.
+    fn try_build_int_column(&self, name: &str) -> Option<Arc<Column>> {
+        if self.data.is_empty() { return None; }
+        let mut builder = IntColBuilder::new();
+        for s in &self.data {
+            // Values like "007" or "+7" would not round trip
+            let value = s.as_ref()?.parse::<i64>().ok()?;
+            if value.to_string() != **s.as_ref()? { return None; }
+            builder.push(&value);
+        }
+        Some(builder.finalize(name))
+    }
+
+    /// Like `finalize`, but returns an error instead of panicking if the column has too many distinct values and the
+    /// overflow policy is `OverflowPolicy::Error`.
+    pub fn try_finalize(self, name: &str) -> Result<Arc<Column>, QueryError> {
+        if self.options.detect_integers {
+            if let Some(column) = self.try_build_int_column(name) {
+                return Ok(column);
+            }
+        }
+        if !self.uniques.exceeds_max_count() {
+            return Ok(build_string_column(name, &self.data, self.uniques, self.sorted, &self.options));
+        }
+        match self.overflow_policy {
+            OverflowPolicy::Packer => Ok(build_string_column(name, &self.data, self.uniques, self.sorted, &self.options)),
+            OverflowPolicy::Error => bail!(QueryError::FatalError, "Column {} has more than {} distinct values",
+                                           name, self.options.unique_strings_limit()),
+            OverflowPolicy::HybridTopK(k) => Ok(hybrid_string_column(name, &self.data, k, &self.options)),
+            OverflowPolicy::MapToOther(ref other) => Ok(map_to_other(name, &self.data, other, &self.options)),
         }
     }
 }
 
-impl ColumnBuilder<str> for StringColBuilder {
+impl<S: BuildHasher + Default> ColumnBuilder<str> for StringColBuilder<S> {
     fn push(&mut self, elem: &str) {
-        let str_opt = Some(Rc::new(elem.to_string()));
+        let elem = match self.options.normalize(elem) {
+            Some(elem) => elem,
+            None => return self.push_null(),
+        };
+        let string = if self.options.case_insensitive {
+            let key = elem.to_lowercase();
+            let existing = self.first_seen.get(&key).cloned();
+            match existing {
+                Some(string) => string,
+                None => {
+                    let string = self.new_string(elem);
+                    self.first_seen.insert(key, string.clone());
+                    string
+                }
+            }
+        } else {
+            self.new_string(elem)
+        };
+        let elem = string.as_str();
+        let str_opt = Some(string.clone());
+        if let Some(&Some(ref last)) = self.data.last() {
+            self.sorted = self.sorted && last.as_str() <= elem;
+        }
         self.data.push(str_opt.clone());
         self.uniques.insert(str_opt);
     }
 
+    /// Panics if the overflow policy is `OverflowPolicy::Error` and the column has too many distinct values.
+    fn finalize(self, name: &str) -> Arc<Column> {
+        self.try_finalize(name).unwrap()
+    }
+}
+
+
+/// Builds a string column from a stream of values without holding all of them in memory twice.
+/// Values are dictionary encoded with codes in order of first occurrence until the number of distinct values exceeds
+/// `options.unique_strings_limit()`. At that point, the values seen so far are decoded into `PackedStrings` and all
+/// further values are packed directly.
+pub struct StreamingStringColBuilder {
+    state: StreamingState,
+    len: usize,
+    options: StringColumnOptions,
+}
+
+enum StreamingState {
+    Dictionary {
+        codes: Vec<u32>,
+        dictionary: HashMap<String, u32>,
+        entries: Vec<String>,
+        /// Code of the entry that represents null, whose placeholder in `entries` is an empty string.
+        null_code: Option<u32>,
+    },
+    Packed(PackedStrings),
+}
+
+impl StreamingStringColBuilder {
+    pub fn new(options: StringColumnOptions) -> StreamingStringColBuilder {
+        StreamingStringColBuilder {
+            state: StreamingState::Dictionary {
+                codes: Vec::new(),
+                dictionary: HashMap::new(),
+                entries: Vec::new(),
+                null_code: None,
+            },
+            len: 0,
+            options,
+        }
+    }
+
+    pub fn push_null(&mut self) {
+        self.push_opt(None);
+    }
+
+    pub fn is_dictionary_encoded(&self) -> bool {
+        match self.state {
+            StreamingState::Dictionary { .. } => true,
+            StreamingState::Packed(_) => false,
+        }
+    }
+
+    fn push_opt(&mut self, elem: Option<&str>) {
+        let elem = elem.and_then(|elem| self.options.normalize(elem));
+        self.len += 1;
+        let spill = match self.state {
+            StreamingState::Dictionary { ref mut codes, ref mut dictionary, ref mut entries, ref mut null_code } => {
+                // Case insensitive columns keep the first spelling of each value while dictionary encoded
+                let key = match elem {
+                    Some(elem) if self.options.case_insensitive => Some(elem.to_lowercase()),
+                    Some(elem) => Some(elem.to_string()),
+                    None => None,
+                };
+                let code = match key {
+                    Some(ref key) => dictionary.get(key).cloned(),
+                    None => *null_code,
+                };
+                match code {
+                    Some(code) => {
+                        codes.push(code);
+                        false
+                    }
+                    None if entries.len() < self.options.unique_strings_limit() => {
+                        let code = entries.len() as u32;
+                        match key {
+                            Some(key) => { dictionary.insert(key, code); }
+                            None => *null_code = Some(code),
+                        }
+                        entries.push(elem.unwrap_or("").to_string());
+                        codes.push(code);
+                        false
+                    }
+                    None => true,
+                }
+            }
+            StreamingState::Packed(ref mut packed) => {
+                match elem {
+                    Some(elem) => packed.push(elem),
+                    None => packed.push_null(),
+                }
+                false
+            }
+        };
+        if spill {
+            let mut packed = self.spill();
+            match elem {
+                Some(elem) => packed.push(elem),
+                None => packed.push_null(),
+            }
+            self.state = StreamingState::Packed(packed);
+        }
+    }
+
+    /// Decodes all values encoded so far into `PackedStrings`.
+    fn spill(&mut self) -> PackedStrings {
+        let mut packed = PackedStrings::default();
+        if let StreamingState::Dictionary { ref codes, ref entries, null_code, .. } = self.state {
+            for &code in codes {
+                if Some(code) == null_code {
+                    packed.push_null();
+                } else {
+                    packed.push(&entries[code as usize]);
+                }
+            }
+        }
+        packed
+    }
+}
+
+impl ColumnBuilder<str> for StreamingStringColBuilder {
+    fn push(&mut self, elem: &str) {
+        self.push_opt(Some(elem));
+    }
+
     fn finalize(self, name: &str) -> Arc<Column> {
-        build_string_column(name, &self.data, self.uniques)
+        match self.state {
+            StreamingState::Dictionary { codes, entries, null_code, .. } => {
+                let entries = entries.iter().enumerate()
+                    .map(|(code, s)| if Some(code as u32) == null_code { None } else { Some(s.as_str()) })
+                    .collect::<Vec<_>>();
+                unordered_dictionary_column(name, &codes, &entries, &self.options)
+            }
+            StreamingState::Packed(packed) => packed_string_column(name, self.len, packed, &self.options),
+        }
     }
 }
 
@@ -49,6 +294,7 @@ pub struct IntColBuilder {
     max: i64,
     increasing: u64,
     allow_delta_encode: bool,
+    bit_pack: bool,
     last: i64,
 }
 
@@ -60,9 +306,16 @@ impl IntColBuilder {
             max: i64::MIN,
             increasing: 0,
             allow_delta_encode: true,
+            bit_pack: false,
             last: i64::MIN,
         }
     }
+
+    /// Store values with the minimal number of bits, see `IntegerColumn::new_boxed_with_bit_packing`.
+    pub fn with_bit_packing(mut self, bit_pack: bool) -> IntColBuilder {
+        self.bit_pack = bit_pack;
+        self
+    }
 }
 
 impl ColumnBuilder<i64> for IntColBuilder {
@@ -84,32 +337,40 @@ impl ColumnBuilder<i64> for IntColBuilder {
         // TODO(clemens): heuristic for deciding delta encoding could probably be improved
         let delta_encode = self.allow_delta_encode &&
             (self.increasing * 10 > self.data.len() as u64 * 9 && cfg!(feature = "enable_lz4"));
-        IntegerColumn::new_boxed(name, self.data, self.min, self.max, delta_encode)
+        IntegerColumn::new_boxed_with_bit_packing(name, self.data, self.min, self.max, delta_encode, self.bit_pack)
     }
 }
 
 
-pub struct UniqueValues<T> {
+/// Collects distinct values up to `max_count`. Deduplicating strings is the hottest loop during ingest, so faster
+/// hashers than the default SipHash can be plugged in with `S` for trusted input. The set of values is the same for
+/// any hasher.
+pub struct UniqueValues<T, S = RandomState> {
     max_count: usize,
-    values: HashSet<T>,
+    values: HashSet<T, S>,
 }
 
-impl<T: cmp::Eq + Hash> UniqueValues<T> {
-    fn new(max_count: usize) -> UniqueValues<T> {
+impl<T: cmp::Eq + Hash, S: BuildHasher + Default> UniqueValues<T, S> {
+    pub fn new(max_count: usize) -> UniqueValues<T, S> {
         UniqueValues {
             max_count,
-            values: HashSet::new(),
+            values: HashSet::default(),
         }
     }
 
-    fn insert(&mut self, value: T) {
-        if self.values.len() < self.max_count {
+    pub fn insert(&mut self, value: T) {
+        if self.values.len() <= self.max_count {
             self.values.insert(value);
         }
     }
 
-    pub fn get_values(self) -> Option<HashSet<T>> {
-        if self.values.len() < self.max_count {
+    /// Whether more than `max_count` distinct values were inserted.
+    pub fn exceeds_max_count(&self) -> bool {
+        self.values.len() > self.max_count
+    }
+
+    pub fn get_values(self) -> Option<HashSet<T, S>> {
+        if self.values.len() <= self.max_count {
             Some(self.values)
         } else {
             None
@@ -0,0 +1,141 @@
+use bit_vec::BitVec;
+use heapsize::HeapSizeOf;
+
+
+/// Result of a predicate under SQL three-valued logic. Each row is either true, false or unknown (null).
+/// Stores one bit per row that is set if the row is true, and one bit per row that is set if the row is not null.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilterMask {
+    /// Only ever set for rows that are known.
+    values: BitVec,
+    known: BitVec,
+}
+
+impl FilterMask {
+    pub fn new<I: IntoIterator<Item=Option<bool>>>(values: I) -> FilterMask {
+        let mut mask = FilterMask { values: BitVec::new(), known: BitVec::new() };
+        for value in values {
+            mask.values.push(value == Some(true));
+            mask.known.push(value.is_some());
+        }
+        mask
+    }
+
+    /// Creates a mask from a filter with one byte per row in which no row is null.
+    pub fn from_filter(filter: &[u8]) -> FilterMask {
+        FilterMask::new(filter.iter().map(|&selected| Some(selected != 0)))
+    }
+
+    /// Creates a mask from a filter with one byte per row and the validity of each row, e.g. of a `NullableColumn`.
+    /// Rows that are not valid are null regardless of `filter`.
+    pub fn from_filter_and_validity(filter: &[u8], validity: &BitVec) -> FilterMask {
+        assert_eq!(filter.len(), validity.len());
+        let mut values = filter.iter().map(|&selected| selected != 0).collect::<BitVec>();
+        values.intersect(validity);
+        FilterMask { values, known: validity.clone() }
+    }
+
+    pub fn len(&self) -> usize { self.values.len() }
+
+    pub fn get(&self, row: usize) -> Option<bool> {
+        if self.known[row] { Some(self.values[row]) } else { None }
+    }
+
+    /// Null if either side is null and neither side is false.
+    pub fn and(&self, other: &FilterMask) -> FilterMask {
+        assert_eq!(self.len(), other.len(), "Cannot combine filters of different lengths");
+        let mut is_true = self.values.clone();
+        is_true.intersect(&other.values);
+        let mut is_false = self.false_rows();
+        is_false.union(&other.false_rows());
+        FilterMask::from_true_and_false(is_true, is_false)
+    }
+
+    /// Null if either side is null and neither side is true.
+    pub fn or(&self, other: &FilterMask) -> FilterMask {
+        assert_eq!(self.len(), other.len(), "Cannot combine filters of different lengths");
+        let mut is_true = self.values.clone();
+        is_true.union(&other.values);
+        let mut is_false = self.false_rows();
+        is_false.intersect(&other.false_rows());
+        FilterMask::from_true_and_false(is_true, is_false)
+    }
+
+    /// Null rows stay null.
+    pub fn not(&self) -> FilterMask {
+        FilterMask { values: self.false_rows(), known: self.known.clone() }
+    }
+
+    /// Rows that are true as a filter with one byte per row, e.g. for `Column::filter_decode_strings`.
+    /// Null rows are not selected, like in a SQL `WHERE` clause.
+    pub fn selection(&self) -> Vec<u8> {
+        self.values.iter().map(|selected| selected as u8).collect()
+    }
+
+    /// Number of rows that are true, e.g. for `COUNT(*)` without decoding any column.
+    pub fn count_selected(&self) -> usize {
+        self.values.storage().iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    fn false_rows(&self) -> BitVec {
+        let mut is_false = self.known.clone();
+        is_false.difference(&self.values);
+        is_false
+    }
+
+    fn from_true_and_false(is_true: BitVec, is_false: BitVec) -> FilterMask {
+        let mut known = is_true.clone();
+        known.union(&is_false);
+        FilterMask { values: is_true, known }
+    }
+}
+
+impl HeapSizeOf for FilterMask {
+    fn heap_size_of_children(&self) -> usize {
+        (self.values.capacity() + self.known.capacity()) / 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const T: Option<bool> = Some(true);
+    const F: Option<bool> = Some(false);
+    const N: Option<bool> = None;
+
+    #[test]
+    fn test_truth_tables() {
+        let lhs = FilterMask::new(vec![T, T, T, F, F, F, N, N, N]);
+        let rhs = FilterMask::new(vec![T, F, N, T, F, N, T, F, N]);
+        let and = lhs.and(&rhs);
+        let or = lhs.or(&rhs);
+        assert_eq!((0..9).map(|row| and.get(row)).collect::<Vec<_>>(), vec![T, F, N, F, F, F, N, F, N]);
+        assert_eq!((0..9).map(|row| or.get(row)).collect::<Vec<_>>(), vec![T, T, T, T, F, N, T, N, N]);
+        assert_eq!(and.selection(), vec![1, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(or.selection(), vec![1, 1, 1, 1, 0, 0, 1, 0, 0]);
+        assert_eq!(and.count_selected(), 1);
+        assert_eq!(or.count_selected(), 5);
+    }
+
+    #[test]
+    fn test_not() {
+        let values = (0..130).map(|i| if i % 3 == 0 { N } else { Some(i % 2 == 0) }).collect::<Vec<_>>();
+        let mask = FilterMask::new(values.clone());
+        let negated = mask.not();
+        assert_eq!((0..130).map(|row| negated.get(row)).collect::<Vec<_>>(),
+                   values.iter().map(|v| v.map(|b| !b)).collect::<Vec<_>>());
+        assert_eq!(negated.not(), mask);
+        // Null rows are selected by neither a predicate nor its negation
+        assert!(mask.selection().iter().zip(negated.selection()).all(|(&a, b)| a & b == 0));
+        assert_eq!(mask.selection().iter().zip(negated.selection()).filter(|&(&a, b)| a + b == 0).count(), 44);
+    }
+
+    #[test]
+    fn test_from_filter_and_validity() {
+        let validity = BitVec::from_fn(4, |row| row % 2 == 0);
+        let mask = FilterMask::from_filter_and_validity(&[1, 1, 0, 0], &validity);
+        assert_eq!((0..4).map(|row| mask.get(row)).collect::<Vec<_>>(), vec![T, N, F, N]);
+        assert_eq!(FilterMask::from_filter(&[1, 0]).not().selection(), vec![0, 1]);
+    }
+}
@@ -0,0 +1,104 @@
+use seahash;
+
+
+/// HyperLogLog sketch for estimating the number of distinct elements.
+/// Uses a fixed hash function so estimates are deterministic for a given input.
+#[derive(Clone, Debug)]
+pub struct HyperLogLog {
+    precision: u8,
+    registers: Vec<u8>,
+}
+
+impl HyperLogLog {
+    pub fn new(precision: u8) -> HyperLogLog {
+        assert!(precision >= 4 && precision <= 16, "HyperLogLog precision must be between 4 and 16, got {}", precision);
+        HyperLogLog {
+            precision,
+            registers: vec![0; 1 << precision],
+        }
+    }
+
+    pub fn insert(&mut self, bytes: &[u8]) {
+        let hash = seahash::hash(bytes);
+        let index = (hash >> (64 - self.precision)) as usize;
+        // Set lowest bit to bound the rank by 64 - precision + 1
+        let rest = (hash << self.precision) | (1 << (self.precision - 1));
+        let rank = rest.leading_zeros() as u8 + 1;
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+    }
+
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        assert_eq!(self.precision, other.precision);
+        for (r, &o) in self.registers.iter_mut().zip(other.registers.iter()) {
+            if o > *r { *r = o; }
+        }
+    }
+
+    pub fn count(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+        let mut sum = 0.0;
+        let mut zeros = 0;
+        for &r in &self.registers {
+            sum += 1.0 / (1u64 << r) as f64;
+            if r == 0 { zeros += 1; }
+        }
+        let estimate = alpha * m * m / sum;
+        if estimate <= 2.5 * m && zeros > 0 {
+            // Linear counting is more accurate for small cardinalities
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            estimate.round() as u64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn estimate(n: usize, precision: u8) -> u64 {
+        let mut hll = HyperLogLog::new(precision);
+        for i in 0..n {
+            hll.insert(format!("value{}", i).as_bytes());
+            hll.insert(format!("value{}", i / 2).as_bytes());
+        }
+        hll.count()
+    }
+
+    #[test]
+    fn test_small_cardinality() {
+        assert_eq!(estimate(0, 12), 0);
+        assert_eq!(estimate(1, 12), 1);
+        let count = estimate(100, 12);
+        assert!(count >= 95 && count <= 105, "{}", count);
+    }
+
+    #[test]
+    fn test_large_cardinality() {
+        let count = estimate(200_000, 12) as f64;
+        // Standard error is 1.04 / sqrt(4096) ~ 1.6%
+        assert!((count - 200_000.0).abs() < 200_000.0 * 0.05, "{}", count);
+        assert_eq!(estimate(200_000, 12), estimate(200_000, 12));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = HyperLogLog::new(10);
+        let mut b = HyperLogLog::new(10);
+        for i in 0..5000 {
+            a.insert(format!("a{}", i).as_bytes());
+            b.insert(format!("b{}", i).as_bytes());
+        }
+        a.merge(&b);
+        let count = a.count() as f64;
+        assert!((count - 10_000.0).abs() < 10_000.0 * 0.1, "{}", count);
+    }
+}
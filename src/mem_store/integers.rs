@@ -1,16 +1,34 @@
 use std::{u16, u32, u8};
 use std::convert::From;
+use std::mem;
 use std::sync::Arc;
 
+use byteorder::{ByteOrder, LittleEndian};
+
 use engine::*;
 use engine::types::*;
 use mem_store::*;
+use mem_store::bit_packing;
+use mem_store::column::ColumnStats;
+use mem_store::hyperloglog::HyperLogLog;
 
 pub struct IntegerColumn;
 
 impl IntegerColumn {
-    pub fn new_boxed(name: &str, mut values: Vec<i64>, mut min: i64, mut max: i64, delta_encode: bool) -> Arc<Column> {
+    pub fn new_boxed(name: &str, values: Vec<i64>, min: i64, max: i64, delta_encode: bool) -> Arc<Column> {
+        IntegerColumn::new_boxed_with_bit_packing(name, values, min, max, delta_encode, false)
+    }
+
+    /// Like `new_boxed`, but if `bit_pack` is set the (offset or delta encoded) values are stored with the
+    /// minimal number of bits whenever that is narrower than the smallest integer type that fits them.
+    pub fn new_boxed_with_bit_packing(name: &str,
+                                      mut values: Vec<i64>,
+                                      mut min: i64,
+                                      mut max: i64,
+                                      delta_encode: bool,
+                                      bit_pack: bool) -> Arc<Column> {
         let original_range = Some((min, max));
+        let distinct_count = IntegerColumn::approx_count_distinct(&values);
         let min0 = min;
         let max0 = max;
         if delta_encode && values.len() > 0 {
@@ -26,17 +44,17 @@ impl IntegerColumn {
             }
         }
         let mut column = if min >= 0 && max <= From::from(u8::MAX) {
-            IntegerColumn::create_col::<u8>(name, values, 0, min0, max0, delta_encode, EncodingType::U8)
+            IntegerColumn::create_col::<u8>(name, values, 0, min0, max0, delta_encode, bit_pack, EncodingType::U8)
         } else if max - min <= From::from(u8::MAX) {
-            IntegerColumn::create_col::<u8>(name, values, min, min0, max0, delta_encode, EncodingType::U8)
+            IntegerColumn::create_col::<u8>(name, values, min, min0, max0, delta_encode, bit_pack, EncodingType::U8)
         } else if min >= 0 && max <= From::from(u16::MAX) {
-            IntegerColumn::create_col::<u16>(name, values, 0, min0, max0, delta_encode, EncodingType::U16)
+            IntegerColumn::create_col::<u16>(name, values, 0, min0, max0, delta_encode, bit_pack, EncodingType::U16)
         } else if max - min <= From::from(u16::MAX) {
-            IntegerColumn::create_col::<u16>(name, values, min, min0, max0, delta_encode, EncodingType::U16)
+            IntegerColumn::create_col::<u16>(name, values, min, min0, max0, delta_encode, bit_pack, EncodingType::U16)
         } else if min >= 0 && max <= From::from(u32::MAX) {
-            IntegerColumn::create_col::<u32>(name, values, 0, min0, max0, delta_encode, EncodingType::U32)
+            IntegerColumn::create_col::<u32>(name, values, 0, min0, max0, delta_encode, bit_pack, EncodingType::U32)
         } else if max - min <= From::from(u32::MAX) {
-            IntegerColumn::create_col::<u32>(name, values, min, min0, max0, delta_encode, EncodingType::U32)
+            IntegerColumn::create_col::<u32>(name, values, min, min0, max0, delta_encode, bit_pack, EncodingType::U32)
         } else {
             values.shrink_to_fit();
             if delta_encode {
@@ -55,27 +73,96 @@ impl IntegerColumn {
                     vec![DataSection::I64(values)])
             }
         };
+        let stats = ColumnStats {
+            row_count: column.len(),
+            null_count: 0,
+            distinct_count,
+            min: None,
+            max: None,
+            int_range: if column.len() > 0 { original_range } else { None },
+            heap_bytes: column.data_heap_size(),
+            encoding_estimate: None,
+        };
+        column.set_stats(stats);
         column.lz4_encode();
         Arc::new(column)
     }
 
-    pub fn create_col<T>(name: &str, values: Vec<i64>, offset: i64, min: i64, max: i64, delta_encode: bool, t: EncodingType) -> Column
+    fn approx_count_distinct(values: &[i64]) -> usize {
+        let mut hll = HyperLogLog::new(strings::STATS_HLL_PRECISION);
+        let mut bytes = [0; 8];
+        for &value in values {
+            LittleEndian::write_i64(&mut bytes, value);
+            hll.insert(&bytes);
+        }
+        hll.count() as usize
+    }
+
+    pub fn create_col<T>(name: &str,
+                         values: Vec<i64>,
+                         offset: i64,
+                         min: i64,
+                         max: i64,
+                         delta_encode: bool,
+                         bit_pack: bool,
+                         t: EncodingType) -> Column
         where T: GenericIntVec<T>, Vec<T>: Into<DataSection> {
         let values = IntegerColumn::encode::<T>(values, offset);
         let len = values.len();
-        let codec = match (offset == 0, delta_encode) {
+        let mut codec = match (offset == 0, delta_encode) {
             (true, true) => vec![CodecOp::Delta(t)],
             (true, false) => vec![CodecOp::ToI64(t)],
             (false, true) => vec![CodecOp::Add(t, offset), CodecOp::Delta(EncodingType::I64)],
             (false, false) => vec![CodecOp::Add(t, offset)],
         };
+        let bits = values.iter().max().map_or(1, |&max| bit_packing::bits_required(max.cast_usize() + 1));
+        let data = if bit_pack && (bits as usize) < mem::size_of::<T>() * 8 {
+            codec.insert(0, CodecOp::UnpackBits(t, bits, len));
+            DataSection::U64(bit_packing::pack(&values, bits))
+        } else {
+            values.into()
+        };
 
         Column::new(
             name,
             len,
             Some((min - offset, max - offset)),
             codec,
-            vec![values.into()])
+            vec![data])
+    }
+
+    /// Decodes all values of an integer column in row order.
+    /// Returns `None` if the column is not an integer column.
+    pub fn decode(column: &Column) -> Option<Vec<i64>> {
+        if column.basic_type() != BasicType::Integer {
+            return None;
+        }
+        let mut values: Vec<i64> = match *strings::first_section(column) {
+            DataSection::U8(ref x) => x.iter().map(|&v| v as i64).collect(),
+            DataSection::U16(ref x) => x.iter().map(|&v| v as i64).collect(),
+            DataSection::U32(ref x) => x.iter().map(|&v| v as i64).collect(),
+            DataSection::I64(ref x) => x.clone(),
+            DataSection::Mapped(MappedSection::U8(ref x)) => x.iter().map(|&v| v as i64).collect(),
+            DataSection::Mapped(MappedSection::U16(ref x)) => x.iter().map(|&v| v as i64).collect(),
+            DataSection::Mapped(MappedSection::U32(ref x)) => x.iter().map(|&v| v as i64).collect(),
+            DataSection::Mapped(MappedSection::I64(ref x)) => x.to_vec(),
+            _ => return None,
+        };
+        for op in column.codec().ops() {
+            match *op {
+                CodecOp::Add(_, offset) => for v in &mut values { *v += offset },
+                CodecOp::Delta(_) => {
+                    let mut previous = 0;
+                    for v in &mut values {
+                        *v += previous;
+                        previous = *v;
+                    }
+                }
+                CodecOp::ToI64(_) | CodecOp::LZ4(_, _) | CodecOp::UnpackBits(_, _, _) => {}
+                _ => return None,
+            }
+        }
+        Some(values)
     }
 
     pub fn encode<T: GenericIntVec<T>>(values: Vec<i64>, offset: i64) -> Vec<T> {
@@ -89,3 +176,98 @@ impl IntegerColumn {
         encoded_vals
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mem_store::column_builder::*;
+    use mem_store::strings::StringColumnOptions;
+    use test::{black_box, Bencher};
+
+    #[test]
+    fn test_integer_column_stats() {
+        let column = IntegerColumn::new_boxed("test", vec![7, -3, 1000, 7], -3, 1000, false);
+        let stats = column.stats().unwrap();
+        assert_eq!((stats.row_count, stats.null_count, stats.distinct_count), (4, 0, 3));
+        assert_eq!(stats.int_range, Some((-3, 1000)));
+        assert!(column.contains_int_range(1000, 2000));
+        assert!(column.contains_int_range(-10, -3));
+        assert!(!column.contains_int_range(1001, 2000));
+        assert!(!column.contains_int_range(-10, -4));
+    }
+
+    #[test]
+    fn test_bit_packed_frame_of_reference() {
+        let values = (0..1000).map(|i| 1000 + i * 7 % 11).collect::<Vec<i64>>();
+        #[allow(unused_mut)]
+        let mut column = Arc::try_unwrap(
+            IntegerColumn::new_boxed_with_bit_packing("test", values.clone(), 1000, 1010, false, true)).unwrap();
+        #[cfg(feature = "enable_lz4")]
+        column.lz4_decode();
+        assert_eq!(column.codec().ops()[..2], [CodecOp::UnpackBits(EncodingType::U8, 4, 1000), CodecOp::Add(EncodingType::U8, 1000)]);
+        assert_eq!(IntegerColumn::decode(&column), Some(values.clone()));
+        assert_eq!(column.stats().unwrap().int_range, Some((1000, 1010)));
+
+        #[allow(unused_mut)]
+        let mut column = Arc::try_unwrap(IntegerColumn::new_boxed("test", values, 1000, 1010, false)).unwrap();
+        #[cfg(feature = "enable_lz4")]
+        column.lz4_decode();
+        assert_eq!(column.codec().ops()[..1], [CodecOp::Add(EncodingType::U8, 1000)]);
+    }
+
+    #[test]
+    fn test_bit_packed_delta() {
+        let timestamps = timestamps(1000);
+        let mut builder = IntColBuilder::new().with_bit_packing(true);
+        for t in &timestamps {
+            builder.push(t);
+        }
+        let column = builder.finalize("test");
+        assert_eq!(IntegerColumn::decode(&column), Some(timestamps));
+    }
+
+    #[test]
+    fn test_detect_integers() {
+        let options = StringColumnOptions::default().with_detect_integers(true);
+        let mut builder = StringColBuilder::with_options(options);
+        for s in &["12", "-4", "9000000000"] {
+            builder.push(s);
+        }
+        let column = builder.finalize("test");
+        assert_eq!(column.basic_type(), BasicType::Integer);
+        assert_eq!(IntegerColumn::decode(&column), Some(vec![12, -4, 9_000_000_000]));
+
+        for values in &[vec!["1", "2", "x"], vec!["1", "007"]] {
+            let mut builder = StringColBuilder::with_options(options);
+            for s in values {
+                builder.push(s);
+            }
+            assert_eq!(builder.finalize("test").basic_type(), BasicType::String);
+        }
+    }
+
+    /// Monotonically increasing timestamps with irregular gaps of up to 5 seconds.
+    fn timestamps(len: usize) -> Vec<i64> {
+        (0..len as i64).scan(1_500_000_000, |t, i| {
+            *t += (i * 2_654_435_761) % 5;
+            Some(*t)
+        }).collect()
+    }
+
+    fn bench_decode(b: &mut Bencher, bit_pack: bool) {
+        let timestamps = timestamps(1 << 16);
+        let (min, max) = (timestamps[0], timestamps[timestamps.len() - 1]);
+        let column = IntegerColumn::new_boxed_with_bit_packing("test", timestamps, min, max, false, bit_pack);
+        b.iter(|| black_box(IntegerColumn::decode(&column)));
+    }
+
+    #[bench]
+    fn bench_decode_timestamps_flat(b: &mut Bencher) {
+        bench_decode(b, false);
+    }
+
+    #[bench]
+    fn bench_decode_timestamps_bit_packed(b: &mut Bencher) {
+        bench_decode(b, true);
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+use std::mem;
+use std::sync::Arc;
+
+
+/// Deduplicates strings across the builders of several partitions of the same column, so that values that recur in
+/// every partition are only held in memory once while the partitions are built.
+/// The dictionaries of the finalized columns are not shared.
+pub struct StringInterner {
+    strings: HashSet<Interned>,
+    max_strings: usize,
+    saved_bytes: usize,
+}
+
+impl StringInterner {
+    /// Creates an interner that stops adding new strings once it holds `max_strings` distinct strings.
+    /// Strings that are already interned are still shared after that.
+    pub fn new(max_strings: usize) -> StringInterner {
+        StringInterner {
+            strings: HashSet::new(),
+            max_strings,
+            saved_bytes: 0,
+        }
+    }
+
+    pub fn intern(&mut self, s: &str) -> Arc<String> {
+        if let Some(interned) = self.strings.get(s) {
+            self.saved_bytes += s.len() + mem::size_of::<String>();
+            return interned.0.clone();
+        }
+        let string = Arc::new(s.to_string());
+        if self.strings.len() < self.max_strings {
+            self.strings.insert(Interned(string.clone()));
+        }
+        string
+    }
+
+    pub fn distinct_count(&self) -> usize { self.strings.len() }
+
+    /// Number of bytes that would have been allocated for strings that were returned from the interner instead.
+    pub fn saved_bytes(&self) -> usize { self.saved_bytes }
+}
+
+/// Hashes and compares like the `str` it points to, so the interner can be queried without allocating a `String`.
+#[derive(PartialEq, Eq, Hash)]
+struct Interned(Arc<String>);
+
+impl ::std::borrow::Borrow<str> for Interned {
+    fn borrow(&self) -> &str { &self.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use mem_store::column_builder::*;
+
+    #[test]
+    fn test_intern() {
+        let mut interner = StringInterner::new(2);
+        let a = interner.intern("hostname1");
+        let b = interner.intern("hostname1");
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(interner.saved_bytes(), 9 + mem::size_of::<String>());
+
+        interner.intern("hostname2");
+        let c = interner.intern("hostname3");
+        let d = interner.intern("hostname3");
+        assert_eq!(interner.distinct_count(), 2);
+        assert!(!Arc::ptr_eq(&c, &d));
+        assert_eq!(*c, *d);
+    }
+
+    #[test]
+    fn test_shared_between_builders() {
+        let interner = Arc::new(Mutex::new(StringInterner::new(100)));
+        let columns = (0..3).map(|_| {
+            let mut builder = StringColBuilder::new().with_interner(interner.clone());
+            for value in &["host1", "host2", "host1"] {
+                builder.push(value);
+            }
+            builder.finalize("hostname")
+        }).collect::<Vec<_>>();
+        assert_eq!(interner.lock().unwrap().distinct_count(), 2);
+        assert_eq!(interner.lock().unwrap().saved_bytes(), 7 * (5 + mem::size_of::<String>()));
+        for column in columns {
+            assert_eq!(column.decode_strings().unwrap(), vec![Some("host1".to_string()), Some("host2".to_string()), Some("host1".to_string())]);
+        }
+    }
+
+    #[test]
+    fn test_builder_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<StringColBuilder>();
+    }
+}
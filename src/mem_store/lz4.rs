@@ -1,6 +1,6 @@
 extern crate lz4;
 
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::mem;
 use std::slice::{from_raw_parts, from_raw_parts_mut};
 use std::fmt::Debug;
@@ -10,6 +10,11 @@ pub fn decoder(data: &[u8]) -> lz4::Decoder<&[u8]> {
     lz4::Decoder::new(data).unwrap()
 }
 
+/// Number of bytes `data` decompresses to, or an error if it is not a valid LZ4 frame. Unlike `decode`, never panics.
+pub fn decompressed_len(data: &[u8]) -> io::Result<u64> {
+    io::copy(&mut lz4::Decoder::new(data)?, &mut io::sink())
+}
+
 pub unsafe fn encode<T: Debug>(data: &[T]) -> Vec<u8> {
     let ptr_t = data.as_ptr();
     // Endianness? Never heard of it...
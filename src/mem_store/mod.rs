@@ -1,7 +1,13 @@
+pub mod bit_packing;
+pub mod bloom_filter;
 pub mod codec;
 pub mod column;
 pub mod column_builder;
+pub mod filter_mask;
+pub mod hyperloglog;
 pub mod integers;
+pub mod interner;
+pub mod nullable;
 pub mod partition;
 pub mod raw_col;
 pub mod strings;
@@ -10,11 +16,14 @@ pub mod tree;
 pub mod value;
 #[cfg(feature = "enable_lz4")]
 pub mod lz4;
+#[cfg(feature = "enable_arrow")]
+pub mod arrow;
 mod mixed_column;
 mod lru;
 mod lru_fork;
 
-pub use self::column::{Column, DataSection};
+pub use self::column::{CmpOp, Column, ColumnStats, DataSection, EncodingEstimate, MappedSection, MappedSlice,
+                        MemoryReport};
 pub use self::codec::{Codec, CodecOp};
 pub use self::tree::*;
 pub use self::table::TableStats;
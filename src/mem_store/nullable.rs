@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use bit_vec::BitVec;
+use heapsize::HeapSizeOf;
+
+use engine::types::BasicType;
+use mem_store::column::Column;
+use mem_store::filter_mask::FilterMask;
+use mem_store::integers::IntegerColumn;
+
+
+/// Wraps a column with a validity mask that marks rows as null independently of how the column is encoded.
+/// The values stored in the inner column for null rows are arbitrary placeholders and are never exposed.
+#[derive(Debug)]
+pub struct NullableColumn {
+    column: Arc<Column>,
+    /// One bit per row, set if the row is not null.
+    present: BitVec,
+}
+
+impl NullableColumn {
+    /// Creates a nullable column from `column` and the validity of each of its rows.
+    pub fn new<I: IntoIterator<Item=bool>>(column: Arc<Column>, present: I) -> NullableColumn {
+        let present = present.into_iter().collect::<BitVec>();
+        assert_eq!(present.len(), column.len(),
+                   "Validity mask length does not match length of column {}", column.name());
+        NullableColumn { column, present }
+    }
+
+    /// Creates a nullable column in which exactly the rows that are null in `column` are null, e.g. the rows with the
+    /// null code of a dictionary encoded string column.
+    pub fn from_column(column: Arc<Column>) -> NullableColumn {
+        let present = match column.basic_type() {
+            BasicType::Null => BitVec::from_elem(column.len(), false),
+            BasicType::String => match column.filter_eq_str(None, false) {
+                Some(nulls) => nulls.iter().map(|&null| null == 0).collect(),
+                None => BitVec::from_elem(column.len(), true),
+            },
+            _ => BitVec::from_elem(column.len(), true),
+        };
+        NullableColumn { column, present }
+    }
+
+    pub fn column(&self) -> &Arc<Column> { &self.column }
+    pub fn name(&self) -> &str { self.column.name() }
+    pub fn len(&self) -> usize { self.column.len() }
+
+    pub fn is_null(&self, row: usize) -> bool {
+        !self.present[row]
+    }
+
+    pub fn null_count(&self) -> usize {
+        self.len() - self.present.storage().iter().map(|w| w.count_ones() as usize).sum::<usize>()
+    }
+
+    /// Validity of each row as a filter with one byte per row that is `1` for non-null rows.
+    pub fn validity(&self) -> Vec<u8> {
+        self.present.iter().map(|present| present as u8).collect()
+    }
+
+    /// Clears all entries of `filter` that correspond to null rows.
+    pub fn apply_validity(&self, filter: &mut [u8]) {
+        for (row, selected) in filter.iter_mut().enumerate() {
+            if self.is_null(row) { *selected = 0; }
+        }
+    }
+
+    /// Converts a filter on the values of the inner column into a `FilterMask` in which null rows are unknown,
+    /// so that it can be combined with other predicates under three-valued logic.
+    pub fn filter_mask(&self, filter: &[u8]) -> FilterMask {
+        FilterMask::from_filter_and_validity(filter, &self.present)
+    }
+
+    /// All values of a string column in row order, see `strings::decode_strings`.
+    pub fn decode_strings(&self) -> Option<Vec<Option<String>>> {
+        self.column.decode_strings().map(|values| self.mask(values.into_iter()))
+    }
+
+    /// All values of an integer column in row order, see `IntegerColumn::decode`.
+    pub fn decode_integers(&self) -> Option<Vec<Option<i64>>> {
+        IntegerColumn::decode(&self.column).map(|values| self.mask(values.into_iter().map(Some)))
+    }
+
+    /// Rows with dictionary code `code` that are not null, see `strings::filter_eq_code`.
+    pub fn filter_eq_code(&self, code: u32) -> Option<Vec<u8>> {
+        self.column.filter_eq_code(code).map(|mut filter| {
+            self.apply_validity(&mut filter);
+            filter
+        })
+    }
+
+    /// Non-null rows that are (not) equal to `value`, see `strings::filter_eq_str`.
+    pub fn filter_eq_str(&self, value: Option<&str>, negate: bool) -> Option<Vec<u8>> {
+        match value {
+            Some(value) => self.column.filter_eq_str(Some(value), negate).map(|mut filter| {
+                self.apply_validity(&mut filter);
+                filter
+            }),
+            // `IS NULL` selects exactly the null rows and `!= NULL` selects nothing
+            None if negate => Some(vec![0; self.len()]),
+            None => Some(self.validity().iter().map(|&present| 1 - present).collect()),
+        }
+    }
+
+    /// Non-null rows that are (not) equal to any of `values`, see `strings::filter_in`.
+    pub fn filter_in(&self, values: &[&str], negate: bool) -> Option<Vec<u8>> {
+        let values = values.iter().map(|&value| Some(value)).collect::<Vec<_>>();
+        self.column.filter_in(&values, negate).map(|mut filter| {
+            self.apply_validity(&mut filter);
+            filter
+        })
+    }
+
+    fn mask<T, I: Iterator<Item=Option<T>>>(&self, values: I) -> Vec<Option<T>> {
+        values.enumerate()
+            .map(|(row, value)| if self.is_null(row) { None } else { value })
+            .collect()
+    }
+}
+
+impl HeapSizeOf for NullableColumn {
+    fn heap_size_of_children(&self) -> usize {
+        Column::heap_size_of_children(&self.column) + self.present.capacity() / 8
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mem_store::column_builder::*;
+    use mem_store::strings::*;
+
+    #[test]
+    fn test_nullable_strings() {
+        let values = (0..100).map(|i| if i % 3 == 0 { None } else { Some(format!("value{}", i % 5)) }).collect::<Vec<_>>();
+        let mut builder = StringColBuilder::new();
+        for value in &values {
+            // Placeholder for null rows, the validity mask takes precedence
+            builder.push(value.as_ref().map_or("value0", |s| s.as_str()));
+        }
+        let column = NullableColumn::new(builder.finalize("test"), values.iter().map(|v| v.is_some()));
+        assert_eq!(column.null_count(), 34);
+        assert!(column.is_null(0) && !column.is_null(1));
+        assert_eq!(column.decode_strings(), Some(values.clone()));
+
+        // "value0" is the smallest string and has code 0
+        let expected = values.iter().map(|v| (v.as_ref().map(|s| s.as_str()) == Some("value0")) as u8).collect::<Vec<_>>();
+        assert_eq!(column.filter_eq_code(0), Some(expected));
+        assert_eq!(column.decode_integers(), None);
+
+        // Negated filters exclude null rows
+        let expected = values.iter().map(|v| v.as_ref().map_or(false, |s| s != "value0") as u8).collect::<Vec<_>>();
+        assert_eq!(column.filter_eq_str(Some("value0"), true), Some(expected));
+        let expected = values.iter()
+            .map(|v| v.as_ref().map_or(false, |s| s != "value0" && s != "value1") as u8)
+            .collect::<Vec<_>>();
+        assert_eq!(column.filter_in(&["value0", "value1"], true), Some(expected));
+        let expected = values.iter().map(|v| v.is_none() as u8).collect::<Vec<_>>();
+        assert_eq!(column.filter_eq_str(None, false), Some(expected));
+        assert_eq!(column.filter_eq_str(None, true), Some(vec![0; 100]));
+
+        // NOT (x = 'value0') is unknown for null rows
+        let mask = column.filter_mask(&column.column().filter_eq_str(Some("value0"), false).unwrap()).not();
+        assert_eq!(mask.get(0), None);
+        assert_eq!(mask.selection(), column.filter_eq_str(Some("value0"), true).unwrap());
+    }
+
+    #[test]
+    fn test_nullable_integers() {
+        let values = vec![Some(5), None, Some(-3), Some(1000), None];
+        let inner = IntegerColumn::new_boxed("test", values.iter().map(|v| v.unwrap_or(0)).collect(), -3, 1000, false);
+        let column = NullableColumn::new(inner, values.iter().map(|v| v.is_some()));
+        assert_eq!(column.decode_integers(), Some(values));
+        assert_eq!(column.validity(), vec![1, 0, 1, 1, 0]);
+        let mut filter = vec![1, 1, 0, 1, 1];
+        column.apply_validity(&mut filter);
+        assert_eq!(filter, vec![1, 0, 0, 1, 0]);
+        assert!(column.heap_size_of_children() > Column::heap_size_of_children(column.column()));
+    }
+
+    #[test]
+    fn test_from_column() {
+        let values = [Some("a"), None, Some(""), None];
+        for &max_unique_strings in &[1 << 19, 0] {
+            let options = StringColumnOptions::default().with_max_unique_strings(max_unique_strings);
+            let column = NullableColumn::from_column(build_string_column_from_strs("test", &values, &options));
+            assert_eq!(column.validity(), vec![1, 0, 1, 0]);
+            assert_eq!(column.null_count(), column.column().null_count());
+            assert_eq!(column.filter_eq_str(Some(""), false), Some(vec![0, 0, 1, 0]));
+        }
+
+        let column = NullableColumn::from_column(Arc::new(Column::null("test", 3)));
+        assert_eq!(column.null_count(), 3);
+        let column = NullableColumn::from_column(IntegerColumn::new_boxed("test", vec![1, 2], 1, 2, false));
+        assert_eq!(column.decode_integers(), Some(vec![Some(1), Some(2)]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_validity_length_mismatch() {
+        let inner = IntegerColumn::new_boxed("test", vec![1, 2, 3], 1, 3, false);
+        NullableColumn::new(inner, vec![true, false]);
+    }
+}
@@ -68,6 +68,20 @@ impl Partition {
         columns
     }
 
+    /// Values of the rows selected by `filter` in the named columns, see `strings::project_strings`. Columns that the
+    /// partition doesn't have are null. Returns `None` if any of the columns is not resident or not a string column.
+    pub fn project(&self, filter: &[u8], columns: &[&str]) -> Option<Vec<Vec<Option<String>>>> {
+        let mut resident = Vec::with_capacity(columns.len());
+        for &name in columns {
+            resident.push(match self.cols.iter().find(|handle| handle.name() == name) {
+                Some(handle) => handle.try_get().clone()?,
+                None => Arc::new(Column::null(name, self.len)),
+            });
+        }
+        let resident = resident.iter().map(|column| &**column).collect::<Vec<_>>();
+        strings::project_strings(&resident, filter)
+    }
+
     pub fn col_names(&self) -> Vec<&str> {
         let mut names = Vec::new();
         for handle in &self.cols {
@@ -162,6 +176,16 @@ impl Partition {
         }
     }
 
+    /// Memory reports of all resident columns.
+    pub fn memory_report_per_column(&self) -> Vec<(String, MemoryReport)> {
+        self.cols.iter()
+            .filter_map(|handle| {
+                let c = handle.col.lock().unwrap();
+                c.as_ref().map(|c| (handle.name().to_string(), c.memory_report()))
+            })
+            .collect()
+    }
+
     pub fn heap_size_per_column(&self) -> Vec<(String, usize)> {
         self.cols.iter()
             .map(|handle| {
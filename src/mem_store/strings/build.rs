@@ -0,0 +1,1091 @@
+use std::borrow::Cow;
+use std::cmp::{self, Reverse};
+use std::collections::hash_set::HashSet;
+use std::hash::{BuildHasher, Hash};
+use std::sync::Arc;
+use std::{u8, u16, u32};
+use num::PrimInt;
+use hex;
+use stringpack::*;
+use mem_store::column_builder::UniqueValues;
+use mem_store::bloom_filter::BloomFilter;
+use super::*;
+
+/// Whether `fast_build_string_column` stops dictionary encoding a column of `len` values once it has seen
+/// `unique_count` distinct values.
+fn exceeds_dictionary_limit(unique_count: usize, len: usize, name: &str, options: &StringColumnOptions) -> bool {
+    // TODO(clemens): is 2 the right constant? and should probably also depend on the length of the strings
+    // TODO(clemens): len > 1000 || name == "string_packed" is a hack to make tests use dictionary encoding. Remove once we are able to group by string packed columns.
+    let ratio_limit = len / DICTIONARY_RATIO;
+    (ratio_limit > 0 && unique_count >= ratio_limit && (len > 1000 || name == "string_packed"))
+        || options.max_unique_strings.map_or(false, |max| unique_count > max)
+}
+
+/// Encoding, as named by `Column::memory_report`, that `fast_build_string_column` chooses for `len` values with
+/// `unique_count` distinct values. Arguments other than `unique_count` and `sorted` are the same as for
+/// `fast_build_string_column`.
+/// Symbol table compression depends on the learned table and is reported as packed.
+pub fn fast_build_encoding(name: &str,
+                           unique_count: usize,
+                           len: usize,
+                           sorted: bool,
+                           lhex: bool,
+                           uhex: bool,
+                           total_bytes: usize,
+                           options: &StringColumnOptions) -> &'static str {
+    if !exceeds_dictionary_limit(unique_count, len, name, options) {
+        "dictionary"
+    } else if (lhex || uhex) && total_bytes / len > 5 {
+        "packed"
+    } else if sorted && options.front_coding {
+        "front_coded"
+    } else {
+        "packed"
+    }
+}
+
+pub fn fast_build_string_column<'a, T>(name: &str,
+                                       strings: T,
+                                       len: usize,
+                                       lhex: bool,
+                                       uhex: bool,
+                                       total_bytes: usize,
+                                       options: &StringColumnOptions)
+                                       -> Arc<Column> where T: Iterator<Item=&'a str> + Clone {
+    if options.case_insensitive {
+        let folded = fold_case(strings);
+        let options = options.with_case_insensitive(false);
+        let column = Arc::try_unwrap(fast_build_string_column(
+            name, folded.iter().cloned(), len, lhex, uhex, total_bytes, &options)).unwrap();
+        return Arc::new(attach_metadata(column, &options.with_case_insensitive(true)));
+    }
+    let mut unique_values = HashSetSea::default();
+    for s in strings.clone() {
+        unique_values.insert(s);
+        if exceeds_dictionary_limit(unique_values.len(), len, name, options) {
+            let (codec, data) = if (lhex || uhex) && total_bytes / len > 5 {
+                let packed = PackedBytes::from_iterator(strings.map(|s| hex::decode(s).unwrap()));
+                (vec![CodecOp::UnhexpackStrings(uhex, total_bytes)], vec![DataSection::U8(packed.into_vec())])
+            } else if options.front_coding && strings.clone().zip(strings.clone().skip(1)).all(|(a, b)| a <= b) {
+                let front_coded = FrontCodedStrings::from_iterator(strings);
+                (vec![CodecOp::UnpackFrontCodedStrings(front_coded.total_bytes())], vec![DataSection::U8(front_coded.into_vec())])
+            } else if let Some(table) = learn_symbol_table(strings.clone(), options) {
+                let symbol_coded = SymbolCodedStrings::from_iterator(strings, table);
+                (vec![CodecOp::UnpackSymbolCodedStrings(symbol_coded.total_bytes())], vec![DataSection::U8(symbol_coded.into_vec())])
+            } else {
+                let packed = PackedStrings::from_iterator(strings);
+                (string_pack_codec(), packed_sections(packed.into_vec(), len, options))
+            };
+            let mut column = attach_metadata(Column::new(
+                name,
+                len,
+                None,
+                codec,
+                data,
+            ), options);
+            column.lz4_encode();
+            return Arc::new(column);
+        }
+    }
+    let dict_size = unique_values.len();
+    let mut mapping = unique_values.into_iter().collect::<Vec<_>>();
+    match options.dictionary_order {
+        DictionaryOrder::Sorted => mapping.sort(),
+        DictionaryOrder::Frequency => {
+            let mut counts: HashMapSea<&str, usize> = HashMapSea::default();
+            for s in strings.clone() {
+                *counts.entry(s).or_insert(0) += 1;
+            }
+            mapping.sort_by_key(|s| (Reverse(counts[s]), *s));
+        }
+        DictionaryOrder::FirstOccurrence => mapping = first_occurrence_order(strings.clone(), dict_size),
+    }
+    let mut packed_mapping = IndexedPackedStrings::default();
+    for s in mapping {
+        packed_mapping.push(s);
+    }
+    let mut column = if dict_size <= From::from(u8::MAX) {
+        let indices: Vec<u8> = {
+            let mut dictionary: HashMapSea<&str, u8> = HashMapSea::default();
+            for (i, s) in packed_mapping.iter().enumerate() {
+                dictionary.insert(&s, i as u8);
+            }
+            strings.map(|s| *dictionary.get(s).unwrap()).collect()
+        };
+        let (dictionary_indices, dictionary_data) = packed_mapping.into_parts();
+        Column::new(
+            name,
+            indices.len(),
+            Some((0, dict_size as i64)),
+            dict_codec(EncodingType::U8, options.dictionary_order),
+            vec![DataSection::U8(indices),
+                 DataSection::U64(dictionary_indices),
+                 DataSection::U8(dictionary_data)])
+    } else if dict_size <= From::from(u16::MAX) {
+        let indices: Vec<u16> = {
+            let mut dictionary: HashMapSea<&str, u16> = HashMapSea::default();
+            for (i, s) in packed_mapping.iter().enumerate() {
+                dictionary.insert(&s, i as u16);
+            }
+            strings.map(|s| *dictionary.get(s).unwrap()).collect()
+        };
+        let (dictionary_indices, dictionary_data) = packed_mapping.into_parts();
+        Column::new(
+            name,
+            indices.len(),
+            Some((0, dict_size as i64)),
+            dict_codec(EncodingType::U16, options.dictionary_order),
+            vec![DataSection::U16(indices),
+                 DataSection::U64(dictionary_indices),
+                 DataSection::U8(dictionary_data)])
+    } else {
+        assert!(dict_size <= u32::MAX as usize);
+        let indices: Vec<u32> = {
+            let mut dictionary: HashMapSea<&str, u32> = HashMapSea::default();
+            for (i, s) in packed_mapping.iter().enumerate() {
+                dictionary.insert(&s, i as u32);
+            }
+            strings.map(|s| *dictionary.get(s).unwrap()).collect()
+        };
+        let (dictionary_indices, dictionary_data) = packed_mapping.into_parts();
+        Column::new(
+            name,
+            indices.len(),
+            Some((0, dict_size as i64)),
+            dict_codec(EncodingType::U32, options.dictionary_order),
+            vec![DataSection::U32(indices),
+                 DataSection::U64(dictionary_indices),
+                 DataSection::U8(dictionary_data)])
+    };
+    column = attach_metadata(column, options);
+    column.lz4_encode();
+    Arc::new(column)
+}
+
+/// Replaces every string with the first string that is equal to it ignoring case.
+fn fold_case<'a, T: Iterator<Item=&'a str>>(strings: T) -> Vec<&'a str> {
+    let mut first_seen: HashMapSea<String, &'a str> = HashMapSea::default();
+    strings.map(|s| *first_seen.entry(s.to_lowercase()).or_insert(s)).collect()
+}
+
+pub fn build_string_column<T, S>(name: &str,
+                                values: &[Option<T>],
+                                unique_values: UniqueValues<Option<T>, S>,
+                                sorted: bool,
+                                options: &StringColumnOptions)
+                                -> Arc<Column> where T: AsStr + cmp::Eq + Hash, S: BuildHasher + Default {
+    let unique_values = unique_values.get_values();
+    let estimate = match unique_values {
+        Some(ref u) if options.adaptive_encoding => Some(estimate_encodings(values, u)),
+        _ => None,
+    };
+    let column = if let Some(u) = unique_values.filter(|_| estimate.map_or(true, |e| e.prefers_dictionary())) {
+        if u.len() <= From::from(u8::MAX) {
+            let (indices, dictionary, null_code) = dictionary_compress::<u8, _, _>(values, u, options.dictionary_order);
+            dictionary_column(name, indices, DataSection::U8, EncodingType::U8, dictionary, null_code, options)
+        } else if u.len() <= From::from(u16::MAX) {
+            let (indices, dictionary, null_code) = dictionary_compress::<u16, _, _>(values, u, options.dictionary_order);
+            dictionary_column(name, indices, DataSection::U16, EncodingType::U16, dictionary, null_code, options)
+        } else {
+            let (indices, dictionary, null_code) = dictionary_compress::<u32, _, _>(values, u, options.dictionary_order);
+            dictionary_column(name, indices, DataSection::U32, EncodingType::U32, dictionary, null_code, options)
+        }
+    } else if sorted && options.front_coding {
+        let front_coded = FrontCodedStrings::from_nullable_strings(values);
+        Arc::new(attach_metadata(Column::new(
+            name,
+            values.len(),
+            None,
+            vec![CodecOp::UnpackFrontCodedStrings(front_coded.total_bytes())],
+            vec![DataSection::U8(front_coded.into_vec())]), options))
+    } else if let Some(table) = learn_symbol_table(values.iter().filter_map(|v| v.as_ref().map(|s| s.as_str())), options) {
+        let symbol_coded = SymbolCodedStrings::from_nullable_strings(values, table);
+        Arc::new(attach_metadata(Column::new(
+            name,
+            values.len(),
+            None,
+            vec![CodecOp::UnpackSymbolCodedStrings(symbol_coded.total_bytes())],
+            vec![DataSection::U8(symbol_coded.into_vec())]), options))
+    } else if let Some(component_coded) = component_code(values, options) {
+        Arc::new(attach_metadata(Column::new(
+            name,
+            values.len(),
+            None,
+            vec![CodecOp::UnpackComponentCodedStrings(component_coded.total_bytes())],
+            vec![DataSection::U8(component_coded.into_vec())]), options))
+    } else {
+        let packed = PackedStrings::from_nullable_strings(values);
+        Arc::new(attach_metadata(Column::new(
+            name,
+            values.len(),
+            None,
+            string_pack_codec(),
+            packed_sections(packed.into_vec(), values.len(), options)), options))
+    };
+    match estimate {
+        Some(estimate) => with_encoding_estimate(column, estimate),
+        None => column,
+    }
+}
+
+/// Estimates the size of dictionary encoding `values` from the exact size of the dictionary, and the size of packing
+/// them from a sample of evenly spaced rows.
+pub(super) fn estimate_encodings<T: AsStr, S: BuildHasher>(values: &[Option<T>],
+                                                unique_values: &HashSet<Option<T>, S>) -> EncodingEstimate {
+    let rows = values.len();
+    let dictionary = unique_values.iter().map(|value| value.as_ref().map_or(0, |s| s.as_str().len()) + 8).sum::<usize>();
+    let code_bytes = if unique_values.len() <= u8::MAX as usize {
+        1
+    } else if unique_values.len() <= u16::MAX as usize {
+        2
+    } else {
+        4
+    };
+    let stride = cmp::max(1, rows / ENCODING_SAMPLE_ROWS);
+    let (mut sampled_rows, mut sampled_bytes) = (0, 0);
+    for value in values.iter().step_by(stride) {
+        let len = value.as_ref().map_or(0, |s| s.as_str().len());
+        sampled_rows += 1;
+        sampled_bytes += len + varint_len(len as u64 + 1);
+    }
+    EncodingEstimate {
+        dictionary_bytes: dictionary + code_bytes * rows,
+        packed_bytes: if sampled_rows == 0 { 0 } else { sampled_bytes * rows / sampled_rows },
+    }
+}
+
+fn varint_len(mut value: u64) -> usize {
+    let mut len = 1;
+    while value >= 0x80 {
+        value >>= 7;
+        len += 1;
+    }
+    len
+}
+
+/// Records the estimate that decided the encoding of `column` in its stats.
+fn with_encoding_estimate(mut column: Arc<Column>, estimate: EncodingEstimate) -> Arc<Column> {
+    if let Some(column) = Arc::get_mut(&mut column) {
+        if let Some(mut stats) = column.stats().cloned() {
+            stats.encoding_estimate = Some(estimate);
+            column.set_stats(stats);
+        }
+    }
+    column
+}
+
+/// Builds a string column from all of its values at once. Use `StringColBuilder` to build the same column row by row
+/// without buffering the values first.
+pub fn build_string_column_from_values(name: &str,
+                                       values: &[Option<Arc<String>>],
+                                       options: &StringColumnOptions) -> Arc<Column> {
+    build_from_values(name, values, options)
+}
+
+/// Like `build_string_column_from_values`, but for borrowed strings. Values are deduplicated without copying them
+/// and only the strings that end up in the dictionary or packed data are copied, so no `Arc` is allocated per row.
+pub fn build_string_column_from_strs(name: &str,
+                                     values: &[Option<&str>],
+                                     options: &StringColumnOptions) -> Arc<Column> {
+    build_from_values(name, values, options)
+}
+
+fn build_from_values<T: AsStr + cmp::Eq + Hash + Clone>(name: &str,
+                                                        values: &[Option<T>],
+                                                        options: &StringColumnOptions) -> Arc<Column> {
+    let mut unique_values = UniqueValues::<_>::new(options.unique_strings_limit());
+    for value in values {
+        unique_values.insert(value.clone());
+    }
+    let sorted = values.windows(2).all(|w| match (&w[0], &w[1]) {
+        (&Some(ref a), &Some(ref b)) => a.as_str() <= b.as_str(),
+        _ => true,
+    });
+    build_string_column(name, values, unique_values, sorted, options)
+}
+
+/// Dictionary encodes the `k` most frequent values and stores all other values inline, see `HybridStrings`.
+pub fn hybrid_string_column<T: AsStr>(name: &str,
+                                      values: &[Option<T>],
+                                      k: usize,
+                                      options: &StringColumnOptions) -> Arc<Column> {
+    let hybrid = HybridStrings::from_nullable_strings(values, most_frequent(values, k).into_iter());
+    Arc::new(attach_metadata(Column::new(
+        name,
+        values.len(),
+        None,
+        vec![CodecOp::UnpackHybridStrings],
+        vec![DataSection::U8(hybrid.into_vec())]), options))
+}
+
+/// Replaces all but the most frequent values with `other` so that there are at most `options.unique_strings_limit()`
+/// distinct values, and builds a column from the result. Nulls are preserved.
+pub fn map_to_other<T: AsStr>(name: &str,
+                              values: &[Option<T>],
+                              other: &str,
+                              options: &StringColumnOptions) -> Arc<Column> {
+    let has_null = values.iter().any(|value| value.is_none());
+    let keep = options.unique_strings_limit().saturating_sub(1 + has_null as usize);
+    let frequent = most_frequent(values, keep).into_iter().collect::<HashSetSea<_>>();
+    let mapped = values.iter()
+        .map(|value| value.as_ref().map(|s| if frequent.contains(s.as_str()) { s.as_str() } else { other }))
+        .collect::<Vec<_>>();
+    build_string_column_from_strs(name, &mapped, options)
+}
+
+/// The `k` most frequent non-null values, ties are broken in favor of the smaller string.
+fn most_frequent<'a, T: AsStr>(values: &'a [Option<T>], k: usize) -> Vec<&'a str> {
+    let mut counts: HashMapSea<&str, usize> = HashMapSea::default();
+    for value in values.iter().filter_map(|value| value.as_ref()) {
+        *counts.entry(value.as_str()).or_insert(0) += 1;
+    }
+    let mut frequent = counts.into_iter().collect::<Vec<_>>();
+    frequent.sort_by_key(|&(s, count)| (Reverse(count), s));
+    frequent.into_iter().take(k).map(|(s, _)| s).collect()
+}
+
+pub(super) fn count_runs<T: PartialEq>(codes: &[T]) -> usize {
+    if codes.is_empty() { return 0; }
+    1 + codes.windows(2).filter(|w| w[0] != w[1]).count()
+}
+
+/// Splits `codes` into the value and length of each run of identical codes.
+pub(super) fn run_length_encode<T: PrimInt>(codes: &[T]) -> (Vec<T>, Vec<u32>) {
+    let mut values = Vec::new();
+    let mut lengths = Vec::<u32>::new();
+    for &code in codes {
+        match (values.last(), lengths.last_mut()) {
+            (Some(&last), Some(length)) if last == code && *length < u32::MAX => {
+                *length += 1;
+                continue;
+            }
+            _ => {}
+        }
+        values.push(code);
+        lengths.push(1);
+    }
+    (values, lengths)
+}
+
+pub(super) fn run_length_decode<T: Copy>(values: &[T], lengths: &[u32]) -> Vec<T> {
+    let mut decoded = Vec::with_capacity(lengths.iter().map(|&l| l as usize).sum());
+    for (&value, &length) in values.iter().zip(lengths) {
+        for _ in 0..length {
+            decoded.push(value);
+        }
+    }
+    decoded
+}
+
+/// Learns a symbol table on a sample of the strings and returns it if it compresses the sample by at least 30%.
+fn learn_symbol_table<'a>(strings: impl Iterator<Item=&'a str> + Clone, options: &StringColumnOptions) -> Option<SymbolTable> {
+    if !options.symbol_table_compression {
+        return None;
+    }
+    let sample = strings.take(SYMBOL_TABLE_SAMPLE_SIZE);
+    let table = SymbolTable::learn(sample.clone().map(|s| s.as_bytes()));
+    let mut encoded = Vec::new();
+    let mut raw_bytes = 0;
+    for string in sample {
+        table.encode(string.as_bytes(), &mut encoded);
+        raw_bytes += string.len();
+    }
+    if encoded.len() * 10 < raw_bytes * 7 {
+        Some(table)
+    } else {
+        None
+    }
+}
+
+/// Splits the strings into components if a separator is configured and components are shared widely enough for the
+/// second level dictionary to pay off.
+fn component_code<T: AsStr>(values: &[Option<T>], options: &StringColumnOptions) -> Option<ComponentCodedStrings> {
+    let separator = options.component_separator?;
+    let component_coded = ComponentCodedStrings::from_nullable_strings(values, separator);
+    if component_coded.component_reuse() >= MIN_COMPONENT_REUSE {
+        Some(component_coded)
+    } else {
+        None
+    }
+}
+
+pub(super) fn attach_metadata(mut column: Column, options: &StringColumnOptions) -> Column {
+    let mut stats = string_column_stats(&column);
+    if options.case_insensitive {
+        let (min, max) = folded_min_max(&column).map_or((None, None), |(min, max)| (Some(min), Some(max)));
+        stats.min = min;
+        stats.max = max;
+    }
+    if options.bloom_filter_bits_per_value > 0 {
+        let bloom = string_bloom_filter(&column, stats.distinct_count, options);
+        column.set_bloom_filter(bloom);
+    }
+    column.set_stats(stats);
+    column.set_case_insensitive(options.case_insensitive);
+    column
+}
+
+fn string_bloom_filter(column: &Column, distinct_count: usize, options: &StringColumnOptions) -> BloomFilter {
+    let mut bloom = BloomFilter::new(distinct_count * options.bloom_filter_bits_per_value,
+                                     options.bloom_filter_hashes);
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            for string in nullable_dictionary_entries(column).into_iter().filter_map(|entry| entry) {
+                bloom.insert(fold_case_for(string, options).as_bytes());
+            }
+        }
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            for string in unsafe { StringPackerIterator::from_slice(&packed) } {
+                if let Some(string) = string {
+                    bloom.insert(fold_case_for(string, options).as_bytes());
+                }
+            }
+        }
+        Some(&CodecOp::UnhexpackStrings(uppercase, _)) => {
+            let packed = packed_data(column);
+            for bytes in PackedBytesIterator::from_slice(&packed) {
+                let string = if uppercase { hex::encode_upper(bytes) } else { hex::encode(bytes) };
+                bloom.insert(fold_case_for(&string, options).as_bytes());
+            }
+        }
+        _ => {}
+    }
+    bloom
+}
+
+/// Stats and bloom filter of case insensitive columns are built from lowercased values, see `Column::might_contain`.
+fn fold_case_for<'a>(string: &'a str, options: &StringColumnOptions) -> Cow<'a, str> {
+    if options.case_insensitive { Cow::Owned(string.to_lowercase()) } else { Cow::Borrowed(string) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+    use std::hash::BuildHasherDefault;
+    use std::thread;
+    use std::collections::hash_map::RandomState;
+    use fnv::FnvHasher;
+    use heapsize::HeapSizeOf;
+    use mem_store::column_builder::*;
+    use mem_store::integers::IntegerColumn;
+    use test::{black_box, Bencher};
+    use super::super::tests::*;
+
+    #[test]
+    fn test_u8_dictionary() {
+        let low_cardinality = (0..1000).map(|i| format!("value{}", i % 255)).collect::<Vec<_>>();
+        let column = build_column(&low_cardinality);
+        assert_eq!(column.encoding_type(), EncodingType::U8);
+        assert_eq!(column.data()[0].len(), 1000);
+        assert_eq!(column.data()[1].len(), 255);
+
+        let high_cardinality = (0..1000).map(|i| format!("value{}", i % 256)).collect::<Vec<_>>();
+        let column_u16 = build_column(&high_cardinality);
+        assert_eq!(column_u16.encoding_type(), EncodingType::U16);
+        assert!(column.data()[0].heap_size_of_children() < column_u16.data()[0].heap_size_of_children());
+        assert_eq!(decode_dictionary(&column), low_cardinality);
+        assert_eq!(decode_dictionary(&column_u16), high_cardinality);
+    }
+
+    #[test]
+    fn test_u32_dictionary() {
+        let values = (0..300_000).map(|i| format!("url{}", (i * 7) % 100_000)).collect::<Vec<_>>();
+        let options = StringColumnOptions::default().with_max_unique_strings(100_000);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("test");
+        assert_eq!(column.encoding_type(), EncodingType::U32);
+        assert_eq!(column.data()[1].len(), 100_000);
+        assert_eq!(decode_dictionary(&column), values);
+
+        #[allow(unused_mut)]
+        let mut fast_column = Arc::try_unwrap(fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0, &options)).unwrap();
+        #[cfg(feature = "enable_lz4")]
+        fast_column.lz4_decode();
+        assert_eq!(fast_column.encoding_type(), EncodingType::U32);
+        assert_eq!(decode_dictionary(&fast_column), values);
+    }
+
+    #[test]
+    fn test_max_unique_strings() {
+        let values = (0..2000).map(|i| format!("value{}", i % 300)).collect::<Vec<_>>();
+        let options = StringColumnOptions::default().with_max_unique_strings(299);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        assert_eq!(builder.finalize("test").codec().ops(), &string_pack_codec()[..]);
+        let column = fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0, &options);
+        // Packed values and their offset index
+        assert_eq!(column.data().len(), 2);
+
+        let options = options.with_max_unique_strings(300);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        assert_eq!(builder.finalize("test").encoding_type(), EncodingType::U16);
+        let column = fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0, &options);
+        assert_eq!(column.data().len(), 3);
+    }
+
+    #[test]
+    fn test_default_max_unique_strings() {
+        let values = (0..100_000).map(|i| format!("value{}", i % 20_000)).collect::<Vec<_>>();
+        let options = StringColumnOptions::default();
+        assert_eq!(options.unique_strings_limit(), 1 << 19);
+        assert_eq!(build_column(&values).encoding_type(), EncodingType::U16);
+        let column = fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0, &options);
+        assert_eq!(column.data().len(), 3);
+    }
+
+    #[test]
+    fn test_memory_report() {
+        let values = (0..2000).map(|i| format!("value{}", i % 300)).collect::<Vec<_>>();
+        let column = build_column(&values);
+        let report = column.memory_report();
+        assert_eq!(report.encoding, "dictionary");
+        assert_eq!(report.dictionary_bytes, column.data()[1].heap_size_of_children() + column.data()[2].heap_size_of_children());
+        assert_eq!(report.data_bytes, column.data()[0].heap_size_of_children());
+        assert_eq!(report.total, column.data_heap_size());
+
+        let mut builder = StringColBuilder::with_options(StringColumnOptions::default().with_max_unique_strings(299));
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("test");
+        let report = column.memory_report();
+        assert_eq!(report.encoding, "packed");
+        assert_eq!((report.dictionary_bytes, report.data_bytes), (0, column.data_heap_size()));
+
+        let column = IntegerColumn::new_boxed("test", vec![1, 2, 3], 1, 3, false);
+        assert_eq!(column.memory_report().encoding, "integer");
+        assert_eq!(Column::null("test", 10).memory_report().encoding, "null");
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let values = ["Gmail.com", "gmail.com", "yahoo.com", "GMAIL.COM"];
+        let options = StringColumnOptions::default().with_case_insensitive(true);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("test");
+        assert!(column.is_case_insensitive());
+        assert_eq!(column.data()[1].len(), 2);
+        assert_eq!(decode_dictionary(&column), vec!["Gmail.com", "Gmail.com", "yahoo.com", "Gmail.com"]);
+        assert!(column.might_contain("GMAIL.COM"));
+
+        let column = fast_build_string_column(
+            "test", values.iter().cloned(), values.len(), false, false, 0, &options);
+        assert!(column.is_case_insensitive());
+        assert_eq!(column.decode_strings().unwrap(), vec![Some("Gmail.com".to_string()), Some("Gmail.com".to_string()),
+                                                          Some("yahoo.com".to_string()), Some("Gmail.com".to_string())]);
+    }
+
+    #[test]
+    fn test_case_insensitive_might_contain() {
+        let values = (0..1000).map(|i| format!("Host{}.Example.com", i)).collect::<Vec<_>>();
+        let options = StringColumnOptions::default().with_case_insensitive(true).with_bloom_filter(10, 7);
+        let columns = vec![
+            build_string_column_from_strs("dict", &values.iter().map(|s| Some(s.as_str())).collect::<Vec<_>>(), &options),
+            fast_build_string_column("packed", values.iter().map(|s| s.as_str()), values.len(), false, false, 0,
+                                     &options.with_max_unique_strings(10)),
+        ];
+        for column in &columns {
+            assert!(column.is_case_insensitive());
+            assert_eq!(column.stats().unwrap().min, Some("host0.example.com".to_string()));
+            for value in &values {
+                assert!(column.might_contain(value), "{}", value);
+                assert!(column.might_contain(&value.to_uppercase()), "{}", value);
+                assert!(column.might_contain(&value.to_lowercase()), "{}", value);
+            }
+            // Outside of the lowercased min/max range
+            assert!(!column.might_contain("ALPHA.example.com"));
+            assert!(!column.might_contain("zulu.example.com"));
+            let false_positives = (1000..11_000).filter(|i| column.might_contain(&format!("HOST{}.EXAMPLE.COM", i))).count();
+            assert!(false_positives < 300, "{} {}", column.name(), false_positives);
+        }
+    }
+
+    #[test]
+    fn test_streaming_builder_spills_to_packed_strings() {
+        let values = (0..1000).map(|i| if i % 11 == 0 { None } else { Some(format!("value{}", i % 150 * i / 300)) })
+            .collect::<Vec<_>>();
+        let options = StringColumnOptions::default().with_max_unique_strings(100);
+        let mut streaming = StreamingStringColBuilder::new(options);
+        let mut builder = StringColBuilder::with_options(options);
+        let mut spilled_at = None;
+        for (i, value) in values.iter().enumerate() {
+            match *value {
+                Some(ref value) => {
+                    streaming.push(value);
+                    builder.push(value);
+                }
+                None => {
+                    streaming.push_null();
+                    builder.push_null();
+                }
+            }
+            if spilled_at.is_none() && !streaming.is_dictionary_encoded() {
+                spilled_at = Some(i);
+            }
+        }
+        let spilled_at = spilled_at.unwrap();
+        assert!(spilled_at > 0 && spilled_at < values.len() - 1);
+
+        let column = streaming.finalize("test");
+        assert!(column.codec().ops().contains(&CodecOp::UnpackStrings));
+        assert_eq!(column.decode_strings().unwrap(), values);
+        assert_eq!(column.decode_strings(), builder.finalize("test").decode_strings());
+
+        // Stays dictionary encoded below the threshold
+        let mut streaming = StreamingStringColBuilder::new(options);
+        for value in values.iter().take(spilled_at) {
+            match *value {
+                Some(ref value) => streaming.push(value),
+                None => streaming.push_null(),
+            }
+        }
+        assert!(streaming.is_dictionary_encoded());
+        let column = streaming.finalize("test");
+        assert_eq!(column.decode_strings().unwrap(), &values[..spilled_at]);
+    }
+
+    #[test]
+    fn test_frequency_ordered_dictionary() {
+        // Zipfian distribution: value i occurs roughly 1/i times as often as value 1
+        let mut values = Vec::new();
+        for i in 1..50 {
+            for _ in 0..(1000 / i) {
+                values.push(format!("value{}", i));
+            }
+        }
+        let options = StringColumnOptions::default().with_dictionary_order(DictionaryOrder::Frequency);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("test");
+        assert!(!column.codec().is_order_preserving());
+        assert_eq!(decode_dictionary(&column), values);
+        match *first_section(&column) {
+            DataSection::U8(ref codes) => {
+                assert_eq!(codes[0], 0);
+                assert_eq!(codes[values.len() - 1], 48);
+            }
+            ref section => panic!("Unexpected index section {:?}", section),
+        }
+
+        let column = fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0, &options);
+        assert!(!column.codec().is_order_preserving());
+        assert_eq!(column.range(), Some((0, 49)));
+    }
+
+    #[test]
+    fn test_frequency_ordered_code_size() {
+        // Zipfian distribution over 1000 values, with the most frequent values sorting last
+        let mut values = Vec::new();
+        for i in 1..1001 {
+            for _ in 0..(100_000 / i) {
+                values.push(format!("value{:04}", 1000 - i));
+            }
+        }
+        let code_sizes = |order: DictionaryOrder| {
+            let options = StringColumnOptions::default().with_dictionary_order(order);
+            let column = fast_build_string_column(
+                "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0, &options);
+            let section = first_section(&column);
+            let varint_bytes = code_iter(Cow::Borrowed(&*section)).map(|code| varint_len(code as u64)).sum::<usize>();
+            (section.heap_size_of_children(), varint_bytes)
+        };
+        let (sorted_bytes, sorted_varint_bytes) = code_sizes(DictionaryOrder::Sorted);
+        let (frequency_bytes, frequency_varint_bytes) = code_sizes(DictionaryOrder::Frequency);
+        // Fixed width codes only depend on the size of the dictionary, variable length codes favour frequency order
+        assert_eq!(frequency_bytes, sorted_bytes);
+        assert!(frequency_varint_bytes * 3 < sorted_varint_bytes * 2,
+                "frequency: {}, sorted: {}", frequency_varint_bytes, sorted_varint_bytes);
+    }
+
+    fn ingest_values(count: usize, distinct: usize) -> Vec<Option<Arc<String>>> {
+        let strings = (0..distinct).map(|i| Arc::new(format!("user-{:08x}", i))).collect::<Vec<_>>();
+        (0..count).map(|i| Some(strings[i.wrapping_mul(2_654_435_761) % distinct].clone())).collect()
+    }
+
+    fn unique_values<S: BuildHasher + Default>(values: &[Option<Arc<String>>]) -> UniqueValues<Option<Arc<String>>, S> {
+        let mut unique_values = UniqueValues::new(1 << 19);
+        for value in values {
+            unique_values.insert(value.clone());
+        }
+        unique_values
+    }
+
+    #[test]
+    fn test_adaptive_encoding() {
+        let options = StringColumnOptions::default().with_adaptive_encoding(true);
+        // Well below the limit on unique values, but long and distinct so the dictionary only adds overhead
+        let long = (0..200).map(|i| Some(Arc::new(format!("{:0>100}", i)))).collect::<Vec<_>>();
+        let column = build_string_column_from_values("test", &long, &options);
+        let estimate = column.stats().unwrap().encoding_estimate.unwrap();
+        assert!(!estimate.prefers_dictionary(), "{:?}", estimate);
+        assert!(column.codec().ops().last() != Some(&CodecOp::DictLookup(EncodingType::U8)));
+        assert_eq!(column.decode_strings().unwrap(), long.iter().map(|s| s.as_ref().map(|s| s.to_string())).collect::<Vec<_>>());
+        let default = build_string_column_from_values("test", &long, &StringColumnOptions::default());
+        assert_eq!(default.codec().ops().last(), Some(&CodecOp::DictLookup(EncodingType::U8)));
+        assert_eq!(default.stats().unwrap().encoding_estimate, None);
+
+        let short = (0..10_000).map(|i| Some(Arc::new(format!("value{}", i % 10)))).collect::<Vec<_>>();
+        let column = build_string_column_from_values("test", &short, &options);
+        let estimate = column.stats().unwrap().encoding_estimate.unwrap();
+        assert!(estimate.prefers_dictionary(), "{:?}", estimate);
+        assert_eq!(estimate.packed_bytes, 10_000 * 7);
+        assert_eq!(column.codec().ops().last(), Some(&CodecOp::DictLookup(EncodingType::U8)));
+    }
+
+    #[test]
+    fn test_dictionary_independent_of_hasher() {
+        let values = ingest_values(10_000, 500);
+        let options = StringColumnOptions::default();
+        let fnv = build_string_column(
+            "test", &values, unique_values::<BuildHasherDefault<FnvHasher>>(&values), false, &options);
+        let sip = build_string_column(
+            "test", &values, unique_values::<RandomState>(&values), false, &options);
+        assert_eq!(fnv.codec().ops(), sip.codec().ops());
+        assert_eq!(fnv.data(), sip.data());
+
+        let fnv = build_with_hasher::<BuildHasherDefault<FnvHasher>>(&values);
+        let sip = build_with_hasher::<RandomState>(&values);
+        assert_eq!(fnv.codec().ops(), sip.codec().ops());
+        assert_eq!(fnv.data(), sip.data());
+    }
+
+    fn build_with_hasher<S: BuildHasher + Default>(values: &[Option<Arc<String>>]) -> Arc<Column> {
+        let options = StringColumnOptions::default().with_max_unique_strings(1 << 19);
+        let mut builder = StringColBuilder::<S>::with_hasher(options);
+        for value in values {
+            builder.push_opt(value.as_ref().map(|s| s.as_str()));
+        }
+        builder.finalize("test")
+    }
+
+    #[test]
+    fn test_build_on_other_thread() {
+        let values = ingest_values(10_000, 500);
+        let expected = values.iter().map(|s| s.as_ref().map(|s| s.to_string())).collect::<Vec<_>>();
+        let handles = (0..2).map(|_| {
+            let values = values.clone();
+            thread::spawn(move || {
+                let uniques = unique_values::<BuildHasherDefault<FnvHasher>>(&values);
+                build_string_column("test", &values, uniques, false, &StringColumnOptions::default())
+            })
+        }).collect::<Vec<_>>();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().decode_strings().unwrap(), expected);
+        }
+    }
+
+    #[bench]
+    fn bench_unique_values_fnv_10m(b: &mut Bencher) {
+        let values = ingest_values(10_000_000, 50_000);
+        b.iter(|| black_box(unique_values::<BuildHasherDefault<FnvHasher>>(&values)));
+    }
+
+    #[bench]
+    fn bench_unique_values_sip_10m(b: &mut Bencher) {
+        let values = ingest_values(10_000_000, 50_000);
+        b.iter(|| black_box(unique_values::<RandomState>(&values)));
+    }
+
+    #[bench]
+    fn bench_string_col_builder_fnv_10m(b: &mut Bencher) {
+        let values = ingest_values(10_000_000, 50_000);
+        b.iter(|| black_box(build_with_hasher::<BuildHasherDefault<FnvHasher>>(&values)));
+    }
+
+    #[bench]
+    fn bench_string_col_builder_sip_10m(b: &mut Bencher) {
+        let values = ingest_values(10_000_000, 50_000);
+        b.iter(|| black_box(build_with_hasher::<RandomState>(&values)));
+    }
+
+    #[test]
+    fn test_build_string_column_from_strs() {
+        let datasets = vec![
+            (0..1000).map(|i| if i % 10 == 3 { None } else { Some(format!("value{}", i % 17)) }).collect::<Vec<_>>(),
+            (0..1000).map(|i| Some(format!("{:04}", i))).collect(),
+            (0..1000).map(|i| Some(format!("unsorted{}", (i * 7919) % 1000))).collect(),
+        ];
+        let options = [
+            StringColumnOptions::default(),
+            StringColumnOptions::default().with_max_unique_strings(10),
+            StringColumnOptions::default().with_dictionary_order(DictionaryOrder::Frequency),
+            StringColumnOptions::default().with_adaptive_encoding(true),
+        ];
+        for values in &datasets {
+            let shared = values.iter().map(|v| v.as_ref().map(|v| Arc::new(v.clone()))).collect::<Vec<_>>();
+            let borrowed = values.iter().map(|v| v.as_ref().map(|v| v.as_str())).collect::<Vec<_>>();
+            for options in &options {
+                let expected = build_string_column_from_values("test", &shared, options);
+                let column = build_string_column_from_strs("test", &borrowed, options);
+                assert_eq!(column.codec().ops(), expected.codec().ops());
+                assert_eq!(column.data(), expected.data());
+                assert_eq!(stats_without_heap_size(&column), stats_without_heap_size(&expected));
+                assert_eq!(column.decode_strings().unwrap(), *values);
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_length_encode() {
+        assert_eq!(run_length_encode::<u8>(&[]), (vec![], vec![]));
+        assert_eq!(run_length_encode(&[3u16, 3, 3, 1, 2, 2]), (vec![3, 1, 2], vec![3, 1, 2]));
+        assert_eq!(run_length_decode(&[3u16, 1, 2], &[3, 1, 2]), vec![3, 3, 3, 1, 2, 2]);
+        assert_eq!(count_runs(&[3u16, 3, 3, 1, 2, 2]), 3);
+    }
+
+    #[test]
+    fn test_run_length_encoded_column() {
+        let values = (0..5000).map(|i| format!("2018-01-{:02}", i / 200)).collect::<Vec<_>>();
+        let column = build_column(&values);
+        assert_eq!(column.codec().ops()[..2], [CodecOp::PushDataSection(3), CodecOp::RunLengthDecode(EncodingType::U8, 5000)]);
+        assert_eq!(column.data()[0].len(), 25);
+        assert_eq!(decode_dictionary(&column), values);
+        assert_eq!(column.decode_strings(), Some(values.iter().map(|s| Some(s.to_string())).collect()));
+
+        let flat = Column::new(
+            "flat", column.len(), column.range(), dict_codec(EncodingType::U8, DictionaryOrder::Sorted),
+            vec![first_section(&column).into_owned(), column.data()[1].clone(), column.data()[2].clone()]);
+        assert_eq!(flat.decode_strings(), column.decode_strings());
+        for code in 0..26 {
+            assert_eq!(column.filter_eq_code(code), flat.filter_eq_code(code));
+        }
+        assert_eq!(filter_code_range_runs(&column, 3, 3), Some(vec![600..800]));
+        assert_eq!(filter_code_range_runs(&column, 3, 5), Some(vec![600..1200]));
+        assert_eq!(filter_code_range_runs(&column, 30, 40), Some(vec![]));
+        assert_eq!(filter_code_range_runs(&flat, 3, 3), None);
+        assert_eq!(column.filter_code_range(3, 5), flat.filter_code_range(3, 5));
+        assert_eq!(column.group_count(), flat.group_count());
+
+        let unclustered = (0..5000).map(|i| format!("2018-01-{:02}", i % 25)).collect::<Vec<_>>();
+        assert_eq!(build_column(&unclustered).codec().ops(), &dict_codec(EncodingType::U8, DictionaryOrder::Sorted)[..]);
+    }
+
+    #[test]
+    fn test_bit_packed_codes() {
+        let options = StringColumnOptions::default().with_bit_packed_codes(true);
+        for &(distinct, bits, t) in &[(5, 3, EncodingType::U8), (3000, 12, EncodingType::U16), (1500, 11, EncodingType::U16)] {
+            let values = (0..10_000).map(|i| format!("value{}", (i * 7) % distinct)).collect::<Vec<_>>();
+            let mut builder = StringColBuilder::with_options(options);
+            for value in &values {
+                builder.push(value);
+            }
+            let packed = builder.finalize("test");
+            let plain = build_column(&values);
+            assert_eq!(packed.codec().ops()[0], CodecOp::UnpackBits(t, bits, values.len()));
+            assert_eq!(packed.data()[0].len(), (values.len() * bits as usize + 63) / 64);
+            assert!(packed.heap_size_of_children() < plain.heap_size_of_children());
+            assert_eq!(decode_dictionary(&packed), values);
+            assert_eq!(packed.decode_strings(), plain.decode_strings());
+            assert_eq!(packed.filter_eq_code(3), plain.filter_eq_code(3));
+        }
+
+        // Codes that already use the full width of their type are not packed
+        let values = (0..1000).map(|i| format!("value{}", i % 255)).collect::<Vec<_>>();
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        assert_eq!(builder.finalize("test").encoding_type(), EncodingType::U8);
+    }
+
+    #[test]
+    fn test_builder_matches_batch_build() {
+        let unsorted = (0..500).map(|i| if i % 9 == 0 { None } else { Some(format!("value{}", i * 7 % 40)) }).collect::<Vec<_>>();
+        let sorted = (0..500).map(|i| if i % 9 == 0 { None } else { Some(format!("value{:03}", i)) }).collect::<Vec<_>>();
+        for values in &[unsorted, sorted] {
+            for &max_unique_strings in &[1 << 19, 10] {
+                let options = StringColumnOptions::default().with_max_unique_strings(max_unique_strings);
+                let mut builder = StringColBuilder::with_options(options);
+                for value in values {
+                    builder.push_opt(value.as_ref().map(|s| s.as_str()));
+                }
+                let streamed = builder.finalize("test");
+                let values = values.iter().map(|v| v.as_ref().map(|s| Arc::new(s.clone()))).collect::<Vec<_>>();
+                let batch = build_string_column_from_values("test", &values, &options);
+                assert_eq!(streamed.codec().ops(), batch.codec().ops());
+                assert_eq!(streamed.data(), batch.data());
+                assert_eq!(stats_without_heap_size(&streamed), stats_without_heap_size(&batch));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let values = (0..2000).map(|i| format!("id{}", i * 3)).collect::<Vec<_>>();
+        for &max_unique_strings in &[1 << 19, 100] {
+            let options = StringColumnOptions::default()
+                .with_max_unique_strings(max_unique_strings)
+                .with_bloom_filter(10, 7);
+            let mut builder = StringColBuilder::with_options(options);
+            for value in &values {
+                builder.push(value);
+            }
+            let column = builder.finalize("test");
+            assert!(values.iter().all(|v| column.might_contain(v)));
+            let false_positives = (0..2000).filter(|i| column.might_contain(&format!("id{}", i * 3 + 1))).count();
+            assert!(false_positives < 100, "{}", false_positives);
+            assert!(!column.might_contain("zzz"));
+            assert!(column.heap_size_of_children() > column.data_heap_size() + 2000 * 10 / 8);
+        }
+    }
+
+    #[test]
+    fn test_front_coded_column() {
+        let values = (0..5000).map(|i| format!("2018-01-01 {:05}", i)).collect::<Vec<_>>();
+        let options = StringColumnOptions::default().with_max_unique_strings(1000).with_front_coding(true);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("test");
+        assert_eq!(column.codec().ops(), &[CodecOp::UnpackFrontCodedStrings(5000 * 16)]);
+        assert!(column.data()[0].len() < 5000 * 8);
+        assert_eq!(column.min_string(), Some(values[0].clone()));
+        assert_eq!(column.max_string(), Some(values[4999].clone()));
+        assert_eq!(column.group_count().unwrap().len(), 5000);
+
+        #[allow(unused_mut)]
+        let mut fast_column = Arc::try_unwrap(fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0, &options)).unwrap();
+        #[cfg(feature = "enable_lz4")]
+        fast_column.lz4_decode();
+        assert_eq!(fast_column.codec().ops(), &[CodecOp::UnpackFrontCodedStrings(5000 * 16)]);
+        assert_eq!(fast_column.distinct_values().unwrap().values, values);
+    }
+
+    #[test]
+    fn test_symbol_coded_column() {
+        let values = (0..3000)
+            .map(|i| format!("INFO [worker-{}] request /api/items/{} completed", i % 13, (i * 7919) % 3000))
+            .collect::<Vec<_>>();
+        let options = StringColumnOptions::default()
+            .with_max_unique_strings(100)
+            .with_symbol_table_compression(true);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("test");
+        let total_bytes = values.iter().map(|s| s.len()).sum::<usize>();
+        assert_eq!(column.codec().ops(), &[CodecOp::UnpackSymbolCodedStrings(total_bytes)]);
+        assert!(column.data()[0].len() * 2 < total_bytes);
+        let mut sorted = values.clone();
+        sorted.sort();
+        assert_eq!(column.distinct_values().unwrap().values, sorted);
+
+        let column = fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0, &options);
+        assert_eq!(column.min_string(), sorted.first().cloned());
+        assert_eq!(column.group_count().unwrap().len(), 3000);
+    }
+
+    #[test]
+    fn test_component_coded_column() {
+        let mut values = (0..2000)
+            .map(|i| Some(Arc::new(format!("/srv/data/shard-{}/table-{}/part-{}.parquet", i % 7, i % 11, (i * 7919) % 200))))
+            .collect::<Vec<_>>();
+        values[3] = None;
+        let options = StringColumnOptions::default()
+            .with_max_unique_strings(100)
+            .with_component_separator('/');
+        let column = build_string_column_from_values("test", &values, &options);
+        let total_bytes = values.iter().map(|s| s.as_ref().map(|s| s.len()).unwrap_or(0)).sum::<usize>();
+        assert_eq!(column.codec().ops(), &[CodecOp::UnpackComponentCodedStrings(total_bytes)]);
+        assert!(column.data()[0].len() * 2 < total_bytes);
+        assert_eq!(column.decode_strings().unwrap(), values.iter().map(|s| s.as_ref().map(|s| s.to_string())).collect::<Vec<_>>());
+
+        let without_separator = build_string_column_from_values(
+            "test", &values, &StringColumnOptions::default().with_max_unique_strings(100));
+        assert_eq!(without_separator.codec().ops(), &string_pack_codec()[..]);
+    }
+
+    #[test]
+    fn test_trim_to_null() {
+        let values = ["a", " b", "c ", "  ", "", "\t\n", " d e ", "a"];
+        let decode = |options: StringColumnOptions| {
+            let mut builder = StringColBuilder::with_options(options);
+            let mut streaming = StreamingStringColBuilder::new(options);
+            for value in &values {
+                builder.push(value);
+                streaming.push(value);
+            }
+            let column = builder.finalize("test");
+            assert_eq!(column.decode_strings(), streaming.finalize("test").decode_strings());
+            column.decode_strings().unwrap()
+        };
+        let s = |s: &str| Some(s.to_string());
+
+        let trimmed = decode(StringColumnOptions::default().with_trim_to_null(true).with_store_trimmed(true));
+        assert_eq!(trimmed, vec![s("a"), s("b"), s("c"), None, None, None, s("d e"), s("a")]);
+        let untrimmed = decode(StringColumnOptions::default().with_trim_to_null(true));
+        assert_eq!(untrimmed, vec![s("a"), s(" b"), s("c "), None, None, None, s(" d e "), s("a")]);
+        let unchanged = decode(StringColumnOptions::default());
+        assert_eq!(unchanged, values.iter().map(|v| s(v)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_overflow_policy() {
+        let values = (0..1000)
+            .map(|i| match i % 4 {
+                0 => Some(format!("rare{}", i)),
+                1 if i % 9 == 0 => None,
+                _ => Some(format!("common{}", i % 5)),
+            })
+            .collect::<Vec<_>>();
+        let options = StringColumnOptions::default().with_max_unique_strings(20);
+        let build = |policy: OverflowPolicy| {
+            let mut builder = StringColBuilder::with_options(options).with_overflow_policy(policy);
+            for value in &values {
+                builder.push_opt(value.as_ref().map(|s| s.as_str()));
+            }
+            builder.try_finalize("test")
+        };
+
+        let packed = build(OverflowPolicy::Packer).unwrap();
+        assert_eq!(packed.codec().ops(), &string_pack_codec()[..]);
+        assert_eq!(packed.decode_strings().unwrap(), values);
+
+        assert!(build(OverflowPolicy::Error).is_err());
+
+        let hybrid = build(OverflowPolicy::HybridTopK(5)).unwrap();
+        assert_eq!(hybrid.codec().ops(), &[CodecOp::UnpackHybridStrings]);
+        assert_eq!(hybrid.memory_report().encoding, "hybrid");
+        assert_eq!(hybrid.decode_strings().unwrap(), values);
+        assert_eq!(iter_strings(&hybrid).unwrap().map(|s| s.map(|s| s.to_string())).collect::<Vec<_>>(), values);
+        assert_eq!(hybrid.filter_eq_str(Some("rare8"), false).unwrap().iter().filter(|&&x| x != 0).count(), 1);
+        let strs = values.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+        let escaped = HybridStrings::from_nullable_strings(&strs, most_frequent(&strs, 5).into_iter()).escaped_count();
+        assert_eq!(escaped, 250);
+
+        let mapped = build(OverflowPolicy::MapToOther("other".to_string())).unwrap();
+        assert_eq!(mapped.memory_report().encoding, "dictionary");
+        // 5 common values, null and "other" leave room for the 13 smallest of the rare values, which all occur once
+        let mut kept = values.iter().flat_map(|s| s.clone()).filter(|s| s.starts_with("rare")).collect::<Vec<_>>();
+        kept.sort();
+        kept.truncate(13);
+        let expected = values.iter()
+            .map(|value| value.as_ref().map(|s| if s.starts_with("rare") && !kept.contains(s) { "other".to_string() } else { s.clone() }))
+            .collect::<Vec<_>>();
+        assert_eq!(mapped.decode_strings().unwrap(), expected);
+    }
+}
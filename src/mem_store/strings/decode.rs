@@ -0,0 +1,1214 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::fmt;
+use std::io;
+use std::iter;
+use std::ops::Range;
+use std::usize;
+use scoped_threadpool::Pool;
+use hex;
+use stringpack::*;
+use ingest::raw_val::RawVal;
+use super::*;
+
+/// Formats the encoding, size and statistics of a string column together with its first and last few values and a
+/// sample of its dictionary. Only decodes a bounded number of values, so this is safe to use on large columns.
+pub fn fmt_debug(column: &Column, f: &mut fmt::Formatter) -> fmt::Result {
+    let report = column.memory_report();
+    let len = column.len();
+    let head = decode_strings_range(column, 0, DEBUG_SAMPLE_VALUES);
+    let tail_len = cmp::min(DEBUG_SAMPLE_VALUES, len.saturating_sub(DEBUG_SAMPLE_VALUES));
+    let tail = decode_strings_range(column, len - tail_len, tail_len);
+    let values = match (head, tail) {
+        (Some(head), Some(tail)) => {
+            fn as_str(values: &[Option<String>]) -> Vec<Option<&str>> {
+                values.iter().map(|v| v.as_ref().map(|v| v.as_str())).collect()
+            }
+            debug_sample(&as_str(&head), &as_str(&tail), len)
+        }
+        _ => "?".to_string(),
+    };
+    let mut debug = f.debug_struct("Column");
+    debug.field("name", &column.name())
+        .field("len", &len)
+        .field("encoding", &report.encoding)
+        .field("distinct", &column.stats().map(|stats| stats.distinct_count))
+        .field("heap_bytes", &report.total);
+    if report.encoding == "dictionary" {
+        let mut entries = nullable_dictionary_entries(column);
+        entries.truncate(DEBUG_SAMPLE_VALUES);
+        let dictionary_len = column.data()[1].len();
+        debug.field("dictionary_len", &dictionary_len)
+            .field("dictionary_bytes", &report.dictionary_bytes)
+            .field("dictionary", &format_args!("{}", debug_sample(&entries, &[], dictionary_len)));
+    }
+    debug.field("values", &format_args!("{}", values))
+        .finish()
+}
+
+/// Validity of each row of a string column, 1 for values and 0 for nulls. Dictionary encoded columns compare codes
+/// with `Column::null_code` and return `None` if there is no null code, other columns check the length prefix of each
+/// value.
+/// Returns `None` if the column is not a string column.
+pub fn null_bitmap(column: &Column) -> Option<Vec<u8>> {
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let null_code = column.null_code()?;
+            Some(column_codes(column).map(|code| (code != null_code) as u8).collect())
+        }
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            Some(unsafe { StringPackerIterator::from_slice(&packed) }.map(|value| value.is_some() as u8).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Decodes all values of a string column in row order.
+/// Returns `None` if the column is not a string column.
+pub fn decode_strings(column: &Column) -> Option<Vec<Option<String>>> {
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            let decode = |code: usize| entries[code].map(|s| s.to_string());
+            Some(match *first_section(column) {
+                DataSection::U8(ref codes) => codes.iter().map(|&code| decode(code as usize)).collect(),
+                DataSection::U16(ref codes) => codes.iter().map(|&code| decode(code as usize)).collect(),
+                DataSection::U32(ref codes) => codes.iter().map(|&code| decode(code as usize)).collect(),
+                DataSection::Mapped(MappedSection::U8(ref codes)) => codes.iter().map(|&code| decode(code as usize)).collect(),
+                DataSection::Mapped(MappedSection::U16(ref codes)) => codes.iter().map(|&code| decode(code as usize)).collect(),
+                DataSection::Mapped(MappedSection::U32(ref codes)) => codes.iter().map(|&code| decode(code as usize)).collect(),
+                ref section => panic!("Unexpected index section {:?}", section),
+            })
+        }
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            let strings = unsafe { StringPackerIterator::from_slice(&packed) }.with_len(column.len());
+            Some(strings.map(|string| string.map(|s| s.to_string())).collect())
+        }
+        Some(&CodecOp::UnhexpackStrings(uppercase, _)) => {
+            let packed = packed_data(column);
+            Some(PackedBytesIterator::from_slice(&packed)
+                .map(|bytes| Some(if uppercase { hex::encode_upper(bytes) } else { hex::encode(bytes) }))
+                .collect())
+        }
+        _ => None,
+    }
+}
+
+/// The value of every row if all rows of a string column are equal, which is detected from the dictionary without
+/// reading the codes of individual rows. Returns `Some(None)` if all rows are null.
+pub fn constant_value(column: &Column) -> Option<Option<&str>> {
+    if column.len() == 0 {
+        return None;
+    }
+    if column.basic_type() == BasicType::Null {
+        return Some(None);
+    }
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) && column.data()[1].len() == 1 =>
+            Some(nullable_dictionary_entries(column)[0]),
+        _ => None,
+    }
+}
+
+/// Lazily yields the values of a string column in row order. Values are borrowed from the column where possible, which
+/// avoids allocating a string for each row. Values of LZ4 compressed packed columns are yielded as owned strings, and
+/// values of front coded, symbol coded, component coded and hex packed columns are decoded in full first.
+/// Returns `None` if the column is not a string column.
+pub fn iter_strings<'a>(column: &'a Column) -> Option<Box<Iterator<Item=Option<Cow<'a, str>>> + 'a>> {
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            return Some(Box::new(column_codes(column).map(move |code| entries[code as usize].map(Cow::Borrowed))));
+        }
+        Some(&CodecOp::UnpackStrings) => return Some(match packed_data(column) {
+            Cow::Borrowed(packed) =>
+                Box::new(unsafe { StringPackerIterator::from_slice(packed) }.map(|value| value.map(Cow::Borrowed))),
+            Cow::Owned(packed) =>
+                Box::new(unsafe { OwnedStringPackerIterator::from_vec(packed) }.map(|value| value.map(Cow::Owned))),
+        }),
+        Some(&CodecOp::UnpackHybridStrings) => if let Cow::Borrowed(section) = first_section(column) {
+            let values = unsafe { HybridIterator::from_slice(u8_section(section)) };
+            return Some(Box::new(values.map(|value| value.map(Cow::Borrowed))));
+        },
+        _ => {}
+    }
+    let strings = decode_strings(column)?;
+    Some(Box::new(strings.into_iter().map(|value| value.map(Cow::Owned))))
+}
+
+/// Groups adjacent equal values of a string column into `(value, run_length)` pairs in row order.
+/// Dictionary encoded columns compare codes and only look up the dictionary entry of each run.
+/// Returns `None` under the same conditions as `iter_strings`.
+pub fn runs<'a>(column: &'a Column) -> Option<Box<Iterator<Item=(Option<Cow<'a, str>>, usize)> + 'a>> {
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            let runs = Runs { values: column_codes(column).peekable() };
+            Some(Box::new(runs.map(move |(code, len)| (entries[code as usize].map(Cow::Borrowed), len))))
+        }
+        _ => iter_strings(column).map(|values| {
+            Box::new(Runs { values: values.peekable() }) as Box<Iterator<Item=(Option<Cow<'a, str>>, usize)> + 'a>
+        }),
+    }
+}
+
+/// Yields each maximal run of equal adjacent values together with its length, see `runs`.
+pub(super) struct Runs<I: Iterator> {
+    values: iter::Peekable<I>,
+}
+
+impl<I: Iterator> Iterator for Runs<I> where I::Item: PartialEq {
+    type Item = (I::Item, usize);
+
+    fn next(&mut self) -> Option<(I::Item, usize)> {
+        let value = self.values.next()?;
+        let mut len = 1;
+        while self.values.peek() == Some(&value) {
+            self.values.next();
+            len += 1;
+        }
+        Some((value, len))
+    }
+}
+
+/// Values of all rows of a string column for which `filter` is nonzero.
+/// Returns `None` under the same conditions as `iter_strings`.
+pub fn filter_decode<'a>(column: &'a Column, filter: &[u8]) -> Option<Vec<Option<Cow<'a, str>>>> {
+    let mut result = Vec::new();
+    if filter_decode_into(column, filter, &mut result) { Some(result) } else { None }
+}
+
+/// Like `filter_decode`, but pairs each value with the index of its row, e.g. to write results back aligned to row ids.
+pub fn filter_decode_indexed<'a>(column: &'a Column, filter: &[u8]) -> Option<Vec<(usize, Option<Cow<'a, str>>)>> {
+    let selected = filter.iter().enumerate()
+        .filter(|&(_, &selected)| selected != 0)
+        .map(|(index, _)| index);
+    if let Some(value) = constant_value(column) {
+        return Some(selected.take_while(|&index| index < column.len()).map(|index| (index, value.map(Cow::Borrowed))).collect());
+    }
+    Some(iter_strings(column)?.zip(filter).enumerate()
+        .filter(|&(_, (_, &selected))| selected != 0)
+        .map(|(index, (string, _))| (index, string))
+        .collect())
+}
+
+/// Writes the values of all rows of a string column for which `filter` is nonzero (or all rows if there is no
+/// filter) to `out`, separated by `separator` and with `null` in place of null values. Values are written one by one
+/// without collecting them into a vector first.
+pub fn write_decoded(column: &Column,
+                     filter: Option<&[u8]>,
+                     out: &mut io::Write,
+                     separator: u8,
+                     null: &[u8]) -> io::Result<()> {
+    if let Some(value) = constant_value(column) {
+        return write_values(iter::repeat(value).take(column.len()), filter, out, separator, null);
+    }
+    match column.codec().ops().last() {
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            let strings = unsafe { StringPackerIterator::from_slice(&packed) }.with_len(column.len());
+            return write_values(strings, filter, out, separator, null);
+        }
+        _ => {}
+    }
+    match iter_strings(column) {
+        Some(strings) => write_values(strings, filter, out, separator, null),
+        None => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a string column", column.name()))),
+    }
+}
+
+fn write_values<S, I>(values: I, filter: Option<&[u8]>, out: &mut io::Write, separator: u8, null: &[u8]) -> io::Result<()>
+    where S: AsRef<str>, I: Iterator<Item=Option<S>> {
+    let mut first = true;
+    for (row, value) in values.enumerate() {
+        if let Some(filter) = filter {
+            if filter.get(row).map_or(true, |&selected| selected == 0) { continue; }
+        }
+        if !first {
+            out.write_all(&[separator])?;
+        }
+        first = false;
+        out.write_all(value.as_ref().map_or(null, |s| s.as_ref().as_bytes()))?;
+    }
+    Ok(())
+}
+
+/// Like `filter_decode`, but clears and refills `out` so that a single buffer can be reused across batches.
+/// Returns `false` and leaves `out` empty under the same conditions for which `iter_strings` returns `None`.
+pub fn filter_decode_into<'a>(column: &'a Column, filter: &[u8], out: &mut Vec<Option<Cow<'a, str>>>) -> bool {
+    out.clear();
+    if let Some(value) = constant_value(column) {
+        let count = filter.iter().filter(|&&selected| selected != 0).count();
+        out.extend(iter::repeat(value.map(Cow::Borrowed)).take(count));
+        return true;
+    }
+    match iter_strings(column) {
+        Some(strings) => {
+            out.extend(strings.zip(filter)
+                .filter(|&(_, &selected)| selected != 0)
+                .map(|(string, _)| string));
+            true
+        }
+        None => false,
+    }
+}
+
+/// How null values are represented by `decode_strings_with`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NullRepr {
+    Null,
+    EmptyString,
+    /// E.g. `\N` for `COPY` in Postgres.
+    Sentinel(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodeOptions {
+    pub null_as: NullRepr,
+}
+
+impl DecodeOptions {
+    pub fn with_null_as(mut self, null_as: NullRepr) -> DecodeOptions {
+        self.null_as = null_as;
+        self
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> DecodeOptions {
+        DecodeOptions { null_as: NullRepr::Null }
+    }
+}
+
+/// Like `decode_strings`, but represents nulls as configured by `options` to suit different consumers.
+pub fn decode_strings_with(column: &Column, options: &DecodeOptions) -> Option<Vec<RawVal>> {
+    let strings = decode_strings(column)?;
+    Some(strings.into_iter()
+        .map(|string| match (string, &options.null_as) {
+            (Some(string), _) => RawVal::Str(string),
+            (None, &NullRepr::Null) => RawVal::Null,
+            (None, &NullRepr::EmptyString) => RawVal::Str(String::new()),
+            (None, &NullRepr::Sentinel(ref sentinel)) => RawVal::Str(sentinel.clone()),
+        })
+        .collect())
+}
+
+/// Decodes the values of rows `start..start + len`, e.g. to display a single page of results.
+/// Returns fewer values if the range extends past the end of the column.
+pub fn decode_strings_range(column: &Column, start: usize, len: usize) -> Option<Vec<Option<String>>> {
+    let start = cmp::min(start, column.len());
+    let end = cmp::min(start.saturating_add(len), column.len());
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            Some(code_range(column, start, end).into_iter()
+                .map(|code| entries[code as usize].map(|s| s.to_string()))
+                .collect())
+        }
+        Some(&CodecOp::UnpackStrings) => {
+            let packed = packed_data(column);
+            let strings = packed_values_from(column, &packed, start);
+            Some(strings.take(end - start).map(|string| string.map(|s| s.to_string())).collect())
+        }
+        Some(&CodecOp::UnpackFrontCodedStrings(_)) => {
+            let data = first_section(column);
+            Some(coded_range(unsafe { FrontCodedIterator::from_slice(u8_section(&data)) }, start, end))
+        }
+        Some(&CodecOp::UnpackSymbolCodedStrings(_)) => {
+            let data = first_section(column);
+            Some(coded_range(unsafe { SymbolCodedIterator::from_slice(u8_section(&data)) }, start, end))
+        }
+        Some(&CodecOp::UnpackComponentCodedStrings(_)) => {
+            let data = first_section(column);
+            Some(coded_range(unsafe { ComponentCodedIterator::from_slice(u8_section(&data)) }, start, end))
+        }
+        Some(&CodecOp::UnpackHybridStrings) => {
+            let data = first_section(column);
+            let strings = unsafe { HybridIterator::from_slice(u8_section(&data)) };
+            Some(strings.skip(start).take(end - start).map(|string| string.map(|s| s.to_string())).collect())
+        }
+        _ => decode_strings(column).map(|strings| strings.into_iter().skip(start).take(end - start).collect()),
+    }
+}
+
+/// Decodes the values `start..end` of `values`, skipping the values before `start` without decoding them.
+fn coded_range<I: CodedValues>(mut values: I, start: usize, end: usize) -> Vec<Option<String>> {
+    values.skip_values(start);
+    (start..end).map(|_| values.next_value().unwrap().map(|s| s.to_string())).collect()
+}
+
+/// Decodes the values of the rows in `indices`, in that order, e.g. to materialize a column after sorting row ids by
+/// another column. Only the selected values are decoded: dictionary codes are looked up by row and packed values are
+/// gathered in a single forward pass that skips the values in between. Panics if any index is out of range.
+pub fn take_strings(column: &Column, indices: &[usize]) -> Option<Vec<Option<String>>> {
+    if let Some(&index) = indices.iter().find(|&&index| index >= column.len()) {
+        panic!("Index {} out of range for column {} of length {}", index, column.name(), column.len());
+    }
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            Some(codes_at(column, indices).into_iter()
+                .map(|code| entries[code as usize].map(|s| s.to_string()))
+                .collect())
+        }
+        Some(&CodecOp::UnpackStrings) => {
+            let packed = packed_data(column);
+            let stride = offset_index(column).map_or(usize::MAX, |(stride, _)| stride);
+            let mut values = packed_values_from(column, &packed, 0);
+            let mut row = 0;
+            Some(gather_ascending(indices, |index| {
+                // Seeking with the offset index skips at most `stride` values
+                if index - row > stride {
+                    values = packed_values_from(column, &packed, index);
+                } else {
+                    values.skip_values(index - row);
+                }
+                row = index + 1;
+                values.next().unwrap().map(|s| s.to_string())
+            }))
+        }
+        Some(&CodecOp::UnpackFrontCodedStrings(_)) => {
+            let data = first_section(column);
+            Some(gather_coded(unsafe { FrontCodedIterator::from_slice(u8_section(&data)) }, indices))
+        }
+        Some(&CodecOp::UnpackSymbolCodedStrings(_)) => {
+            let data = first_section(column);
+            Some(gather_coded(unsafe { SymbolCodedIterator::from_slice(u8_section(&data)) }, indices))
+        }
+        Some(&CodecOp::UnpackComponentCodedStrings(_)) => {
+            let data = first_section(column);
+            Some(gather_coded(unsafe { ComponentCodedIterator::from_slice(u8_section(&data)) }, indices))
+        }
+        Some(&CodecOp::UnpackHybridStrings) => {
+            let data = first_section(column);
+            let mut values = unsafe { HybridIterator::from_slice(u8_section(&data)) };
+            let mut row = 0;
+            Some(gather_ascending(indices, |index| {
+                let value = values.nth(index - row).unwrap();
+                row = index + 1;
+                value.map(|s| s.to_string())
+            }))
+        }
+        _ => decode_strings(column).map(|strings| indices.iter().map(|&i| strings[i].clone()).collect()),
+    }
+}
+
+/// Decodes the rows in `indices` of `values` in a single forward pass, see `gather_ascending`.
+fn gather_coded<I: CodedValues>(mut values: I, indices: &[usize]) -> Vec<Option<String>> {
+    let mut row = 0;
+    gather_ascending(indices, |index| {
+        values.skip_values(index - row);
+        row = index + 1;
+        values.next_value().unwrap().map(|s| s.to_string())
+    })
+}
+
+/// Calls `value_at` for each distinct row in `indices` in ascending order and returns the values in the order of
+/// `indices`, which lets decoders that can only move forward serve indices in any order.
+fn gather_ascending<F: FnMut(usize) -> Option<String>>(indices: &[usize], mut value_at: F) -> Vec<Option<String>> {
+    if indices.windows(2).all(|w| w[0] <= w[1]) {
+        let mut values: Vec<Option<String>> = Vec::with_capacity(indices.len());
+        for (i, &index) in indices.iter().enumerate() {
+            let value = if i > 0 && indices[i - 1] == index { values[i - 1].clone() } else { value_at(index) };
+            values.push(value);
+        }
+        return values;
+    }
+    let mut order = (0..indices.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| indices[i]);
+    let ascending = order.iter().map(|&i| indices[i]).collect::<Vec<_>>();
+    let mut values = vec![None; indices.len()];
+    for (value, &i) in gather_ascending(&ascending, value_at).into_iter().zip(&order) {
+        values[i] = value;
+    }
+    values
+}
+
+/// Whether the values of a string column are in ascending order, with nulls first. Dictionary encoded columns compare
+/// the dictionary entries of adjacent distinct codes, other columns compare adjacent values.
+/// Returns `None` if the column is not a string column.
+pub fn is_sorted(column: &Column) -> Option<bool> {
+    if constant_value(column).is_some() || column.len() < 2 {
+        return Some(true);
+    }
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            let mut codes = column_codes(column);
+            let mut previous = codes.next()?;
+            for code in codes {
+                if code != previous {
+                    if entries[previous as usize] > entries[code as usize] {
+                        return Some(false);
+                    }
+                    previous = code;
+                }
+            }
+            Some(true)
+        }
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            let strings = unsafe { StringPackerIterator::from_slice(&packed) }.with_len(column.len());
+            Some(is_ascending(strings))
+        }
+        _ => decode_strings(column).map(|strings| is_ascending(strings.iter())),
+    }
+}
+
+/// Rows of a sorted string column that are equal to `value`, found by binary search. Since the column is sorted, the
+/// matching rows are adjacent. Returns `None` if the column does not contain `value`.
+/// The result is unspecified if the column is not sorted, see `is_sorted`.
+pub fn search_sorted(column: &Column, value: &str) -> Option<Range<usize>> {
+    let target = Some(value);
+    let (start, end) = match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            let codes = first_section(column);
+            let entry = |row: usize| entries[code_at(&codes, row) as usize];
+            (lower_bound(column.len(), |row| entry(row) < target),
+             lower_bound(column.len(), |row| entry(row) <= target))
+        }
+        // Each probe seeks to the closest indexed value and skips fewer than `offset_index_stride` values from there
+        Some(&CodecOp::UnpackStrings) if has_offset_index(column) => {
+            let packed = packed_data(column);
+            let value_at = |row: usize| packed_values_from(column, &packed, row).next().unwrap();
+            (lower_bound(column.len(), |row| value_at(row) < target),
+             lower_bound(column.len(), |row| value_at(row) <= target))
+        }
+        // Packed strings without an offset index are collected in full
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            let strings = unsafe { StringPackerIterator::from_slice(&packed) }.with_len(column.len()).collect::<Vec<_>>();
+            (lower_bound(strings.len(), |row| strings[row] < target),
+             lower_bound(strings.len(), |row| strings[row] <= target))
+        }
+        _ => {
+            let strings = decode_strings(column)?;
+            let entry = |row: usize| strings[row].as_ref().map(|s| s.as_str());
+            (lower_bound(strings.len(), |row| entry(row) < target),
+             lower_bound(strings.len(), |row| entry(row) <= target))
+        }
+    };
+    if start < end { Some(start..end) } else { None }
+}
+
+/// Index of the first row for which `is_before` is false, assuming it is true for all rows before and false for all
+/// rows after that row.
+fn lower_bound<F: Fn(usize) -> bool>(len: usize, is_before: F) -> usize {
+    let (mut lo, mut hi) = (0, len);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if is_before(mid) { lo = mid + 1 } else { hi = mid }
+    }
+    lo
+}
+
+fn is_ascending<T: PartialOrd, I: Iterator<Item=T>>(mut values: I) -> bool {
+    let mut previous = match values.next() {
+        Some(value) => value,
+        None => return true,
+    };
+    for value in values {
+        if previous > value {
+            return false;
+        }
+        previous = value;
+    }
+    true
+}
+
+/// Values of the rows selected by `filter` in each of `columns`, which must have the same length. The selected rows
+/// are determined once and then gathered from every column, which is faster than calling `filter_decode` for each
+/// column if the filter is selective. Returns `None` if any of the columns is not a string or null column.
+pub fn project_strings(columns: &[&Column], filter: &[u8]) -> Option<Vec<Vec<Option<String>>>> {
+    let len = columns.first().map_or(0, |column| column.len());
+    let indices = filter.iter().take(len).enumerate()
+        .filter(|&(_, &selected)| selected != 0)
+        .map(|(index, _)| index)
+        .collect::<Vec<_>>();
+    columns.iter()
+        .map(|column| if column.basic_type() == BasicType::Null {
+            Some(vec![None; indices.len()])
+        } else {
+            take_strings(column, &indices)
+        })
+        .collect()
+}
+
+/// Decodes `n` distinct rows drawn uniformly at random, in row order. The rows only depend on `seed` and the length of
+/// the column, so the same seed always yields the same sample. Returns all rows if `n` is at least the number of rows.
+pub fn sample_strings(column: &Column, n: usize, seed: u64) -> Option<Vec<Option<String>>> {
+    take_strings(column, &sample_indices(column.len(), n, seed))
+}
+
+/// Draws `n` distinct indices below `len` with Floyd's algorithm, which takes `O(n)` time regardless of `len`.
+fn sample_indices(len: usize, n: usize, seed: u64) -> Vec<usize> {
+    if n >= len {
+        return (0..len).collect();
+    }
+    let mut rng = SplitMix64(seed);
+    let mut selected = HashSetSea::default();
+    for j in (len - n)..len {
+        let index = rng.below(j + 1);
+        if !selected.insert(index) {
+            selected.insert(j);
+        }
+    }
+    let mut indices = selected.into_iter().collect::<Vec<_>>();
+    indices.sort_unstable();
+    indices
+}
+
+/// Small seedable random number generator (Vigna's SplitMix64) used to draw reproducible samples.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed number in `[0, bound)`.
+    fn below(&mut self, bound: usize) -> usize {
+        ((u128::from(self.next()) * bound as u128) >> 64) as usize
+    }
+}
+
+/// Returns the row indices of a string column in order of their values. Rows with equal values keep their relative
+/// order. Dictionary encoded columns are sorted with a counting sort on the rank of each code.
+pub fn sort_indices(column: &Column, descending: bool, nulls_first: bool) -> Option<Vec<usize>> {
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = dictionary_entries(column).collect::<Vec<_>>();
+            let null_code = column.null_code();
+            let mut by_value = (0..entries.len() as u32).filter(|&code| Some(code) != null_code).collect::<Vec<_>>();
+            if let Some(&CodecOp::UnorderedDictLookup(_)) = column.codec().ops().last() {
+                by_value.sort_by_key(|&code| entries[code as usize]);
+            }
+            if descending {
+                by_value.reverse();
+            }
+            if let Some(null_code) = null_code {
+                if nulls_first { by_value.insert(0, null_code) } else { by_value.push(null_code) }
+            }
+            let mut ranks = vec![0; entries.len()];
+            for (rank, code) in by_value.into_iter().enumerate() {
+                ranks[code as usize] = rank as u32;
+            }
+            Some(sort_indices_counting(&global_codes(column, &ranks), entries.len()))
+        }
+        Some(&CodecOp::UnpackStrings) => {
+            let packed = packed_data(column);
+            let strings = unsafe { StringPackerIterator::from_slice(&packed) }.collect::<Vec<_>>();
+            let mut indices = (0..strings.len()).collect::<Vec<_>>();
+            indices.sort_by(|&a, &b| compare_nullable(strings[a], strings[b], descending, nulls_first));
+            Some(indices)
+        }
+        _ => {
+            let strings = decode_strings(column)?;
+            let mut indices = (0..strings.len()).collect::<Vec<_>>();
+            indices.sort_by(|&a, &b| compare_nullable(
+                strings[a].as_ref().map(|s| s.as_str()), strings[b].as_ref().map(|s| s.as_str()), descending, nulls_first));
+            Some(indices)
+        }
+    }
+}
+
+fn compare_nullable(a: Option<&str>, b: Option<&str>, descending: bool, nulls_first: bool) -> cmp::Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => if descending { b.cmp(a) } else { a.cmp(b) },
+        (None, None) => cmp::Ordering::Equal,
+        (None, Some(_)) => if nulls_first { cmp::Ordering::Less } else { cmp::Ordering::Greater },
+        (Some(_), None) => if nulls_first { cmp::Ordering::Greater } else { cmp::Ordering::Less },
+    }
+}
+
+/// Like `decode_strings`, but decodes large packed string columns on `threads` threads.
+/// Each thread decodes a contiguous range of rows, results are in row order.
+pub fn decode_strings_parallel(column: &Column, threads: usize) -> Option<Vec<Option<String>>> {
+    if column.codec().ops().last() != Some(&CodecOp::UnpackStrings) {
+        return decode_strings(column);
+    }
+    let packed = packed_data(column);
+    if threads <= 1 || packed.len() < PARALLEL_DECODE_MIN_BYTES {
+        return decode_strings(column);
+    }
+    let offsets = chunk_offsets(&packed, (column.len() + threads - 1) / threads);
+    let mut chunks = vec![Vec::new(); offsets.len() - 1];
+    Pool::new(threads as u32).scoped(|scope| {
+        for (chunk, range) in chunks.iter_mut().zip(offsets.windows(2)) {
+            let data = &packed[range[0]..range[1]];
+            scope.execute(move || {
+                let strings = unsafe { StringPackerIterator::from_slice(data) };
+                *chunk = strings.map(|string| string.map(|s| s.to_string())).collect();
+            });
+        }
+    });
+    Some(chunks.into_iter().flat_map(|chunk| chunk).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use heapsize::HeapSizeOf;
+    use mem_store::column_builder::*;
+    use mem_store::integers::IntegerColumn;
+    use test::{black_box, Bencher};
+    use super::super::tests::*;
+
+    #[test]
+    fn test_decode_strings() {
+        let values = (0..1000).map(|i| format!("value{}", i % 300)).collect::<Vec<_>>();
+        let expected = values.iter().map(|s| Some(s.to_string())).collect::<Vec<_>>();
+        assert_eq!(build_column(&values).decode_strings(), Some(expected.clone()));
+        let packed = fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0,
+            &StringColumnOptions::default().with_max_unique_strings(10));
+        assert_eq!(packed.decode_strings(), Some(expected));
+
+        let nullable = vec![Some("a"), None, Some("b")];
+        let packed = PackedStrings::from_nullable_strings(&nullable.iter()
+            .map(|s| s.map(|s| Arc::new(s.to_string())))
+            .collect::<Vec<_>>());
+        let column = Column::new("test", nullable.len(), None, string_pack_codec(), vec![DataSection::U8(packed.into_vec())]);
+        assert_eq!(column.decode_strings(), Some(vec![Some("a".to_string()), None, Some("b".to_string())]));
+    }
+
+    #[test]
+    fn test_iter_strings() {
+        let values = (0..1000).map(|i| format!("value{}", i % 300)).collect::<Vec<_>>();
+        let column = build_column(&values);
+        assert!(column.iter_strings().unwrap().eq(values.iter().map(|s| Some(Cow::Borrowed(s.as_str())))));
+
+        let nullable = vec![Some("a"), None, Some("b")];
+        let packed = PackedStrings::from_nullable_strings(&nullable.iter()
+            .map(|s| s.map(|s| Arc::new(s.to_string())))
+            .collect::<Vec<_>>());
+        let column = Column::new("test", nullable.len(), None, string_pack_codec(), vec![DataSection::U8(packed.into_vec())]);
+        assert_eq!(strs(&column.iter_strings().unwrap().collect::<Vec<_>>()), nullable);
+
+        let mut builder = StringColBuilder::new();
+        builder.push("a");
+        builder.push_null();
+        let column = builder.finalize("test");
+        assert_eq!(strs(&column.iter_strings().unwrap().collect::<Vec<_>>()), vec![Some("a"), None]);
+    }
+
+    #[test]
+    fn test_iter_strings_encodings() {
+        let clustered = (0..5000).map(|i| format!("2018-01-{:02}", i / 200)).collect::<Vec<_>>();
+        let repetitive = (0..5000).map(|i| format!("value{}", i % 20)).collect::<Vec<_>>();
+        let options = StringColumnOptions::default();
+        let columns = vec![
+            build_column(&clustered),
+            build_string_column_from_strs(
+                "bits", &repetitive.iter().map(|s| Some(s.as_str())).collect::<Vec<_>>(), &options.with_bit_packed_codes(true)),
+            fast_build_string_column(
+                "packed", repetitive.iter().map(|s| s.as_str()), repetitive.len(), false, false, 0,
+                &options.with_max_unique_strings(10)),
+        ];
+        assert!(run_lengths(&columns[0]).is_some());
+        assert!(code_bits(&columns[1]).is_some());
+        #[cfg(feature = "enable_lz4")]
+        assert!(match columns[2].codec().ops()[0] { CodecOp::LZ4(_, _) => true, _ => false });
+        let filter = (0..5000).map(|i| (i % 3 == 0) as u8).collect::<Vec<_>>();
+        for column in &columns {
+            let decoded = column.decode_strings().unwrap();
+            assert!(column.iter_strings().unwrap().map(|s| s.map(|s| s.into_owned())).eq(decoded.iter().cloned()), "{}", column.name());
+            let filtered = decoded.iter().zip(&filter).filter(|&(_, &f)| f != 0).map(|(s, _)| s.clone()).collect::<Vec<_>>();
+            let mut buffer = Vec::new();
+            assert!(filter_decode_into(column, &filter, &mut buffer));
+            assert_eq!(buffer.into_iter().map(|s| s.map(|s| s.into_owned())).collect::<Vec<_>>(), filtered, "{}", column.name());
+        }
+    }
+
+    #[test]
+    fn test_decode_strings_parallel() {
+        let values = (0..200_000).map(|i| format!("value{}", i)).collect::<Vec<_>>();
+        let column = fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()).rev(), values.len(), false, false, 0,
+            &StringColumnOptions::default().with_max_unique_strings(10));
+        #[allow(unused_mut)]
+        let mut column = Arc::try_unwrap(column).unwrap();
+        #[cfg(feature = "enable_lz4")]
+        column.lz4_decode();
+        assert_eq!(column.codec().ops(), &string_pack_codec()[..]);
+        assert!(column.data()[0].len() >= PARALLEL_DECODE_MIN_BYTES);
+        let sequential = column.decode_strings().unwrap();
+        assert_eq!(sequential.len(), values.len());
+        assert_eq!(column.decode_strings_parallel(4).unwrap(), sequential);
+        assert_eq!(column.decode_strings_parallel(7).unwrap(), sequential);
+        assert_eq!(column.decode_strings_parallel(1).unwrap(), sequential);
+    }
+
+    #[test]
+    fn test_decode_strings_range() {
+        let values = (0..1000).map(|i| if i % 13 == 0 { None } else { Some(format!("value{}", i)) }).collect::<Vec<_>>();
+        for &max_unique_strings in &[1 << 19, 10] {
+            let mut builder = StringColBuilder::with_options(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            for value in &values {
+                match *value {
+                    Some(ref value) => builder.push(value),
+                    None => builder.push_null(),
+                }
+            }
+            let column = builder.finalize("test");
+            let decoded = column.decode_strings().unwrap();
+            assert_eq!(column.decode_strings_range(0, 50).unwrap(), &decoded[0..50]);
+            assert_eq!(column.decode_strings_range(517, 50).unwrap(), &decoded[517..567]);
+            assert_eq!(column.decode_strings_range(990, 50).unwrap(), &decoded[990..]);
+            assert!(column.decode_strings_range(2000, 50).unwrap().is_empty());
+            assert!(column.decode_strings_range(10, 0).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_decode_strings_range_encodings() {
+        let clustered = (0..5000).map(|i| format!("2018-01-{:02}", i / 200)).collect::<Vec<_>>();
+        let paths = (0..3000).map(|i| format!("/usr/local/{}/file{}.txt", i % 7, i)).collect::<Vec<_>>();
+        let options = StringColumnOptions::default();
+        let packed = options.with_max_unique_strings(10);
+        let mut hybrid = StringColBuilder::with_options(packed).with_overflow_policy(OverflowPolicy::HybridTopK(3));
+        for value in &paths {
+            hybrid.push(value);
+        }
+        let columns = vec![
+            (build_column(&clustered), "RunLengthDecode"),
+            (build_column(&(0..1000).map(|i| format!("value{}", i * 7 % 50)).collect::<Vec<_>>()), "DictLookup"),
+            (build_string_column_from_strs(
+                "bits", &paths.iter().map(|s| Some(&s[..12])).collect::<Vec<_>>(), &options.with_bit_packed_codes(true)),
+             "UnpackBits"),
+            (build_string_column_from_strs(
+                "front", &clustered.iter().enumerate().map(|(i, s)| if i % 11 == 0 { None } else { Some(s.as_str()) }).collect::<Vec<_>>(),
+                &packed.with_max_unique_strings(1).with_front_coding(true)),
+             "UnpackFrontCodedStrings"),
+            (build_string_column_from_strs(
+                "symbol", &paths.iter().map(|s| Some(s.as_str())).collect::<Vec<_>>(), &packed.with_symbol_table_compression(true)),
+             "UnpackSymbolCodedStrings"),
+            (build_string_column_from_strs(
+                "component", &paths.iter().map(|s| Some(s.as_str())).collect::<Vec<_>>(), &packed.with_component_separator('/')),
+             "UnpackComponentCodedStrings"),
+            (hybrid.finalize("hybrid"), "UnpackHybridStrings"),
+        ];
+        for &(ref column, op) in &columns {
+            assert!(column.codec().ops().iter().any(|o| format!("{:?}", o).starts_with(op)), "{:?}", column.codec().ops());
+            let decoded = column.decode_strings().unwrap();
+            for &(start, len) in &[(0, 10), (190, 20), (199, 1), (200, 0), (1001, 500), (decoded.len() - 3, 10), (decoded.len(), 5)] {
+                let (start, end) = (cmp::min(start, decoded.len()), cmp::min(start + len, decoded.len()));
+                assert_eq!(column.decode_strings_range(start, len).unwrap(), &decoded[start..end], "{} {}..{}", op, start, end);
+            }
+        }
+    }
+
+    #[test]
+    fn test_take_strings() {
+        let values = (0..300).map(|i| format!("value{}", i)).collect::<Vec<_>>();
+        let indices = [299, 0, 17, 17, 150];
+        for &max_unique_strings in &[1 << 19, 10] {
+            let mut builder = StringColBuilder::with_options(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            for value in &values {
+                builder.push(value);
+            }
+            let column = builder.finalize("test");
+            let expected = indices.iter().map(|&i| Some(values[i].clone())).collect::<Vec<_>>();
+            assert_eq!(column.take_strings(&indices).unwrap(), expected);
+            assert!(column.take_strings(&[]).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_take_strings_offset_index() {
+        let values = (0..1000).map(|i| if i % 13 == 0 { None } else { Some(format!("value{}", i * 7 % 1000)) }).collect::<Vec<_>>();
+        let indices = (0..500).map(|i| i * 389 % 1000).collect::<Vec<_>>();
+        for &stride in &[0, 1, 7, 64, 999, 1000] {
+            let options = StringColumnOptions::default().with_max_unique_strings(10).with_offset_index_stride(stride);
+            let mut builder = StringColBuilder::with_options(options);
+            for value in &values {
+                match *value {
+                    Some(ref value) => builder.push(value),
+                    None => builder.push_null(),
+                }
+            }
+            let column = builder.finalize("test");
+            assert_eq!(column.codec().ops().last(), Some(&CodecOp::UnpackStrings));
+            assert_eq!(has_offset_index(&column), stride > 0 && stride < values.len());
+            let decoded = column.decode_strings().unwrap();
+            assert_eq!(decoded, values);
+            let expected = indices.iter().map(|&i| decoded[i].clone()).collect::<Vec<_>>();
+            assert_eq!(column.take_strings(&indices).unwrap(), expected);
+            let start = stride % 900;
+            assert_eq!(column.decode_strings_range(start, 100).unwrap(), &decoded[start..start + 100]);
+            assert_eq!(column.decode_strings_range(950, 100).unwrap(), &decoded[950..]);
+        }
+    }
+
+    #[test]
+    fn test_take_strings_encodings() {
+        let clustered = (0..3000)
+            .map(|i| if i / 100 % 9 == 4 { None } else { Some(format!("host-{}/path-{}", i / 100, i / 25 % 7)) })
+            .collect::<Vec<_>>();
+        let shuffled = (0..3000).map(|i| clustered[i * 7 % 3000].clone()).collect::<Vec<_>>();
+        let options = StringColumnOptions::default();
+        let packed = options.with_max_unique_strings(0);
+        let build = |name, values: &Vec<Option<String>>, options| {
+            let strs = values.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+            build_string_column_from_strs(name, &strs, &options)
+        };
+        let strs = clustered.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+        let columns = vec![
+            (build("runs", &clustered, options), &clustered),
+            (build("bits", &shuffled, options.with_bit_packed_codes(true)), &shuffled),
+            (build("front_coded", &clustered, packed.with_front_coding(true)), &clustered),
+            (build("symbols", &clustered, packed.with_symbol_table_compression(true)), &clustered),
+            (build("components", &clustered, packed.with_component_separator('/')), &clustered),
+            (hybrid_string_column("hybrid", &strs, 5, &options), &clustered),
+        ];
+        assert!(run_lengths(&columns[0].0).is_some());
+        assert!(code_bits(&columns[1].0).is_some());
+        let indices = (0..700).map(|i| i * 2503 % 3000).chain(vec![2999, 0, 0, 1500]).collect::<Vec<_>>();
+        let mut ascending = indices.clone();
+        ascending.sort();
+        for &(ref column, values) in &columns {
+            for indices in &[&indices, &ascending] {
+                let expected = indices.iter().map(|&i| values[i].clone()).collect::<Vec<_>>();
+                assert_eq!(column.take_strings(indices).unwrap(), expected, "{}", column.name());
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Index 300 out of range")]
+    fn test_take_strings_out_of_range() {
+        let values = (0..300).map(|i| format!("value{}", i)).collect::<Vec<_>>();
+        build_column(&values).take_strings(&[5, 300]);
+    }
+
+    #[test]
+    fn test_sort_indices() {
+        let values = ["b", "", "c", "a", "b", "", "a"];
+        let options = [StringColumnOptions::default(),
+            StringColumnOptions::default().with_dictionary_order(DictionaryOrder::Frequency),
+            StringColumnOptions::default().with_max_unique_strings(1)];
+        for options in &options {
+            let mut builder = StringColBuilder::with_options(*options);
+            for value in &values {
+                if value.is_empty() { builder.push_null() } else { builder.push(value) }
+            }
+            let column = builder.finalize("test");
+            let ascending = column.sort_indices(false).unwrap();
+            let descending = column.sort_indices(true).unwrap();
+            assert_eq!(ascending, vec![3, 6, 0, 4, 2, 1, 5]);
+            assert_eq!(descending, vec![2, 0, 4, 3, 6, 1, 5]);
+            assert_eq!(sort_indices(&column, false, true).unwrap(), vec![1, 5, 3, 6, 0, 4, 2]);
+            let sorted = column.take_strings(&ascending).unwrap();
+            assert!(sorted.windows(2).all(|w| w[0] <= w[1] || w[1].is_none()));
+        }
+    }
+
+    #[test]
+    fn test_constant_column() {
+        let values = vec!["same".to_string(); 100_000];
+        let column = build_column(&values);
+        assert_eq!(column.constant_value(), Some(Some("same")));
+        assert!(column.heap_size_of_children() < 1000);
+        assert_eq!(column.decode_strings(), Some(values.iter().map(|s| Some(s.to_string())).collect()));
+        assert_eq!(column.filter_eq_str(Some("same"), false), Some(vec![1; 100_000]));
+        assert_eq!(column.filter_eq_str(Some("other"), false), Some(vec![0; 100_000]));
+        assert_eq!(column.filter_in(&[Some("other"), None], false), Some(vec![0; 100_000]));
+        assert_eq!(column.filter_in(&[Some("other")], true), Some(vec![1; 100_000]));
+        let filter = (0..100_000).map(|i| (i % 3 == 0) as u8).collect::<Vec<_>>();
+        assert_eq!(strs(&column.filter_decode_strings(&filter).unwrap()), vec![Some("same"); 33_334]);
+
+        let mut builder = StringColBuilder::new();
+        for _ in 0..10 {
+            builder.push_null();
+        }
+        let nulls = builder.finalize("test");
+        assert_eq!(nulls.constant_value(), Some(None));
+        assert_eq!(nulls.filter_eq_str(None, false), Some(vec![1; 10]));
+        assert_eq!(nulls.filter_in(&[Some("")], true), Some(vec![0; 10]));
+
+        assert_eq!(build_column(&["a".to_string(), "b".to_string()]).constant_value(), None);
+    }
+
+    #[test]
+    fn test_debug_column() {
+        let values = (0..1000).map(|i| format!("value{}", i % 7)).collect::<Vec<_>>();
+        let column = build_column(&values);
+        let debug = format!("{:#?}", column);
+        assert!(debug.contains("encoding: \"dictionary\""), "{}", debug);
+        assert!(debug.contains("dictionary_len: 7"), "{}", debug);
+        assert!(debug.contains("dictionary: [\"value0\", \"value1\", \"value2\", … 4 more …]"), "{}", debug);
+        let sample = "values: [\"value0\", \"value1\", \"value2\", … 994 more …, \"value3\", \"value4\", \"value5\"]";
+        assert!(debug.contains(sample), "{}", debug);
+        assert!(!format!("{:?}", column).contains('\n'));
+
+        let long = vec![Some(Arc::new("y".repeat(1000))), Some(Arc::new("a".to_string())), None];
+        let options = StringColumnOptions::default().with_max_unique_strings(0);
+        let debug = format!("{:#?}", build_string_column_from_values("test", &long, &options));
+        assert!(debug.contains("encoding: \"packed\""), "{}", debug);
+        assert!(debug.contains(&format!("values: [\"{}\"…, \"a\", null]", "y".repeat(32))), "{}", debug);
+    }
+
+    #[test]
+    fn test_sample_strings() {
+        let values = (0..1000).map(|i| format!("value{}", i)).collect::<Vec<_>>();
+        let dictionary = build_column(&values[..200]);
+        let options = StringColumnOptions::default().with_max_unique_strings(0);
+        let shuffled = (0..1000).map(|i| Some(Arc::new(values[(i * 7919) % 1000].clone()))).collect::<Vec<_>>();
+        let packed = build_string_column_from_values("test", &shuffled, &options);
+        for column in &[dictionary, packed] {
+            let sample = column.sample_strings(10, 42).unwrap();
+            assert_eq!(sample.len(), 10);
+            assert_eq!(column.sample_strings(10, 42).unwrap(), sample);
+            assert!(column.sample_strings(10, 43).unwrap() != sample);
+            let all = column.decode_strings().unwrap();
+            let mut remaining = all.iter();
+            for value in &sample {
+                // Sampled rows are distinct and in row order
+                assert!(remaining.any(|v| v == value), "{:?}", value);
+            }
+            assert_eq!(column.sample_strings(column.len(), 1).unwrap(), all);
+            assert_eq!(column.sample_strings(usize::max_value(), 1).unwrap(), all);
+            assert!(column.sample_strings(0, 1).unwrap().is_empty());
+        }
+
+        let indices = sample_indices(1_000_000, 10_000, 7);
+        assert_eq!(indices.len(), 10_000);
+        assert!(indices.windows(2).all(|w| w[0] < w[1]));
+        let mean = indices.iter().sum::<usize>() as f64 / indices.len() as f64;
+        assert!((mean - 500_000.0).abs() < 10_000.0, "{}", mean);
+    }
+
+    #[test]
+    fn test_filter_decode_into() {
+        let column = build_column(&["a", "b", "c", "b"].iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        let mut buffer = vec![Some(Cow::Borrowed("x")); 10];
+        assert!(filter_decode_into(&column, &[1, 0, 0, 1], &mut buffer));
+        assert_eq!(strs(&buffer), vec![Some("a"), Some("b")]);
+        assert!(filter_decode_into(&column, &[0, 1, 1, 0], &mut buffer));
+        assert_eq!(strs(&buffer), vec![Some("b"), Some("c")]);
+        assert_eq!(strs(&filter_decode(&column, &[0, 0, 1, 0]).unwrap()), vec![Some("c")]);
+        let null_column = Column::null("test", 4);
+        let mut buffer = vec![Some(Cow::Borrowed("x"))];
+        assert!(filter_decode_into(&null_column, &[1, 0, 1, 1], &mut buffer));
+        assert_eq!(buffer, vec![None; 3]);
+    }
+
+    #[test]
+    fn test_filter_decode_indexed() {
+        let values = (0..1000).map(|i| format!("value{}", i % 13)).collect::<Vec<_>>();
+        let filter = (0..1000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 31) as u8).collect::<Vec<_>>();
+        let dictionary = build_column(&values);
+        let packed = build_string_column_from_strs(
+            "test", &values.iter().map(|s| Some(s.as_str())).collect::<Vec<_>>(),
+            &StringColumnOptions::default().with_max_unique_strings(0));
+        let set_bits = filter.iter().enumerate().filter(|&(_, &f)| f != 0).map(|(i, _)| i).collect::<Vec<_>>();
+        for column in &[dictionary, packed] {
+            let indexed = filter_decode_indexed(column, &filter).unwrap();
+            assert_eq!(indexed.iter().map(|&(i, _)| i).collect::<Vec<_>>(), set_bits);
+            for &(index, ref value) in &indexed {
+                assert_eq!(value.as_ref().map(|s| s.as_ref()), Some(values[index].as_str()));
+            }
+            assert_eq!(indexed.iter().map(|&(_, ref v)| v.clone()).collect::<Vec<_>>(), filter_decode(column, &filter).unwrap());
+        }
+        assert_eq!(filter_decode_indexed(&Column::null("test", 4), &[0, 1, 0, 1]), Some(vec![(1, None), (3, None)]));
+    }
+
+    #[test]
+    fn test_write_decoded() {
+        let values = (0..1000).map(|i| if i % 9 == 0 { None } else { Some(format!("value{}", i % 13)) }).collect::<Vec<_>>();
+        let strs = values.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+        let filter = (0..1000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 31) as u8).collect::<Vec<_>>();
+        let sorted = (0..1000).map(|i| Some(format!("{:04}", i))).collect::<Vec<_>>();
+        let columns = vec![
+            build_string_column_from_strs("dict", &strs, &StringColumnOptions::default()),
+            build_string_column_from_strs("packed", &strs, &StringColumnOptions::default().with_max_unique_strings(0)),
+            build_string_column_from_strs(
+                "front_coded", &sorted.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>(),
+                &StringColumnOptions::default().with_max_unique_strings(0).with_front_coding(true)),
+            Arc::new(Column::null("null", 10)),
+        ];
+        for column in &columns {
+            let decoded = column.decode_strings().unwrap_or_else(|| vec![None; column.len()]);
+            let join = |filter: Option<&[u8]>| decoded.iter().enumerate()
+                .filter(|&(i, _)| filter.map_or(true, |f| f[i] != 0))
+                .map(|(_, s)| s.clone().unwrap_or_else(|| "NULL".to_string()))
+                .collect::<Vec<_>>()
+                .join(",");
+            for &filter in &[None, Some(&filter[..column.len()])] {
+                let mut out = Vec::new();
+                column.write_decoded(filter, &mut out, b',', b"NULL").unwrap();
+                assert_eq!(String::from_utf8(out).unwrap(), join(filter), "{}", column.name());
+            }
+        }
+        let mut out = Vec::new();
+        let ints = IntegerColumn::new_boxed("ints", vec![3, -1, 4], -1, 4, false);
+        ints.write_decoded(Some(&[1, 0, 1][..]), &mut out, b'\n', b"").unwrap();
+        assert_eq!(out, b"3\n4");
+    }
+
+    #[test]
+    fn test_project_strings() {
+        let values = (0..1000).map(|i| if i % 9 == 0 { None } else { Some(format!("value{}", i % 13)) }).collect::<Vec<_>>();
+        let strs = values.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+        let columns = vec![
+            build_string_column_from_strs("dict", &strs, &StringColumnOptions::default()),
+            build_string_column_from_strs("packed", &strs, &StringColumnOptions::default().with_max_unique_strings(0)),
+            Arc::new(Column::null("null", 1000)),
+        ];
+        let columns = columns.iter().map(|c| &**c).collect::<Vec<_>>();
+        for &every in &[1, 3, 97, 2000] {
+            let filter = (0..1000).map(|i| (i % every == 0) as u8).collect::<Vec<_>>();
+            let projected = project_strings(&columns, &filter).unwrap();
+            for (column, projected) in columns.iter().zip(projected) {
+                let expected = filter_decode(column, &filter).unwrap().into_iter()
+                    .map(|s| s.map(|s| s.into_owned()))
+                    .collect::<Vec<_>>();
+                assert_eq!(projected, expected, "{}", column.name());
+            }
+        }
+        let ints = IntegerColumn::new_boxed("ints", vec![1, 2, 3], 1, 3, false);
+        assert_eq!(project_strings(&[&ints], &[1, 1, 1]), None);
+    }
+
+    #[test]
+    fn test_is_sorted() {
+        let sorted = vec![None, Some("a"), Some("a"), Some("b"), Some("c")];
+        let unsorted = vec![Some("b"), Some("a"), Some("c")];
+        for &max_unique_strings in &[1 << 19, 0] {
+            let options = StringColumnOptions::default().with_max_unique_strings(max_unique_strings);
+            assert!(build_string_column_from_strs("test", &sorted, &options).is_sorted());
+            assert!(!build_string_column_from_strs("test", &unsorted, &options).is_sorted());
+        }
+        // Codes are ascending but the dictionary is not
+        let column = unordered_dictionary_column("test", &[0, 0, 1, 2], &[Some("b"), Some("a"), Some("c")], &StringColumnOptions::default());
+        assert!(!column.is_sorted());
+        let column = unordered_dictionary_column("test", &[1, 1, 0, 2], &[Some("b"), Some("a"), Some("c")], &StringColumnOptions::default());
+        assert!(column.is_sorted());
+        assert!(Column::null("test", 10).is_sorted());
+        assert!(IntegerColumn::new_boxed("ints", vec![1, 2, 2, 5], 1, 5, false).is_sorted());
+        assert!(!IntegerColumn::new_boxed("ints", vec![1, 3, 2], 1, 3, false).is_sorted());
+    }
+
+    #[test]
+    fn test_search_sorted() {
+        let mut values = vec![None; 3];
+        for &(value, count) in &[("apple", 1), ("banana", 4), ("date", 1), ("fig", 300)] {
+            values.extend(iter::repeat(Some(value)).take(count));
+        }
+        let options = StringColumnOptions::default();
+        let columns = vec![
+            build_string_column_from_strs("dict", &values, &options),
+            build_string_column_from_strs("packed", &values, &options.with_max_unique_strings(0)),
+            build_string_column_from_strs("indexed", &values, &options.with_max_unique_strings(0).with_offset_index_stride(3)),
+            build_string_column_from_strs("unindexed", &values, &options.with_max_unique_strings(0).with_offset_index_stride(0)),
+        ];
+        assert!(has_offset_index(&columns[1]) && has_offset_index(&columns[2]) && !has_offset_index(&columns[3]));
+        for column in &columns {
+            assert!(column.is_sorted());
+            assert_eq!(column.search_sorted("apple"), Some(3..4), "{}", column.name());
+            assert_eq!(column.search_sorted("banana"), Some(4..8), "{}", column.name());
+            assert_eq!(column.search_sorted("fig"), Some(9..309), "{}", column.name());
+            assert_eq!(column.search_sorted("cherry"), None);
+            assert_eq!(column.search_sorted("aaa"), None);
+            assert_eq!(column.search_sorted("zzz"), None);
+        }
+        let unordered = unordered_dictionary_column("test", &[1, 0, 0, 0], &[Some("banana"), Some("apple")], &options);
+        assert_eq!(unordered.search_sorted("banana"), Some(1..4));
+        assert_eq!(unordered.search_sorted("apple"), Some(0..1));
+        let unsorted = build_string_column_from_strs("test", &[Some("b"), Some("a")], &options);
+        assert_eq!(unsorted.search_sorted("a"), None);
+    }
+
+    fn bench_filters() -> Vec<Vec<u8>> {
+        (0..100u32).map(|batch| (0..1u32 << 12)
+            .map(|i| ((batch * 4096 + i).wrapping_mul(2_654_435_761) >> 31) as u8)
+            .collect()).collect()
+    }
+
+    #[bench]
+    fn bench_filter_decode_100x4k(b: &mut Bencher) {
+        let (column, filters) = (projection_column(), bench_filters());
+        b.iter(|| for filter in &filters {
+            black_box(filter_decode(&column, filter));
+        });
+    }
+
+    #[bench]
+    fn bench_filter_decode_into_100x4k(b: &mut Bencher) {
+        let (column, filters) = (projection_column(), bench_filters());
+        let mut buffer = Vec::new();
+        b.iter(|| for filter in &filters {
+            filter_decode_into(&column, filter, &mut buffer);
+            black_box(&buffer);
+        });
+    }
+
+    #[test]
+    fn test_decode_strings_with() {
+        for &max_unique_strings in &[1 << 19, 1] {
+            let mut builder = StringColBuilder::with_options(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            for value in &[Some("a"), None, Some("b"), None] {
+                builder.push_opt(*value);
+            }
+            let column = builder.finalize("test");
+            let decode = |null_as: NullRepr| column.decode_strings_with(&DecodeOptions::default().with_null_as(null_as)).unwrap();
+            let a = RawVal::Str("a".to_string());
+            let b = RawVal::Str("b".to_string());
+            assert_eq!(column.decode_strings_with(&DecodeOptions::default()).unwrap(),
+                       vec![a.clone(), RawVal::Null, b.clone(), RawVal::Null]);
+            let empty = RawVal::Str("".to_string());
+            assert_eq!(decode(NullRepr::EmptyString), vec![a.clone(), empty.clone(), b.clone(), empty]);
+            let sentinel = RawVal::Str("\\N".to_string());
+            assert_eq!(decode(NullRepr::Sentinel("\\N".to_string())), vec![a, sentinel.clone(), b, sentinel]);
+        }
+    }
+
+    #[test]
+    fn test_runs() {
+        let values = vec![Some("b"), Some("b"), Some("a"), None, None, Some("b"), Some("c"), Some("c"), Some("c"), None];
+        let expected = vec![(Some("b"), 2), (Some("a"), 1), (None, 2), (Some("b"), 1), (Some("c"), 3), (None, 1)];
+        let dictionary = build_string_column_from_strs("test", &values, &StringColumnOptions::default());
+        let packed = build_string_column_from_strs(
+            "test", &values, &StringColumnOptions::default().with_max_unique_strings(0));
+        assert_eq!(packed.codec().ops(), &string_pack_codec()[..]);
+        for column in &[dictionary, packed] {
+            let runs = column.runs().unwrap().collect::<Vec<_>>();
+            assert_eq!(runs.iter().map(|&(ref value, len)| (value.as_ref().map(|s| s.as_ref()), len)).collect::<Vec<_>>(), expected);
+        }
+        let empty = build_string_column_from_strs("test", &[], &StringColumnOptions::default());
+        assert_eq!(empty.runs().unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_null_bitmap() {
+        let values = vec![Some("a"), None, Some("b"), Some("a"), None, Some("c")];
+        let dictionary = build_string_column_from_strs("test", &values, &StringColumnOptions::default());
+        assert_eq!(dictionary.null_bitmap(), Some(vec![1, 0, 1, 1, 0, 1]));
+        assert_eq!(strs(&dictionary.filter_decode_strings(&dictionary.null_bitmap().unwrap()).unwrap()),
+                   vec![Some("a"), Some("b"), Some("a"), Some("c")]);
+
+        let with_empty = vec![Some(""), None, Some("b"), Some("")];
+        let packed = build_string_column_from_strs(
+            "test", &with_empty, &StringColumnOptions::default().with_max_unique_strings(0));
+        assert_eq!(packed.null_bitmap(), Some(vec![1, 0, 1, 1]));
+
+        let no_nulls = build_string_column_from_strs("test", &[Some("a"), Some("")], &StringColumnOptions::default());
+        assert_eq!(no_nulls.null_bitmap(), None);
+        let no_nulls = build_string_column_from_strs(
+            "test", &[Some("a"), Some("")], &StringColumnOptions::default().with_max_unique_strings(0));
+        assert_eq!(no_nulls.null_bitmap(), None);
+
+        assert_eq!(Column::null("test", 3).null_bitmap(), Some(vec![0, 0, 0]));
+    }
+}
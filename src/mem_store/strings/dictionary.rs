@@ -0,0 +1,932 @@
+use std::borrow::Cow;
+use std::cmp::{self, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::collections::hash_set::HashSet;
+use std::hash::{BuildHasher, Hash};
+use std::ops::Range;
+use std::mem;
+use std::str;
+use std::sync::Arc;
+use std::{u8, u16, u32};
+use num::PrimInt;
+use hex;
+use stringpack::*;
+use errors::QueryError;
+use mem_store::bit_packing;
+use mem_store::value::Val;
+use ingest::raw_val::RawVal;
+use super::*;
+
+/// Creates a dictionary encoded column from `codes`.
+/// If the codes form long runs, e.g. because the column is clustered, the runs are stored as `(code, length)` pairs
+/// in section 0 and section 3 and expanded by `CodecOp::RunLengthDecode`. Constant columns always consist of a single
+/// run and take up constant space.
+/// Otherwise, if enabled in `options`, codes are bit packed if they require fewer bits than `T`.
+pub(super) fn dictionary_column<T: PrimInt>(name: &str,
+                                 codes: Vec<T>,
+                                 section: fn(Vec<T>) -> DataSection,
+                                 t: EncodingType,
+                                 dictionary: IndexedPackedStrings,
+                                 null_code: Option<u32>,
+                                 options: &StringColumnOptions) -> Arc<Column> {
+    let (dictionary_indices, dictionary_data) = dictionary.into_parts();
+    let len = codes.len();
+    let range = Some((0, dictionary_indices.len() as i64));
+    let runs = count_runs(&codes);
+    let mut column = if runs > 0 && (runs == 1 || len >= runs * RUN_LENGTH_MIN_AVERAGE) {
+        let (values, lengths) = run_length_encode(&codes);
+        let mut codec = vec![CodecOp::PushDataSection(3), CodecOp::RunLengthDecode(t, len)];
+        codec.extend(dict_codec(t, options.dictionary_order));
+        Column::new(name, len, range, codec,
+                    vec![section(values),
+                         DataSection::U64(dictionary_indices),
+                         DataSection::U8(dictionary_data),
+                         DataSection::U32(lengths)])
+    } else if options.bit_packed_codes && (bit_packing::bits_required(dictionary_indices.len()) as usize) < mem::size_of::<T>() * 8 {
+        let bits = bit_packing::bits_required(dictionary_indices.len());
+        let mut codec = vec![CodecOp::UnpackBits(t, bits, len)];
+        codec.extend(dict_codec(t, options.dictionary_order));
+        Column::new(name, len, range, codec,
+                    vec![DataSection::U64(bit_packing::pack(&codes, bits)),
+                         DataSection::U64(dictionary_indices),
+                         DataSection::U8(dictionary_data)])
+    } else {
+        Column::new(name, len, range, dict_codec(t, options.dictionary_order),
+                    vec![section(codes),
+                         DataSection::U64(dictionary_indices),
+                         DataSection::U8(dictionary_data)])
+    };
+    column.set_null_code(null_code);
+    Arc::new(attach_metadata(column, options))
+}
+
+/// Returns the code of every string, the dictionary and the code of the dictionary entry that represents null.
+/// Null sorts before all strings, so in sorted dictionaries it is always code 0.
+pub(super) fn dictionary_compress<T: PrimInt, V: AsStr + cmp::Eq + Hash, S: BuildHasher>(strings: &[Option<V>],
+                                                                              unique_values: HashSet<Option<V>, S>,
+                                                                              order: DictionaryOrder)
+                                                                              -> (Vec<T>, IndexedPackedStrings, Option<u32>) {
+    let mut mapping = unique_values.iter().map(|o| o.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+    mapping.sort();
+    match order {
+        DictionaryOrder::Sorted => {}
+        DictionaryOrder::Frequency => {
+            let mut counts: HashMap<Option<&str>, usize> = HashMap::default();
+            for s in strings {
+                *counts.entry(s.as_ref().map(|s| s.as_str())).or_insert(0) += 1;
+            }
+            mapping.sort_by(|a, b| counts[a].cmp(&counts[b]).reverse().then(a.cmp(b)));
+        }
+        DictionaryOrder::FirstOccurrence => {
+            let distinct = mapping.len();
+            mapping = first_occurrence_order(strings.iter().map(|s| s.as_ref().map(|s| s.as_str())), distinct);
+        }
+    }
+    let encoded_values: Vec<T> = {
+        let mut reverse_mapping: HashMap<Option<&str>, T> = HashMap::default();
+        let mut index = T::zero();
+        for &entry in &mapping {
+            reverse_mapping.insert(entry, index);
+            index = index + T::one();
+        }
+        strings.iter().map(|o| reverse_mapping[&o.as_ref().map(|s| s.as_str())]).collect()
+    };
+    let (dictionary, null_code) = nullable_dictionary(mapping.into_iter());
+    (encoded_values, dictionary, null_code)
+}
+
+/// Packs the entries of a dictionary in which `None` represents null. The null entry is stored as an empty placeholder
+/// and its code is returned so that it can be told apart from the empty string.
+fn nullable_dictionary<'a>(entries: impl Iterator<Item=Option<&'a str>>) -> (IndexedPackedStrings, Option<u32>) {
+    let mut dictionary = IndexedPackedStrings::default();
+    let mut null_code = None;
+    for entry in entries {
+        if entry.is_none() {
+            null_code = Some(dictionary.len() as u32);
+        }
+        dictionary.push(entry.unwrap_or(""));
+    }
+    (dictionary, null_code)
+}
+
+/// The `distinct` distinct values of `values` in the order in which they first occur.
+pub(super) fn first_occurrence_order<T, I>(values: I, distinct: usize) -> Vec<T>
+    where T: Copy + cmp::Eq + Hash, I: Iterator<Item=T> {
+    let mut seen = HashSetSea::default();
+    let mut order = Vec::with_capacity(distinct);
+    for value in values {
+        if seen.insert(value) {
+            order.push(value);
+            if order.len() == distinct { break; }
+        }
+    }
+    order
+}
+
+/// Creates a dictionary encoded column from codes that index into an existing dictionary in arbitrary order.
+/// The `None` entry of `dictionary`, if any, represents null.
+pub fn unordered_dictionary_column(name: &str,
+                                   codes: &[u32],
+                                   dictionary: &[Option<&str>],
+                                   options: &StringColumnOptions) -> Arc<Column> {
+    let (packed_mapping, null_code) = nullable_dictionary(dictionary.iter().cloned());
+    let (dictionary_indices, dictionary_data) = packed_mapping.into_parts();
+    let range = Some((0, dictionary_indices.len() as i64));
+    let (index_type, codes) = if dictionary.len() <= From::from(u8::MAX) {
+        (EncodingType::U8, DataSection::U8(codes.iter().map(|&code| code as u8).collect()))
+    } else if dictionary.len() <= From::from(u16::MAX) {
+        (EncodingType::U16, DataSection::U16(codes.iter().map(|&code| code as u16).collect()))
+    } else {
+        (EncodingType::U32, DataSection::U32(codes.to_vec()))
+    };
+    let mut column = Column::new(
+        name,
+        codes.len(),
+        range,
+        dict_codec(index_type, DictionaryOrder::Frequency),
+        vec![codes,
+             DataSection::U64(dictionary_indices),
+             DataSection::U8(dictionary_data)]);
+    column.set_null_code(null_code);
+    Arc::new(attach_metadata(column, options))
+}
+
+/// Builds a dictionary encoded column whose codes index into `dictionary`, so that columns from the same domain, e.g. a
+/// foreign key and the key it references, can be joined by comparing codes. Values that are not in `dictionary` are
+/// appended to it if `extend` is set and are an error otherwise. Either way, the dictionary of the returned column
+/// starts with `dictionary` and the codes of its entries are unchanged. If there are nulls, an entry for null is
+/// appended after the first null.
+pub fn build_string_column_with_dictionary(name: &str,
+                                           values: &[Option<&str>],
+                                           dictionary: &[&str],
+                                           extend: bool,
+                                           options: &StringColumnOptions) -> Result<Arc<Column>, QueryError> {
+    let mut entries = dictionary.iter().map(|&entry| Some(entry)).collect::<Vec<_>>();
+    let mut codes_by_entry = entries.iter().enumerate()
+        .map(|(code, &entry)| (entry, code as u32))
+        .collect::<HashMapSea<_, _>>();
+    ensure!(codes_by_entry.len() == entries.len(), "Dictionary for column {} contains duplicate entries", name);
+    let mut codes = Vec::with_capacity(values.len());
+    for &value in values {
+        let code = match codes_by_entry.get(&value) {
+            Some(&code) => code,
+            None => {
+                if let (Some(value), false) = (value, extend) {
+                    bail!(QueryError::TypeError, "Value {:?} of column {} is not in the dictionary", value, name);
+                }
+                entries.push(value);
+                codes_by_entry.insert(value, entries.len() as u32 - 1);
+                entries.len() as u32 - 1
+            }
+        };
+        codes.push(code);
+    }
+    Ok(unordered_dictionary_column(name, &codes, &entries, options))
+}
+
+/// Builds a dictionary encoded column from a dictionary and codes that index into it, e.g. from an Arrow or Parquet
+/// dictionary array, without deduplicating the values again. `None` entries of `mapping` represent null.
+/// Returns an error if a code is out of range or the dictionary has duplicate entries.
+pub fn dictionary_column_from_parts(name: &str,
+                                    mapping: Vec<Option<String>>,
+                                    codes: Vec<u16>,
+                                    options: &StringColumnOptions) -> Result<Arc<Column>, QueryError> {
+    ensure!(mapping.len() <= u16::MAX as usize,
+            "Dictionary of column {} has {} entries, at most {} are supported", name, mapping.len(), u16::MAX);
+    if let Some((row, &code)) = codes.iter().enumerate().find(|&(_, &code)| code as usize >= mapping.len()) {
+        bail!(QueryError::FatalError, "Code {} in row {} of column {} is out of range for a dictionary with {} entries",
+              code, row, name, mapping.len());
+    }
+    let entries = mapping.iter().map(|entry| entry.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+    ensure!(entries.iter().collect::<HashSetSea<_>>().len() == entries.len(),
+            "Dictionary of column {} contains duplicate entries", name);
+
+    let (packed_mapping, null_code) = nullable_dictionary(entries.into_iter());
+    let (dictionary_indices, dictionary_data) = packed_mapping.into_parts();
+    let range = Some((0, dictionary_indices.len() as i64));
+    let len = codes.len();
+    let (index_type, codes) = if mapping.len() <= From::from(u8::MAX) {
+        (EncodingType::U8, DataSection::U8(codes.iter().map(|&code| code as u8).collect()))
+    } else {
+        (EncodingType::U16, DataSection::U16(codes))
+    };
+    let mut column = Column::new(
+        name,
+        len,
+        range,
+        dict_codec(index_type, DictionaryOrder::Frequency),
+        vec![codes,
+             DataSection::U64(dictionary_indices),
+             DataSection::U8(dictionary_data)]);
+    column.set_null_code(null_code);
+    Ok(Arc::new(attach_metadata(column, options)))
+}
+
+/// Creates a dictionary encoded column with the narrowest code type that fits `dictionary`, in which `None` represents
+/// null. `options.dictionary_order` must describe the order of `dictionary`.
+pub(super) fn dictionary_column_from_codes<'a>(name: &str,
+                                    codes: &[u32],
+                                    dictionary: impl Iterator<Item=Option<&'a str>>,
+                                    options: &StringColumnOptions) -> Arc<Column> {
+    let (mapping, null_code) = nullable_dictionary(dictionary);
+    let dictionary_len = mapping.len();
+    if dictionary_len <= From::from(u8::MAX) {
+        let codes = codes.iter().map(|&code| code as u8).collect();
+        dictionary_column(name, codes, DataSection::U8, EncodingType::U8, mapping, null_code, options)
+    } else if dictionary_len <= From::from(u16::MAX) {
+        let codes = codes.iter().map(|&code| code as u16).collect();
+        dictionary_column(name, codes, DataSection::U16, EncodingType::U16, mapping, null_code, options)
+    } else {
+        dictionary_column(name, codes.to_vec(), DataSection::U32, EncodingType::U32, mapping, null_code, options)
+    }
+}
+
+/// Removes all entries from the dictionary of a dictionary encoded column that are not referenced by any row, e.g. after
+/// the column was split. Codes are remapped and narrowed if the remaining entries fit into a smaller code type.
+/// Returns `None` if the column is not dictionary encoded.
+pub fn compact_dictionary(column: &Column) -> Option<Arc<Column>> {
+    if !is_dictionary_encoded(column) {
+        return None;
+    }
+    let counts = code_counts(column);
+    let mut remap = Vec::with_capacity(counts.len());
+    let mut live = 0;
+    for &count in &counts {
+        remap.push(live);
+        if count > 0 { live += 1; }
+    }
+    let codes = global_codes(column, &remap);
+    // Retaining entries in their original order preserves a sorted dictionary
+    let entries = nullable_dictionary_entries(column).into_iter().zip(counts)
+        .filter(|&(_, count)| count > 0)
+        .map(|(entry, _)| entry);
+    Some(dictionary_column_from_codes(column.name(), &codes, entries, &derived_column_options(column)))
+}
+
+/// Iterates over the dictionary of a dictionary encoded column in code order. The entry that represents null is an
+/// empty placeholder, see `nullable_dictionary_entries`.
+pub fn dictionary_entries<'a>(column: &'a Column) -> impl Iterator<Item=&'a str> + 'a {
+    let (offset_len, data) = dictionary_sections(column).expect("Column is not dictionary encoded");
+    offset_len.iter().map(move |&ol| dictionary_entry(data, ol))
+}
+
+/// Offset and length of each dictionary entry and the bytes of all entries, or `None` if the column is not dictionary
+/// encoded.
+fn dictionary_sections(column: &Column) -> Option<(&[u64], &[u8])> {
+    if !is_dictionary_encoded(column) {
+        return None;
+    }
+    let offset_len: &[u64] = match column.data()[1] {
+        DataSection::U64(ref offset_len) => offset_len,
+        DataSection::Mapped(MappedSection::U64(ref offset_len)) => offset_len,
+        ref section => panic!("Unexpected dictionary section {:?}", section),
+    };
+    Some((offset_len, u8_section(&column.data()[2])))
+}
+
+fn dictionary_entry(data: &[u8], offset_len: u64) -> &str {
+    let offset = (offset_len >> 24) as usize;
+    let len = (offset_len & 0xffffff) as usize;
+    unsafe { str::from_utf8_unchecked(&data[offset..(offset + len)]) }
+}
+
+/// Codes of the dictionary entries between `lo` and `hi` (inclusive) of a column with a sorted dictionary, found by
+/// binary search so `lo` and `hi` don't have to occur in the dictionary. The code representing null is never part of
+/// the range. Returns `None` if the dictionary is not sorted, or ignores case and so is not sorted by byte order.
+pub fn dictionary_code_range(column: &Column, lo: &str, hi: &str) -> Option<Range<u32>> {
+    match column.codec().ops().last() {
+        Some(&CodecOp::DictLookup(_)) if !column.is_case_insensitive() => {}
+        _ => return None,
+    }
+    let (offset_len, data) = dictionary_sections(column)?;
+    // Null is code 0 of sorted dictionaries. Its empty placeholder is excluded from the search, otherwise it could be
+    // found instead of an actual empty string.
+    let first = if column.null_code() == Some(0) { 1 } else { 0 };
+    let entries = &offset_len[first..];
+    let start = match entries.binary_search_by(|&ol| dictionary_entry(data, ol).cmp(lo)) {
+        Ok(index) | Err(index) => (first + index) as u32,
+    };
+    let end = match entries.binary_search_by(|&ol| dictionary_entry(data, ol).cmp(hi)) {
+        Ok(index) => (first + index) as u32 + 1,
+        Err(index) => (first + index) as u32,
+    };
+    Some(start..cmp::max(start, end))
+}
+
+/// Translates dictionary `codes` into values that borrow from the dictionary of `column`, e.g. to materialize the rows
+/// of a projection without allocating a string for each row. Returns `None` if the column is not dictionary encoded.
+pub fn decode_codes<'a>(column: &'a Column, codes: &[u32]) -> Option<Vec<Val<'a>>> {
+    let null_code = column.null_code();
+    let (offset_len, data) = dictionary_sections(column)?;
+    Some(codes.iter()
+        .map(|&code| if Some(code) == null_code { Val::Null } else { Val::Str(dictionary_entry(data, offset_len[code as usize])) })
+        .collect())
+}
+
+/// Value of a single dictionary code, see `decode_codes` to decode many codes at once.
+pub fn decode_code(column: &Column, code: u32) -> Option<RawVal> {
+    if Some(code) == column.null_code() {
+        return Some(RawVal::Null);
+    }
+    let (offset_len, data) = dictionary_sections(column)?;
+    Some(RawVal::Str(dictionary_entry(data, offset_len[code as usize]).to_string()))
+}
+
+/// Decodes a dictionary encoded column into the concatenated bytes of all rows and the end offset of each row, the
+/// layout used by Arrow and Parquet. The output is sized upfront from the dictionary entry lengths so the bytes are
+/// copied without reallocating. Nulls decode to empty strings. Returns `None` if the column is not dictionary encoded.
+pub fn decode_to_bytes(column: &Column) -> Option<(Vec<u8>, Vec<u32>)> {
+    let (offset_len, data) = dictionary_sections(column)?;
+    let section = first_section(column);
+    let total_bytes = code_iter(Cow::Borrowed(&*section))
+        .map(|code| (offset_len[code as usize] & 0xffffff) as usize)
+        .sum::<usize>();
+    assert!(total_bytes <= u32::MAX as usize, "Decoded column exceeds 4GiB");
+    let mut bytes = Vec::with_capacity(total_bytes);
+    let mut offsets = Vec::with_capacity(column.len());
+    for code in code_iter(Cow::Borrowed(&*section)) {
+        bytes.extend_from_slice(dictionary_entry(data, offset_len[code as usize]).as_bytes());
+        offsets.push(bytes.len() as u32);
+    }
+    Some((bytes, offsets))
+}
+
+/// Like `dictionary_entries`, but with `None` for the entry that represents null, see `Column::null_code`.
+pub fn nullable_dictionary_entries(column: &Column) -> Vec<Option<&str>> {
+    let null_code = column.null_code();
+    dictionary_entries(column).enumerate()
+        .map(|(code, entry)| if Some(code as u32) == null_code { None } else { Some(entry) })
+        .collect()
+}
+
+/// Counts the occurrences of every distinct value in a string column, ordered by value.
+/// Dictionary encoded columns are tallied on codes and only decode each dictionary entry once.
+/// Returns `None` if the column is not a string column.
+pub fn group_count(column: &Column) -> Option<Vec<(Option<String>, u64)>> {
+    let mut groups = match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let counts = code_counts(column);
+            nullable_dictionary_entries(column).into_iter().zip(counts)
+                .filter(|&(_, count)| count > 0)
+                .map(|(string, count)| (string.map(|s| s.to_string()), count))
+                .collect::<Vec<_>>()
+        }
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            let mut counts: HashMapSea<Option<&str>, u64> = HashMapSea::default();
+            for string in unsafe { StringPackerIterator::from_slice(&packed) } {
+                *counts.entry(string).or_insert(0) += 1;
+            }
+            counts.into_iter().map(|(string, count)| (string.map(|s| s.to_string()), count)).collect()
+        }
+        Some(&CodecOp::UnhexpackStrings(uppercase, _)) => {
+            let packed = packed_data(column);
+            let mut counts: HashMapSea<&[u8], u64> = HashMapSea::default();
+            for bytes in PackedBytesIterator::from_slice(&packed) {
+                *counts.entry(bytes).or_insert(0) += 1;
+            }
+            counts.into_iter()
+                .map(|(bytes, count)| (Some(if uppercase { hex::encode_upper(bytes) } else { hex::encode(bytes) }), count))
+                .collect()
+        }
+        _ => return None,
+    };
+    groups.sort();
+    Some(groups)
+}
+
+/// Union of the dictionaries of several dictionary encoded columns, see `merge_dictionaries`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlobalDictionary {
+    /// Sorted distinct entries of all dictionaries, global codes index into this.
+    pub values: Vec<String>,
+    /// `remaps[i][code]` is the global code of `code` in the dictionary of the i-th column, or `NO_MATCH` for null.
+    pub remaps: Vec<Vec<u32>>,
+}
+
+/// Merges the dictionaries of `columns` into a single dictionary and computes the mapping from the codes of each
+/// column to global codes. This allows grouping multiple partitions by code without decoding strings.
+/// Returns `None` if any of the columns is not dictionary encoded.
+pub fn merge_dictionaries(columns: &[&Column]) -> Option<GlobalDictionary> {
+    let mut values = Vec::new();
+    for column in columns {
+        if !is_dictionary_encoded(column) {
+            return None;
+        }
+        values.extend(nullable_dictionary_entries(column).into_iter().filter_map(|entry| entry));
+    }
+    values.sort();
+    values.dedup();
+    let remaps = columns.iter()
+        .map(|column| nullable_dictionary_entries(column).into_iter()
+            .map(|entry| entry.map_or(NO_MATCH, |entry| values.binary_search(&entry).unwrap() as u32))
+            .collect())
+        .collect();
+    Some(GlobalDictionary {
+        values: values.into_iter().map(|s| s.to_string()).collect(),
+        remaps,
+    })
+}
+
+/// Translates the code of every row of a dictionary encoded column with `remap`, see `GlobalDictionary`.
+pub fn global_codes(column: &Column, remap: &[u32]) -> Vec<u32> {
+    match *first_section(column) {
+        DataSection::U8(ref codes) => codes.iter().map(|&code| remap[code as usize]).collect(),
+        DataSection::U16(ref codes) => codes.iter().map(|&code| remap[code as usize]).collect(),
+        DataSection::U32(ref codes) => codes.iter().map(|&code| remap[code as usize]).collect(),
+        DataSection::Mapped(MappedSection::U8(ref codes)) => codes.iter().map(|&code| remap[code as usize]).collect(),
+        DataSection::Mapped(MappedSection::U16(ref codes)) => codes.iter().map(|&code| remap[code as usize]).collect(),
+        DataSection::Mapped(MappedSection::U32(ref codes)) => codes.iter().map(|&code| remap[code as usize]).collect(),
+        ref section => panic!("Unexpected index section {:?}", section),
+    }
+}
+
+/// Join key that `join_keys` assigns to nulls and to values that are not in the global dictionary.
+pub const NO_MATCH: u32 = u32::MAX;
+
+/// Global code of the value of every row of a string column, e.g. to key the hash table of an equality join between
+/// columns whose dictionaries were merged with `merge_dictionaries`. Nulls and values that are not in `global` are
+/// assigned `NO_MATCH` so that they never join. Dictionary encoded columns look up each dictionary entry only once.
+/// Returns `None` if the column is not a string column.
+pub fn join_keys(column: &Column, global: &GlobalDictionary) -> Option<Vec<u32>> {
+    let global_code = |value: Option<&str>| value
+        .and_then(|value| global.values.binary_search_by(|entry| entry.as_str().cmp(value)).ok())
+        .map_or(NO_MATCH, |code| code as u32);
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let remap = nullable_dictionary_entries(column).into_iter().map(global_code).collect::<Vec<_>>();
+            Some(global_codes(column, &remap))
+        }
+        _ => Some(decode_strings(column)?.iter().map(|value| global_code(value.as_ref().map(|s| s.as_str()))).collect()),
+    }
+}
+
+/// Returns the `k` most frequent non-null values of a string column with their counts.
+/// Ties are broken by string value.
+pub fn top_k(column: &Column, k: usize) -> Option<Vec<(String, u64)>> {
+    // The heap evicts the least frequent value, and the largest string among values with equal counts
+    let mut heap = BinaryHeap::with_capacity(k + 1);
+    for (string, count) in group_count(column)? {
+        if let Some(string) = string {
+            heap.push((Reverse(count), string));
+            if heap.len() > k {
+                heap.pop();
+            }
+        }
+    }
+    Some(heap.into_sorted_vec().into_iter().map(|(Reverse(count), string)| (string, count)).collect())
+}
+
+/// Stable counting sort of the row indices of `codes`, all of which must be smaller than `code_count`.
+/// Runs in O(n + k) which beats comparison sorts on dictionary codes.
+pub fn sort_indices_counting(codes: &[u32], code_count: usize) -> Vec<usize> {
+    let mut offsets = vec![0; code_count + 1];
+    for &code in codes {
+        offsets[code as usize + 1] += 1;
+    }
+    for i in 1..offsets.len() {
+        offsets[i] += offsets[i - 1];
+    }
+    let mut indices = vec![0; codes.len()];
+    for (row, &code) in codes.iter().enumerate() {
+        indices[offsets[code as usize]] = row;
+        offsets[code as usize] += 1;
+    }
+    indices
+}
+
+/// Number of occurrences of each code in a dictionary encoded column.
+pub(super) fn code_counts(column: &Column) -> Vec<u64> {
+    let mut counts = vec![0u64; column.data()[1].len()];
+    match *first_section(column) {
+        DataSection::U8(ref codes) => for &code in codes { counts[code as usize] += 1 },
+        DataSection::U16(ref codes) => for &code in codes { counts[code as usize] += 1 },
+        DataSection::U32(ref codes) => for &code in codes { counts[code as usize] += 1 },
+        DataSection::Mapped(MappedSection::U8(ref codes)) => for &code in codes.iter() { counts[code as usize] += 1 },
+        DataSection::Mapped(MappedSection::U16(ref codes)) => for &code in codes.iter() { counts[code as usize] += 1 },
+        DataSection::Mapped(MappedSection::U32(ref codes)) => for &code in codes.iter() { counts[code as usize] += 1 },
+        ref section => panic!("Unexpected index section {:?}", section),
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapsize::HeapSizeOf;
+    use mem_store::column_builder::*;
+    use test::{black_box, Bencher};
+    use super::super::tests::*;
+
+    #[test]
+    fn test_sort_indices_counting() {
+        assert_eq!(sort_indices_counting(&[2, 0, 1, 0, 2, 2], 4), vec![1, 3, 2, 0, 4, 5]);
+        assert!(sort_indices_counting(&[], 0).is_empty());
+    }
+
+    fn bench_codes() -> Vec<u32> {
+        (0..10_000_000u32).map(|i| i.wrapping_mul(2_654_435_761) % 500).collect()
+    }
+
+    #[bench]
+    fn bench_sort_indices_counting_10m(b: &mut Bencher) {
+        let codes = bench_codes();
+        b.iter(|| black_box(sort_indices_counting(&codes, 500)));
+    }
+
+    #[bench]
+    fn bench_sort_indices_comparison_10m(b: &mut Bencher) {
+        let codes = bench_codes();
+        b.iter(|| {
+            let mut indices = (0..codes.len()).collect::<Vec<_>>();
+            indices.sort_by_key(|&i| codes[i]);
+            black_box(indices)
+        });
+    }
+
+    #[test]
+    fn test_compact_dictionary() {
+        let values = (0..1000).map(|i| if i % 7 == 0 { None } else { Some(format!("value{:02}", i / 50)) }).collect::<Vec<_>>();
+        let mut builder = StringColBuilder::new();
+        for value in &values {
+            builder.push_opt(value.as_ref().map(|s| s.as_str()));
+        }
+        let column = builder.finalize("test");
+        let partition = column.split(100).unwrap().remove(0);
+        assert_eq!(partition.data()[1].len(), 21);
+
+        let compacted = partition.compact_dictionary().unwrap();
+        // "value00", "value01" and the empty string representing null
+        assert_eq!(compacted.data()[1].len(), 3);
+        assert_eq!(compacted.decode_strings(), partition.decode_strings());
+        assert_eq!(compacted.null_count(), partition.null_count());
+        assert_eq!(compacted.filter_eq_str(Some("value01"), false), partition.filter_eq_str(Some("value01"), false));
+
+        let wide = build_column(&(0..1000).map(|i| format!("value{:04}", i)).collect::<Vec<_>>());
+        assert_eq!(wide.codec().ops().last(), Some(&CodecOp::DictLookup(EncodingType::U16)));
+        let compacted = wide.split(200).unwrap().remove(0).compact_dictionary().unwrap();
+        assert_eq!(compacted.codec().ops().last(), Some(&CodecOp::DictLookup(EncodingType::U8)));
+        assert_eq!(compacted.data()[1].len(), 200);
+        assert!(Column::null("test", 10).compact_dictionary().is_none());
+    }
+
+    #[test]
+    fn test_dictionary_code_lookup_is_cached() {
+        let column = build_column(&(0..100).map(|i| format!("value{}", i)).collect::<Vec<_>>());
+        let heap_size = column.heap_size_of_children();
+        assert_eq!(column.filter_eq_str(Some("value3"), false).unwrap().iter().filter(|&&x| x == 1).count(), 1);
+        let cached_heap_size = column.heap_size_of_children();
+        assert!(cached_heap_size > heap_size);
+        for value in &["value7", "value99", "missing"] {
+            column.filter_eq_str(Some(value), false).unwrap();
+        }
+        column.filter_in(&[Some("value1"), Some("value2")], false).unwrap();
+        assert_eq!(column.heap_size_of_children(), cached_heap_size);
+    }
+
+    #[test]
+    fn test_build_string_column_with_dictionary() {
+        let options = StringColumnOptions::default();
+        let keys = ["us", "de", "fr", "jp"].iter().map(|&s| Some(s)).collect::<Vec<_>>();
+        let primary = build_string_column_from_strs("country", &keys, &options);
+        let dictionary = dictionary_entries(&primary).collect::<Vec<_>>();
+        let foreign = [Some("fr"), Some("us"), None, Some("fr"), Some("jp")];
+        let column = build_string_column_with_dictionary("country", &foreign, &dictionary, false, &options).unwrap();
+        assert_eq!(dictionary_entries(&column).take(dictionary.len()).collect::<Vec<_>>(), dictionary);
+        let primary_codes = code_iter(first_section(&primary)).collect::<Vec<_>>();
+        let foreign_codes = code_iter(first_section(&column)).collect::<Vec<_>>();
+        for (value, &code) in foreign.iter().zip(&foreign_codes) {
+            if let Some(value) = *value {
+                let key = keys.iter().position(|&key| key == Some(value)).unwrap();
+                assert_eq!(code, primary_codes[key]);
+            }
+        }
+        assert_eq!(column.decode_strings().unwrap(), foreign.iter().map(|v| v.map(|v| v.to_string())).collect::<Vec<_>>());
+        assert_eq!(column.null_count(), 1);
+
+        let unknown = [Some("fr"), Some("br")];
+        assert!(build_string_column_with_dictionary("country", &unknown, &dictionary, false, &options).is_err());
+        let extended = build_string_column_with_dictionary("country", &unknown, &dictionary, true, &options).unwrap();
+        let mut expected = dictionary.clone();
+        expected.push("br");
+        assert_eq!(dictionary_entries(&extended).collect::<Vec<_>>(), expected);
+        assert_eq!(extended.decode_strings().unwrap(), vec![Some("fr".to_string()), Some("br".to_string())]);
+        assert!(build_string_column_with_dictionary("country", &unknown, &["fr", "fr"], true, &options).is_err());
+    }
+
+    #[test]
+    fn test_decode_codes() {
+        let values = ["b", "a", "c", "a"].iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let column = build_column(&values);
+        assert_eq!(decode_codes(&column, &[2, 0, 0]), Some(vec![Val::Str("c"), Val::Str("a"), Val::Str("a")]));
+        let raw = decode_code(&column, 1).unwrap();
+        assert_eq!(raw, RawVal::Str("b".to_string()));
+        assert_eq!(Val::from(&raw), Val::Str("b"));
+        let packed = fast_build_string_column("test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0,
+                                              &StringColumnOptions::default().with_max_unique_strings(1));
+        assert_eq!(decode_codes(&packed, &[0]), None);
+        assert_eq!(decode_code(&packed, 0), None);
+    }
+
+    #[bench]
+    fn bench_decode_codes_1m(b: &mut Bencher) {
+        let (column, codes) = (projection_column(), projection_codes());
+        b.iter(|| black_box(decode_codes(&column, &codes)));
+    }
+
+    #[bench]
+    fn bench_decode_code_1m(b: &mut Bencher) {
+        let (column, codes) = (projection_column(), projection_codes());
+        b.iter(|| black_box(codes.iter().map(|&code| decode_code(&column, code)).collect::<Vec<_>>()));
+    }
+
+    #[test]
+    fn test_dictionary_column_from_parts() {
+        let options = StringColumnOptions::default();
+        let mapping = vec![Some("b".to_string()), None, Some("a".to_string())];
+        let column = dictionary_column_from_parts("test", mapping.clone(), vec![0, 2, 1, 0, 2], &options).unwrap();
+        assert_eq!(column.decode_strings().unwrap(), vec![
+            Some("b".to_string()), Some("a".to_string()), None, Some("b".to_string()), Some("a".to_string())]);
+        assert_eq!(column.null_count(), 1);
+        assert_eq!(column.filter_eq_str(Some("a"), false), Some(vec![0, 1, 0, 0, 1]));
+
+        let large = (0..1000).map(|i| Some(format!("value{}", i))).collect::<Vec<_>>();
+        let codes = (0..5000).map(|i| (i * 7 % 1000) as u16).collect::<Vec<_>>();
+        let column = dictionary_column_from_parts("test", large.clone(), codes.clone(), &options).unwrap();
+        assert_eq!(column.encoding_type(), EncodingType::U16);
+        assert_eq!(column.decode_strings().unwrap(), codes.iter().map(|&code| large[code as usize].clone()).collect::<Vec<_>>());
+
+        let err = dictionary_column_from_parts("test", mapping, vec![0, 3, 1], &options).unwrap_err();
+        assert!(format!("{}", err).contains("Code 3 in row 1"), "{}", err);
+        assert!(dictionary_column_from_parts("test", vec![Some("a".to_string()), Some("a".to_string())], vec![0], &options).is_err());
+        assert!(dictionary_column_from_parts("test", vec![None, Some("a".to_string()), None], vec![0], &options).is_err());
+    }
+
+    #[test]
+    fn test_dictionary_column_from_parts_null_and_empty_string() {
+        let mapping = vec![Some("".to_string()), None, Some("a".to_string())];
+        let options = StringColumnOptions::default();
+        let column = dictionary_column_from_parts("test", mapping, vec![0, 1, 2, 1, 0], &options).unwrap();
+        assert_eq!(column.decode_strings().unwrap(), vec![
+            Some("".to_string()), None, Some("a".to_string()), None, Some("".to_string())]);
+        assert_eq!(column.null_code(), Some(1));
+        assert_eq!(column.null_count(), 2);
+        assert_eq!(column.filter_eq_str(None, false), Some(vec![0, 1, 0, 1, 0]));
+        assert_eq!(column.filter_eq_str(Some(""), false), Some(vec![1, 0, 0, 0, 1]));
+        assert_eq!(column.group_count().unwrap(), vec![(None, 2), (Some("".to_string()), 2), (Some("a".to_string()), 1)]);
+    }
+
+    #[bench]
+    fn bench_decode_to_bytes_100k(b: &mut Bencher) {
+        let column = build_column(&(0..100_000).map(|i| format!("value{}", (i * 7919) % 500)).collect::<Vec<_>>());
+        b.iter(|| black_box(decode_to_bytes(&column)));
+    }
+
+    #[bench]
+    fn bench_decode_to_str_and_concat_100k(b: &mut Bencher) {
+        let column = build_column(&(0..100_000).map(|i| format!("value{}", (i * 7919) % 500)).collect::<Vec<_>>());
+        b.iter(|| {
+            let strings = iter_strings(&column).unwrap().collect::<Vec<_>>();
+            let mut bytes = Vec::new();
+            let mut offsets = Vec::with_capacity(strings.len());
+            for string in strings {
+                bytes.extend_from_slice(string.as_ref().map_or("", |s| s.as_ref()).as_bytes());
+                offsets.push(bytes.len() as u32);
+            }
+            black_box((bytes, offsets))
+        });
+    }
+
+    #[test]
+    fn test_decode_to_bytes() {
+        let values = [Some("ab"), None, Some("cde"), Some(""), Some("ab")];
+        let mut builder = StringColBuilder::new();
+        for value in &values {
+            builder.push_opt(*value);
+        }
+        let column = builder.finalize("test");
+        let (bytes, offsets) = column.decode_to_bytes().unwrap();
+        assert_eq!(bytes, b"abcdeab");
+        assert_eq!(bytes.capacity(), bytes.len());
+        assert_eq!(offsets, vec![2, 2, 5, 5, 7]);
+
+        let packed = PackedStrings::from_nullable_strings(&[Some(Arc::new("a".to_string()))]);
+        let packed = packed_string_column("test", 1, packed, &StringColumnOptions::default());
+        assert_eq!(packed.decode_to_bytes(), None);
+    }
+
+    #[test]
+    fn test_dictionary_with_nulls() {
+        let mut builder = StringColBuilder::new();
+        for value in &[Some("a"), None, Some("b"), None] {
+            builder.push_opt(*value);
+        }
+        let column = builder.finalize("test");
+        assert!(column.codec().ops().contains(&CodecOp::DictLookup(EncodingType::U8)));
+        let expected = vec![Some("a".to_string()), None, Some("b".to_string()), None];
+        assert_eq!(column.decode_strings(), Some(expected.clone()));
+        assert_eq!(column.decode_strings_range(1, 2), Some(expected[1..3].to_vec()));
+        assert_eq!(column.take_strings(&[3, 0]), Some(vec![None, Some("a".to_string())]));
+        let null_code = column.null_code().unwrap();
+        assert_eq!(column.dictionary_code(""), None);
+        assert_eq!(decode_codes(&column, &[null_code]), Some(vec![Val::Null]));
+        assert_eq!(decode_code(&column, null_code), Some(RawVal::Null));
+        assert_eq!(column.group_count().unwrap()[0], (None, 2));
+        assert_eq!(column.distinct_values().unwrap(), DistinctValues { values: vec!["a".to_string(), "b".to_string()], has_null: true });
+
+        // Without nulls, the empty string is a regular value
+        let column = build_column(&["".to_string(), "a".to_string()]);
+        assert_eq!(column.decode_strings(), Some(vec![Some("".to_string()), Some("a".to_string())]));
+    }
+
+    #[test]
+    fn test_group_count() {
+        let values = (0..5000).map(|i| format!("value{}", (i * i) % 37)).collect::<Vec<_>>();
+        let mut expected: HashMap<String, u64> = HashMap::default();
+        for value in &values {
+            *expected.entry(value.clone()).or_insert(0) += 1;
+        }
+        let mut expected = expected.into_iter().map(|(s, c)| (Some(s), c)).collect::<Vec<_>>();
+        expected.sort();
+
+        for &max_unique_strings in &[1 << 19, 10] {
+            let options = StringColumnOptions::default().with_max_unique_strings(max_unique_strings);
+            let mut builder = StringColBuilder::with_options(options);
+            for value in &values {
+                builder.push(value);
+            }
+            assert_eq!(builder.finalize("test").group_count(), Some(expected.clone()));
+        }
+
+        let column = fast_build_string_column(
+            "test", values.iter().map(|s| s.as_str()), values.len(), false, false, 0,
+            &StringColumnOptions::default());
+        assert_eq!(column.group_count(), Some(expected));
+    }
+
+    #[test]
+    fn test_merge_dictionaries() {
+        let build = |values: &[&str]| {
+            let mut builder = StringColBuilder::new();
+            for value in values {
+                builder.push(value);
+            }
+            builder.finalize("test")
+        };
+        let a = build(&["b", "a", "b", "c"]);
+        let b = build(&["d", "b", "d", "e", "e", "e"]);
+        let c = build(&["x", "y"]);
+        let columns = [&*a, &*b, &*c];
+        let dictionary = merge_dictionaries(&columns).unwrap();
+        assert_eq!(dictionary.values, vec!["a", "b", "c", "d", "e", "x", "y"]);
+        assert_eq!(dictionary.remaps, vec![vec![0, 1, 2], vec![1, 3, 4], vec![5, 6]]);
+
+        // Grouping by global codes gives the same result as grouping the decoded strings
+        let mut counts = vec![0u64; dictionary.values.len()];
+        for (column, remap) in columns.iter().zip(&dictionary.remaps) {
+            for code in global_codes(column, remap) {
+                counts[code as usize] += 1;
+            }
+        }
+        let mut expected: HashMap<String, u64> = HashMap::new();
+        for column in &columns {
+            for value in column.decode_strings().unwrap() {
+                *expected.entry(value.unwrap()).or_insert(0) += 1;
+            }
+        }
+        let grouped = dictionary.values.iter().cloned().zip(counts).collect::<HashMap<_, _>>();
+        assert_eq!(grouped, expected);
+
+        let options = StringColumnOptions::default().with_max_unique_strings(1);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &["a", "b"] {
+            builder.push(value);
+        }
+        assert_eq!(merge_dictionaries(&[&*a, &*builder.finalize("test")]), None);
+    }
+
+    #[test]
+    fn test_top_k() {
+        let values = (0..1000).map(|i| format!("value{}", i % 7 % 4)).collect::<Vec<_>>();
+        let expected = vec![("value0".to_string(), 286), ("value1".to_string(), 286), ("value2".to_string(), 285)];
+        for &max_unique_strings in &[1 << 19, 2] {
+            let options = StringColumnOptions::default().with_max_unique_strings(max_unique_strings);
+            let mut builder = StringColBuilder::with_options(options);
+            for value in &values {
+                builder.push(value);
+            }
+            let column = builder.finalize("test");
+            assert_eq!(column.top_k(3), Some(expected.clone()));
+            assert_eq!(column.top_k(0), Some(vec![]));
+            assert_eq!(column.top_k(10).unwrap().len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_null_code() {
+        let values = vec![Some("b"), None, Some(""), Some("a"), None, Some("b")];
+        for &(ref values, has_null) in &[(values.clone(), true), (values[2..4].to_vec(), false)] {
+            for &order in &[DictionaryOrder::Sorted, DictionaryOrder::Frequency] {
+                let options = StringColumnOptions::default().with_dictionary_order(order);
+                let column = build_string_column_from_strs("test", values, &options);
+                assert_eq!(column.null_code().is_some(), has_null);
+                let entries = nullable_dictionary_entries(&column);
+                assert_eq!(entries.iter().filter(|entry| entry.is_none()).count(), has_null as usize);
+                if let Some(null_code) = column.null_code() {
+                    assert_eq!(entries[null_code as usize], None);
+                    assert_eq!(column.filter_eq_str(None, false), column.filter_eq_code(null_code));
+                    assert_eq!(column.filter_eq_str(None, false), Some(vec![0, 1, 0, 0, 1, 0]));
+                    assert_eq!(column.filter_eq_str(Some(""), false), Some(vec![0, 0, 1, 0, 0, 0]));
+                    assert_eq!(column.decode_strings_range(1, 2), Some(vec![None, Some("".to_string())]));
+                    assert_eq!(column.null_count(), 2);
+                } else {
+                    assert_eq!(column.filter_eq_str(None, false), Some(vec![0; values.len()]));
+                }
+            }
+        }
+        let packed = build_string_column_from_strs(
+            "test", &values, &StringColumnOptions::default().with_max_unique_strings(0));
+        assert_eq!(packed.null_code(), None);
+    }
+
+    #[test]
+    fn test_dictionary_code_range_empty_string() {
+        let values = vec![Some(""), None, Some("a"), Some(""), None, Some("b")];
+        let column = build_string_column_from_strs("test", &values, &StringColumnOptions::default());
+        assert_eq!(column.null_code(), Some(0));
+        assert_eq!(column.dictionary_code_range("", ""), Some(1..2));
+        assert_eq!(column.dictionary_code_range("", "a"), Some(1..3));
+        assert_eq!(column.dictionary_code_range("a", "z"), Some(2..4));
+        assert_eq!(column.filter_between_str("", ""), Some(vec![1, 0, 0, 1, 0, 0]));
+    }
+
+    #[test]
+    fn test_first_occurrence_order() {
+        let values = (0..2000).map(|i| Some(format!("value{}", (i / 10 * 37) % 101))).collect::<Vec<_>>();
+        let strs = values.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+        let mut expected = Vec::new();
+        for s in &strs {
+            if !expected.contains(s) { expected.push(*s); }
+        }
+        let options = StringColumnOptions::default().with_dictionary_order(DictionaryOrder::FirstOccurrence);
+        let build = || build_string_column_from_strs("test", &strs, &options);
+        let fast_build = || fast_build_string_column(
+            "test", strs.iter().map(|s| s.unwrap()), strs.len(), false, false, 0, &options);
+        for &(ref a, ref b) in &[(build(), build()), (fast_build(), fast_build())] {
+            assert_eq!(a.codec().ops(), b.codec().ops());
+            assert_eq!(a.data(), b.data());
+            assert_eq!(dictionary_entries(a).map(Some).collect::<Vec<_>>(), expected);
+            assert_eq!(a.decode_strings().unwrap(), values);
+        }
+    }
+
+    #[test]
+    fn test_join_keys() {
+        let left_values = vec![Some("a"), Some("b"), Some("c"), None, Some("b")];
+        let right_values = vec![Some("b"), Some("c"), Some("d"), Some("c"), None, Some("x"), Some("a")];
+        let left = build_string_column_from_strs("left", &left_values, &StringColumnOptions::default());
+        let right = build_string_column_from_strs("right", &right_values, &StringColumnOptions::default());
+        let right_packed = build_string_column_from_strs(
+            "right", &right_values, &StringColumnOptions::default().with_max_unique_strings(0));
+
+        let mut expected = Vec::new();
+        for (i, l) in left_values.iter().enumerate() {
+            for (j, r) in right_values.iter().enumerate() {
+                if l.is_some() && l == r {
+                    expected.push((i, j));
+                }
+            }
+        }
+        let hash_join = |build: &[u32], probe: &[u32]| {
+            let mut table: HashMap<u32, Vec<usize>> = HashMap::new();
+            for (i, &key) in build.iter().enumerate() {
+                if key != NO_MATCH {
+                    table.entry(key).or_insert_with(Vec::new).push(i);
+                }
+            }
+            let mut pairs = Vec::new();
+            for (j, key) in probe.iter().enumerate() {
+                for &i in table.get(key).into_iter().flatten() {
+                    pairs.push((i, j));
+                }
+            }
+            pairs.sort();
+            pairs
+        };
+
+        let merged = merge_dictionaries(&[&*left, &*right]).unwrap();
+        let left_keys = join_keys(&left, &merged).unwrap();
+        assert_eq!(left_keys[3], NO_MATCH);
+        for right in &[&right, &right_packed] {
+            let right_keys = join_keys(right, &merged).unwrap();
+            assert_eq!(hash_join(&left_keys, &right_keys), expected);
+        }
+
+        // "d" and "x" are only in the right column and never join with the dictionary of the left column
+        let left_only = merge_dictionaries(&[&*left]).unwrap();
+        let right_keys = join_keys(&right, &left_only).unwrap();
+        assert_eq!(right_keys[2], NO_MATCH);
+        assert_eq!(right_keys[5], NO_MATCH);
+        assert_eq!(hash_join(&join_keys(&left, &left_only).unwrap(), &right_keys), expected);
+    }
+}
@@ -0,0 +1,547 @@
+use std::cmp;
+use std::collections::hash_set::HashSet;
+use std::ops::Range;
+use num::{NumCast, PrimInt};
+use stringpack::*;
+use engine::vector_op;
+use mem_store::bit_packing;
+use super::*;
+
+/// Returns one byte per row that is `1` if the row equals `value` and `0` otherwise. A `None` value selects null rows.
+/// Dictionary encoded columns look up the code of `value` and only scan the codes if it is present.
+/// If `negate` is set, selects the rows that are not equal to `value` instead, see `filter_in`.
+pub fn filter_eq_str(column: &Column, value: Option<&str>, negate: bool) -> Option<Vec<u8>> {
+    if negate {
+        return filter_in(column, &[value], true);
+    }
+    if let Some(value) = value {
+        if !column.might_contain(value) {
+            return Some(vec![0; column.len()]);
+        }
+    }
+    if let Some(constant) = constant_value(column) {
+        return Some(vec![eq_nullable(constant, value, column.is_case_insensitive()) as u8; column.len()]);
+    }
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let code = match value {
+                Some(value) => column.dictionary_code(value),
+                None => column.null_code(),
+            };
+            match code {
+                Some(code) => filter_eq_code(column, code),
+                None => Some(vec![0; column.len()]),
+            }
+        }
+        Some(&CodecOp::UnpackStrings) => {
+            let packed = packed_data(column);
+            let strings = unsafe { StringPackerIterator::from_slice(&packed) };
+            Some(strings.map(|string| eq_nullable(string, value, column.is_case_insensitive()) as u8).collect())
+        }
+        _ => {
+            let strings = decode_strings(column)?;
+            Some(strings.iter()
+                .map(|string| eq_nullable(string.as_ref().map(|s| s.as_str()), value, column.is_case_insensitive()) as u8)
+                .collect())
+        }
+    }
+}
+
+/// Returns one byte per row that is `1` if the bytes of the row start with `prefix`, e.g. to select a shard of a column
+/// of hex encoded hashes. The prefix may end in the middle of a UTF-8 sequence and is compared exactly, also for case
+/// insensitive columns. Null rows are never selected.
+/// Dictionary encoded columns compare each dictionary entry once, packed strings are compared without decoding them.
+pub fn filter_byte_prefix(column: &Column, prefix: &[u8]) -> Option<Vec<u8>> {
+    if let Some(constant) = constant_value(column) {
+        return Some(vec![constant.map_or(false, |s| s.as_bytes().starts_with(prefix)) as u8; column.len()]);
+    }
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let matches = nullable_dictionary_entries(column).iter()
+                .map(|entry| entry.map_or(false, |s| s.as_bytes().starts_with(prefix)) as u8)
+                .collect::<Vec<_>>();
+            Some(column_codes(column).map(|code| matches[code as usize]).collect())
+        }
+        Some(&CodecOp::UnpackStrings) => {
+            let packed = packed_data(column);
+            let strings = unsafe { StringPackerIterator::from_slice(&packed) };
+            Some(strings.map(|string| string.map_or(false, |s| s.as_bytes().starts_with(prefix)) as u8).collect())
+        }
+        _ => {
+            let strings = decode_strings(column)?;
+            Some(strings.iter()
+                .map(|string| string.as_ref().map_or(false, |s| s.as_bytes().starts_with(prefix)) as u8)
+                .collect())
+        }
+    }
+}
+
+/// Returns one byte per row that is `1` if the row equals any of `values`. `None` values select null rows.
+/// Values that don't occur in the column are skipped.
+/// If `negate` is set, selects the rows that are not equal to any of `values`. Following SQL, comparisons with null
+/// are never true, so null rows are never selected and nothing is selected if `values` contains `None`.
+pub fn filter_in(column: &Column, values: &[Option<&str>], negate: bool) -> Option<Vec<u8>> {
+    if negate && values.contains(&None) {
+        return Some(vec![0; column.len()]);
+    }
+    if let Some(constant) = constant_value(column) {
+        let contained = values.iter().any(|&value| eq_nullable(constant, value, column.is_case_insensitive()));
+        let selected = if negate { constant.is_some() && !contained } else { contained };
+        return Some(vec![selected as u8; column.len()]);
+    }
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let mut selected = vec![negate as u8; column.data()[1].len()];
+            for value in values {
+                let code = match *value {
+                    Some(value) => column.dictionary_code(value),
+                    None => column.null_code(),
+                };
+                if let Some(code) = code {
+                    selected[code as usize] = !negate as u8;
+                }
+            }
+            if let (Some(null_code), true) = (column.null_code(), negate) {
+                selected[null_code as usize] = 0;
+            }
+            let lookup = |code: usize| selected[code];
+            Some(match *first_section(column) {
+                DataSection::U8(ref codes) => codes.iter().map(|&code| lookup(code as usize)).collect(),
+                DataSection::U16(ref codes) => codes.iter().map(|&code| lookup(code as usize)).collect(),
+                DataSection::U32(ref codes) => codes.iter().map(|&code| lookup(code as usize)).collect(),
+                DataSection::Mapped(MappedSection::U8(ref codes)) => codes.iter().map(|&code| lookup(code as usize)).collect(),
+                DataSection::Mapped(MappedSection::U16(ref codes)) => codes.iter().map(|&code| lookup(code as usize)).collect(),
+                DataSection::Mapped(MappedSection::U32(ref codes)) => codes.iter().map(|&code| lookup(code as usize)).collect(),
+                ref section => panic!("Unexpected index section {:?}", section),
+            })
+        }
+        _ => {
+            let case_insensitive = column.is_case_insensitive();
+            let folded = values.iter().map(|value| value.map(|value| value.to_lowercase())).collect::<Vec<_>>();
+            let set = if case_insensitive {
+                folded.iter().map(|value| value.as_ref().map(|value| value.as_str())).collect::<HashSet<_>>()
+            } else {
+                values.iter().cloned().collect::<HashSet<_>>()
+            };
+            let contains = |string: Option<&str>| {
+                let contained = if case_insensitive {
+                    let string = string.map(|s| s.to_lowercase());
+                    set.contains(&string.as_ref().map(|s| s.as_str()))
+                } else {
+                    set.contains(&string)
+                };
+                (if negate { string.is_some() && !contained } else { contained }) as u8
+            };
+            if column.codec().ops().last() == Some(&CodecOp::UnpackStrings) {
+                let packed = packed_data(column);
+                let strings = unsafe { StringPackerIterator::from_slice(&packed) };
+                Some(strings.map(contains).collect())
+            } else {
+                let strings = decode_strings(column)?;
+                Some(strings.iter().map(|string| contains(string.as_ref().map(|s| s.as_str()))).collect())
+            }
+        }
+    }
+}
+
+fn eq_nullable(a: Option<&str>, b: Option<&str>, case_insensitive: bool) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) if case_insensitive => a.to_lowercase() == b.to_lowercase(),
+        _ => a == b,
+    }
+}
+
+/// Returns one byte per row that is `1` if the dictionary code of the row equals `code` and `0` otherwise.
+/// Returns `None` if the column is not dictionary encoded.
+pub fn filter_eq_code(column: &Column, code: u32) -> Option<Vec<u8>> {
+    if !is_dictionary_encoded(column) {
+        return None;
+    }
+    if let Some(rows) = filter_code_range_runs(column, code, code) {
+        return Some(row_ranges_mask(&rows, column.len()));
+    }
+    let mut output = Vec::with_capacity(column.len());
+    match *first_section(column) {
+        DataSection::U8(ref codes) => filter_codes(codes, code, &mut output),
+        DataSection::U16(ref codes) => filter_codes(codes, code, &mut output),
+        DataSection::U32(ref codes) => filter_codes(codes, code, &mut output),
+        DataSection::Mapped(MappedSection::U8(ref codes)) => filter_codes(codes, code, &mut output),
+        DataSection::Mapped(MappedSection::U16(ref codes)) => filter_codes(codes, code, &mut output),
+        DataSection::Mapped(MappedSection::U32(ref codes)) => filter_codes(codes, code, &mut output),
+        ref section => panic!("Unexpected index section {:?}", section),
+    }
+    Some(output)
+}
+
+fn filter_codes<T: PrimInt>(codes: &[T], code: u32, output: &mut Vec<u8>) {
+    match <T as NumCast>::from(code) {
+        Some(code) => vector_op::filter_eq_code(codes, code, output),
+        None => output.resize(codes.len(), 0),
+    }
+}
+
+/// Rows of a run length encoded column whose dictionary code lies between `lo` and `hi` (inclusive), as ranges of
+/// consecutive rows. Each run is compared only once and adjacent matching runs are merged into a single range.
+/// Returns `None` if the column is not run length encoded.
+pub fn filter_code_range_runs(column: &Column, lo: u32, hi: u32) -> Option<Vec<Range<usize>>> {
+    let lengths = run_lengths(column)?;
+    let mut rows: Vec<Range<usize>> = Vec::new();
+    let mut start = 0;
+    for (code, &length) in code_iter(encoded_section(column)).zip(lengths) {
+        let end = start + length as usize;
+        if lo <= code && code <= hi {
+            if rows.last().map_or(false, |last| last.end == start) {
+                rows.last_mut().unwrap().end = end;
+            } else {
+                rows.push(start..end);
+            }
+        }
+        start = end;
+    }
+    Some(rows)
+}
+
+/// Converts ranges of selected rows into a filter with one byte per row.
+fn row_ranges_mask(rows: &[Range<usize>], len: usize) -> Vec<u8> {
+    let mut mask = vec![0; len];
+    for range in rows {
+        for selected in &mut mask[range.clone()] {
+            *selected = 1;
+        }
+    }
+    mask
+}
+
+/// Returns one byte per row that is `1` if the dictionary code of the row lies between `lo` and `hi` (inclusive).
+/// Returns `None` if the column is not dictionary encoded.
+pub fn filter_code_range(column: &Column, lo: u32, hi: u32) -> Option<Vec<u8>> {
+    if !is_dictionary_encoded(column) {
+        return None;
+    }
+    if let Some(rows) = filter_code_range_runs(column, lo, hi) {
+        return Some(row_ranges_mask(&rows, column.len()));
+    }
+    let in_range = |code: u32| (lo <= code && code <= hi) as u8;
+    Some(column_codes(column).map(in_range).collect())
+}
+
+/// Returns one byte per row that is `1` if the row lies between `lo` and `hi` (inclusive) in byte order, e.g. for
+/// date range filters on zero padded keys. Columns with a sorted dictionary are filtered on codes without decoding,
+/// see `dictionary_code_range`. Null rows are never selected.
+pub fn filter_between_str(column: &Column, lo: &str, hi: &str) -> Option<Vec<u8>> {
+    if let Some(codes) = dictionary_code_range(column, lo, hi) {
+        if codes.start == codes.end {
+            return Some(vec![0; column.len()]);
+        }
+        return filter_code_range(column, codes.start, codes.end - 1);
+    }
+    let strings = decode_strings(column)?;
+    Some(strings.iter()
+        .map(|string| string.as_ref().map_or(false, |s| lo <= s.as_str() && s.as_str() <= hi) as u8)
+        .collect())
+}
+
+/// Returns one byte per row that is `1` if the row of `column` compares to the same row of `other` as `op` requires.
+/// Rows where either side is null are never selected. If both columns are dictionary encoded, rows are compared on
+/// codes of the merged dictionary which preserves the order of the values, see `merge_dictionaries`.
+/// Returns `None` if either column is not a string column.
+pub fn compare_strings(column: &Column, other: &Column, op: CmpOp) -> Option<Vec<u8>> {
+    assert_eq!(column.len(), other.len());
+    let case_insensitive = column.is_case_insensitive() || other.is_case_insensitive();
+    if !case_insensitive {
+        if let Some(dictionary) = merge_dictionaries(&[column, other]) {
+            let nullable_codes = |column: &Column, remap: &[u32]| {
+                let null_code = column.null_code();
+                let remap = remap.iter().enumerate()
+                    .map(|(code, &global)| if Some(code as u32) == null_code { None } else { Some(global) })
+                    .collect::<Vec<_>>();
+                column_codes(column).map(|code| remap[code as usize]).collect::<Vec<_>>()
+            };
+            let lhs = nullable_codes(column, &dictionary.remaps[0]);
+            let rhs = nullable_codes(other, &dictionary.remaps[1]);
+            return Some(lhs.iter().zip(&rhs)
+                .map(|(lhs, rhs)| match (lhs, rhs) {
+                    (&Some(lhs), &Some(rhs)) => op.eval(&lhs, &rhs) as u8,
+                    _ => 0,
+                })
+                .collect());
+        }
+    }
+    let lhs = decode_strings(column)?;
+    let rhs = decode_strings(other)?;
+    Some(lhs.iter().zip(&rhs)
+        .map(|(lhs, rhs)| match (lhs, rhs) {
+            (&Some(ref lhs), &Some(ref rhs)) if case_insensitive => op.eval(&lhs.to_lowercase(), &rhs.to_lowercase()) as u8,
+            (&Some(ref lhs), &Some(ref rhs)) => op.eval(lhs, rhs) as u8,
+            _ => 0,
+        })
+        .collect())
+}
+
+/// Estimates the fraction of rows equal to `value`, e.g. to order predicates before executing them. Values that are not
+/// in the dictionary or excluded by the min/max statistics and bloom filter have a selectivity of exactly 0.
+/// Dictionary encoded columns count matching codes in a sample of at most `SELECTIVITY_SAMPLE_ROWS` rows, or in all
+/// runs of run length encoded columns. Other columns assume all distinct values are equally frequent.
+/// Never decodes the column.
+pub fn estimate_eq_selectivity(column: &Column, value: &str) -> f64 {
+    if column.len() == 0 || !column.might_contain(value) {
+        return 0.0;
+    }
+    if let Some(constant) = constant_value(column) {
+        return eq_nullable(constant, Some(value), column.is_case_insensitive()) as u8 as f64;
+    }
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let code = match column.dictionary_code(value) {
+                Some(code) if Some(code) != column.null_code() => code,
+                _ => return 0.0,
+            };
+            let (matches, sampled) = sample_code_count(column, code);
+            if matches > 0 {
+                matches as f64 / sampled as f64
+            } else {
+                // Too rare to show up in the sample
+                f64::min(1.0 / column.data()[1].len() as f64, 1.0 / sampled as f64)
+            }
+        }
+        _ => match column.stats() {
+            Some(stats) if stats.distinct_count > 0 => {
+                let non_null = (stats.row_count - stats.null_count) as f64 / stats.row_count as f64;
+                f64::min(1.0, non_null / stats.distinct_count as f64)
+            }
+            Some(_) => 0.0,
+            None => 1.0,
+        },
+    }
+}
+
+/// Counts the rows with `code` among evenly spaced rows of a dictionary encoded column and returns it together with the
+/// number of sampled rows. Samples at most `SELECTIVITY_SAMPLE_ROWS` rows, except for run length encoded columns where
+/// counting each run once covers all rows.
+fn sample_code_count(column: &Column, code: u32) -> (usize, usize) {
+    let len = column.len();
+    let section = encoded_section(column);
+    if let Some(lengths) = run_lengths(column) {
+        let matches = code_iter(section).zip(lengths)
+            .filter(|&(c, _)| c == code)
+            .map(|(_, &length)| length as usize)
+            .sum();
+        return (matches, len);
+    }
+    let stride = cmp::max(1, len / SELECTIVITY_SAMPLE_ROWS);
+    let rows = (0..len).step_by(stride);
+    let sampled = rows.len();
+    let matches = if let Some(&CodecOp::UnpackBits(_, bits, _)) = column.codec().ops().first() {
+        let words: &[u64] = match *section {
+            DataSection::U64(ref words) => words,
+            DataSection::Mapped(MappedSection::U64(ref words)) => words,
+            ref section => panic!("Unexpected bit packed section {:?}", section),
+        };
+        rows.filter(|&row| bit_packing::unpack(words, bits, row) == code as u64).count()
+    } else {
+        match *section {
+            DataSection::U8(ref codes) => rows.filter(|&row| codes[row] as u32 == code).count(),
+            DataSection::U16(ref codes) => rows.filter(|&row| codes[row] as u32 == code).count(),
+            DataSection::U32(ref codes) => rows.filter(|&row| codes[row] == code).count(),
+            DataSection::Mapped(MappedSection::U8(ref codes)) => rows.filter(|&row| codes[row] as u32 == code).count(),
+            DataSection::Mapped(MappedSection::U16(ref codes)) => rows.filter(|&row| codes[row] as u32 == code).count(),
+            DataSection::Mapped(MappedSection::U32(ref codes)) => rows.filter(|&row| codes[row] == code).count(),
+            ref section => panic!("Unexpected index section {:?}", section),
+        }
+    };
+    (matches, sampled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use mem_store::column_builder::*;
+    use super::super::tests::*;
+
+    #[test]
+    fn test_filter_eq_str() {
+        let values = ["GET", "", "POST", "GET", "PUT"];
+        for &max_unique_strings in &[1 << 19, 1] {
+            let mut builder = StringColBuilder::with_options(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            for value in &values {
+                if value.is_empty() { builder.push_null() } else { builder.push(value) }
+            }
+            let column = builder.finalize("test");
+            assert_eq!(column.filter_eq_str(Some("GET"), false), Some(vec![1, 0, 0, 1, 0]));
+            assert_eq!(column.filter_eq_str(Some("PUT"), false), Some(vec![0, 0, 0, 0, 1]));
+            assert_eq!(column.filter_eq_str(Some("DELETE"), false), Some(vec![0; 5]));
+            assert_eq!(column.filter_eq_str(None, false), Some(vec![0, 1, 0, 0, 0]));
+        }
+
+        let options = StringColumnOptions::default().with_case_insensitive(true);
+        let column = fast_build_string_column("test", values.iter().cloned(), values.len(), false, false, 0, &options);
+        assert_eq!(column.filter_eq_str(Some("get"), false), Some(vec![1, 0, 0, 1, 0]));
+        assert_eq!(column.dictionary_code("Post"), column.dictionary_code("POST"));
+    }
+
+    #[test]
+    fn test_filter_in() {
+        let values = ["GET", "", "POST", "GET", "PUT", "PATCH"];
+        for &max_unique_strings in &[1 << 19, 1] {
+            let mut builder = StringColBuilder::with_options(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            for value in &values {
+                if value.is_empty() { builder.push_null() } else { builder.push(value) }
+            }
+            let column = builder.finalize("test");
+            assert_eq!(filter_in(&column, &[Some("GET"), Some("PUT")], false), Some(vec![1, 0, 0, 1, 1, 0]));
+            assert_eq!(filter_in(&column, &[Some("DELETE"), Some("PATCH"), Some("HEAD")], false), Some(vec![0, 0, 0, 0, 0, 1]));
+            assert_eq!(filter_in(&column, &[None, Some("POST")], false), Some(vec![0, 1, 1, 0, 0, 0]));
+            assert_eq!(filter_in(&column, &[Some("DELETE")], false), Some(vec![0; 6]));
+            assert_eq!(filter_in(&column, &[], false), Some(vec![0; 6]));
+
+            // Null rows are never selected by negated filters
+            assert_eq!(filter_in(&column, &[Some("GET"), Some("PUT")], true), Some(vec![0, 0, 1, 0, 0, 1]));
+            assert_eq!(filter_in(&column, &[Some("DELETE")], true), Some(vec![1, 0, 1, 1, 1, 1]));
+            assert_eq!(filter_in(&column, &[], true), Some(vec![1, 0, 1, 1, 1, 1]));
+            assert_eq!(filter_in(&column, &[None, Some("POST")], true), Some(vec![0; 6]));
+            assert_eq!(column.filter_eq_str(Some("GET"), true), Some(vec![0, 0, 1, 0, 1, 1]));
+            assert_eq!(column.filter_eq_str(None, true), Some(vec![0; 6]));
+        }
+    }
+
+    #[test]
+    fn test_filter_eq_code() {
+        let values = (0..1000).map(|i| format!("value{}", i % 37)).collect::<Vec<_>>();
+        let column = build_column(&values);
+        let entries = dictionary_entries(&column).map(|s| s.to_string()).collect::<Vec<_>>();
+        let code = entries.iter().position(|s| s == "value5").unwrap() as u32;
+        let expected = values.iter().map(|s| (s == "value5") as u8).collect::<Vec<_>>();
+        assert_eq!(column.filter_eq_code(code), Some(expected));
+        assert_eq!(column.filter_eq_code(1 << 20), Some(vec![0; values.len()]));
+    }
+
+    #[test]
+    fn test_filter_between_str() {
+        let values = (0..3000)
+            .map(|i| if i % 100 == 7 { None } else { Some(Arc::new(format!("2024-{:02}-{:02}", 1 + i % 3, 1 + 2 * (i % 14)))) })
+            .collect::<Vec<_>>();
+        let expected = |lo: &str, hi: &str| values.iter()
+            .map(|value| value.as_ref().map_or(false, |s| lo <= s.as_str() && s.as_str() <= hi) as u8)
+            .collect::<Vec<_>>();
+        let column = build_string_column_from_values("test", &values, &StringColumnOptions::default());
+        assert_eq!(column.codec().ops().last(), Some(&CodecOp::DictLookup(EncodingType::U8)));
+        let entries = dictionary_entries(&column).collect::<Vec<_>>();
+        let code = |entry: &str| entries.iter().position(|&e| e == entry).unwrap() as u32;
+        assert_eq!(column.dictionary_code_range("2024-01-02", "2024-01-30"), Some(code("2024-01-03")..code("2024-02-01")));
+        assert_eq!(column.dictionary_code_range("2024-01-03", "2024-01-03"), Some(code("2024-01-03")..code("2024-01-05")));
+        assert_eq!(column.dictionary_code_range("", "2024-01-01"), Some(code("2024-01-01")..code("2024-01-03")));
+        assert_eq!(column.dictionary_code_range("2024-01-04", "2024-01-04").map(|r| r.len()), Some(0));
+
+        let ranges = [
+            ("2024-01-02", "2024-01-30"),
+            ("2024-01-03", "2024-01-03"),
+            ("2024-01-04", "2024-01-04"),
+            ("", "2024-01-05"),
+            ("2024-02", "2024-03"),
+            ("2025", "2026"),
+            ("2024-02-10", "2024-01-01"),
+        ];
+        let mut sorted = values.clone();
+        sorted.sort();
+        let run_length_encoded = build_string_column_from_values("test", &sorted, &StringColumnOptions::default());
+        assert!(run_lengths(&run_length_encoded).is_some());
+        let unordered = build_string_column_from_values(
+            "test", &values, &StringColumnOptions::default().with_dictionary_order(DictionaryOrder::Frequency));
+        assert_eq!(unordered.dictionary_code_range("2024-01-02", "2024-01-30"), None);
+        for &(lo, hi) in &ranges {
+            assert_eq!(column.filter_between_str(lo, hi), Some(expected(lo, hi)), "{} {}", lo, hi);
+            assert_eq!(unordered.filter_between_str(lo, hi), Some(expected(lo, hi)), "{} {}", lo, hi);
+            let expected_sorted = sorted.iter()
+                .map(|value| value.as_ref().map_or(false, |s| lo <= s.as_str() && s.as_str() <= hi) as u8)
+                .collect::<Vec<_>>();
+            assert_eq!(run_length_encoded.filter_between_str(lo, hi), Some(expected_sorted), "{} {}", lo, hi);
+        }
+    }
+
+    #[test]
+    fn test_compare_strings() {
+        let first = [Some("ann"), Some("bob"), None, Some("dan"), Some("eve"), None, Some("Gus")];
+        let last = [Some("ann"), Some("abe"), Some("cat"), Some("eve"), None, None, Some("gus")];
+        let columns = |values: &[Option<&str>], options: &StringColumnOptions| {
+            let values = values.iter().map(|v| v.map(|v| Arc::new(v.to_string()))).collect::<Vec<_>>();
+            build_string_column_from_values("test", &values, options)
+        };
+        let dictionary = StringColumnOptions::default();
+        let packed = StringColumnOptions::default().with_max_unique_strings(0);
+        let expected = [
+            (CmpOp::Eq, vec![1, 0, 0, 0, 0, 0, 0]),
+            (CmpOp::NotEq, vec![0, 1, 0, 1, 0, 0, 1]),
+            (CmpOp::Lt, vec![0, 0, 0, 1, 0, 0, 1]),
+            (CmpOp::LtEq, vec![1, 0, 0, 1, 0, 0, 1]),
+            (CmpOp::Gt, vec![0, 1, 0, 0, 0, 0, 0]),
+            (CmpOp::GtEq, vec![1, 1, 0, 0, 0, 0, 0]),
+        ];
+        for &(lhs_options, rhs_options) in &[(&dictionary, &dictionary), (&dictionary, &packed), (&packed, &packed)] {
+            let (lhs, rhs) = (columns(&first, lhs_options), columns(&last, rhs_options));
+            for &(op, ref expected) in &expected {
+                assert_eq!(&lhs.compare_with(&rhs, op).unwrap(), expected, "{:?}", op);
+            }
+        }
+
+        let case_insensitive = columns(&first, &dictionary.with_case_insensitive(true));
+        let rhs = columns(&last, &dictionary);
+        assert_eq!(case_insensitive.compare_with(&rhs, CmpOp::Eq).unwrap(), vec![1, 0, 0, 0, 0, 0, 1]);
+        assert!(case_insensitive.compare_with(&columns(&first[1..], &dictionary), CmpOp::Eq).is_err());
+    }
+
+    #[test]
+    fn test_estimate_eq_selectivity() {
+        let values = (0..100_000).map(|i| format!("value{}", i % 7)).collect::<Vec<_>>();
+        let column = build_column(&values);
+        let estimate = column.estimate_eq_selectivity("value0");
+        assert!((estimate - 1.0 / 7.0).abs() < 0.01, "{}", estimate);
+        assert_eq!(column.estimate_eq_selectivity("value7"), 0.0);
+        assert_eq!(column.estimate_eq_selectivity("zzz"), 0.0);
+
+        let mut sorted = values.clone();
+        sorted.sort();
+        let run_length_encoded = build_column(&sorted);
+        assert!(run_lengths(&run_length_encoded).is_some());
+        let exact = values.iter().filter(|&v| v == "value3").count() as f64 / values.len() as f64;
+        assert_eq!(run_length_encoded.estimate_eq_selectivity("value3"), exact);
+
+        let constant = build_column(&vec!["a".to_string(); 100]);
+        assert_eq!(constant.estimate_eq_selectivity("a"), 1.0);
+        assert_eq!(constant.estimate_eq_selectivity("b"), 0.0);
+
+        let options = StringColumnOptions::default().with_max_unique_strings(0);
+        let distinct = (0..1000).map(|i| Some(Arc::new(format!("{:x}", (i * 7919) % 1000)))).collect::<Vec<_>>();
+        let packed = build_string_column_from_values("test", &distinct, &options);
+        let estimate = packed.estimate_eq_selectivity("1f");
+        assert!(estimate > 0.0005 && estimate < 0.002, "{}", estimate);
+        for estimate in [column.estimate_eq_selectivity("value1"), packed.estimate_eq_selectivity("a")].iter() {
+            assert!(*estimate >= 0.0 && *estimate <= 1.0);
+        }
+    }
+
+    #[test]
+    fn test_filter_byte_prefix() {
+        let values = vec![Some("é1"), Some("è2"), None, Some("a3"), Some("éa"), Some("b")];
+        // "é" and "è" are encoded as [0xc3, 0xa9] and [0xc3, 0xa8]
+        let cases: Vec<(&[u8], Vec<u8>)> = vec![
+            (&[0xc3], vec![1, 1, 0, 0, 1, 0]),
+            (&[0xc3, 0xa9], vec![1, 0, 0, 0, 1, 0]),
+            ("éa".as_bytes(), vec![0, 0, 0, 0, 1, 0]),
+            (b"a", vec![0, 0, 0, 1, 0, 0]),
+            (b"", vec![1, 1, 0, 1, 1, 1]),
+        ];
+        let options = StringColumnOptions::default();
+        let columns = vec![
+            build_string_column_from_strs("test", &values, &options),
+            build_string_column_from_strs("test", &values, &options.with_max_unique_strings(0)),
+        ];
+        for column in &columns {
+            for &(prefix, ref expected) in &cases {
+                assert_eq!(column.filter_byte_prefix(prefix).as_ref(), Some(expected), "{:?} {:?}", column, prefix);
+            }
+        }
+    }
+}
@@ -0,0 +1,500 @@
+use std::borrow::Cow;
+use std::cmp;
+use std::collections::HashMap;
+use std::collections::hash_set::HashSet;
+use std::hash::BuildHasherDefault;
+use std::iter;
+use num::PrimInt;
+use seahash::SeaHasher;
+use stringpack::*;
+use engine::types::*;
+use mem_store::*;
+use mem_store::bit_packing;
+
+mod build;
+mod decode;
+mod dictionary;
+mod filters;
+mod packed;
+mod stats;
+mod transform;
+mod validate;
+
+pub use self::build::*;
+pub use self::decode::*;
+pub use self::dictionary::*;
+pub use self::filters::*;
+pub use self::packed::*;
+pub use self::stats::*;
+pub use self::transform::*;
+pub use self::validate::*;
+
+type HashMapSea<K, V> = HashMap<K, V, BuildHasherDefault<SeaHasher>>;
+type HashSetSea<K> = HashSet<K, BuildHasherDefault<SeaHasher>>;
+
+/// Default for `StringColumnOptions::max_unique_strings` when building columns with `StringColBuilder`.
+pub const MAX_UNIQUE_STRINGS: usize = 1 << 19;
+/// Default for `StringColumnOptions::offset_index_stride`.
+pub const OFFSET_INDEX_STRIDE: usize = 64;
+const DICTIONARY_RATIO: usize = 2;
+const SYMBOL_TABLE_SAMPLE_SIZE: usize = 1000;
+/// Packed string columns smaller than this are always decoded on a single thread.
+const PARALLEL_DECODE_MIN_BYTES: usize = 1 << 20;
+/// Dictionary codes are run length encoded if runs of identical codes are at least this long on average.
+const RUN_LENGTH_MIN_AVERAGE: usize = 16;
+/// Number of rows sampled to estimate the size of packed strings, see `estimate_encodings`.
+const ENCODING_SAMPLE_ROWS: usize = 4096;
+/// Columns are only component coded if each distinct component occurs at least this many times on average.
+const MIN_COMPONENT_REUSE: f64 = 4.0;
+/// Maximum number of rows sampled by `estimate_eq_selectivity`.
+const SELECTIVITY_SAMPLE_ROWS: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DictionaryOrder {
+    /// Codes follow the lexicographic order of the strings which allows range predicates on codes.
+    Sorted,
+    /// Code 0 is assigned to the most frequent string, 1 to the next most frequent and so on.
+    Frequency,
+    /// Codes are assigned in the order in which strings first occur, which keeps the codes of clustered data close.
+    FirstOccurrence,
+}
+
+/// How `StringColBuilder` stores columns with more than `StringColumnOptions::max_unique_strings` distinct values.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OverflowPolicy {
+    /// Store the values without a dictionary, see `build_string_column`.
+    Packer,
+    /// Fail to build the column.
+    Error,
+    /// Dictionary encode the given number of most frequent values and store all other values inline, see
+    /// `hybrid_string_column`.
+    HybridTopK(usize),
+    /// Replace all but the most frequent values with the given string, see `map_to_other`.
+    MapToOther(String),
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> OverflowPolicy {
+        OverflowPolicy::Packer
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct StringColumnOptions {
+    /// Columns with more unique values than this are not dictionary encoded. If not set, `StringColBuilder` uses
+    /// `MAX_UNIQUE_STRINGS` and `fast_build_string_column` only applies its ratio of unique values to rows.
+    pub max_unique_strings: Option<usize>,
+    pub dictionary_order: DictionaryOrder,
+    /// Size of the per-column bloom filter in bits per distinct value, 0 disables the bloom filter.
+    pub bloom_filter_bits_per_value: usize,
+    pub bloom_filter_hashes: u32,
+    /// Whether to try compressing columns that are not dictionary encoded with a learned symbol table.
+    pub symbol_table_compression: bool,
+    /// Whether to store dictionary codes with the minimal number of bits instead of the next wider integer type.
+    pub bit_packed_codes: bool,
+    /// Whether sorted columns that are not dictionary encoded store each value as the length of the prefix shared with
+    /// the previous value and the remaining suffix. Saves space on keys and timestamps, but values can only be decoded
+    /// sequentially.
+    pub front_coding: bool,
+    /// Whether `StringColBuilder` stores columns that contain only integers as integer columns.
+    pub detect_integers: bool,
+    /// Whether strings that differ only in case are stored as the same value, which keeps the casing of its first
+    /// occurrence. Equality filters on such columns ignore case.
+    pub case_insensitive: bool,
+    /// Whether columns with few enough unique values are only dictionary encoded if the dictionary is estimated to be
+    /// smaller than packing the strings, see `EncodingEstimate`.
+    pub adaptive_encoding: bool,
+    /// Separator used to split columns that are not dictionary encoded into components (e.g. path segments) which are
+    /// stored in a second level dictionary. `None` disables component coding.
+    pub component_separator: Option<u8>,
+    /// Whether values that are empty or consist only of whitespace are stored as null by `StringColBuilder` and
+    /// `StreamingStringColBuilder`.
+    pub trim_to_null: bool,
+    /// Whether leading and trailing whitespace is removed from values before they are stored.
+    pub store_trimmed: bool,
+    /// Packed string columns store the byte offset of every `offset_index_stride`th value, which lets
+    /// `take_strings`, `decode_strings_range` and `search_sorted` seek to a row instead of scanning all preceding
+    /// values. Smaller strides seek faster but use more memory, 0 disables the offset index.
+    pub offset_index_stride: usize,
+}
+
+impl StringColumnOptions {
+    pub fn with_max_unique_strings(mut self, max_unique_strings: usize) -> StringColumnOptions {
+        self.max_unique_strings = Some(max_unique_strings);
+        self
+    }
+
+    /// Maximum number of unique values of dictionary encoded columns built with `StringColBuilder`.
+    pub fn unique_strings_limit(&self) -> usize {
+        self.max_unique_strings.unwrap_or(MAX_UNIQUE_STRINGS)
+    }
+
+    pub fn with_dictionary_order(mut self, dictionary_order: DictionaryOrder) -> StringColumnOptions {
+        self.dictionary_order = dictionary_order;
+        self
+    }
+
+    pub fn with_bloom_filter(mut self, bits_per_value: usize, hashes: u32) -> StringColumnOptions {
+        self.bloom_filter_bits_per_value = bits_per_value;
+        self.bloom_filter_hashes = hashes;
+        self
+    }
+
+    pub fn with_symbol_table_compression(mut self, symbol_table_compression: bool) -> StringColumnOptions {
+        self.symbol_table_compression = symbol_table_compression;
+        self
+    }
+
+    pub fn with_bit_packed_codes(mut self, bit_packed_codes: bool) -> StringColumnOptions {
+        self.bit_packed_codes = bit_packed_codes;
+        self
+    }
+
+    pub fn with_front_coding(mut self, front_coding: bool) -> StringColumnOptions {
+        self.front_coding = front_coding;
+        self
+    }
+
+    pub fn with_detect_integers(mut self, detect_integers: bool) -> StringColumnOptions {
+        self.detect_integers = detect_integers;
+        self
+    }
+
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> StringColumnOptions {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    pub fn with_adaptive_encoding(mut self, adaptive_encoding: bool) -> StringColumnOptions {
+        self.adaptive_encoding = adaptive_encoding;
+        self
+    }
+
+    pub fn with_component_separator(mut self, separator: char) -> StringColumnOptions {
+        assert!(separator.is_ascii(), "Component separator {:?} is not ASCII", separator);
+        self.component_separator = Some(separator as u8);
+        self
+    }
+
+    pub fn with_trim_to_null(mut self, trim_to_null: bool) -> StringColumnOptions {
+        self.trim_to_null = trim_to_null;
+        self
+    }
+
+    pub fn with_store_trimmed(mut self, store_trimmed: bool) -> StringColumnOptions {
+        self.store_trimmed = store_trimmed;
+        self
+    }
+
+    pub fn with_offset_index_stride(mut self, offset_index_stride: usize) -> StringColumnOptions {
+        self.offset_index_stride = offset_index_stride;
+        self
+    }
+
+    /// Applies `trim_to_null` and `store_trimmed` to an incoming value, returns `None` if it should be stored as null.
+    pub fn normalize<'a>(&self, value: &'a str) -> Option<&'a str> {
+        if !self.trim_to_null && !self.store_trimmed { return Some(value); }
+        let trimmed = value.trim();
+        if self.trim_to_null && trimmed.is_empty() {
+            None
+        } else if self.store_trimmed {
+            Some(trimmed)
+        } else {
+            Some(value)
+        }
+    }
+}
+
+impl Default for StringColumnOptions {
+    fn default() -> StringColumnOptions {
+        StringColumnOptions {
+            max_unique_strings: None,
+            dictionary_order: DictionaryOrder::Sorted,
+            bloom_filter_bits_per_value: 0,
+            bloom_filter_hashes: 7,
+            symbol_table_compression: false,
+            bit_packed_codes: false,
+            front_coding: false,
+            detect_integers: false,
+            case_insensitive: false,
+            adaptive_encoding: false,
+            component_separator: None,
+            trim_to_null: false,
+            store_trimmed: false,
+            offset_index_stride: OFFSET_INDEX_STRIDE,
+        }
+    }
+}
+
+pub fn dict_codec(index_type: EncodingType, order: DictionaryOrder) -> Vec<CodecOp> {
+    vec![
+        CodecOp::PushDataSection(1),
+        CodecOp::PushDataSection(2),
+        match order {
+            DictionaryOrder::Sorted => CodecOp::DictLookup(index_type),
+            DictionaryOrder::Frequency | DictionaryOrder::FirstOccurrence => CodecOp::UnorderedDictLookup(index_type),
+        },
+    ]
+}
+
+pub fn string_pack_codec() -> Vec<CodecOp> {
+    vec![CodecOp::UnpackStrings]
+}
+
+/// Whether `op` looks up the codes of a dictionary encoded string column in its dictionary.
+pub fn is_dictionary_lookup(op: &CodecOp) -> bool {
+    match *op {
+        CodecOp::DictLookup(_) | CodecOp::UnorderedDictLookup(_) => true,
+        _ => false,
+    }
+}
+
+/// Whether `op` unpacks the strings of a column that stores all values one after another, see `packed_data`.
+pub fn is_packed_strings(op: &CodecOp) -> bool {
+    match *op {
+        CodecOp::UnpackStrings | CodecOp::UnpackFrontCodedStrings(_) | CodecOp::UnpackSymbolCodedStrings(_) |
+        CodecOp::UnpackComponentCodedStrings(_) | CodecOp::UnpackHybridStrings => true,
+        _ => false,
+    }
+}
+
+pub fn is_dictionary_encoded(column: &Column) -> bool {
+    column.codec().ops().last().map_or(false, is_dictionary_lookup)
+}
+
+fn code_iter<'a>(section: Cow<'a, DataSection>) -> Box<Iterator<Item=u32> + 'a> {
+    match section {
+        Cow::Borrowed(section) => match *section {
+            DataSection::U8(ref codes) => Box::new(codes.iter().map(|&code| code as u32)),
+            DataSection::U16(ref codes) => Box::new(codes.iter().map(|&code| code as u32)),
+            DataSection::U32(ref codes) => Box::new(codes.iter().cloned()),
+            DataSection::Mapped(MappedSection::U8(ref codes)) => Box::new(codes.iter().map(|&code| code as u32)),
+            DataSection::Mapped(MappedSection::U16(ref codes)) => Box::new(codes.iter().map(|&code| code as u32)),
+            DataSection::Mapped(MappedSection::U32(ref codes)) => Box::new(codes.iter().cloned()),
+            ref section => panic!("Unexpected index section {:?}", section),
+        },
+        // Decompressed codes are owned by the iterator
+        Cow::Owned(section) => Box::new((0..section.len()).map(move |row| code_at(&section, row))),
+    }
+}
+
+/// Lazily yields the code of each row of a dictionary encoded column. Unlike `first_section`, runs are expanded and bit
+/// packed codes are unpacked as they are consumed instead of all at once.
+fn column_codes<'a>(column: &'a Column) -> Box<Iterator<Item=u32> + 'a> {
+    let section = encoded_section(column);
+    if let Some(lengths) = run_lengths(column) {
+        return Box::new(code_iter(section).zip(lengths)
+            .flat_map(|(code, &length)| iter::repeat(code).take(length as usize)));
+    }
+    let len = column.len();
+    match (code_bits(column), section) {
+        (Some(bits), Cow::Borrowed(section)) => {
+            let words = u64_section(section);
+            Box::new((0..len).map(move |row| bit_packing::unpack(words, bits, row) as u32))
+        }
+        (Some(bits), Cow::Owned(section)) =>
+            Box::new((0..len).map(move |row| bit_packing::unpack(u64_section(&section), bits, row) as u32)),
+        (None, section) => code_iter(section),
+    }
+}
+
+/// Number of bits per code if the codes of the column are bit packed.
+fn code_bits(column: &Column) -> Option<u8> {
+    column.codec().ops().iter()
+        .filter_map(|op| if let CodecOp::UnpackBits(_, bits, _) = *op { Some(bits) } else { None })
+        .next()
+}
+
+fn u64_section(section: &DataSection) -> &[u64] {
+    match *section {
+        DataSection::U64(ref words) => words,
+        DataSection::Mapped(MappedSection::U64(ref words)) => words,
+        ref section => panic!("Unexpected bit packed section {:?}", section),
+    }
+}
+
+fn code_at(codes: &DataSection, row: usize) -> u32 {
+    match *codes {
+        DataSection::U8(ref codes) => codes[row] as u32,
+        DataSection::U16(ref codes) => codes[row] as u32,
+        DataSection::U32(ref codes) => codes[row],
+        DataSection::Mapped(MappedSection::U8(ref codes)) => codes[row] as u32,
+        DataSection::Mapped(MappedSection::U16(ref codes)) => codes[row] as u32,
+        DataSection::Mapped(MappedSection::U32(ref codes)) => codes[row],
+        ref section => panic!("Unexpected index section {:?}", section),
+    }
+}
+
+fn u8_section(section: &DataSection) -> &[u8] {
+    match *section {
+        DataSection::U8(ref data) => data,
+        DataSection::Mapped(MappedSection::U8(ref data)) => data,
+        ref section => panic!("Unexpected data section for packed strings {:?}", section),
+    }
+}
+
+/// Returns the first data section of the column, decompressing it and expanding runs if necessary.
+/// For dictionary encoded columns, this holds the code of each row.
+pub fn first_section(column: &Column) -> Cow<DataSection> {
+    let section = encoded_section(column);
+    match run_lengths(column) {
+        Some(lengths) => Cow::Owned(match *section {
+            DataSection::U8(ref values) => DataSection::U8(run_length_decode(values, lengths)),
+            DataSection::U16(ref values) => DataSection::U16(run_length_decode(values, lengths)),
+            DataSection::U32(ref values) => DataSection::U32(run_length_decode(values, lengths)),
+            DataSection::Mapped(MappedSection::U8(ref values)) => DataSection::U8(run_length_decode(values, lengths)),
+            DataSection::Mapped(MappedSection::U16(ref values)) => DataSection::U16(run_length_decode(values, lengths)),
+            DataSection::Mapped(MappedSection::U32(ref values)) => DataSection::U32(run_length_decode(values, lengths)),
+            ref section => panic!("Unexpected run value section {:?}", section),
+        }),
+        None => match column.codec().ops().first() {
+            Some(&CodecOp::UnpackBits(t, bits, len)) => {
+                let words: &[u64] = match *section {
+                    DataSection::U64(ref words) => words,
+                    DataSection::Mapped(MappedSection::U64(ref words)) => words,
+                    ref section => panic!("Unexpected bit packed section {:?}", section),
+                };
+                Cow::Owned(match t {
+                    EncodingType::U8 => DataSection::U8(unpack_bits(words, bits, len)),
+                    EncodingType::U16 => DataSection::U16(unpack_bits(words, bits, len)),
+                    EncodingType::U32 => DataSection::U32(unpack_bits(words, bits, len)),
+                    _ => panic!("Unexpected bit packed type {:?}", t),
+                })
+            }
+            _ => section,
+        },
+    }
+}
+
+/// Codes of the rows `start..end` of a dictionary encoded column. Unlike `first_section`, this only expands the runs
+/// and unpacks the bit packed codes that overlap the range.
+fn code_range(column: &Column, start: usize, end: usize) -> Vec<u32> {
+    let section = encoded_section(column);
+    let mut codes = Vec::with_capacity(end - start);
+    if start >= end { return codes; }
+    if let Some(lengths) = run_lengths(column) {
+        let mut run_start = 0;
+        for (run, &length) in lengths.iter().enumerate() {
+            let run_end = run_start + length as usize;
+            if run_end > start {
+                let count = cmp::min(end, run_end) - cmp::max(start, run_start);
+                codes.extend(iter::repeat(code_at(&section, run)).take(count));
+                if run_end >= end { break; }
+            }
+            run_start = run_end;
+        }
+        return codes;
+    }
+    match code_bits(column) {
+        Some(bits) => bit_packing::unpack_range(u64_section(&section), bits, start, end - start, &mut codes),
+        None => codes.extend((start..end).map(|row| code_at(&section, row))),
+    }
+    codes
+}
+
+/// Codes of the rows in `indices` of a dictionary encoded column. Unlike `first_section`, runs are found by binary search
+/// instead of being expanded and only the bit packed codes of the given rows are unpacked.
+fn codes_at(column: &Column, indices: &[usize]) -> Vec<u32> {
+    let section = encoded_section(column);
+    if let Some(lengths) = run_lengths(column) {
+        let mut run_ends = Vec::with_capacity(lengths.len());
+        let mut end = 0;
+        for &length in lengths {
+            end += length as usize;
+            run_ends.push(end);
+        }
+        return indices.iter()
+            .map(|&row| match run_ends.binary_search(&row) {
+                Ok(run) => code_at(&section, run + 1),
+                Err(run) => code_at(&section, run),
+            })
+            .collect();
+    }
+    match code_bits(column) {
+        Some(bits) => {
+            let words = u64_section(&section);
+            indices.iter().map(|&row| bit_packing::unpack(words, bits, row) as u32).collect()
+        }
+        None => indices.iter().map(|&row| code_at(&section, row)).collect(),
+    }
+}
+
+fn unpack_bits<T: PrimInt>(words: &[u64], bits: u8, len: usize) -> Vec<T> {
+    let mut codes = Vec::with_capacity(len);
+    bit_packing::unpack_range(words, bits, 0, len, &mut codes);
+    codes
+}
+
+/// Returns the first data section of the column, decompressing it if necessary.
+fn encoded_section(column: &Column) -> Cow<DataSection> {
+    #[cfg(feature = "enable_lz4")]
+    {
+        if let Some(&CodecOp::LZ4(t, len)) = column.codec().ops().first() {
+            return Cow::Owned(column.data()[0].lz4_decode(t, len));
+        }
+    }
+    Cow::Borrowed(&column.data()[0])
+}
+
+/// Returns the length of each run if the column is run length encoded.
+fn run_lengths(column: &Column) -> Option<&[u32]> {
+    if !column.codec().ops().iter().any(|op| if let CodecOp::RunLengthDecode(..) = op { true } else { false }) {
+        return None;
+    }
+    match column.data()[3] {
+        DataSection::U32(ref lengths) => Some(&lengths[..]),
+        DataSection::Mapped(MappedSection::U32(ref lengths)) => Some(lengths),
+        ref section => panic!("Unexpected run length section {:?}", section),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str;
+    use std::sync::Arc;
+    use mem_store::column_builder::*;
+
+    pub(super) fn decode_dictionary(column: &Column) -> Vec<String> {
+        let indices: Vec<usize> = match *first_section(column) {
+            DataSection::U8(ref x) => x.iter().map(|&i| i as usize).collect(),
+            DataSection::U16(ref x) => x.iter().map(|&i| i as usize).collect(),
+            DataSection::U32(ref x) => x.iter().map(|&i| i as usize).collect(),
+            ref section => panic!("Unexpected index section {:?}", section),
+        };
+        match (&column.data()[1], &column.data()[2]) {
+            (DataSection::U64(offset_len), DataSection::U8(data)) => indices.iter().map(|&i| {
+                let offset = (offset_len[i] >> 24) as usize;
+                let len = (offset_len[i] & 0xffffff) as usize;
+                str::from_utf8(&data[offset..(offset + len)]).unwrap().to_string()
+            }).collect(),
+            _ => panic!("Unexpected dictionary sections"),
+        }
+    }
+
+    /// Borrows values returned by `iter_strings`, `filter_decode` and similar for comparison with string literals.
+    pub(super) fn strs<'a>(values: &'a [Option<Cow<str>>]) -> Vec<Option<&'a str>> {
+        values.iter().map(|value| value.as_ref().map(|s| s.as_ref())).collect()
+    }
+
+    pub(super) fn build_column(values: &[String]) -> Arc<Column> {
+        let mut builder = StringColBuilder::new();
+        for value in values {
+            builder.push(value);
+        }
+        builder.finalize("test")
+    }
+
+    /// Heap size depends on the capacity the sections happened to grow to, so it differs between equivalent columns.
+    pub(super) fn stats_without_heap_size(column: &Column) -> Option<ColumnStats> {
+        column.stats().map(|stats| ColumnStats { heap_bytes: 0, ..stats.clone() })
+    }
+
+    pub(super) fn projection_codes() -> Vec<u32> {
+        (0..1_000_000u32).map(|i| i.wrapping_mul(2_654_435_761) % 500).collect()
+    }
+
+    pub(super) fn projection_column() -> Arc<Column> {
+        build_column(&(0..500).map(|i| format!("value{}", i)).collect::<Vec<_>>())
+    }
+}
@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+use std::u32;
+use std::sync::Arc;
+use stringpack::*;
+use super::*;
+
+/// Creates a column from strings that have already been packed.
+pub fn packed_string_column(name: &str, len: usize, packed: PackedStrings, options: &StringColumnOptions) -> Arc<Column> {
+    packed_column(name, len, packed.into_vec(), options)
+}
+
+pub(super) fn packed_column(name: &str, len: usize, data: Vec<u8>, options: &StringColumnOptions) -> Arc<Column> {
+    let mut column = attach_metadata(Column::new(
+        name,
+        len,
+        None,
+        string_pack_codec(),
+        packed_sections(data, len, options)), options);
+    column.lz4_encode();
+    Arc::new(column)
+}
+
+/// Data sections of a column that holds `len` values in the format of `PackedStrings`. The values are followed by an
+/// offset index unless the column has no more than `options.offset_index_stride` values. The first element of the
+/// offset index is the stride, followed by the byte offset of every `stride`th value.
+pub(super) fn packed_sections(data: Vec<u8>, len: usize, options: &StringColumnOptions) -> Vec<DataSection> {
+    let stride = options.offset_index_stride;
+    if stride == 0 || len <= stride || data.len() > u32::MAX as usize {
+        return vec![DataSection::U8(data)];
+    }
+    let mut index = Vec::with_capacity((len + stride - 1) / stride + 1);
+    index.push(stride as u32);
+    let offsets = chunk_offsets(&data, stride);
+    index.extend(offsets[..offsets.len() - 1].iter().map(|&offset| offset as u32));
+    vec![DataSection::U8(data), DataSection::U32(index)]
+}
+
+/// Stride and offsets of the offset index of a packed string column, see `packed_sections`.
+pub(super) fn offset_index(column: &Column) -> Option<(usize, &[u32])> {
+    if column.codec().ops().last() != Some(&CodecOp::UnpackStrings) {
+        return None;
+    }
+    let index: &[u32] = match column.data().get(1) {
+        Some(&DataSection::U32(ref index)) => index,
+        Some(&DataSection::Mapped(MappedSection::U32(ref index))) => index,
+        _ => return None,
+    };
+    match index.split_first() {
+        Some((&stride, offsets)) if stride > 0 => Some((stride as usize, offsets)),
+        _ => None,
+    }
+}
+
+/// Iterates over the values of a packed string column starting at `row`, given the `packed` data of the column.
+/// Columns with an offset index seek to the closest preceding indexed value, other columns skip all preceding values.
+pub(super) fn packed_values_from<'a>(column: &Column, packed: &'a [u8], row: usize) -> StringPackerIterator<'a> {
+    let (offset, skip) = match offset_index(column) {
+        Some((stride, offsets)) if row / stride < offsets.len() => (offsets[row / stride] as usize, row % stride),
+        _ => (0, row),
+    };
+    let mut values = unsafe { StringPackerIterator::from_slice(&packed[offset..]) }
+        .with_len(column.len().saturating_sub(row) + skip);
+    values.skip_values(skip);
+    values
+}
+
+/// Whether `packed_values_from` can seek to any row of `column` without scanning the values before it.
+pub(super) fn has_offset_index(column: &Column) -> bool {
+    offset_index(column).is_some()
+}
+
+/// Values of a string column in the format of `PackedStrings`.
+pub(super) fn packed_strings(column: &Column) -> Vec<u8> {
+    match column.codec().ops().last() {
+        Some(op) if is_packed_strings(op) =>
+            packed_data(column).into_owned(),
+        _ => {
+            let mut packed = PackedStrings::default();
+            for value in decode_strings(column).unwrap_or_default() {
+                match value {
+                    Some(value) => packed.push(&value),
+                    None => packed.push_null(),
+                }
+            }
+            packed.into_vec()
+        }
+    }
+}
+
+/// Returns the data of a packed string column in the format of `PackedStrings`.
+pub(super) fn packed_data(column: &Column) -> Cow<[u8]> {
+    match column.codec().ops().last() {
+        Some(&CodecOp::UnpackFrontCodedStrings(_)) => {
+            let data = first_section(column);
+            return Cow::Owned(repack(unsafe { FrontCodedIterator::from_slice(u8_section(&data)) }));
+        }
+        Some(&CodecOp::UnpackSymbolCodedStrings(_)) => {
+            let data = first_section(column);
+            return Cow::Owned(repack(unsafe { SymbolCodedIterator::from_slice(u8_section(&data)) }));
+        }
+        Some(&CodecOp::UnpackComponentCodedStrings(_)) => {
+            let data = first_section(column);
+            return Cow::Owned(repack(unsafe { ComponentCodedIterator::from_slice(u8_section(&data)) }));
+        }
+        Some(&CodecOp::UnpackHybridStrings) => {
+            let data = first_section(column);
+            let mut packed = PackedStrings::default();
+            for string in unsafe { HybridIterator::from_slice(u8_section(&data)) } {
+                match string {
+                    Some(string) => packed.push(string),
+                    None => packed.push_null(),
+                }
+            }
+            return Cow::Owned(packed.into_vec());
+        }
+        _ => {}
+    }
+    match first_section(column) {
+        Cow::Owned(DataSection::U8(data)) => Cow::Owned(data),
+        Cow::Borrowed(&DataSection::U8(ref data)) => Cow::Borrowed(&data[..]),
+        Cow::Borrowed(&DataSection::Mapped(MappedSection::U8(ref data))) => Cow::Borrowed(data),
+        section => panic!("Unexpected data section for packed strings {:?}", section),
+    }
+}
+
+fn repack<I: CodedValues>(mut values: I) -> Vec<u8> {
+    let mut packed = PackedStrings::default();
+    while let Some(value) = values.next_value() {
+        match value {
+            Some(value) => packed.push(value),
+            None => packed.push_null(),
+        }
+    }
+    packed.into_vec()
+}
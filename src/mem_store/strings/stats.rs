@@ -0,0 +1,386 @@
+use hex;
+use stringpack::*;
+use mem_store::hyperloglog::HyperLogLog;
+use super::*;
+
+/// Estimates the number of distinct values in a string column using a HyperLogLog sketch.
+/// Dictionary encoded columns return the exact number of dictionary entries other than null instead.
+/// Returns `None` if the column is not a string column.
+pub fn approx_count_distinct(column: &Column, precision: u8) -> Option<u64> {
+    let codec = column.codec();
+    if codec.decoded_type() == BasicType::Null {
+        return Some(0);
+    }
+    match codec.ops().last() {
+        Some(op) if is_dictionary_lookup(op) =>
+            Some((column.data()[1].len() - column.null_code().is_some() as usize) as u64),
+        Some(op) if is_packed_strings(op) => {
+            let mut hll = HyperLogLog::new(precision);
+            let packed = packed_data(column);
+            for string in unsafe { StringPackerIterator::from_slice(&packed) } {
+                if let Some(string) = string {
+                    hll.insert(string.as_bytes());
+                }
+            }
+            Some(hll.count())
+        }
+        Some(&CodecOp::UnhexpackStrings(_, _)) => {
+            // Hex encoding is injective so it suffices to count distinct byte strings
+            let mut hll = HyperLogLog::new(precision);
+            let packed = packed_data(column);
+            for bytes in PackedBytesIterator::from_slice(&packed) {
+                hll.insert(bytes);
+            }
+            Some(hll.count())
+        }
+        _ => None,
+    }
+}
+
+pub const STATS_HLL_PRECISION: u8 = 12;
+
+/// Computes `ColumnStats` for a string column.
+/// Packed string columns are summarized in a single pass over the values.
+pub fn string_column_stats(column: &Column) -> ColumnStats {
+    let (null_count, distinct_count, min_max) = match column.codec().ops().last() {
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            let mut null_count = 0;
+            let mut hll = HyperLogLog::new(STATS_HLL_PRECISION);
+            let mut min_max: Option<(&str, &str)> = None;
+            for string in unsafe { StringPackerIterator::from_slice(&packed) } {
+                match string {
+                    Some(string) => {
+                        hll.insert(string.as_bytes());
+                        min_max = Some(match min_max {
+                            Some((min, max)) => (if string < min { string } else { min },
+                                                 if string > max { string } else { max }),
+                            None => (string, string),
+                        });
+                    }
+                    None => null_count += 1,
+                }
+            }
+            (null_count, hll.count() as usize, min_max.map(|(min, max)| (min.to_string(), max.to_string())))
+        }
+        Some(op) if is_dictionary_lookup(op) =>
+            (column.null_code().map_or(0, |null_code| code_counts(column)[null_code as usize] as usize),
+             approx_count_distinct(column, STATS_HLL_PRECISION).unwrap_or(0) as usize,
+             min_max_string(column)),
+        _ => (0,
+              approx_count_distinct(column, STATS_HLL_PRECISION).unwrap_or(0) as usize,
+              min_max_string(column)),
+    };
+    let (min, max) = match min_max {
+        Some((min, max)) => (Some(min), Some(max)),
+        None => (None, None),
+    };
+    ColumnStats {
+        row_count: column.len(),
+        null_count,
+        distinct_count,
+        min,
+        max,
+        int_range: None,
+        heap_bytes: column.data_heap_size(),
+        encoding_estimate: None,
+    }
+}
+
+/// Distinct values of a string column in sorted order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistinctValues {
+    pub values: Vec<String>,
+    pub has_null: bool,
+}
+
+/// Returns the sorted distinct non-null values of a string column and whether the column contains nulls.
+/// For dictionary encoded columns this is just the dictionary.
+/// Returns `None` if the column is not a string column.
+pub fn distinct_values(column: &Column) -> Option<DistinctValues> {
+    let codec = column.codec();
+    if codec.decoded_type() == BasicType::Null {
+        return Some(DistinctValues { values: vec![], has_null: column.len() > 0 });
+    }
+    let mut has_null = false;
+    let mut values = match codec.ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            has_null = entries.contains(&None);
+            entries.into_iter().filter_map(|s| s.map(|s| s.to_string())).collect::<Vec<_>>()
+        }
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            let mut has_null = false;
+            let mut uniques = HashSetSea::default();
+            for string in unsafe { StringPackerIterator::from_slice(&packed) } {
+                match string {
+                    Some(string) => { uniques.insert(string); }
+                    None => has_null = true,
+                }
+            }
+            let mut values = uniques.into_iter().map(|s| s.to_string()).collect::<Vec<_>>();
+            values.sort();
+            return Some(DistinctValues { values, has_null });
+        }
+        Some(&CodecOp::UnhexpackStrings(uppercase, _)) => {
+            let packed = packed_data(column);
+            let uniques = PackedBytesIterator::from_slice(&packed).collect::<HashSetSea<_>>();
+            uniques.into_iter()
+                .map(|bytes| if uppercase { hex::encode_upper(bytes) } else { hex::encode(bytes) })
+                .collect::<Vec<_>>()
+        }
+        _ => return None,
+    };
+    if !codec.is_order_preserving() {
+        values.sort();
+    }
+    Some(DistinctValues { values, has_null })
+}
+
+/// Returns the smallest and largest non-null value of a string column.
+/// Returns `None` if the column is empty, contains only nulls or is not a string column.
+pub fn min_max_string(column: &Column) -> Option<(String, String)> {
+    let codec = column.codec();
+    match codec.ops().last() {
+        Some(&CodecOp::DictLookup(_)) => {
+            // Dictionary is sorted
+            let mut entries = nullable_dictionary_entries(column).into_iter().filter_map(|entry| entry);
+            let min = entries.next()?;
+            let max = entries.last().unwrap_or(min);
+            Some((min.to_string(), max.to_string()))
+        }
+        Some(&CodecOp::UnorderedDictLookup(_)) =>
+            min_max(nullable_dictionary_entries(column).into_iter().filter_map(|entry| entry))
+                .map(|(min, max)| (min.to_string(), max.to_string())),
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            let strings = unsafe { StringPackerIterator::from_slice(&packed) };
+            min_max(strings.filter_map(|s| s)).map(|(min, max)| (min.to_string(), max.to_string()))
+        }
+        Some(&CodecOp::UnhexpackStrings(uppercase, _)) => {
+            // Hex encoding preserves the lexicographic order of the underlying bytes
+            let packed = packed_data(column);
+            let encode = |bytes: &[u8]| if uppercase { hex::encode_upper(bytes) } else { hex::encode(bytes) };
+            min_max(PackedBytesIterator::from_slice(&packed)).map(|(min, max)| (encode(min), encode(max)))
+        }
+        _ => None,
+    }
+}
+
+/// Like `min_max_string` but compares the lowercased values, as used for the stats of case insensitive columns.
+pub(super) fn folded_min_max(column: &Column) -> Option<(String, String)> {
+    let mut folded = distinct_values(column)?.values.into_iter().map(|value| value.to_lowercase());
+    let first = folded.next()?;
+    Some(folded.fold((first.clone(), first), |(min, max), x| {
+        if x < min { (x, max) } else if x > max { (min, x) } else { (min, max) }
+    }))
+}
+
+fn min_max<T: Ord + Copy>(mut values: impl Iterator<Item=T>) -> Option<(T, T)> {
+    let first = values.next()?;
+    Some(values.fold((first, first), |(min, max), x| {
+        (if x < min { x } else { min }, if x > max { x } else { max })
+    }))
+}
+
+/// Histogram of the byte lengths of the values in a string column.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LengthHistogram {
+    /// `counts[i]` is the number of values with length in `[buckets[i - 1], buckets[i])`.
+    /// The last entry counts all values with length of at least `buckets[buckets.len() - 1]`.
+    pub counts: Vec<u64>,
+    pub nulls: u64,
+}
+
+/// Bins the byte length of every value into the buckets delimited by the sorted boundaries in `buckets`.
+/// Dictionary encoded columns compute the bucket of each dictionary entry only once.
+/// Returns `None` if the column is not a string column.
+pub fn length_histogram(column: &Column, buckets: &[usize]) -> Option<LengthHistogram> {
+    let bucket = |len: usize| match buckets.binary_search(&len) {
+        Ok(i) => i + 1,
+        Err(i) => i,
+    };
+    let mut counts = vec![0; buckets.len() + 1];
+    let mut nulls = 0;
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            for (string, count) in nullable_dictionary_entries(column).into_iter().zip(code_counts(column)) {
+                match string {
+                    Some(string) => counts[bucket(string.len())] += count,
+                    None => nulls += count,
+                }
+            }
+        }
+        Some(op) if is_packed_strings(op) => {
+            let packed = packed_data(column);
+            for string in unsafe { StringPackerIterator::from_slice(&packed) } {
+                match string {
+                    Some(string) => counts[bucket(string.len())] += 1,
+                    None => nulls += 1,
+                }
+            }
+        }
+        Some(&CodecOp::UnhexpackStrings(_, _)) => {
+            let packed = packed_data(column);
+            for bytes in PackedBytesIterator::from_slice(&packed) {
+                counts[bucket(2 * bytes.len())] += 1;
+            }
+        }
+        _ => return None,
+    }
+    Some(LengthHistogram { counts, nulls })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use mem_store::column_builder::*;
+    use super::super::tests::*;
+
+    #[test]
+    fn test_approx_count_distinct() {
+        let values = (0..1000).map(|i| format!("value{}", i % 255)).collect::<Vec<_>>();
+        let column = build_column(&values);
+        assert_eq!(column.approx_count_distinct(4), Some(255));
+
+        let values = (0..20_000).map(|i| format!("value{}", i % 15_000)).collect::<Vec<_>>();
+        let mut builder = StringColBuilder::with_options(StringColumnOptions::default().with_max_unique_strings(1000));
+        for value in &values {
+            builder.push(value);
+        }
+        let column = builder.finalize("test");
+        assert_eq!(column.codec().ops(), &string_pack_codec()[..]);
+        let count = column.approx_count_distinct(12).unwrap() as f64;
+        assert!((count - 15_000.0).abs() < 15_000.0 * 0.05, "{}", count);
+        assert_eq!(column.approx_count_distinct(12), column.approx_count_distinct(12));
+    }
+
+    #[test]
+    fn test_distinct_values() {
+        let values = ["b", "a", "c", "a", "b"].iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let expected = DistinctValues {
+            values: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            has_null: false,
+        };
+        assert_eq!(build_column(&values).distinct_values(), Some(expected.clone()));
+
+        let options = StringColumnOptions::default().with_dictionary_order(DictionaryOrder::Frequency);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        assert_eq!(builder.finalize("test").distinct_values(), Some(expected.clone()));
+
+        let nullable = [None, Some("c"), Some("a"), None, Some("b"), Some("a")].iter()
+            .map(|s| s.map(|s| Arc::new(s.to_string())))
+            .collect::<Vec<_>>();
+        let packed = PackedStrings::from_nullable_strings(&nullable);
+        let column = Column::new("test", nullable.len(), None, string_pack_codec(), vec![DataSection::U8(packed.into_vec())]);
+        assert_eq!(column.distinct_values(), Some(DistinctValues { has_null: true, ..expected }));
+    }
+
+    #[test]
+    fn test_min_max_string() {
+        let values = ["pear", "apple", "zucchini", "banana"].iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let column = build_column(&values);
+        assert_eq!(column.min_string(), Some("apple".to_string()));
+        assert_eq!(column.max_string(), Some("zucchini".to_string()));
+
+        let options = StringColumnOptions::default().with_dictionary_order(DictionaryOrder::Frequency);
+        let mut builder = StringColBuilder::with_options(options);
+        for value in &values {
+            builder.push(value);
+        }
+        assert_eq!(min_max_string(&builder.finalize("test")), Some(("apple".to_string(), "zucchini".to_string())));
+
+        let nullable = [None, Some("pear"), Some("apple"), None].iter()
+            .map(|s| s.map(|s| Arc::new(s.to_string())))
+            .collect::<Vec<_>>();
+        let packed = PackedStrings::from_nullable_strings(&nullable);
+        let column = Column::new("test", nullable.len(), None, string_pack_codec(), vec![DataSection::U8(packed.into_vec())]);
+        assert_eq!(min_max_string(&column), Some(("apple".to_string(), "pear".to_string())));
+
+        let packed = PackedStrings::from_nullable_strings(&[None::<Arc<String>>, None]);
+        let column = Column::new("test", 2, None, string_pack_codec(), vec![DataSection::U8(packed.into_vec())]);
+        assert_eq!(column.min_string(), None);
+        assert_eq!(build_column(&[]).max_string(), None);
+    }
+
+    #[test]
+    fn test_column_stats() {
+        let values = ["pear", "apple", "zucchini", "apple"].iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let column = build_column(&values);
+        let stats = column.stats().unwrap();
+        assert_eq!(stats.row_count, 4);
+        assert_eq!(stats.null_count, 0);
+        assert_eq!(stats.distinct_count, 3);
+        assert_eq!(stats.min, Some("apple".to_string()));
+        assert_eq!(stats.max, Some("zucchini".to_string()));
+        assert_eq!(stats.heap_bytes, column.data_heap_size());
+
+        let nullable = [None, Some("pear"), Some("apple"), None, Some("pear")].iter()
+            .map(|s| s.map(|s| Arc::new(s.to_string())))
+            .collect::<Vec<_>>();
+        let packed = PackedStrings::from_nullable_strings(&nullable);
+        let column = Column::new("test", nullable.len(), None, string_pack_codec(), vec![DataSection::U8(packed.into_vec())]);
+        assert_eq!(string_column_stats(&column), ColumnStats {
+            row_count: 5,
+            null_count: 2,
+            distinct_count: 2,
+            min: Some("apple".to_string()),
+            max: Some("pear".to_string()),
+            int_range: None,
+            heap_bytes: column.data_heap_size(),
+            encoding_estimate: None,
+        });
+    }
+
+    #[test]
+    fn test_null_count() {
+        let values = (0..100).map(|i| if i % 7 == 3 { None } else { Some(format!("value{}", i % 4)) }).collect::<Vec<_>>();
+        for &max_unique_strings in &[1 << 19, 2] {
+            let mut builder = StringColBuilder::with_options(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            let mut streaming = StreamingStringColBuilder::new(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            for value in &values {
+                match *value {
+                    Some(ref value) => {
+                        builder.push(value);
+                        streaming.push(value);
+                    }
+                    None => {
+                        builder.push_null();
+                        streaming.push_null();
+                    }
+                }
+            }
+            for column in vec![builder.finalize("test"), streaming.finalize("test")] {
+                assert_eq!(column.null_count(), 14);
+                assert_eq!(column.count_non_null(), 86);
+                assert_eq!(column.len(), 100);
+            }
+        }
+        assert_eq!(Column::null("test", 5).null_count(), 5);
+        assert_eq!(build_column(&["a".to_string()]).count_non_null(), 1);
+        assert_eq!(Column::null("test", 5).count_filtered(&[1, 0, 2, 0, 1]), 3);
+    }
+
+    #[test]
+    fn test_length_histogram() {
+        let values = ["", "a", "abcd", "abcdefgh", "abc", "abcdefghijklmnop", "a"]
+            .iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let expected = LengthHistogram { counts: vec![4, 1, 2], nulls: 0 };
+        assert_eq!(build_column(&values).length_histogram(&[4, 8]), Some(expected));
+
+        let nullable = [Some("abcd"), None, Some(""), Some("abcdefgh"), None].iter()
+            .map(|s| s.map(|s| Arc::new(s.to_string())))
+            .collect::<Vec<_>>();
+        let packed = PackedStrings::from_nullable_strings(&nullable);
+        let column = Column::new("test", nullable.len(), None, string_pack_codec(), vec![DataSection::U8(packed.into_vec())]);
+        assert_eq!(column.length_histogram(&[1, 5]), Some(LengthHistogram { counts: vec![1, 1, 1], nulls: 2 }));
+        assert_eq!(column.length_histogram(&[]), Some(LengthHistogram { counts: vec![3], nulls: 2 }));
+    }
+}
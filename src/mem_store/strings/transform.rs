@@ -0,0 +1,217 @@
+use std::cmp;
+use std::sync::Arc;
+use stringpack::*;
+use super::*;
+
+/// Appends the values of `other` to the values of `column`. If both columns are dictionary encoded and the union of
+/// their dictionaries has at most `options.unique_strings_limit()` entries, the result is dictionary encoded with the
+/// merged dictionary. Otherwise the values are packed.
+pub fn concat_strings(column: &Column, other: &Column, options: &StringColumnOptions) -> Arc<Column> {
+    let len = column.len() + other.len();
+    if let Some(dictionary) = merge_dictionaries(&[column, other]) {
+        if dictionary.values.len() <= options.unique_strings_limit() {
+            // Null becomes code 0 and precedes the merged dictionary, which is sorted
+            let has_null = column.null_code().is_some() || other.null_code().is_some();
+            let shift = |remap: &[u32]| remap.iter()
+                .map(|&code| if code == NO_MATCH { 0 } else { code + has_null as u32 })
+                .collect::<Vec<_>>();
+            let mut codes = global_codes(column, &shift(&dictionary.remaps[0]));
+            codes.extend(global_codes(other, &shift(&dictionary.remaps[1])));
+            let options = options.with_dictionary_order(DictionaryOrder::Sorted);
+            let null = if has_null { Some(None) } else { None };
+            let values = null.into_iter().chain(dictionary.values.iter().map(|s| Some(s.as_str())));
+            return dictionary_column_from_codes(column.name(), &codes, values, &options);
+        }
+    }
+    let mut data = packed_strings(column);
+    data.extend_from_slice(&packed_strings(other));
+    packed_column(column.name(), len, data, options)
+}
+
+/// Splits a string column into partitions of `partition_size` consecutive rows, the last partition may be smaller.
+/// Partitions of dictionary encoded columns share a single copy of the full dictionary.
+pub fn split_strings(column: &Column, partition_size: usize) -> Vec<Arc<Column>> {
+    assert!(partition_size > 0, "Partition size must be positive");
+    let options = derived_column_options(column);
+    let partition_len = |i: usize| cmp::min(partition_size, column.len() - i * partition_size);
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let entries = nullable_dictionary_entries(column);
+            let codes = global_codes(column, &(0..entries.len() as u32).collect::<Vec<_>>());
+            let mut partitions = codes.chunks(partition_size)
+                .map(|codes| dictionary_column_from_codes(column.name(), codes, entries.iter().cloned(), &options))
+                .map(|partition| Arc::try_unwrap(partition).ok().expect("Partition is not shared yet"))
+                .collect::<Vec<_>>();
+            Column::share_data_sections(&mut partitions, &[1, 2]);
+            partitions.into_iter().map(Arc::new).collect()
+        }
+        _ => {
+            let packed = packed_strings(column);
+            chunk_offsets(&packed, partition_size).windows(2).enumerate()
+                .filter(|&(_, range)| range[0] < range[1])
+                .map(|(i, range)| packed_column(column.name(), partition_len(i), packed[range[0]..range[1]].to_vec(), &options))
+                .collect()
+        }
+    }
+}
+
+/// Applies `f` to every value of a string column, e.g. to compute `UPPER(name)`, and builds a new column from the
+/// results. Nulls are passed through. For dictionary encoded columns, `f` is applied once per dictionary entry and the
+/// codes are remapped to the sorted and deduplicated results. Other columns are rebuilt with `build_string_column`.
+/// Returns `None` if the column is not a string column.
+pub fn map_strings<F: Fn(&str) -> String>(column: &Column, f: F) -> Option<Arc<Column>> {
+    let options = StringColumnOptions::default().with_case_insensitive(column.is_case_insensitive());
+    match column.codec().ops().last() {
+        Some(op) if is_dictionary_lookup(op) => {
+            let mapped = nullable_dictionary_entries(column).into_iter()
+                .map(|entry| entry.map(&f))
+                .collect::<Vec<_>>();
+            let entries = mapped.iter().map(|value| value.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+            // Null sorts first, as required for sorted dictionaries
+            let mut dictionary = entries.clone();
+            dictionary.sort();
+            dictionary.dedup();
+            let remap = entries.iter()
+                .map(|entry| dictionary.binary_search(entry).unwrap() as u32)
+                .collect::<Vec<_>>();
+            let codes = global_codes(column, &remap);
+            Some(dictionary_column_from_codes(column.name(), &codes, dictionary.into_iter(), &options))
+        }
+        _ => {
+            let mapped = decode_strings(column)?.into_iter()
+                .map(|value| value.map(|s| Arc::new(f(&s))))
+                .collect::<Vec<_>>();
+            Some(build_string_column_from_values(column.name(), &mapped, &options))
+        }
+    }
+}
+
+/// Options for building a column from the values of `column` that preserve its dictionary order and case sensitivity.
+pub(super) fn derived_column_options(column: &Column) -> StringColumnOptions {
+    let order = match column.codec().ops().last() {
+        Some(&CodecOp::DictLookup(_)) => DictionaryOrder::Sorted,
+        _ => DictionaryOrder::Frequency,
+    };
+    StringColumnOptions::default()
+        .with_case_insensitive(column.is_case_insensitive())
+        .with_dictionary_order(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use mem_store::column_builder::*;
+    use mem_store::integers::IntegerColumn;
+    use super::super::tests::*;
+
+    #[test]
+    fn test_concat_strings() {
+        let build = |values: &[&str], max_unique_strings: usize| {
+            let mut builder = StringColBuilder::with_options(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            for value in values {
+                if value.is_empty() { builder.push_null() } else { builder.push(value) }
+            }
+            builder.finalize("test")
+        };
+        let decoded = |column: &Column| column.decode_strings().unwrap().into_iter()
+            .map(|value| value.unwrap_or_default())
+            .collect::<Vec<_>>();
+        let dictionary = build(&["b", "a", "", "b"], 1 << 19);
+        let other = build(&["c", "a", "d"], 1 << 19);
+        let packed = build(&["x", "", "y", "z"], 1);
+
+        let column = dictionary.concat(&other, &StringColumnOptions::default()).unwrap();
+        assert_eq!(decoded(&column), vec!["b", "a", "", "b", "c", "a", "d"]);
+        assert_eq!(column.codec().ops().last(), Some(&CodecOp::DictLookup(EncodingType::U8)));
+        assert_eq!(column.null_count(), 1);
+
+        // Merged dictionary is too large
+        let column = dictionary.concat(&other, &StringColumnOptions::default().with_max_unique_strings(3)).unwrap();
+        assert!(column.codec().ops().contains(&CodecOp::UnpackStrings));
+        assert_eq!(decoded(&column), vec!["b", "a", "", "b", "c", "a", "d"]);
+
+        let column = dictionary.concat(&packed, &StringColumnOptions::default()).unwrap();
+        assert!(column.codec().ops().contains(&CodecOp::UnpackStrings));
+        assert_eq!(decoded(&column), vec!["b", "a", "", "b", "x", "", "y", "z"]);
+        let column = packed.concat(&packed, &StringColumnOptions::default()).unwrap();
+        assert_eq!(column.decode_strings().unwrap()[4..6], [Some("x".to_string()), None]);
+        assert_eq!(column.null_count(), 2);
+
+        let integers = IntegerColumn::new_boxed("test", vec![1, 2], 1, 2, false);
+        assert!(dictionary.concat(&integers, &StringColumnOptions::default()).is_err());
+        let column = integers.concat(&integers, &StringColumnOptions::default()).unwrap();
+        assert_eq!(IntegerColumn::decode(&column), Some(vec![1, 2, 1, 2]));
+    }
+
+    #[test]
+    fn test_split() {
+        let values = (0..1000).map(|i| if i % 7 == 0 { None } else { Some(format!("value{}", i % 31)) }).collect::<Vec<_>>();
+        for &max_unique_strings in &[1 << 19, 10] {
+            let mut builder = StringColBuilder::with_options(
+                StringColumnOptions::default().with_max_unique_strings(max_unique_strings));
+            for value in &values {
+                builder.push_opt(value.as_ref().map(|s| s.as_str()));
+            }
+            let column = builder.finalize("test");
+            let partitions = column.split(300).unwrap();
+            assert_eq!(partitions.iter().map(|p| p.len()).collect::<Vec<_>>(), vec![300, 300, 300, 100]);
+            assert_eq!(partitions[0].codec().ops().last(), column.codec().ops().last());
+            let decoded = partitions.iter().flat_map(|p| p.decode_strings().unwrap()).collect::<Vec<_>>();
+            assert_eq!(decoded, column.decode_strings().unwrap());
+            assert_eq!(partitions.iter().map(|p| p.null_count()).sum::<usize>(), column.null_count());
+            assert!(column.split(0).is_err());
+        }
+
+        let integers = IntegerColumn::new_boxed("test", (0..10).collect(), 0, 9, false);
+        let partitions = integers.split(4).unwrap();
+        let decoded = partitions.iter().flat_map(|p| IntegerColumn::decode(p).unwrap()).collect::<Vec<_>>();
+        assert_eq!(decoded, (0..10).collect::<Vec<_>>());
+        assert_eq!(Column::null("test", 5).split(2).unwrap().iter().map(|p| p.len()).collect::<Vec<_>>(), vec![2, 2, 1]);
+    }
+
+    #[test]
+    fn test_split_shares_dictionary() {
+        let values = (0..20_000).map(|i| format!("value{}", i * 7919 % 5000)).collect::<Vec<_>>();
+        let column = build_column(&values);
+        let partitions = column.split(1000).unwrap();
+        assert_eq!(partitions.len(), 20);
+        let dictionary_data = partitions.iter().map(|p| u8_section(&p.data()[2]).as_ptr()).collect::<Vec<_>>();
+        assert!(dictionary_data.iter().all(|&ptr| ptr == dictionary_data[0]));
+        let dictionary_bytes = partitions.iter().map(|p| p.memory_report().dictionary_bytes).sum::<usize>();
+        assert!(dictionary_bytes <= column.memory_report().dictionary_bytes * 11 / 10,
+                "{} {}", dictionary_bytes, column.memory_report().dictionary_bytes);
+        let decoded = partitions.iter().flat_map(|p| p.decode_strings().unwrap()).collect::<Vec<_>>();
+        assert_eq!(decoded, column.decode_strings().unwrap());
+        assert_eq!(partitions[19].dictionary_code("value42"), column.dictionary_code("value42"));
+    }
+
+    #[test]
+    fn test_map_strings() {
+        let values = (0..1000)
+            .map(|i| if i % 10 == 0 { None } else { Some(["a", "A", "b", "cc"][i % 4]) })
+            .collect::<Vec<_>>();
+        let expected = values.iter().map(|s| s.map(|s| s.to_uppercase())).collect::<Vec<_>>();
+        let calls = Cell::new(0);
+        let upper = |s: &str| {
+            calls.set(calls.get() + 1);
+            s.to_uppercase()
+        };
+
+        let dictionary = build_string_column_from_strs("test", &values, &StringColumnOptions::default());
+        let mapped = dictionary.map_strings(&upper).unwrap();
+        assert_eq!(calls.get(), 4);
+        assert_eq!(dictionary_entries(&mapped).collect::<Vec<_>>(), vec!["", "A", "B", "CC"]);
+        assert_eq!(mapped.null_count(), 100);
+        assert_eq!(mapped.decode_strings().unwrap(), expected);
+
+        calls.set(0);
+        let packed = build_string_column_from_strs(
+            "test", &values, &StringColumnOptions::default().with_max_unique_strings(0));
+        let mapped = packed.map_strings(&upper).unwrap();
+        assert_eq!(calls.get(), 900);
+        assert_eq!(mapped.memory_report().encoding, "dictionary");
+        assert_eq!(mapped.decode_strings().unwrap(), expected);
+    }
+}
@@ -0,0 +1,226 @@
+use std::str;
+use super::*;
+use errors::QueryError;
+
+/// Checks that the data sections of a string column are well formed, so that decoding them can neither read out of
+/// bounds nor produce strings that are not valid UTF-8. Decoding skips these checks for speed, which means columns
+/// read from disk have to pass this before they can be queried. Columns that don't hold strings are not checked.
+pub fn validate_string_column(column: &Column) -> Result<(), QueryError> {
+    let codec = column.codec();
+    let ops = codec.ops();
+    let last = match ops.last() {
+        Some(&op) => op,
+        None => return Ok(()),
+    };
+    if is_dictionary_lookup(&last) {
+        return validate_dictionary_column(column);
+    }
+    let validate: fn(&[u8]) -> Option<usize> = match last {
+        CodecOp::UnpackStrings => validate_packed_strings,
+        CodecOp::UnpackFrontCodedStrings(_) => validate_front_coded_strings,
+        CodecOp::UnpackSymbolCodedStrings(_) => validate_symbol_coded_strings,
+        CodecOp::UnpackComponentCodedStrings(_) => validate_component_coded_strings,
+        CodecOp::UnpackHybridStrings => validate_hybrid_strings,
+        CodecOp::UnhexpackStrings(..) => validate_packed_bytes,
+        _ => return Ok(()),
+    };
+    ensure!(ops.len() == 1 || ops.len() == 2 && is_lz4(&ops[0]), "Unexpected codec {:?} for string column {}", ops, column.name());
+    ensure!(!column.data().is_empty(), "String column {} has no data section", column.name());
+    check_encoded_type(column, EncodingType::U8)?;
+    let data = encoded_section(column);
+    let data = u8_section(&data);
+    let count = validate(data);
+    ensure!(count == Some(column.len()),
+            "Corrupt data in string column {}: expected {} values, found {:?}", column.name(), column.len(), count);
+    if last == CodecOp::UnpackStrings && column.data().len() > 1 {
+        validate_offset_index(column, data)?;
+    }
+    Ok(())
+}
+
+fn validate_dictionary_column(column: &Column) -> Result<(), QueryError> {
+    let name = column.name();
+    ensure!(column.data().len() >= 3, "Dictionary encoded column {} has {} data sections", name, column.data().len());
+    let offset_len: &[u64] = match column.data()[1] {
+        DataSection::U64(ref offset_len) => offset_len,
+        DataSection::Mapped(MappedSection::U64(ref offset_len)) => offset_len,
+        ref section => bail!(QueryError::FatalError, "Unexpected dictionary section {:?} in column {}", section, name),
+    };
+    let data = match column.data()[2] {
+        DataSection::U8(ref data) => &data[..],
+        DataSection::Mapped(MappedSection::U8(ref data)) => &data[..],
+        ref section => bail!(QueryError::FatalError, "Unexpected dictionary data {:?} in column {}", section, name),
+    };
+    for (code, &ol) in offset_len.iter().enumerate() {
+        let offset = (ol >> 24) as usize;
+        let len = (ol & 0xff_ffff) as usize;
+        ensure!(offset + len <= data.len(),
+                "Dictionary entry {} of column {} ends at {}, past the end of the dictionary data", code, name, offset + len);
+        ensure!(str::from_utf8(&data[offset..(offset + len)]).is_ok(),
+                "Dictionary entry {} of column {} is not valid UTF-8", code, name);
+    }
+    let entries = offset_len.len();
+    if let Some(null_code) = column.null_code() {
+        ensure!((null_code as usize) < entries, "Null code {} out of range for column {}", null_code, name);
+    }
+
+    let codec = column.codec();
+    let mut ops = codec.ops();
+    if ops.first().map_or(false, is_lz4) {
+        ops = &ops[1..];
+    }
+    match ops {
+        [CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), _] => {
+            check_encoded_type(column, EncodingType::U32)?;
+        }
+        [CodecOp::PushDataSection(3), CodecOp::RunLengthDecode(_, len), CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), _] => {
+            ensure!(*len == column.len(), "Run length decoded length {} of column {} does not match its length {}", len, name, column.len());
+            check_encoded_type(column, EncodingType::U32)?;
+            let lengths: &[u32] = match column.data().get(3) {
+                Some(&DataSection::U32(ref lengths)) => lengths,
+                Some(&DataSection::Mapped(MappedSection::U32(ref lengths))) => lengths,
+                section => bail!(QueryError::FatalError, "Unexpected run length section {:?} in column {}", section, name),
+            };
+            ensure!(lengths.len() == encoded_section(column).len(),
+                    "Column {} has {} runs but {} run values", name, lengths.len(), encoded_section(column).len());
+            let total = lengths.iter().map(|&length| length as u64).sum::<u64>();
+            ensure!(total == column.len() as u64, "Runs of column {} cover {} rows instead of {}", name, total, column.len());
+        }
+        [CodecOp::UnpackBits(t, bits, len), CodecOp::PushDataSection(1), CodecOp::PushDataSection(2), _] => {
+            ensure!(*bits > 0 && *bits <= 32, "Invalid number of bits {} per code in column {}", bits, name);
+            ensure!(*len == column.len(), "Bit packed length {} of column {} does not match its length {}", len, name, column.len());
+            ensure!(*t == EncodingType::U8 || *t == EncodingType::U16 || *t == EncodingType::U32,
+                    "Unexpected bit packed type {:?} in column {}", t, name);
+            check_encoded_type(column, EncodingType::U64)?;
+            let words = encoded_section(column).len();
+            ensure!(words as u64 * 64 >= column.len() as u64 * u64::from(*bits),
+                    "Column {} has {} bit packed words, too few for {} codes of {} bits", name, words, len, bits);
+        }
+        _ => bail!(QueryError::FatalError, "Unexpected codec {:?} for dictionary encoded column {}", column.codec().ops(), name),
+    }
+
+    let mut rows = 0;
+    for code in column_codes(column) {
+        ensure!((code as usize) < entries, "Code {} out of range for dictionary of length {} in column {}", code, entries, name);
+        rows += 1;
+    }
+    ensure!(rows == column.len(), "Column {} has {} codes but {} rows", name, rows, column.len());
+    Ok(())
+}
+
+fn is_lz4(op: &CodecOp) -> bool {
+    if let CodecOp::LZ4(..) = *op { true } else { false }
+}
+
+/// Checks that the first data section has a type `encoded_section` can decode, and that codes or packed values fit
+/// into `max_type`.
+fn check_encoded_type(column: &Column, max_type: EncodingType) -> Result<(), QueryError> {
+    let section = &column.data()[0];
+    let encoded_type = match column.codec().ops().first() {
+        Some(&CodecOp::LZ4(t, len)) => {
+            ensure!(section.encoding_type() == EncodingType::U8, "LZ4 compressed section of column {} is not bytes", column.name());
+            check_lz4_frame(column, t, len)?;
+            t
+        }
+        _ => section.encoding_type(),
+    };
+    let valid = match (max_type, encoded_type) {
+        (EncodingType::U8, EncodingType::U8) => true,
+        (EncodingType::U32, EncodingType::U8) | (EncodingType::U32, EncodingType::U16) | (EncodingType::U32, EncodingType::U32) => true,
+        (EncodingType::U64, EncodingType::U64) => true,
+        _ => false,
+    };
+    ensure!(valid, "Unexpected first data section of type {:?} in column {}", encoded_type, column.name());
+    Ok(())
+}
+
+#[cfg(feature = "enable_lz4")]
+fn check_lz4_frame(column: &Column, t: EncodingType, len: usize) -> Result<(), QueryError> {
+    let element_size = match t {
+        EncodingType::U8 => 1,
+        EncodingType::U16 => 2,
+        EncodingType::U32 => 4,
+        EncodingType::U64 | EncodingType::I64 => 8,
+        _ => bail!(QueryError::FatalError, "Unexpected LZ4 decoded type {:?} in column {}", t, column.name()),
+    };
+    let compressed = u8_section(&column.data()[0]);
+    match lz4::decompressed_len(compressed) {
+        Ok(decompressed) => ensure!(decompressed == len as u64 * element_size,
+                                    "LZ4 section of column {} decompresses to {} bytes instead of {}",
+                                    column.name(), decompressed, len as u64 * element_size),
+        Err(err) => bail!(QueryError::FatalError, "Corrupt LZ4 section in column {}: {}", column.name(), err),
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "enable_lz4"))]
+fn check_lz4_frame(column: &Column, _: EncodingType, _: usize) -> Result<(), QueryError> {
+    bail!(QueryError::NotImplemented, "Column {} is LZ4 compressed, recompile with --features enable_lz4", column.name())
+}
+
+/// The offset index has to point at the start of every `stride`th value, otherwise seeking to a row decodes from the
+/// middle of a value.
+fn validate_offset_index(column: &Column, data: &[u8]) -> Result<(), QueryError> {
+    let index: &[u32] = match column.data()[1] {
+        DataSection::U32(ref index) => index,
+        DataSection::Mapped(MappedSection::U32(ref index)) => index,
+        ref section => bail!(QueryError::FatalError, "Unexpected offset index {:?} in column {}", section, column.name()),
+    };
+    let (&stride, offsets) = match index.split_first() {
+        Some(split) => split,
+        None => bail!(QueryError::FatalError, "Empty offset index in column {}", column.name()),
+    };
+    ensure!(stride > 0, "Offset index of column {} has stride 0", column.name());
+    let expected = chunk_offsets(data, stride as usize);
+    ensure!(offsets.len() + 1 == expected.len() && offsets.iter().zip(&expected).all(|(&a, &b)| a as usize == b),
+            "Offset index of column {} does not match its data", column.name());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::tests::*;
+
+    fn corrupt(column: &Column, section: usize, f: impl FnOnce(&mut Vec<u8>)) -> Column {
+        let mut sections = column.data().to_vec();
+        match sections[section] {
+            DataSection::U8(ref mut data) => f(data),
+            ref section => panic!("Unexpected section {:?}", section),
+        }
+        Column::new(column.name(), column.len(), column.range(), column.codec().ops().to_vec(), sections)
+    }
+
+    #[test]
+    fn test_validate_dictionary_column() {
+        let column = build_column(&["a".to_string(), "bc".to_string(), "a".to_string()]);
+        assert!(is_dictionary_encoded(&column));
+        assert!(validate_string_column(&column).is_ok());
+
+        let invalid_utf8 = corrupt(&column, 2, |data| data[0] = 0xff);
+        assert!(validate_string_column(&invalid_utf8).is_err());
+        let truncated = corrupt(&column, 2, |data| { data.pop(); });
+        assert!(validate_string_column(&truncated).is_err());
+        let bad_code = corrupt(&column, 0, |codes| codes[1] = 7);
+        assert!(validate_string_column(&bad_code).is_err());
+    }
+
+    #[test]
+    fn test_validate_packed_column() {
+        let mut packed = PackedStrings::default();
+        for i in 0..200 {
+            packed.push(&format!("value{}", i));
+        }
+        let options = StringColumnOptions::default().with_offset_index_stride(16);
+        let column = Column::new("s", 200, None, string_pack_codec(), packed_sections(packed.into_vec(), 200, &options));
+        assert!(has_offset_index(&column));
+        assert!(validate_string_column(&column).is_ok());
+
+        let invalid_utf8 = corrupt(&column, 0, |data| data[1] = 0xff);
+        assert!(validate_string_column(&invalid_utf8).is_err());
+        let truncated = corrupt(&column, 0, |data| { data.pop(); });
+        assert!(validate_string_column(&truncated).is_err());
+        let bad_varint = corrupt(&column, 0, |data| data[0] = 0xff);
+        assert!(validate_string_column(&bad_varint).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use std::cmp;
 use std::collections::HashMap;
 use std::mem;
 use std::ops::DerefMut;
@@ -86,6 +87,29 @@ impl Table {
         self.batch_if_needed(&mut buffer);
     }
 
+    /// Values of the rows selected by `filter` in the named columns. `filter` covers the rows of all partitions in the
+    /// order of their ids, and the rows selected from each partition are gathered from all columns at once, see
+    /// `Partition::project`. Partitions without selected rows are skipped. Returns `None` if any of the columns of the
+    /// other partitions is not resident or not a string column.
+    pub fn project(&self, filter: &[u8], columns: &[&str]) -> Option<Vec<Vec<Option<String>>>> {
+        let mut partitions = self.snapshot();
+        partitions.sort_by_key(|partition| partition.id());
+        let mut projected = vec![Vec::new(); columns.len()];
+        let mut offset = 0;
+        for partition in partitions {
+            let start = cmp::min(offset, filter.len());
+            let end = cmp::min(offset + partition.len(), filter.len());
+            offset += partition.len();
+            if !filter[start..end].iter().any(|&selected| selected != 0) {
+                continue;
+            }
+            for (values, partition_values) in projected.iter_mut().zip(partition.project(&filter[start..end], columns)?) {
+                values.extend(partition_values);
+            }
+        }
+        Some(projected)
+    }
+
     pub fn load_partition(&self, partition: Partition) {
         let mut partitions = self.partitions.write().unwrap();
         partitions.insert(partition.id(), Arc::new(partition));
@@ -145,6 +169,7 @@ impl Table {
             buffer_length: buffer.len(),
             buffer_bytes: buffer.heap_size_of_children(),
             size_per_column,
+            memory_per_column: Table::memory_per_column(&partitions),
         }
     }
 
@@ -162,6 +187,21 @@ impl Table {
         }
         sizes.iter().map(|(name, size)| (name.to_string(), *size)).collect()
     }
+
+    /// Sums the memory reports of all resident columns by column name and encoding.
+    fn memory_per_column(partitions: &[Arc<Partition>]) -> Vec<(String, MemoryReport)> {
+        let mut reports: HashMap<(String, &'static str), MemoryReport> = HashMap::default();
+        for partition in partitions {
+            for (colname, report) in partition.memory_report_per_column() {
+                reports.entry((colname, report.encoding))
+                    .or_insert(MemoryReport { encoding: report.encoding, ..MemoryReport::default() })
+                    .add(&report);
+            }
+        }
+        let mut reports = reports.into_iter().map(|((name, _), report)| (name, report)).collect::<Vec<_>>();
+        reports.sort_by(|a, b| (&a.0, a.1.encoding).cmp(&(&b.0, b.1.encoding)));
+        reports
+    }
 }
 
 fn batch_size_override(batch_size: usize, tablename: &str) -> usize {
@@ -197,6 +237,48 @@ pub struct TableStats {
     pub buffer_length: usize,
     pub buffer_bytes: usize,
     pub size_per_column: Vec<(String, usize)>,
+    /// Memory used by each column broken down by encoding, see `Column::memory_report`.
+    pub memory_per_column: Vec<(String, MemoryReport)>,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mem_store::strings::*;
+
+    #[test]
+    fn test_project() {
+        let lru = LRU::default();
+        let table = Table::new(1000, "test", lru.clone());
+        let mut partitions = Vec::new();
+        for id in 0..3u64 {
+            let values = (0..500).map(|i| if i % 7 == 0 { None } else { Some(format!("{}-{}", id, i % 11)) }).collect::<Vec<_>>();
+            let strs = values.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+            let dict = build_string_column_from_strs("dict", &strs, &StringColumnOptions::default());
+            let packed = build_string_column_from_strs(
+                "packed", &strs, &StringColumnOptions::default().with_max_unique_strings(0).with_offset_index_stride(16));
+            // Column order differs between partitions and the last partition has no dict column
+            let cols = match id {
+                0 => vec![dict, packed],
+                1 => vec![packed, dict],
+                _ => vec![packed],
+            };
+            partitions.push(cols.clone());
+            table.load_partition(Partition::new(id, cols, lru.clone()).0);
+        }
 
+        let filter = (0..1500).map(|i| (i % 97 == 3 || (i > 1000 && i % 5 == 0)) as u8).collect::<Vec<_>>();
+        let projected = table.project(&filter, &["packed", "dict"]).unwrap();
+        for (i, name) in ["packed", "dict"].iter().enumerate() {
+            let mut expected = Vec::new();
+            for (cols, filter) in partitions.iter().zip(filter.chunks(500)) {
+                match cols.iter().find(|c| c.name() == *name) {
+                    Some(column) => expected.extend(filter_decode(column, filter).unwrap().into_iter().map(|s| s.map(|s| s.into_owned()))),
+                    None => expected.extend(filter.iter().filter(|&&selected| selected != 0).map(|_| None)),
+                }
+            }
+            assert_eq!(projected[i], expected, "{}", name);
+        }
+        assert_eq!(table.project(&[], &["packed"]), Some(vec![vec![]]));
+    }
+}
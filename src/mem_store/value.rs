@@ -11,6 +11,12 @@ pub enum Val<'a> {
     Str(&'a str),
 }
 
+impl<'a> Val<'a> {
+    /// Owned copy of the value for results that outlive the column the value borrows from.
+    pub fn to_raw(&self) -> RawVal {
+        RawVal::from(self)
+    }
+}
 
 impl<'a> fmt::Display for Val<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -68,6 +74,17 @@ impl<'a, T> From<Option<T>> for Val<'a>
     }
 }
 
+/// Borrows the string of `RawVal::Str` instead of cloning it.
+impl<'a> From<&'a RawVal> for Val<'a> {
+    fn from(raw: &'a RawVal) -> Val<'a> {
+        match *raw {
+            RawVal::Int(i) => Val::Integer(i),
+            RawVal::Str(ref s) => Val::Str(s),
+            RawVal::Null => Val::Null,
+        }
+    }
+}
+
 impl<'a, 'b> From<&'a Val<'b>> for RawVal {
     fn from(val: &Val) -> RawVal {
         match *val {
@@ -1,7 +1,25 @@
-use std::rc::Rc;
+use std::cell::Cell;
+use std::cmp::{self, Reverse};
+use std::collections::HashMap;
+use std::fmt;
 use std::str;
+use std::sync::Arc;
 
-#[derive(Default)]
+/// Strings that can be packed, implemented for both shared and borrowed strings so that columns can be built without
+/// wrapping every borrowed value in an `Arc`.
+pub trait AsStr {
+    fn as_str(&self) -> &str;
+}
+
+impl AsStr for Arc<String> {
+    fn as_str(&self) -> &str { self }
+}
+
+impl<'a> AsStr for &'a str {
+    fn as_str(&self) -> &str { self }
+}
+
+#[derive(Clone, Default)]
 pub struct IndexedPackedStrings {
     data: Vec<u64>,
     backing_store: Vec<u8>,
@@ -10,7 +28,8 @@ pub struct IndexedPackedStrings {
 impl IndexedPackedStrings {
     pub fn push(&mut self, elem: &str) {
         let bytes = elem.as_bytes();
-        // TODO(clemens): overflow
+        assert!(bytes.len() < 1 << 24, "String of {} bytes exceeds the maximum length of dictionary entries", bytes.len());
+        assert!(self.backing_store.len() < 1 << 40, "Dictionary exceeds the maximum size of 1TiB");
         self.data.push(((self.backing_store.len() << 24) + bytes.len()) as u64);
         self.backing_store.extend_from_slice(bytes);
     }
@@ -37,18 +56,37 @@ impl IndexedPackedStrings {
     }
 }
 
+/// Each value is prefixed by a LEB128 varint holding `len + 1`, with a length of 0 representing null.
+#[derive(HeapSizeOf)]
 pub struct PackedStrings {
     data: Vec<u8>,
+    /// Number of values, which can't be determined from `data` without scanning all length prefixes.
+    len: usize,
+    /// Whether all values are known to be valid UTF-8.
+    validated: bool,
+    /// Byte offset of every `offset_stride`th value if enabled with `with_offset_index`, which requires `data` to
+    /// be smaller than 4GiB.
+    offset_index: Option<Vec<u32>>,
+    offset_stride: usize,
+}
+
+impl Default for PackedStrings {
+    fn default() -> PackedStrings {
+        PackedStrings::new(true)
+    }
 }
 
-// TODO(clemens): encode using variable size length + special value to represent null
 impl PackedStrings {
-    pub fn from_nullable_strings(strings: &[Option<Rc<String>>]) -> PackedStrings {
-        let mut sp = PackedStrings { data: Vec::new() };
+    fn new(validated: bool) -> PackedStrings {
+        PackedStrings { data: Vec::new(), len: 0, validated, offset_index: None, offset_stride: 0 }
+    }
+
+    pub fn from_nullable_strings<T: AsStr>(strings: &[Option<T>]) -> PackedStrings {
+        let mut sp = PackedStrings::new(true);
         for string in strings {
             match *string {
-                Some(ref string) => sp.push(string),
-                None => sp.push(""),
+                Some(ref string) => sp.push(string.as_str()),
+                None => sp.push_null(),
             }
         }
         sp.shrink_to_fit();
@@ -56,7 +94,7 @@ impl PackedStrings {
     }
 
     pub fn from_iterator<'a>(strings: impl Iterator<Item=&'a str>) -> PackedStrings {
-        let mut sp = PackedStrings { data: Vec::new() };
+        let mut sp = PackedStrings::new(true);
         for string in strings {
             sp.push(string);
         }
@@ -64,49 +102,1085 @@ impl PackedStrings {
         sp
     }
 
+    /// Validates untrusted input once on ingest, invalid UTF-8 sequences are replaced lossily.
+    pub fn from_strings_checked<'a>(strings: impl Iterator<Item=&'a [u8]>) -> PackedStrings {
+        let mut sp = PackedStrings::new(true);
+        for bytes in strings {
+            sp.push(&String::from_utf8_lossy(bytes));
+        }
+        sp.shrink_to_fit();
+        sp
+    }
+
+    /// Packs raw bytes without validation, values are checked during iteration instead.
+    pub fn from_bytes<'a>(strings: impl Iterator<Item=&'a [u8]>) -> PackedStrings {
+        let mut sp = PackedStrings::new(false);
+        for bytes in strings {
+            sp.push_bytes(bytes);
+        }
+        sp.shrink_to_fit();
+        sp
+    }
+
     pub fn push(&mut self, string: &str) {
-        for &byte in string.as_bytes().iter() {
-            self.data.push(byte);
+        self.push_bytes(string.as_bytes());
+    }
+
+    /// Records the offset of every `stride`th value, which allows `take` and `decode_range` to seek to any value by
+    /// skipping at most `stride - 1` values. Smaller strides seek faster but require more memory.
+    pub fn with_offset_index(mut self, stride: usize) -> PackedStrings {
+        assert!(stride > 0, "Offset index stride must be positive");
+        let mut offsets = chunk_offsets(&self.data, stride);
+        offsets.truncate((self.len + stride - 1) / stride);
+        self.offset_index = Some(offsets.into_iter().map(index_offset).collect());
+        self.offset_stride = stride;
+        self
+    }
+
+    fn push_bytes(&mut self, bytes: &[u8]) {
+        self.index_next();
+        push_varint(&mut self.data, bytes.len() as u64 + 1);
+        self.data.extend_from_slice(bytes);
+        self.len += 1;
+    }
+
+    pub fn push_null(&mut self) {
+        self.index_next();
+        push_varint(&mut self.data, 0);
+        self.len += 1;
+    }
+
+    fn index_next(&mut self) {
+        if let Some(ref mut offsets) = self.offset_index {
+            if self.len % self.offset_stride == 0 {
+                offsets.push(index_offset(self.data.len()));
+            }
         }
-        self.data.push(0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
     }
 
     pub fn shrink_to_fit(&mut self) {
         self.data.shrink_to_fit();
     }
 
+    pub fn is_validated(&self) -> bool {
+        self.validated
+    }
+
+    /// Number of values that are not valid UTF-8 and decode to their longest valid prefix.
+    pub fn invalid_values(&self) -> usize {
+        if self.validated { return 0; }
+        let mut iter = self.iter();
+        while iter.next().is_some() {}
+        iter.invalid_values()
+    }
+
+    pub fn iter(&self) -> StringPackerIterator {
+        StringPackerIterator::new(&self.data, self.validated).with_len(self.len)
+    }
+
+    /// Values at positions `indices`, in that order. Panics if any index is out of range.
+    pub fn take(&self, indices: &[usize]) -> Vec<Option<&str>> {
+        indices.iter().map(|&i| {
+            assert!(i < self.len, "Index {} out of range for {} packed strings", i, self.len);
+            self.iter_from(i).next().unwrap()
+        }).collect()
+    }
+
+    /// The `len` values starting at position `start`. Panics if the range is out of bounds.
+    pub fn decode_range(&self, start: usize, len: usize) -> Vec<Option<&str>> {
+        assert!(start + len <= self.len, "Range {}..{} out of range for {} packed strings", start, start + len, self.len);
+        if len == 0 { return Vec::new(); }
+        self.iter_from(start).take(len).collect()
+    }
+
+    /// Iterates over the values starting at position `row`, which must be in range.
+    fn iter_from(&self, row: usize) -> StringPackerIterator {
+        let (offset, skip) = match self.offset_index {
+            Some(ref offsets) => (offsets[row / self.offset_stride] as usize, row % self.offset_stride),
+            None => (0, row),
+        };
+        let mut iter = StringPackerIterator::new(&self.data[offset..], self.validated).with_len(self.len - row + skip);
+        iter.skip_values(skip);
+        iter
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+fn index_offset(offset: usize) -> u32 {
+    assert!(offset <= u32::MAX as usize, "Offset index does not support more than 4GiB of packed strings");
+    offset as u32
+}
+
+impl fmt::Debug for PackedStrings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let len = self.len;
+        let head = self.iter().take(DEBUG_SAMPLE_VALUES).collect::<Vec<_>>();
+        let mut tail = self.iter().rev().take(cmp::min(DEBUG_SAMPLE_VALUES, len - head.len())).collect::<Vec<_>>();
+        tail.reverse();
+        f.debug_struct("PackedStrings")
+            .field("len", &len)
+            .field("bytes", &self.data.len())
+            .field("validated", &self.validated)
+            .field("values", &format_args!("{}", debug_sample(&head, &tail, len)))
+            .finish()
+    }
+}
+
+/// Number of values shown at the start and end of a column by `Debug` implementations.
+pub const DEBUG_SAMPLE_VALUES: usize = 3;
+/// Strings longer than this are truncated by `debug_sample`.
+const DEBUG_MAX_CHARS: usize = 32;
+
+/// Formats the first and last values of a column of `len` values, eliding the values in between and truncating long
+/// strings so the output stays short even for huge columns.
+pub fn debug_sample(head: &[Option<&str>], tail: &[Option<&str>], len: usize) -> String {
+    let format = |value: &Option<&str>| match *value {
+        Some(string) if string.chars().count() > DEBUG_MAX_CHARS =>
+            format!("{:?}…", string.chars().take(DEBUG_MAX_CHARS).collect::<String>()),
+        Some(string) => format!("{:?}", string),
+        None => "null".to_string(),
+    };
+    let mut values = head.iter().map(&format).collect::<Vec<_>>();
+    let elided = len - head.len() - tail.len();
+    if elided > 0 {
+        values.push(format!("… {} more …", elided));
+    }
+    values.extend(tail.iter().map(&format));
+    format!("[{}]", values.join(", "))
+}
+
+/// Front coding for sorted strings. Each value is stored as a varint holding the length of the prefix shared
+/// with the previous non-null value, followed by a varint holding `len + 1` of the remaining suffix
+/// (0 representing null) and the suffix bytes.
+pub struct FrontCodedStrings {
+    data: Vec<u8>,
+    prev: Vec<u8>,
+    total_bytes: usize,
+}
+
+impl FrontCodedStrings {
+    pub fn from_nullable_strings<T: AsStr>(strings: &[Option<T>]) -> FrontCodedStrings {
+        let mut fcs = FrontCodedStrings { data: Vec::new(), prev: Vec::new(), total_bytes: 0 };
+        for string in strings {
+            match *string {
+                Some(ref string) => fcs.push(string.as_str()),
+                None => fcs.push_null(),
+            }
+        }
+        fcs.data.shrink_to_fit();
+        fcs
+    }
+
+    pub fn from_iterator<'a>(strings: impl Iterator<Item=&'a str>) -> FrontCodedStrings {
+        let mut fcs = FrontCodedStrings { data: Vec::new(), prev: Vec::new(), total_bytes: 0 };
+        for string in strings {
+            fcs.push(string);
+        }
+        fcs.data.shrink_to_fit();
+        fcs
+    }
+
+    pub fn push(&mut self, string: &str) {
+        let bytes = string.as_bytes();
+        let prefix_len = self.prev.iter().zip(bytes).take_while(|&(a, b)| a == b).count();
+        push_varint(&mut self.data, prefix_len as u64);
+        push_varint(&mut self.data, (bytes.len() - prefix_len) as u64 + 1);
+        self.data.extend_from_slice(&bytes[prefix_len..]);
+        self.prev.truncate(prefix_len);
+        self.prev.extend_from_slice(&bytes[prefix_len..]);
+        self.total_bytes += bytes.len();
+    }
+
+    pub fn push_null(&mut self) {
+        push_varint(&mut self.data, 0);
+        push_varint(&mut self.data, 0);
+    }
+
+    /// Combined length of all decoded values.
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
     pub fn into_vec(self) -> Vec<u8> {
         self.data
     }
 }
 
+/// Decodes values one at a time, see `FrontCodedIterator`, `SymbolCodedIterator` and `ComponentCodedIterator`.
+pub trait CodedValues {
+    fn next_value(&mut self) -> Option<Option<&str>>;
+
+    /// Advances past the next `count` values, doing only as much work as the encoding requires to find the next value.
+    fn skip_values(&mut self, count: usize);
+}
+
+/// Reconstructs front coded values. Returned values borrow from the iterator so this can't implement `Iterator`.
+pub struct FrontCodedIterator<'a> {
+    data: &'a [u8],
+    curr_index: usize,
+    prev: Vec<u8>,
+}
+
+impl<'a> FrontCodedIterator<'a> {
+    /// `data` must be valid encoding for FrontCodedStrings
+    pub unsafe fn from_slice(data: &'a [u8]) -> FrontCodedIterator<'a> {
+        FrontCodedIterator { data, curr_index: 0, prev: Vec::new() }
+    }
+}
+
+impl<'a> CodedValues for FrontCodedIterator<'a> {
+    fn next_value(&mut self) -> Option<Option<&str>> {
+        if self.curr_index >= self.data.len() {
+            return None;
+        }
+
+        let prefix_len = read_varint(self.data, &mut self.curr_index) as usize;
+        let len = read_varint(self.data, &mut self.curr_index) as usize;
+        if len == 0 {
+            return Some(None);
+        }
+        let start = self.curr_index;
+        self.curr_index += len - 1;
+        self.prev.truncate(prefix_len);
+        self.prev.extend_from_slice(&self.data[start..self.curr_index]);
+        Some(Some(unsafe { str::from_utf8_unchecked(&self.prev) }))
+    }
+
+    /// Values are reconstructed from the previous value, so skipped values still have to be applied to `prev`.
+    fn skip_values(&mut self, count: usize) {
+        for _ in 0..count {
+            if self.curr_index >= self.data.len() { return; }
+            let prefix_len = read_varint(self.data, &mut self.curr_index) as usize;
+            let len = read_varint(self.data, &mut self.curr_index) as usize;
+            if len > 0 {
+                let start = self.curr_index;
+                self.curr_index += len - 1;
+                self.prev.truncate(prefix_len);
+                self.prev.extend_from_slice(&self.data[start..self.curr_index]);
+            }
+        }
+    }
+}
+
+const ESCAPE: u8 = 255;
+const MAX_SYMBOL_LEN: usize = 8;
+
+/// Table of up to 255 frequent byte sequences that are each encoded as a single byte code, similar to FSST.
+/// Bytes not covered by any symbol are stored as `ESCAPE` followed by the literal byte.
+pub struct SymbolTable {
+    symbols: Vec<Vec<u8>>,
+    codes: HashMap<Vec<u8>, u8>,
+}
+
+impl SymbolTable {
+    fn new(symbols: Vec<Vec<u8>>) -> SymbolTable {
+        assert!(symbols.len() <= ESCAPE as usize);
+        let codes = symbols.iter().enumerate().map(|(i, s)| (s.clone(), i as u8)).collect();
+        SymbolTable { symbols, codes }
+    }
+
+    /// Picks the substrings which save the most bytes when replaced by a single byte code.
+    pub fn learn<'a>(sample: impl Iterator<Item=&'a [u8]>) -> SymbolTable {
+        let mut counts: HashMap<&[u8], usize> = HashMap::new();
+        for bytes in sample {
+            for start in 0..bytes.len() {
+                for len in 2..(cmp::min(MAX_SYMBOL_LEN, bytes.len() - start) + 1) {
+                    *counts.entry(&bytes[start..(start + len)]).or_insert(0) += 1;
+                }
+            }
+        }
+        let mut candidates = counts.into_iter().filter(|&(_, count)| count > 1).collect::<Vec<_>>();
+        candidates.sort_by_key(|&(symbol, count)| (Reverse(count * (symbol.len() - 1)), symbol));
+        SymbolTable::new(candidates.into_iter().take(ESCAPE as usize).map(|(symbol, _)| symbol.to_vec()).collect())
+    }
+
+    pub fn encode(&self, bytes: &[u8], output: &mut Vec<u8>) {
+        let mut i = 0;
+        while i < bytes.len() {
+            let max_len = cmp::min(MAX_SYMBOL_LEN, bytes.len() - i);
+            let longest_match = (2..(max_len + 1)).rev()
+                .filter_map(|len| self.codes.get(&bytes[i..(i + len)]).map(|&code| (len, code)))
+                .next();
+            match longest_match {
+                Some((len, code)) => {
+                    output.push(code);
+                    i += len;
+                }
+                None => {
+                    output.push(ESCAPE);
+                    output.push(bytes[i]);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    pub fn decode(&self, encoded: &[u8], output: &mut Vec<u8>) {
+        let mut i = 0;
+        while i < encoded.len() {
+            if encoded[i] == ESCAPE {
+                output.push(encoded[i + 1]);
+                i += 2;
+            } else {
+                output.extend_from_slice(&self.symbols[encoded[i] as usize]);
+                i += 1;
+            }
+        }
+    }
+
+    fn write(&self, data: &mut Vec<u8>) {
+        data.push(self.symbols.len() as u8);
+        for symbol in &self.symbols {
+            data.push(symbol.len() as u8);
+            data.extend_from_slice(symbol);
+        }
+    }
+
+    fn read(data: &[u8], index: &mut usize) -> SymbolTable {
+        let count = data[*index] as usize;
+        *index += 1;
+        let mut symbols = Vec::with_capacity(count);
+        for _ in 0..count {
+            let len = data[*index] as usize;
+            symbols.push(data[(*index + 1)..(*index + 1 + len)].to_vec());
+            *index += 1 + len;
+        }
+        SymbolTable::new(symbols)
+    }
+}
+
+/// Strings compressed with a `SymbolTable` which is stored at the start of the data.
+/// Values are framed like `PackedStrings`, with the length referring to the encoded bytes.
+pub struct SymbolCodedStrings {
+    data: Vec<u8>,
+    table: SymbolTable,
+    buffer: Vec<u8>,
+    total_bytes: usize,
+}
+
+impl SymbolCodedStrings {
+    fn new(table: SymbolTable) -> SymbolCodedStrings {
+        let mut data = Vec::new();
+        table.write(&mut data);
+        SymbolCodedStrings { data, table, buffer: Vec::new(), total_bytes: 0 }
+    }
+
+    pub fn from_nullable_strings<T: AsStr>(strings: &[Option<T>], table: SymbolTable) -> SymbolCodedStrings {
+        let mut scs = SymbolCodedStrings::new(table);
+        for string in strings {
+            match *string {
+                Some(ref string) => scs.push(string.as_str()),
+                None => push_varint(&mut scs.data, 0),
+            }
+        }
+        scs.data.shrink_to_fit();
+        scs
+    }
+
+    pub fn from_iterator<'a>(strings: impl Iterator<Item=&'a str>, table: SymbolTable) -> SymbolCodedStrings {
+        let mut scs = SymbolCodedStrings::new(table);
+        for string in strings {
+            scs.push(string);
+        }
+        scs.data.shrink_to_fit();
+        scs
+    }
+
+    pub fn push(&mut self, string: &str) {
+        self.buffer.clear();
+        self.table.encode(string.as_bytes(), &mut self.buffer);
+        push_varint(&mut self.data, self.buffer.len() as u64 + 1);
+        self.data.extend_from_slice(&self.buffer);
+        self.total_bytes += string.len();
+    }
+
+    /// Combined length of all decoded values.
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+/// Decompresses symbol coded values. Returned values borrow from the iterator so this can't implement `Iterator`.
+pub struct SymbolCodedIterator<'a> {
+    data: &'a [u8],
+    curr_index: usize,
+    table: SymbolTable,
+    buffer: Vec<u8>,
+}
+
+impl<'a> SymbolCodedIterator<'a> {
+    /// `data` must be valid encoding for SymbolCodedStrings
+    pub unsafe fn from_slice(data: &'a [u8]) -> SymbolCodedIterator<'a> {
+        let mut curr_index = 0;
+        let table = SymbolTable::read(data, &mut curr_index);
+        SymbolCodedIterator { data, curr_index, table, buffer: Vec::new() }
+    }
+}
+
+impl<'a> CodedValues for SymbolCodedIterator<'a> {
+    fn next_value(&mut self) -> Option<Option<&str>> {
+        if self.curr_index >= self.data.len() {
+            return None;
+        }
+
+        let len = read_varint(self.data, &mut self.curr_index) as usize;
+        if len == 0 {
+            return Some(None);
+        }
+        let start = self.curr_index;
+        self.curr_index += len - 1;
+        self.buffer.clear();
+        self.table.decode(&self.data[start..self.curr_index], &mut self.buffer);
+        Some(Some(unsafe { str::from_utf8_unchecked(&self.buffer) }))
+    }
+
+    fn skip_values(&mut self, count: usize) {
+        for _ in 0..count {
+            if self.curr_index >= self.data.len() { return; }
+            let len = read_varint(self.data, &mut self.curr_index) as usize;
+            self.curr_index += len.saturating_sub(1);
+        }
+    }
+}
+
+/// Strings that consist of components delimited by a separator, e.g. paths or URLs, stored as sequences of codes into a
+/// dictionary of all distinct components. The separator and the dictionary are stored at the start of the data.
+/// Each value is stored as the varint number of components plus one, 0 for null, followed by a varint for each code.
+pub struct ComponentCodedStrings {
+    separator: u8,
+    components: Vec<String>,
+    codes: HashMap<String, u32>,
+    values: Vec<u8>,
+    total_components: usize,
+    total_bytes: usize,
+}
+
+impl ComponentCodedStrings {
+    /// `separator` must be an ASCII character.
+    pub fn new(separator: u8) -> ComponentCodedStrings {
+        assert!(separator < 0x80, "Separator must be an ASCII character");
+        ComponentCodedStrings {
+            separator,
+            components: Vec::new(),
+            codes: HashMap::new(),
+            values: Vec::new(),
+            total_components: 0,
+            total_bytes: 0,
+        }
+    }
+
+    pub fn from_nullable_strings<T: AsStr>(strings: &[Option<T>], separator: u8) -> ComponentCodedStrings {
+        let mut ccs = ComponentCodedStrings::new(separator);
+        for string in strings {
+            match *string {
+                Some(ref string) => ccs.push(string.as_str()),
+                None => ccs.push_null(),
+            }
+        }
+        ccs
+    }
+
+    pub fn push(&mut self, string: &str) {
+        // Splitting on an ASCII character always yields valid UTF-8
+        let components = string.split(self.separator as char).collect::<Vec<_>>();
+        push_varint(&mut self.values, components.len() as u64 + 1);
+        self.total_components += components.len();
+        for component in components {
+            let code = match self.codes.get(component) {
+                Some(&code) => code,
+                None => {
+                    let code = self.components.len() as u32;
+                    self.components.push(component.to_string());
+                    self.codes.insert(component.to_string(), code);
+                    code
+                }
+            };
+            push_varint(&mut self.values, u64::from(code));
+        }
+        self.total_bytes += string.len();
+    }
+
+    pub fn push_null(&mut self) {
+        push_varint(&mut self.values, 0);
+    }
+
+    /// Number of distinct components.
+    pub fn component_count(&self) -> usize {
+        self.components.len()
+    }
+
+    /// Average number of occurrences of each distinct component.
+    pub fn component_reuse(&self) -> f64 {
+        if self.components.is_empty() { 0.0 } else { self.total_components as f64 / self.components.len() as f64 }
+    }
+
+    /// Combined length of all decoded values.
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        let mut data = vec![self.separator];
+        push_varint(&mut data, self.components.len() as u64);
+        for component in &self.components {
+            push_varint(&mut data, component.len() as u64);
+            data.extend_from_slice(component.as_bytes());
+        }
+        data.extend_from_slice(&self.values);
+        data.shrink_to_fit();
+        data
+    }
+}
+
+/// Decodes component coded values by joining their components. Returned values borrow from the iterator so this can't
+/// implement `Iterator`.
+pub struct ComponentCodedIterator<'a> {
+    data: &'a [u8],
+    curr_index: usize,
+    separator: u8,
+    components: Vec<&'a [u8]>,
+    buffer: Vec<u8>,
+}
+
+impl<'a> ComponentCodedIterator<'a> {
+    /// `data` must be valid encoding for ComponentCodedStrings
+    pub unsafe fn from_slice(data: &'a [u8]) -> ComponentCodedIterator<'a> {
+        let separator = data[0];
+        let mut curr_index = 1;
+        let count = read_varint(data, &mut curr_index) as usize;
+        let mut components = Vec::with_capacity(count);
+        for _ in 0..count {
+            let len = read_varint(data, &mut curr_index) as usize;
+            components.push(&data[curr_index..(curr_index + len)]);
+            curr_index += len;
+        }
+        ComponentCodedIterator { data, curr_index, separator, components, buffer: Vec::new() }
+    }
+}
+
+impl<'a> CodedValues for ComponentCodedIterator<'a> {
+    fn next_value(&mut self) -> Option<Option<&str>> {
+        if self.curr_index >= self.data.len() {
+            return None;
+        }
+
+        let count = read_varint(self.data, &mut self.curr_index) as usize;
+        if count == 0 {
+            return Some(None);
+        }
+        self.buffer.clear();
+        for i in 0..(count - 1) {
+            if i > 0 {
+                self.buffer.push(self.separator);
+            }
+            let code = read_varint(self.data, &mut self.curr_index) as usize;
+            self.buffer.extend_from_slice(self.components[code]);
+        }
+        Some(Some(unsafe { str::from_utf8_unchecked(&self.buffer) }))
+    }
+
+    fn skip_values(&mut self, count: usize) {
+        for _ in 0..count {
+            if self.curr_index >= self.data.len() { return; }
+            let components = read_varint(self.data, &mut self.curr_index) as usize;
+            for _ in 0..components.saturating_sub(1) {
+                read_varint(self.data, &mut self.curr_index);
+            }
+        }
+    }
+}
+
+/// Strings stored as codes into a dictionary of frequent values, with all other values stored inline after an escape
+/// code. The dictionary is stored at the start of the data. Each value is stored as a varint that is 0 for null, 1 for
+/// a value that is not in the dictionary followed by its varint length and bytes, and the dictionary code plus 2
+/// otherwise.
+pub struct HybridStrings {
+    dictionary: Vec<String>,
+    codes: HashMap<String, u64>,
+    values: Vec<u8>,
+    escaped: usize,
+}
+
+impl HybridStrings {
+    pub fn new<'a>(dictionary: impl Iterator<Item=&'a str>) -> HybridStrings {
+        let dictionary = dictionary.map(|s| s.to_string()).collect::<Vec<_>>();
+        let codes = dictionary.iter().enumerate().map(|(i, s)| (s.clone(), i as u64)).collect();
+        HybridStrings { dictionary, codes, values: Vec::new(), escaped: 0 }
+    }
+
+    pub fn from_nullable_strings<'a, T: AsStr>(strings: &[Option<T>], dictionary: impl Iterator<Item=&'a str>) -> HybridStrings {
+        let mut hybrid = HybridStrings::new(dictionary);
+        for string in strings {
+            match *string {
+                Some(ref string) => hybrid.push(string.as_str()),
+                None => hybrid.push_null(),
+            }
+        }
+        hybrid
+    }
+
+    pub fn push(&mut self, string: &str) {
+        match self.codes.get(string) {
+            Some(&code) => push_varint(&mut self.values, code + 2),
+            None => {
+                push_varint(&mut self.values, 1);
+                push_varint(&mut self.values, string.len() as u64);
+                self.values.extend_from_slice(string.as_bytes());
+                self.escaped += 1;
+            }
+        }
+    }
+
+    pub fn push_null(&mut self) {
+        push_varint(&mut self.values, 0);
+    }
+
+    /// Number of values that are not in the dictionary.
+    pub fn escaped_count(&self) -> usize {
+        self.escaped
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        let mut data = Vec::new();
+        push_varint(&mut data, self.dictionary.len() as u64);
+        for entry in &self.dictionary {
+            push_varint(&mut data, entry.len() as u64);
+            data.extend_from_slice(entry.as_bytes());
+        }
+        data.extend_from_slice(&self.values);
+        data.shrink_to_fit();
+        data
+    }
+}
+
+pub struct HybridIterator<'a> {
+    data: &'a [u8],
+    curr_index: usize,
+    dictionary: Vec<&'a str>,
+}
+
+impl<'a> HybridIterator<'a> {
+    /// `data` must be valid encoding for HybridStrings
+    pub unsafe fn from_slice(data: &'a [u8]) -> HybridIterator<'a> {
+        let mut curr_index = 0;
+        let count = read_varint(data, &mut curr_index) as usize;
+        let mut dictionary = Vec::with_capacity(count);
+        for _ in 0..count {
+            let len = read_varint(data, &mut curr_index) as usize;
+            dictionary.push(str::from_utf8_unchecked(&data[curr_index..(curr_index + len)]));
+            curr_index += len;
+        }
+        HybridIterator { data, curr_index, dictionary }
+    }
+}
+
+impl<'a> Iterator for HybridIterator<'a> {
+    type Item = Option<&'a str>;
+
+    fn next(&mut self) -> Option<Option<&'a str>> {
+        if self.curr_index >= self.data.len() {
+            return None;
+        }
+
+        match read_varint(self.data, &mut self.curr_index) {
+            0 => Some(None),
+            1 => {
+                let len = read_varint(self.data, &mut self.curr_index) as usize;
+                let start = self.curr_index;
+                self.curr_index += len;
+                Some(Some(unsafe { str::from_utf8_unchecked(&self.data[start..self.curr_index]) }))
+            }
+            code => Some(Some(self.dictionary[code as usize - 2])),
+        }
+    }
+}
+
+/// Returns the byte offsets at which every `rows_per_chunk`th value in the `PackedStrings` format starts,
+/// followed by the length of `data`. Each consecutive pair of offsets delimits a chunk that can be decoded independently.
+pub fn chunk_offsets(data: &[u8], rows_per_chunk: usize) -> Vec<usize> {
+    assert!(rows_per_chunk > 0);
+    let mut offsets = vec![0];
+    let mut index = 0;
+    let mut rows = 0;
+    while index < data.len() {
+        if rows == rows_per_chunk {
+            offsets.push(index);
+            rows = 0;
+        }
+        let len = read_varint(data, &mut index) as usize;
+        index += len.saturating_sub(1);
+        rows += 1;
+    }
+    offsets.push(data.len());
+    offsets
+}
+
+fn push_varint(data: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        data.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    data.push(value as u8);
+}
+
+fn read_varint(data: &[u8], index: &mut usize) -> u64 {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = data[*index];
+        *index += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 { return value; }
+        shift += 7;
+    }
+}
+
+/// Like `read_varint`, but returns `None` instead of panicking if the varint is truncated or overflows.
+fn read_varint_checked(data: &[u8], index: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*index)?;
+        *index += 1;
+        if shift >= 64 || (shift == 63 && byte & 0x7e != 0) { return None; }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 { return Some(value); }
+        shift += 7;
+    }
+}
+
+/// Returns the next `len` bytes of `data` and advances `index` past them, or `None` if `data` is too short.
+fn read_bytes_checked<'a>(data: &'a [u8], index: &mut usize, len: u64) -> Option<&'a [u8]> {
+    let end = (*index as u64).checked_add(len)?;
+    if end > data.len() as u64 { return None; }
+    let bytes = &data[*index..(end as usize)];
+    *index = end as usize;
+    Some(bytes)
+}
+
+/// Reads a varint length followed by that many bytes of valid UTF-8.
+fn read_str_checked<'a>(data: &'a [u8], index: &mut usize) -> Option<&'a str> {
+    let len = read_varint_checked(data, index)?;
+    str::from_utf8(read_bytes_checked(data, index, len)?).ok()
+}
+
+/// Returns the number of values if `data` is a valid encoding for `PackedStrings`, which makes it safe to pass to
+/// `StringPackerIterator::from_slice`.
+pub fn validate_packed_strings(data: &[u8]) -> Option<usize> {
+    let mut index = 0;
+    let mut count = 0;
+    while index < data.len() {
+        let len = read_varint_checked(data, &mut index)?;
+        if len > 0 {
+            str::from_utf8(read_bytes_checked(data, &mut index, len - 1)?).ok()?;
+        }
+        count += 1;
+    }
+    Some(count)
+}
+
+/// Returns the number of values if `data` is a valid encoding for `FrontCodedStrings`.
+pub fn validate_front_coded_strings(data: &[u8]) -> Option<usize> {
+    let mut index = 0;
+    let mut count = 0;
+    let mut prev = Vec::new();
+    while index < data.len() {
+        let prefix_len = read_varint_checked(data, &mut index)?;
+        let len = read_varint_checked(data, &mut index)?;
+        if len > 0 {
+            if prefix_len > prev.len() as u64 { return None; }
+            prev.truncate(prefix_len as usize);
+            prev.extend_from_slice(read_bytes_checked(data, &mut index, len - 1)?);
+            str::from_utf8(&prev).ok()?;
+        }
+        count += 1;
+    }
+    Some(count)
+}
+
+/// Returns the number of values if `data` is a valid encoding for `SymbolCodedStrings`.
+pub fn validate_symbol_coded_strings(data: &[u8]) -> Option<usize> {
+    let mut index = 0;
+    let symbol_count = *data.get(0)? as usize;
+    index += 1;
+    let mut symbols = Vec::with_capacity(symbol_count);
+    for _ in 0..symbol_count {
+        let len = *data.get(index)?;
+        index += 1;
+        symbols.push(read_bytes_checked(data, &mut index, u64::from(len))?);
+    }
+    let mut count = 0;
+    let mut buffer = Vec::new();
+    while index < data.len() {
+        let len = read_varint_checked(data, &mut index)?;
+        if len > 0 {
+            let encoded = read_bytes_checked(data, &mut index, len - 1)?;
+            buffer.clear();
+            let mut i = 0;
+            while i < encoded.len() {
+                if encoded[i] == ESCAPE {
+                    buffer.push(*encoded.get(i + 1)?);
+                    i += 2;
+                } else {
+                    buffer.extend_from_slice(symbols.get(encoded[i] as usize)?);
+                    i += 1;
+                }
+            }
+            str::from_utf8(&buffer).ok()?;
+        }
+        count += 1;
+    }
+    Some(count)
+}
+
+/// Returns the number of values if `data` is a valid encoding for `ComponentCodedStrings`.
+pub fn validate_component_coded_strings(data: &[u8]) -> Option<usize> {
+    let separator = *data.get(0)?;
+    let mut index = 1;
+    let component_count = read_varint_checked(data, &mut index)?;
+    let mut components = Vec::new();
+    for _ in 0..component_count {
+        let len = read_varint_checked(data, &mut index)?;
+        components.push(read_bytes_checked(data, &mut index, len)?);
+    }
+    let mut count = 0;
+    let mut buffer = Vec::new();
+    while index < data.len() {
+        let value_components = read_varint_checked(data, &mut index)?;
+        buffer.clear();
+        for i in 0..value_components.saturating_sub(1) {
+            if i > 0 {
+                buffer.push(separator);
+            }
+            let code = read_varint_checked(data, &mut index)?;
+            buffer.extend_from_slice(components.get(code as usize)?);
+        }
+        str::from_utf8(&buffer).ok()?;
+        count += 1;
+    }
+    Some(count)
+}
+
+/// Returns the number of values if `data` is a valid encoding for `HybridStrings`.
+pub fn validate_hybrid_strings(data: &[u8]) -> Option<usize> {
+    let mut index = 0;
+    let dictionary_len = read_varint_checked(data, &mut index)?;
+    for _ in 0..dictionary_len {
+        read_str_checked(data, &mut index)?;
+    }
+    let mut count = 0;
+    while index < data.len() {
+        match read_varint_checked(data, &mut index)? {
+            0 => {}
+            1 => { read_str_checked(data, &mut index)?; }
+            code => if code - 2 >= dictionary_len { return None; },
+        }
+        count += 1;
+    }
+    Some(count)
+}
+
+/// Returns the number of values if `data` is a valid encoding for `PackedBytes`.
+pub fn validate_packed_bytes(data: &[u8]) -> Option<usize> {
+    let mut index = 0;
+    let mut count = 0;
+    while index < data.len() {
+        let mut len = 0u64;
+        while *data.get(index)? == 255 {
+            len += 255;
+            index += 1;
+        }
+        len += u64::from(data[index]);
+        index += 1;
+        read_bytes_checked(data, &mut index, len)?;
+        count += 1;
+    }
+    Some(count)
+}
+
 pub struct StringPackerIterator<'a> {
     data: &'a [u8],
     curr_index: usize,
+    end_index: usize,
+    validated: bool,
+    // Start offsets of remaining values, built on first call to `next_back`
+    offsets: Vec<usize>,
+    remaining: Cell<Option<usize>>,
+    invalid: Cell<usize>,
 }
 
 impl<'a> StringPackerIterator<'a> {
     /// `data` must be valid encoding for StringPacker
     pub unsafe fn from_slice(data: &'a [u8]) -> StringPackerIterator<'a> {
-        StringPackerIterator { data, curr_index: 0 }
+        StringPackerIterator::new(data, true)
+    }
+
+    /// Checks that each value is valid UTF-8, invalid values are truncated to their longest valid prefix.
+    pub fn from_slice_checked(data: &'a [u8]) -> StringPackerIterator<'a> {
+        StringPackerIterator::new(data, false)
+    }
+
+    fn new(data: &'a [u8], validated: bool) -> StringPackerIterator<'a> {
+        StringPackerIterator {
+            data,
+            curr_index: 0,
+            end_index: data.len(),
+            validated,
+            offsets: Vec::new(),
+            remaining: Cell::new(None),
+            invalid: Cell::new(0),
+        }
+    }
+
+    /// Sets the number of values remaining in the iterator if already known to the caller, which saves a pass over
+    /// the data when determining the exact length.
+    pub fn with_len(self, len: usize) -> StringPackerIterator<'a> {
+        self.remaining.set(Some(len));
+        self
+    }
+
+    /// Number of values decoded so far that were not valid UTF-8 and were truncated to their longest valid prefix.
+    /// Always 0 for iterators created with `from_slice`, which don't check their input.
+    pub fn invalid_values(&self) -> usize {
+        self.invalid.get()
+    }
+
+    /// Advances past the next `count` values by reading only their length prefixes.
+    pub fn skip_values(&mut self, count: usize) {
+        for _ in 0..count {
+            if self.curr_index >= self.end_index { return; }
+            let len = read_varint(self.data, &mut self.curr_index) as usize;
+            self.curr_index += len.saturating_sub(1);
+            self.consumed();
+        }
+    }
+
+    fn consumed(&self) {
+        if let Some(remaining) = self.remaining.get() {
+            self.remaining.set(Some(remaining - 1));
+        }
+    }
+
+    fn decode(&self, index: &mut usize) -> Option<&'a str> {
+        let len = read_varint(self.data, index) as usize;
+        if len == 0 {
+            return None;
+        }
+        let start = *index;
+        *index += len - 1;
+        let bytes = &self.data[start..*index];
+        if self.validated {
+            Some(unsafe { str::from_utf8_unchecked(bytes) })
+        } else {
+            match str::from_utf8(bytes) {
+                Ok(string) => Some(string),
+                Err(err) => {
+                    self.invalid.set(self.invalid.get() + 1);
+                    Some(unsafe { str::from_utf8_unchecked(&bytes[..err.valid_up_to()]) })
+                }
+            }
+        }
     }
 }
 
 impl<'a> Iterator for StringPackerIterator<'a> {
-    type Item = &'a str;
+    type Item = Option<&'a str>;
 
-    fn next(&mut self) -> Option<&'a str> {
-        if self.curr_index >= self.data.len() {
+    fn next(&mut self) -> Option<Option<&'a str>> {
+        if self.curr_index >= self.end_index {
             return None;
         }
-
         let mut index = self.curr_index;
-        while self.data[index] != 0 {
-            index += 1;
+        let value = self.decode(&mut index);
+        self.curr_index = index;
+        self.consumed();
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = match self.remaining.get() {
+            Some(remaining) => remaining,
+            None => {
+                let mut index = self.curr_index;
+                let mut count = 0;
+                while index < self.end_index {
+                    let len = read_varint(self.data, &mut index) as usize;
+                    index += len.saturating_sub(1);
+                    count += 1;
+                }
+                self.remaining.set(Some(count));
+                count
+            }
+        };
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> DoubleEndedIterator for StringPackerIterator<'a> {
+    fn next_back(&mut self) -> Option<Option<&'a str>> {
+        if self.curr_index >= self.end_index {
+            return None;
         }
-        let result = unsafe { str::from_utf8_unchecked(&self.data[self.curr_index..index]) };
-        self.curr_index = index + 1;
-        Some(result)
+        if self.offsets.is_empty() {
+            let mut index = self.curr_index;
+            while index < self.end_index {
+                self.offsets.push(index);
+                let len = read_varint(self.data, &mut index) as usize;
+                index += len.saturating_sub(1);
+            }
+        }
+        let start = self.offsets.pop().unwrap();
+        self.end_index = start;
+        self.consumed();
+        let mut index = start;
+        Some(self.decode(&mut index))
+    }
+}
+
+impl<'a> ExactSizeIterator for StringPackerIterator<'a> {}
+
+/// Like `StringPackerIterator`, but owns the packed data, e.g. after decompressing it, and so yields owned strings.
+pub struct OwnedStringPackerIterator {
+    data: Vec<u8>,
+    curr_index: usize,
+}
+
+impl OwnedStringPackerIterator {
+    /// `data` must be valid encoding for StringPacker
+    pub unsafe fn from_vec(data: Vec<u8>) -> OwnedStringPackerIterator {
+        OwnedStringPackerIterator { data, curr_index: 0 }
+    }
+}
+
+impl Iterator for OwnedStringPackerIterator {
+    type Item = Option<String>;
+
+    fn next(&mut self) -> Option<Option<String>> {
+        if self.curr_index >= self.data.len() {
+            return None;
+        }
+        let len = read_varint(&self.data, &mut self.curr_index) as usize;
+        if len == 0 {
+            return Some(None);
+        }
+        let start = self.curr_index;
+        self.curr_index += len - 1;
+        Some(Some(unsafe { str::from_utf8_unchecked(&self.data[start..self.curr_index]) }.to_string()))
     }
 }
 
@@ -169,3 +1243,296 @@ impl<'a> Iterator for PackedBytesIterator<'a> {
         Some(result)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapsize::HeapSizeOf;
+
+    #[test]
+    fn test_debug_sample() {
+        let long = "x".repeat(100);
+        let strings = vec![Some("a"), None, Some(long.as_str()), Some("d"), Some("e"), Some("f"), Some("g")];
+        let packed = PackedStrings::from_nullable_strings(&strings);
+        assert_eq!(format!("{:?}", packed), format!(
+            "PackedStrings {{ len: 7, bytes: {}, validated: true, \
+             values: [\"a\", null, \"{}\"…, … 1 more …, \"e\", \"f\", \"g\"] }}",
+            packed.data.len(), "x".repeat(DEBUG_MAX_CHARS)));
+        let packed = PackedStrings::from_nullable_strings(&strings[..4]);
+        assert!(format!("{:?}", packed).ends_with(&format!("[\"a\", null, \"{}\"…, \"d\"] }}", "x".repeat(DEBUG_MAX_CHARS))));
+        assert_eq!(debug_sample(&[], &[], 0), "[]");
+    }
+
+    #[test]
+    fn test_packed_len() {
+        let mut packed = PackedStrings::default();
+        assert!(packed.is_empty());
+        packed.push("a");
+        packed.push_null();
+        packed.push("");
+        assert_eq!(packed.len(), 3);
+        assert_eq!(packed.iter().len(), 3);
+        let from_bytes = PackedStrings::from_bytes(vec![&b"x"[..], b"yz"].into_iter());
+        assert_eq!(from_bytes.len(), 2);
+    }
+
+    #[test]
+    fn test_offset_index() {
+        let strings = (0..1000)
+            .map(|i| if i % 13 == 0 { None } else { Some("x".repeat(i % 7)) })
+            .collect::<Vec<_>>();
+        let strs = strings.iter().map(|s| s.as_ref().map(|s| s.as_str())).collect::<Vec<_>>();
+        let linear = PackedStrings::from_nullable_strings(&strs);
+        let indices = (0..200).map(|i| (i * 7919) % 1000).collect::<Vec<_>>();
+        let expected = indices.iter().map(|&i| strs[i]).collect::<Vec<_>>();
+        assert_eq!(linear.take(&indices), expected);
+        for &stride in &[1, 16, 999, 1000, 5000] {
+            let indexed = PackedStrings::from_nullable_strings(&strs).with_offset_index(stride);
+            assert_eq!(indexed.take(&indices), expected);
+            assert_eq!(indexed.decode_range(990, 10), &strs[990..]);
+            assert_eq!(indexed.decode_range(0, 0), Vec::<Option<&str>>::new());
+            assert!(indexed.heap_size_of_children() > linear.heap_size_of_children());
+
+            let mut pushed = PackedStrings::default().with_offset_index(stride);
+            for s in &strs {
+                match *s {
+                    Some(s) => pushed.push(s),
+                    None => pushed.push_null(),
+                }
+            }
+            assert_eq!(pushed.offset_index, indexed.offset_index);
+            assert_eq!(pushed.take(&indices), expected);
+        }
+    }
+
+    #[test]
+    fn test_null_and_empty() {
+        let strings = vec![
+            Some(Arc::new("a".to_string())),
+            None,
+            Some(Arc::new("".to_string())),
+            None,
+            Some(Arc::new("bc".to_string())),
+        ];
+        let packed = PackedStrings::from_nullable_strings(&strings).into_vec();
+        let unpacked = unsafe { StringPackerIterator::from_slice(&packed) }.collect::<Vec<_>>();
+        assert_eq!(unpacked, vec![Some("a"), None, Some(""), None, Some("bc")]);
+    }
+
+    #[test]
+    fn test_embedded_nul() {
+        let strings = vec!["a\0b", "\0", "", "\0\0c\0"];
+        let packed = PackedStrings::from_iterator(strings.iter().cloned()).into_vec();
+        let unpacked = unsafe { StringPackerIterator::from_slice(&packed) }.collect::<Vec<_>>();
+        assert_eq!(unpacked, strings.into_iter().map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_long_strings() {
+        let strings = vec![
+            "x".repeat(127),
+            "y".repeat(128),
+            "short".to_string(),
+            "z".repeat(5000),
+            "w".repeat(70_000),
+            "end".to_string(),
+        ];
+        let packed = PackedStrings::from_iterator(strings.iter().map(|s| s.as_str())).into_vec();
+        let unpacked = unsafe { StringPackerIterator::from_slice(&packed) }.collect::<Vec<_>>();
+        assert_eq!(unpacked, strings.iter().map(|s| Some(s.as_str())).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds the maximum length of dictionary entries")]
+    fn test_oversized_dictionary_entry() {
+        let mut dictionary = IndexedPackedStrings::default();
+        dictionary.push(&"x".repeat(1 << 24));
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let bytes: Vec<&[u8]> = vec![b"ok", b"a\xffb", b"\xc3"];
+        let unchecked = PackedStrings::from_bytes(bytes.iter().cloned());
+        assert!(!unchecked.is_validated());
+        assert_eq!(unchecked.iter().collect::<Vec<_>>(), vec![Some("ok"), Some("a"), Some("")]);
+        assert_eq!(unchecked.invalid_values(), 2);
+        let mut iter = unchecked.iter();
+        assert_eq!(iter.next(), Some(Some("ok")));
+        assert_eq!(iter.invalid_values(), 0);
+        assert_eq!(iter.next_back(), Some(Some("")));
+        assert_eq!(iter.invalid_values(), 1);
+
+        let checked = PackedStrings::from_strings_checked(bytes.iter().cloned());
+        assert!(checked.is_validated());
+        assert_eq!(checked.iter().collect::<Vec<_>>(), vec![Some("ok"), Some("a\u{FFFD}b"), Some("\u{FFFD}")]);
+        assert_eq!(checked.invalid_values(), 0);
+    }
+
+    #[test]
+    fn test_chunk_offsets() {
+        let strings = vec![Some("a"), None, Some("bcd"), Some(""), Some("efgh")];
+        let packed = PackedStrings::from_nullable_strings(&strings.iter()
+            .map(|s| s.map(|s| Arc::new(s.to_string())))
+            .collect::<Vec<_>>()).into_vec();
+        let offsets = chunk_offsets(&packed, 2);
+        assert_eq!(offsets, vec![0, 3, 8, packed.len()]);
+        let decoded = offsets.windows(2)
+            .flat_map(|range| unsafe { StringPackerIterator::from_slice(&packed[range[0]..range[1]]) })
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, strings);
+        assert_eq!(chunk_offsets(&[], 2), vec![0, 0]);
+    }
+
+    #[test]
+    fn test_reverse_iteration() {
+        let long = "x".repeat(300);
+        let strings = vec![Some("a"), None, Some(""), Some(&long[..]), Some("bc"), None];
+        let packed = PackedStrings::from_nullable_strings(&strings.iter()
+            .map(|s| s.map(|s| Arc::new(s.to_string())))
+            .collect::<Vec<_>>()).into_vec();
+        let forward = unsafe { StringPackerIterator::from_slice(&packed) }.collect::<Vec<_>>();
+        let mut reverse = unsafe { StringPackerIterator::from_slice(&packed) }.rev().collect::<Vec<_>>();
+        assert_eq!(forward, strings);
+        reverse.reverse();
+        assert_eq!(reverse, strings);
+        assert_eq!(StringPackerIterator::from_slice_checked(&[]).next_back(), None);
+    }
+
+    #[test]
+    fn test_mixed_direction_iteration() {
+        let strings = vec!["a", "b", "c", "d", "e"];
+        let packed = PackedStrings::from_iterator(strings.iter().cloned()).into_vec();
+        let mut iter = unsafe { StringPackerIterator::from_slice(&packed) };
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(Some("a")));
+        assert_eq!(iter.next_back(), Some(Some("e")));
+        assert_eq!(iter.len(), 3);
+        iter.skip_values(1);
+        assert_eq!(iter.next_back(), Some(Some("d")));
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next(), Some(Some("c")));
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    /// Checks that skipping any number of values leaves the iterator at the same value as decoding them.
+    fn assert_skips<I: CodedValues, F: Fn() -> I>(values: F, expected: &[Option<String>]) {
+        for skip in 0..(expected.len() + 2) {
+            let mut iter = values();
+            iter.skip_values(skip);
+            let mut remaining = Vec::new();
+            while let Some(value) = iter.next_value() {
+                remaining.push(value.map(|s| s.to_string()));
+            }
+            assert_eq!(&remaining[..], &expected[cmp::min(skip, expected.len())..], "skipped {}", skip);
+        }
+    }
+
+    #[test]
+    fn test_front_coding() {
+        let strings = vec![
+            Some(Arc::new("2018-01-01 10:00:00".to_string())),
+            Some(Arc::new("2018-01-01 10:00:05".to_string())),
+            None,
+            Some(Arc::new("2018-01-02 00:00:00".to_string())),
+            Some(Arc::new("2018-01-02".to_string())),
+            Some(Arc::new("".to_string())),
+            Some(Arc::new("2019".to_string())),
+        ];
+        let front_coded = FrontCodedStrings::from_nullable_strings(&strings);
+        assert_eq!(front_coded.total_bytes(), 19 * 3 + 10 + 4);
+        let data = front_coded.into_vec();
+        assert!(data.len() < PackedStrings::from_nullable_strings(&strings).into_vec().len());
+
+        let mut iter = unsafe { FrontCodedIterator::from_slice(&data) };
+        let mut unpacked = Vec::new();
+        while let Some(value) = iter.next_value() {
+            unpacked.push(value.map(|s| s.to_string()));
+        }
+        assert_eq!(unpacked, strings.iter().map(|s| s.as_ref().map(|s| s.to_string())).collect::<Vec<_>>());
+        assert_skips(|| unsafe { FrontCodedIterator::from_slice(&data) }, &unpacked);
+        assert_eq!(validate_front_coded_strings(&data), Some(strings.len()));
+        assert_eq!(validate_front_coded_strings(&data[..data.len() - 1]), None);
+    }
+
+    #[test]
+    fn test_component_coding() {
+        let strings = vec![
+            Some("/usr/local/bin/locustdb"),
+            Some("/usr/local/bin/cargo"),
+            None,
+            Some("/usr/local/lib/libc.so"),
+            Some(""),
+            Some("relative/dir/"),
+            Some("//"),
+            Some("/usr/local/bin/cargo"),
+            Some("ü/ÿ"),
+        ].into_iter().map(|s| s.map(|s| Arc::new(s.to_string()))).collect::<Vec<_>>();
+        let coded = ComponentCodedStrings::from_nullable_strings(&strings, b'/');
+        assert_eq!(coded.total_bytes(), strings.iter().map(|s| s.as_ref().map_or(0, |s| s.len())).sum::<usize>());
+        // "", "usr", "local", "bin", "locustdb", "cargo", "lib", "libc.so", "relative", "dir", "ü", "ÿ"
+        assert_eq!(coded.component_count(), 12);
+        assert_eq!(coded.component_reuse(), 29.0 / 12.0);
+        let data = coded.into_vec();
+
+        let mut iter = unsafe { ComponentCodedIterator::from_slice(&data) };
+        let mut unpacked = Vec::new();
+        while let Some(value) = iter.next_value() {
+            unpacked.push(value.map(|s| s.to_string()));
+        }
+        assert_eq!(unpacked, strings.iter().map(|s| s.as_ref().map(|s| s.to_string())).collect::<Vec<_>>());
+        assert_skips(|| unsafe { ComponentCodedIterator::from_slice(&data) }, &unpacked);
+        assert_eq!(validate_component_coded_strings(&data), Some(strings.len()));
+        assert_eq!(validate_component_coded_strings(&data[..data.len() - 1]), None);
+    }
+
+    #[test]
+    fn test_symbol_coding() {
+        let lines = (0..200)
+            .map(|i| format!("GET /api/v1/users/{} HTTP/1.1 200 user_agent=Mozilla/5.0", i * 7919 % 1000))
+            .collect::<Vec<_>>();
+        let table = SymbolTable::learn(lines.iter().take(50).map(|s| s.as_bytes()));
+        let strings = lines.iter().map(|s| Some(Arc::new(s.clone())))
+            .chain(vec![None, Some(Arc::new("".to_string())), Some(Arc::new("\u{FFFD}ÿ\0".to_string()))])
+            .collect::<Vec<_>>();
+        let coded = SymbolCodedStrings::from_nullable_strings(&strings, table);
+        assert_eq!(coded.total_bytes(), strings.iter().map(|s| s.as_ref().map_or(0, |s| s.len())).sum::<usize>());
+        let data = coded.into_vec();
+        assert!(data.len() * 2 < PackedStrings::from_nullable_strings(&strings).into_vec().len());
+
+        let mut iter = unsafe { SymbolCodedIterator::from_slice(&data) };
+        let mut unpacked = Vec::new();
+        while let Some(value) = iter.next_value() {
+            unpacked.push(value.map(|s| s.to_string()));
+        }
+        assert_eq!(unpacked, strings.iter().map(|s| s.as_ref().map(|s| s.to_string())).collect::<Vec<_>>());
+        assert_skips(|| unsafe { SymbolCodedIterator::from_slice(&data) }, &unpacked);
+        assert_eq!(validate_symbol_coded_strings(&data), Some(strings.len()));
+        assert_eq!(validate_symbol_coded_strings(&data[..data.len() - 1]), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let strings = vec![Some("a"), None, Some(""), Some("ü")];
+        let packed = PackedStrings::from_nullable_strings(&strings).into_vec();
+        assert_eq!(validate_packed_strings(&packed), Some(4));
+        assert_eq!(validate_packed_strings(&packed[..packed.len() - 1]), None);
+        assert_eq!(validate_packed_strings(&[3, b'a', 0xff]), None);
+        assert_eq!(validate_packed_strings(&[0xff; 11]), None);
+
+        let hybrid = HybridStrings::from_nullable_strings(&strings, vec!["a"].into_iter()).into_vec();
+        assert_eq!(validate_hybrid_strings(&hybrid), Some(4));
+        // Code 3 refers to the second dictionary entry, which doesn't exist
+        assert_eq!(validate_hybrid_strings(&[1, 1, b'a', 3]), None);
+        assert_eq!(validate_hybrid_strings(&[0, 1, 1, 0xff]), None);
+
+        // Escaped byte that is not valid UTF-8 on its own
+        assert_eq!(validate_symbol_coded_strings(&[0, 3, ESCAPE, b'a']), Some(1));
+        assert_eq!(validate_symbol_coded_strings(&[0, 3, ESCAPE, 0xff]), None);
+        assert_eq!(validate_symbol_coded_strings(&[0, 2, ESCAPE]), None);
+
+        assert_eq!(validate_packed_bytes(&[2, 1, 2, 0]), Some(2));
+        assert_eq!(validate_packed_bytes(&[255, 3]), None);
+    }
+}
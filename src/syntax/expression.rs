@@ -24,6 +24,8 @@ pub enum Func2Type {
     Multiply,
     Divide,
     RegexMatch,
+    Like,
+    Contains,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -302,7 +302,7 @@ named!(function_name<&[u8], Func2Type>,
 );
 
 named!(infix_function_name<&[u8], Func2Type>,
-    alt!( equals | not_equals | and | or | greater | less | add | subtract | divide | multiply )
+    alt!( equals | not_equals | and | or | like | contains | greater | less | add | subtract | divide | multiply )
 );
 
 named!(divide<&[u8], Func2Type>,
@@ -338,11 +338,28 @@ named!(less<&[u8], Func2Type>,
 );
 
 named!(and<&[u8], Func2Type>,
-    map!( tag_no_case!("and"), |_| Func2Type::And)
+    map!( terminated!(tag_no_case!("and"), keyword_end), |_| Func2Type::And)
 );
 
 named!(or<&[u8], Func2Type>,
-    map!( tag_no_case!("or"), |_| Func2Type::Or)
+    map!( terminated!(tag_no_case!("or"), keyword_end), |_| Func2Type::Or)
+);
+
+/// Ensures keywords are not matched as the prefix of an identifier (e.g. `or` in `order`).
+named!(keyword_end<&[u8], &[u8]>,
+    not!(ident_chars)
+);
+
+named!(ident_chars<&[u8], &[u8]>,
+    take_while1!(is_ident_char)
+);
+
+named!(like<&[u8], Func2Type>,
+    map!( tag_no_case!("like"), |_| Func2Type::Like)
+);
+
+named!(contains<&[u8], Func2Type>,
+    map!( tag_no_case!("contains"), |_| Func2Type::Contains)
 );
 
 named!(regex<&[u8], Func2Type>,
@@ -440,6 +457,13 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_like() {
+        assert_eq!(
+            format!("{:?}", parse_query("select first_name from default where first_name like \"Ad%\";".as_bytes())),
+            "Done([], Query { select: [ColName(\"first_name\")], table: \"default\", filter: Func2(Like, ColName(\"first_name\"), Const(Str(\"Ad%\"))), aggregate: [], order_by: None, order_desc: false, limit: LimitClause { limit: 100, offset: 0 }, order_by_index: None })");
+    }
+
     #[test]
     fn test_to_year() {
         assert_eq!(
@@ -137,6 +137,13 @@ fn test_group_by_limit() {
     )
 }
 
+#[test]
+fn count_without_group_by() {
+    test_query("select count(1) from default where num < 8;", &[vec![99.into()]]);
+    test_query("select count(1), count(0) from default where first_name = \"Adam\";", &[vec![2.into(), 2.into()]]);
+    test_query("select count(1) from default;", &[vec![100.into()]]);
+}
+
 #[test]
 fn group_by_string_filter_string_eq() {
     test_query(
@@ -166,6 +173,306 @@ fn test_string_packed_column() {
     )
 }
 
+#[test]
+fn group_by_string_filter_string_lt() {
+    test_query(
+        "select first_name, count(1) from default where first_name < \"B\";",
+        &[
+            vec!["Adam".into(), 2.into()],
+            vec!["Amanda".into(), 1.into()],
+            vec!["Amy".into(), 2.into()],
+            vec!["Angela".into(), 1.into()],
+            vec!["Anna".into(), 1.into()],
+            vec!["Anne".into(), 2.into()],
+            vec!["Anthony".into(), 1.into()],
+            vec!["Antonio".into(), 1.into()],
+        ],
+    )
+}
+
+#[test]
+fn test_string_range_filter() {
+    test_query(
+        "select first_name from default where (first_name > \"Anne\") AND (first_name < \"Benjamin\") order by first_name;",
+        &[
+            vec!["Anthony".into()],
+            vec!["Antonio".into()],
+        ],
+    )
+}
+
+#[test]
+fn test_string_packed_range_filter() {
+    test_query_ec(
+        "select string_packed from default where string_packed > \"s\" order by string_packed;",
+        &[
+            vec!["sss".into()],
+            vec!["t".into()],
+            vec!["xyz".into()],
+        ],
+    );
+    test_query_ec(
+        "select string_packed from default where string_packed < \"axz\" order by string_packed;",
+        &[
+            vec!["abc".into()],
+            vec!["asd".into()],
+            vec!["axy".into()],
+        ],
+    )
+}
+
+#[test]
+fn test_like_prefix() {
+    test_query(
+        "select first_name, count(1) from default where first_name like \"Ch%\";",
+        &[
+            vec!["Charles".into(), 1.into()],
+            vec!["Christina".into(), 2.into()],
+        ],
+    )
+}
+
+#[test]
+fn test_like_empty_prefix() {
+    test_query(
+        "select tld, count(1) from default where tld like \"%\";",
+        &[
+            vec!["".into(), 8.into()],
+            vec!["biz".into(), 10.into()],
+            vec!["com".into(), 9.into()],
+            vec!["edu".into(), 13.into()],
+            vec!["gov".into(), 5.into()],
+            vec!["info".into(), 13.into()],
+            vec!["mil".into(), 11.into()],
+            vec!["name".into(), 17.into()],
+            vec!["net".into(), 6.into()],
+            vec!["org".into(), 8.into()],
+        ],
+    )
+}
+
+#[test]
+fn test_string_packed_like_prefix() {
+    test_query_ec(
+        "select string_packed from default where string_packed like \"a%\" order by string_packed;",
+        &[
+            vec!["abc".into()],
+            vec!["asd".into()],
+            vec!["axy".into()],
+            vec!["axz".into()],
+            vec!["azy".into()],
+        ],
+    )
+}
+
+#[test]
+fn test_like_contains() {
+    test_query(
+        "select first_name, count(1) from default where first_name like \"%ar%\";",
+        &[
+            vec!["Carl".into(), 1.into()],
+            vec!["Carolyn".into(), 1.into()],
+            vec!["Charles".into(), 1.into()],
+            vec!["Gary".into(), 1.into()],
+            vec!["Harry".into(), 1.into()],
+            vec!["Larry".into(), 2.into()],
+            vec!["Margaret".into(), 2.into()],
+            vec!["Marilyn".into(), 1.into()],
+            vec!["Mark".into(), 2.into()],
+            vec!["Martin".into(), 1.into()],
+            vec!["Mary".into(), 1.into()],
+            vec!["Sara".into(), 1.into()],
+        ],
+    )
+}
+
+#[test]
+fn test_like_single_char_wildcard() {
+    test_query(
+        "select tld, count(1) from default where tld like \"c_m\";",
+        &[vec!["com".into(), 9.into()]],
+    );
+    test_query(
+        "select tld, count(1) from default where tld like \"_o_\";",
+        &[
+            vec!["com".into(), 9.into()],
+            vec!["gov".into(), 5.into()],
+        ],
+    )
+}
+
+#[test]
+fn test_string_packed_like_single_char_wildcard() {
+    test_query_ec(
+        "select string_packed from default where string_packed like \"a_y\" order by string_packed;",
+        &[
+            vec!["axy".into()],
+            vec!["azy".into()],
+        ],
+    )
+}
+
+#[test]
+fn test_contains() {
+    test_query(
+        "select tld, count(1) from default where tld contains \"o\";",
+        &[
+            vec!["com".into(), 9.into()],
+            vec!["gov".into(), 5.into()],
+            vec!["info".into(), 13.into()],
+            vec!["org".into(), 8.into()],
+        ],
+    )
+}
+
+#[test]
+fn test_string_packed_contains() {
+    test_query_ec(
+        "select string_packed from default where string_packed contains \"z\" order by string_packed;",
+        &[
+            vec!["axz".into()],
+            vec!["azy".into()],
+            vec!["xyz".into()],
+        ],
+    )
+}
+
+#[test]
+fn test_zone_map_skips_partitions() {
+    let locustdb = LocustDB::memory_only();
+    let _ = block_on(locustdb.load_csv(
+        LoadOptions::new("test_data/edge_cases.csv", "default")
+            .with_partition_size(3)));
+    let query = "select string_packed from default where string_packed = \"abc\";";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::from("abc")]]);
+    assert_eq!(result.stats.rows_scanned, 3);
+
+    let query = "select string_packed from default where string_packed = \"a\";";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, Vec::<Vec<Value>>::new());
+    assert_eq!(result.stats.rows_scanned, 0);
+}
+
+#[test]
+fn test_integer_zone_map_skips_partitions() {
+    let locustdb = LocustDB::memory_only();
+    let _ = block_on(locustdb.load_csv(
+        LoadOptions::new("test_data/edge_cases.csv", "default")
+            .with_partition_size(3)));
+    let query = "select non_dense_ints from default where u8_offset_encoded = 511;";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::from(2)]]);
+    assert_eq!(result.stats.rows_scanned, 3);
+
+    let query = "select non_dense_ints from default where u8_offset_encoded > 500;";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::from(2)]]);
+    assert_eq!(result.stats.rows_scanned, 3);
+
+    let query = "select non_dense_ints from default where u8_offset_encoded < 256;";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, Vec::<Vec<Value>>::new());
+    assert_eq!(result.stats.rows_scanned, 0);
+}
+
+#[test]
+fn test_bit_packed_integers() {
+    let locustdb = LocustDB::memory_only();
+    let _ = block_on(locustdb.load_csv(
+        LoadOptions::new("test_data/edge_cases.csv", "default")
+            .with_bit_packed_integers(true)));
+    let query = "select u8_offset_encoded, non_dense_ints from default where u8_offset_encoded > 432;";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![
+        vec![Value::from(500), Value::from(0)],
+        vec![Value::from(511), Value::from(2)],
+        vec![Value::from(500), Value::from(3)],
+    ]);
+    let query = "select non_dense_ints, count(1) from default;";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![
+        vec![Value::from(0), Value::from(2)],
+        vec![Value::from(1), Value::from(2)],
+        vec![Value::from(2), Value::from(3)],
+        vec![Value::from(3), Value::from(2)],
+        vec![Value::from(4), Value::from(1)],
+    ]);
+}
+
+#[test]
+fn test_case_insensitive() {
+    let locustdb = LocustDB::memory_only();
+    let _ = block_on(locustdb.load_csv(
+        LoadOptions::new("test_data/email_domains.csv", "default")
+            .with_case_insensitive(true)));
+    let query = "select domain, count(1) from default where domain = \"GMAIL.com\";";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::from("Gmail.com"), Value::from(3)]]);
+
+    let query = "select domain, count(1) from default;";
+    let mut rows = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap().rows;
+    rows.sort();
+    assert_eq!(rows, vec![
+        vec![Value::from("Gmail.com"), Value::from(3)],
+        vec![Value::from("yahoo.com"), Value::from(2)],
+    ]);
+
+    let query = "select domain, count(1) from default where domain <> \"GMAIL.com\";";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::from("yahoo.com"), Value::from(2)]]);
+
+    let query = "select domain, count(1) from default where domain like \"YAH%\";";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::from("yahoo.com"), Value::from(2)]]);
+
+    let query = "select domain, count(1) from default where domain contains \"MAIL\";";
+    let result = block_on(locustdb.run_query(query, false, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::from("Gmail.com"), Value::from(3)]]);
+}
+
+#[test]
+fn test_symbol_table_compression() {
+    let locustdb = LocustDB::memory_only();
+    let _ = block_on(locustdb.load_csv(
+        LoadOptions::new("test_data/tiny.csv", "default")
+            .with_partition_size(40)
+            .with_max_unique_strings(0)
+            .with_symbol_table_compression(true)));
+    let query = "select tld from default where tld like \"co%\";";
+    let result = block_on(locustdb.run_query(query, true, vec![])).unwrap().0.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::from("com")]; 9]);
+    assert!(result.query_plans.keys().any(|plan| plan.contains("unpack_symbol_coded_strings")));
+}
+
+#[test]
+fn test_regex() {
+    test_query(
+        "select first_name, count(1) from default where regex(first_name, \"^[A-G].*y$\");",
+        &[
+            vec!["Amy".into(), 2.into()],
+            vec!["Anthony".into(), 1.into()],
+            vec!["Billy".into(), 1.into()],
+            vec!["Bobby".into(), 1.into()],
+            vec!["Emily".into(), 1.into()],
+            vec!["Gary".into(), 1.into()],
+            vec!["Gregory".into(), 2.into()],
+        ],
+    )
+}
+
+#[test]
+fn test_string_packed_regex() {
+    test_query_ec(
+        "select string_packed from default where (regex(string_packed, \"^a.y$\")) OR (string_packed = \"t\") order by string_packed;",
+        &[
+            vec!["axy".into()],
+            vec!["azy".into()],
+            vec!["t".into()],
+        ],
+    )
+}
+
 #[test]
 fn test_and_or() {
     test_query(
@@ -224,6 +531,14 @@ fn test_multiple_group_by_2() {
     )
 }
 
+#[test]
+fn gt_filter_on_offset_encoded_column() {
+    test_query_ec(
+        "select u8_offset_encoded from default where u8_offset_encoded > 500;",
+        &[vec![511.into()]],
+    )
+}
+
 #[test]
 fn test_division() {
     test_query(